@@ -0,0 +1,106 @@
+// Parse throughput, run with `cargo bench --bench parse`.
+//
+// The synthetic documents come from `combine_sandbox::generate`, so the same inputs can be
+// produced outside the benchmark. `BENCH_FILES` adds real documents, e.g.
+//
+//   BENCH_FILES='docs/**/*.adoc' cargo bench --bench parse
+//
+// With `--features parallel` the synthetic documents are also parsed section by section on all
+// cores.
+use combine_sandbox::generate::{generate, DocumentSize, GenerateOptions};
+use combine_sandbox::{Document, ParseOptions, RecoveryPolicy};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+fn synthetic(c: &mut Criterion) {
+    let lenient = ParseOptions::new().recovery(RecoveryPolicy::default());
+    let lazy = ParseOptions::new().lazy_inlines(true);
+    #[cfg(feature = "parallel")]
+    let parallel = ParseOptions::new().parallel(true);
+    let mut group = c.benchmark_group("synthetic");
+    for size in DocumentSize::ALL.iter() {
+        let source = generate(&GenerateOptions::new().size(*size));
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        if *size == DocumentSize::Large {
+            group.sample_size(10);
+        }
+        group.bench_with_input(
+            BenchmarkId::new("parse", size.name()),
+            &source,
+            |b, source| b.iter(|| Document::parse(source).unwrap()),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("parse_lenient", size.name()),
+            &source,
+            |b, source| b.iter(|| Document::parse_with_options(source, &lenient).unwrap()),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("parse_lazy", size.name()),
+            &source,
+            |b, source| b.iter(|| Document::parse_with_options(source, &lazy).unwrap()),
+        );
+        #[cfg(feature = "parallel")]
+        group.bench_with_input(
+            BenchmarkId::new("parse_parallel", size.name()),
+            &source,
+            |b, source| b.iter(|| Document::parse_with_options(source, &parallel).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+// Text where almost every word holds a mark that doesn't open a span, so runs of text end at
+// terminator characters all the time, in paragraphs and in list items. Plain prose of the same
+// size is the baseline.
+fn terminators(c: &mut Criterion) {
+    let marked = "snake_case a*b x`y c#d 2*3_4 [ref] x_1 ";
+    let plain = "plain words of prose, no marks in them ";
+    let document = |words: &str| {
+        let line = words.repeat(4);
+        let paragraph = format!("{}\n", line).repeat(8);
+        let list = format!("* {}\n", line).repeat(8);
+        format!("{}\n{}\n", paragraph, list).repeat(200)
+    };
+    let mut group = c.benchmark_group("terminators");
+    for (name, words) in [("marked", marked), ("plain", plain)] {
+        let source = document(words);
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_with_input(BenchmarkId::new("parse", name), &source, |b, source| {
+            b.iter(|| Document::parse(source).unwrap())
+        });
+    }
+    group.finish();
+}
+
+// The corpus and compatibility fixtures, plus whatever `BENCH_FILES` matches.
+fn files(c: &mut Criterion) {
+    let mut patterns = vec![
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/corpus/*.adoc").to_string(),
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/compat/*.adoc").to_string(),
+    ];
+    if let Ok(pattern) = env::var("BENCH_FILES") {
+        patterns.push(pattern);
+    }
+    let paths: Vec<PathBuf> = patterns
+        .iter()
+        .flat_map(|pattern| glob::glob(pattern).expect("invalid BENCH_FILES pattern"))
+        .filter_map(Result::ok)
+        .collect();
+
+    let options = ParseOptions::new().recovery(RecoveryPolicy::default());
+    let mut group = c.benchmark_group("files");
+    for path in paths {
+        let source = fs::read_to_string(&path).unwrap();
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_with_input(BenchmarkId::new("parse", name), &source, |b, source| {
+            b.iter(|| Document::parse_with_options(source, &options).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, synthetic, terminators, files);
+criterion_main!(benches);
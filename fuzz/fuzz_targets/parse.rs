@@ -0,0 +1,19 @@
+#![no_main]
+// `cargo +nightly fuzz run parse -- -max_len=4096`
+
+use libfuzzer_sys::fuzz_target;
+use std::time::{Duration, Instant};
+
+// Far above what any input within `-max_len` needs, so hitting it means the parser looped.
+const BUDGET: Duration = Duration::from_secs(2);
+
+fuzz_target!(|source: &str| {
+    let start = Instant::now();
+    let _ = combine_sandbox::parse(source);
+    assert!(
+        start.elapsed() < BUDGET,
+        "parse took {:?} for {} bytes",
+        start.elapsed(),
+        source.len()
+    );
+});
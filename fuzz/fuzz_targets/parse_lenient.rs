@@ -0,0 +1,46 @@
+#![no_main]
+// `cargo +nightly fuzz run parse_lenient -- -max_len=4096`
+
+use combine_sandbox::{Document, ParseOptions, RecoveryPolicy, Span};
+use libfuzzer_sys::fuzz_target;
+use std::time::{Duration, Instant};
+
+// Far above what any input within `-max_len` needs, so hitting it means the parser looped.
+const BUDGET: Duration = Duration::from_secs(2);
+
+fn assert_in_bounds(span: Span, len: usize) {
+    assert!(
+        span.start <= span.end && span.end <= len,
+        "span {}..{} outside of {} bytes",
+        span.start,
+        span.end,
+        len
+    );
+}
+
+fuzz_target!(|source: &str| {
+    let start = Instant::now();
+    let options = ParseOptions::new().recovery(RecoveryPolicy::default());
+    // The recovering parser never fails on text it only has to read.
+    let document = Document::parse_with_options(source, &options).unwrap();
+    assert!(
+        start.elapsed() < BUDGET,
+        "parse took {:?} for {} bytes",
+        start.elapsed(),
+        source.len()
+    );
+
+    // Spans index the preprocessed text, which only matches the input when no attribute was
+    // set or referenced. Includes stay as written without a resolver.
+    if !document.attributes().is_empty() || source.contains('{') {
+        return;
+    }
+    for index in 0..document.blocks().len() {
+        if let Some(span) = document.span(index) {
+            assert_in_bounds(span, source.len());
+        }
+    }
+    for diagnostic in document.diagnostics() {
+        assert_in_bounds(diagnostic.span, source.len());
+    }
+});
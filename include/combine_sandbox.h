@@ -0,0 +1,32 @@
+#ifndef COMBINE_SANDBOX_H
+#define COMBINE_SANDBOX_H
+
+/* Generated from src/ffi.rs by tests/ffi_header.rs, do not edit. */
+
+#include <stdint.h>
+
+#define ASCIIDOC_HTML_SANITIZE 1
+
+#define ASCIIDOC_HTML_ASCIIDOCTOR (1 << 1)
+
+#define ASCIIDOC_HTML_HEADING_IDS (1 << 2)
+
+#define ASCIIDOC_HTML_HIDE_TITLE (1 << 3)
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+char *asciidoc_parse_to_json(const char *input);
+
+char *asciidoc_render_html(const char *input, uint32_t flags);
+
+void asciidoc_string_free(char *s);
+
+const char *asciidoc_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* COMBINE_SANDBOX_H */
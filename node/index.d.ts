@@ -0,0 +1,23 @@
+/* Types of the exports of src/lib.rs. */
+
+export interface ParseOptions {
+  /** Attributes that win over the ones defined in the document, like `-a name=value`. */
+  attributes?: Record<string, string>
+}
+export interface RenderOptions {
+  attributes?: Record<string, string>
+  /** asciidoctor's markup and class names, for its stylesheets. */
+  asciidoctor?: boolean
+  /** Drop link and image targets that can run script, for untrusted input. */
+  sanitize?: boolean
+  hideTitle?: boolean
+  /** Give headings without an explicit id one derived from their text. */
+  headingIds?: boolean
+  /** Render like github.com does, the flags above are ignored. */
+  github?: boolean
+}
+/** The JSON AST described by `schema/ast-v2.schema.json`. */
+export function parse(source: string, options?: ParseOptions): Promise<Record<string, unknown>>
+export function render(source: string, options?: RenderOptions): Promise<string>
+export function parseSync(source: string, options?: ParseOptions): Record<string, unknown>
+export function renderSync(source: string, options?: RenderOptions): string
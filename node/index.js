@@ -0,0 +1,2 @@
+// `npm run build` writes the addon of the current platform next to this file.
+module.exports = require('./combine-sandbox.node')
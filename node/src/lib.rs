@@ -0,0 +1,132 @@
+// Node.js bindings. Parsing and rendering run on the libuv thread pool and resolve a promise,
+// a site generator can convert many pages at once without blocking the event loop. Sources are
+// parsed leniently, problems end up in the `diagnostics` of the AST.
+use combine_sandbox::render::html::{render_with_options, HtmlOptions, HtmlProfile};
+use combine_sandbox::{to_json_ast, Document, RecoveryPolicy, SlugStrategy};
+use napi::bindgen_prelude::AsyncTask;
+use napi::{Env, Error, JsUnknown, Result, Task};
+use napi_derive::napi;
+use serde_json::Value;
+use std::collections::HashMap;
+
+#[napi(object)]
+#[derive(Default)]
+pub struct ParseOptions {
+    // Attributes that win over the ones defined in the document, like `-a name=value`.
+    pub attributes: Option<HashMap<String, String>>,
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct RenderOptions {
+    pub attributes: Option<HashMap<String, String>>,
+    // asciidoctor's markup and class names, for its stylesheets.
+    pub asciidoctor: Option<bool>,
+    // Drop link and image targets that can run script, for untrusted input.
+    pub sanitize: Option<bool>,
+    pub hide_title: Option<bool>,
+    // Give headings without an explicit id one derived from their text.
+    pub heading_ids: Option<bool>,
+    // Render like github.com does, the flags above are ignored.
+    pub github: Option<bool>,
+}
+
+impl RenderOptions {
+    fn html_options(&self) -> HtmlOptions {
+        if self.github == Some(true) {
+            return HtmlOptions::github();
+        }
+        let mut options = HtmlOptions::new()
+            .sanitize(self.sanitize == Some(true))
+            .hide_title(self.hide_title == Some(true));
+        if self.asciidoctor == Some(true) {
+            options = options.profile(HtmlProfile::Asciidoctor);
+        }
+        if self.heading_ids == Some(true) {
+            options = options.heading_ids(SlugStrategy::default());
+        }
+        options
+    }
+}
+
+fn parse_document(source: &str, attributes: Option<&HashMap<String, String>>) -> Result<Document> {
+    let mut options = combine_sandbox::ParseOptions::new().recovery(RecoveryPolicy::default());
+    for (name, value) in attributes.into_iter().flatten() {
+        options = options.attribute(name.as_str(), value.as_str());
+    }
+    Document::parse_with_options(source, &options)
+        .map_err(|error| Error::from_reason(format!("{:#}", error)))
+}
+
+pub struct ParseTask {
+    source: String,
+    options: ParseOptions,
+}
+
+impl Task for ParseTask {
+    type Output = Value;
+    type JsValue = JsUnknown;
+
+    fn compute(&mut self) -> Result<Value> {
+        let document = parse_document(&self.source, self.options.attributes.as_ref())?;
+        Ok(to_json_ast(&document))
+    }
+
+    fn resolve(&mut self, env: Env, output: Value) -> Result<JsUnknown> {
+        env.to_js_value(&output)
+    }
+}
+
+pub struct RenderTask {
+    source: String,
+    options: RenderOptions,
+}
+
+impl Task for RenderTask {
+    type Output = String;
+    type JsValue = String;
+
+    fn compute(&mut self) -> Result<String> {
+        let document = parse_document(&self.source, self.options.attributes.as_ref())?;
+        Ok(render_with_options(&document, &self.options.html_options()))
+    }
+
+    fn resolve(&mut self, _: Env, output: String) -> Result<String> {
+        Ok(output)
+    }
+}
+
+// The JSON AST described by `schema/ast-v2.schema.json`.
+#[napi(ts_return_type = "Promise<Record<string, unknown>>")]
+pub fn parse(source: String, options: Option<ParseOptions>) -> AsyncTask<ParseTask> {
+    AsyncTask::new(ParseTask {
+        source,
+        options: options.unwrap_or_default(),
+    })
+}
+
+#[napi]
+pub fn render(source: String, options: Option<RenderOptions>) -> AsyncTask<RenderTask> {
+    AsyncTask::new(RenderTask {
+        source,
+        options: options.unwrap_or_default(),
+    })
+}
+
+#[napi]
+pub fn parse_sync(source: String, options: Option<ParseOptions>) -> Result<Value> {
+    ParseTask {
+        source,
+        options: options.unwrap_or_default(),
+    }
+    .compute()
+}
+
+#[napi]
+pub fn render_sync(source: String, options: Option<RenderOptions>) -> Result<String> {
+    RenderTask {
+        source,
+        options: options.unwrap_or_default(),
+    }
+    .compute()
+}
@@ -0,0 +1,20 @@
+const assert = require('node:assert')
+const { test } = require('node:test')
+const { parse, parseSync, render, renderSync } = require('..')
+
+test('render', async () => {
+  const html = await render('== Intro\n\nsome *bold* {product}', {
+    headingIds: true,
+    attributes: { product: 'Combine' },
+  })
+  assert.strictEqual(html, '<h2 id="_intro">Intro</h2>\n<p>some <strong>bold</strong> Combine</p>\n')
+  assert.strictEqual(renderSync('== Intro'), '<h2>Intro</h2>\n')
+})
+
+test('parse', async () => {
+  const ast = await parse('=broken\n\nok')
+  assert.strictEqual(ast.version, 2)
+  assert.strictEqual(ast.blocks.length, 3)
+  assert.strictEqual(ast.diagnostics.length, 1)
+  assert.deepStrictEqual(parseSync('=broken\n\nok'), ast)
+})
@@ -0,0 +1,137 @@
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::lines::LineIndex;
+use serde_json::{json, Value};
+
+// A diagnostic at a position of a file, what CI services show inline on pull requests.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Annotation {
+    pub path: String,
+    // 1-based, columns count characters.
+    pub line: usize,
+    pub column: usize,
+    pub severity: Severity,
+    pub message: String,
+    // Lint rule or other check that reported it.
+    pub check: String,
+}
+
+impl Annotation {
+    // `diagnostic` of the file at `path`, whose text is `source`.
+    pub fn from_diagnostic<P: Into<String>, C: Into<String>>(
+        path: P,
+        source: &str,
+        diagnostic: &Diagnostic,
+        check: C,
+    ) -> Self {
+        let (line, column) = LineIndex::new(source).line_column(diagnostic.span.start);
+        Annotation {
+            path: path.into(),
+            line,
+            column,
+            severity: diagnostic.severity,
+            message: diagnostic.message.clone(),
+            check: check.into(),
+        }
+    }
+}
+
+// GitHub Actions workflow commands, one `::error file=...,line=...::message` line per
+// annotation. Printed to stdout by a step they show up on the lines of the pull request diff.
+pub fn to_github(annotations: &[Annotation]) -> String {
+    annotations
+        .iter()
+        .map(|annotation| {
+            let command = match annotation.severity {
+                Severity::Info => "notice",
+                Severity::Warning => "warning",
+                Severity::Error => "error",
+            };
+            format!(
+                "::{} file={},line={},col={},title={}::{}\n",
+                command,
+                escape_property(&annotation.path),
+                annotation.line,
+                annotation.column,
+                escape_property(&annotation.check),
+                escape_data(&annotation.message)
+            )
+        })
+        .collect()
+}
+
+// GitLab Code Quality report, the JSON artifact of `artifacts:reports:codequality`.
+pub fn to_gitlab(annotations: &[Annotation]) -> Value {
+    annotations
+        .iter()
+        .map(|annotation| {
+            let severity = match annotation.severity {
+                Severity::Info => "info",
+                Severity::Warning => "minor",
+                Severity::Error => "major",
+            };
+            json!({
+                "description": annotation.message,
+                "check_name": annotation.check,
+                "fingerprint": fingerprint(annotation),
+                "severity": severity,
+                "location": {
+                    "path": annotation.path,
+                    "lines": { "begin": annotation.line },
+                },
+            })
+        })
+        .collect()
+}
+
+// Stable across runs and releases so GitLab can tell new findings from ones that were already
+// there, FNV-1a over the fields. The line is left out, edits above a finding don't make it new.
+fn fingerprint(annotation: &Annotation) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for field in [&annotation.path, &annotation.check, &annotation.message] {
+        for byte in field.bytes().chain(std::iter::once(0)) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    format!("{:016x}", hash)
+}
+
+fn escape_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(text: &str) -> String {
+    escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Span;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_annotations() {
+        let diagnostic = Diagnostic::warning("50% done,\nnot closed", Span { start: 6, end: 7 });
+        let annotation =
+            Annotation::from_diagnostic("docs/a,b.adoc", "first\nsecond", &diagnostic, "parse");
+        assert_eq!((annotation.line, annotation.column), (2, 1));
+        assert_eq!(
+            to_github(std::slice::from_ref(&annotation)),
+            "::warning file=docs/a%2Cb.adoc,line=2,col=1,title=parse::50%25 done,%0Anot closed\n"
+        );
+        let report = to_gitlab(std::slice::from_ref(&annotation));
+        assert_eq!(report[0]["severity"], "minor");
+        assert_eq!(report[0]["location"]["lines"]["begin"], 2);
+        let moved = Annotation {
+            line: 10,
+            ..annotation
+        };
+        assert_eq!(
+            to_gitlab(&[moved])[0]["fingerprint"],
+            report[0]["fingerprint"]
+        );
+    }
+}
@@ -0,0 +1,3 @@
+fn main() -> anyhow::Result<()> {
+    combine_sandbox::lsp::serve()
+}
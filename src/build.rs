@@ -0,0 +1,959 @@
+use crate::cache::ParseCache;
+use crate::config::Config;
+use crate::document::Document;
+use crate::options::ParseOptions;
+use crate::parallel;
+use crate::render::html::{self, escape, heading_ids, HtmlOptions};
+use crate::text::{block_text, inline_text};
+use crate::{Block, HeadingLevel, ListItem, SlugStrategy};
+use anyhow::{bail, Context, Result};
+use serde_json::json;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct BuildOptions {
+    pub html: HtmlOptions,
+    // Base options of every document. Includes resolve relative to each document.
+    pub parse: ParseOptions,
+    pub config: Config,
+    // File name of the generated index page, inside the output directory.
+    pub index: String,
+    // Deepest section level listed under each page in the index.
+    pub index_depth: u8,
+    // Deepest section level in the table of contents at the top of each page.
+    pub toc_depth: u8,
+    // Page template used instead of the built-in layout. Its `{{title}}`, `{{content}}`,
+    // `{{toc}}`, `{{pagination}}`, `{{index}}` and `{{root}}` placeholders are the page title,
+    // the rendered document, the table of contents, the previous and next links, the index
+    // page and the relative path to the output directory, e.g. for `{{root}}style.css`.
+    pub theme: Option<String>,
+    // File name of a search index written next to the index page, a JSON array with the
+    // `title`, `url` and plain text `body` of each page as tinysearch and lunr take it.
+    pub search_index: Option<String>,
+    // Documents parsed by earlier builds sharing the cache, unchanged ones aren't parsed again.
+    pub cache: Option<Arc<ParseCache>>,
+}
+
+impl Default for BuildOptions {
+    fn default() -> Self {
+        BuildOptions {
+            html: HtmlOptions::new().heading_ids(SlugStrategy::default()),
+            parse: ParseOptions::new(),
+            config: Config::default(),
+            index: "index.html".to_string(),
+            index_depth: 1,
+            toc_depth: 2,
+            theme: None,
+            search_index: None,
+            cache: None,
+        }
+    }
+}
+
+impl BuildOptions {
+    pub fn new() -> Self {
+        BuildOptions::default()
+    }
+
+    pub fn html(mut self, html: HtmlOptions) -> Self {
+        self.html = html;
+        self
+    }
+
+    pub fn parse(mut self, parse: ParseOptions) -> Self {
+        self.parse = parse;
+        self
+    }
+
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn index<S: Into<String>>(mut self, index: S) -> Self {
+        self.index = index.into();
+        self
+    }
+
+    pub fn index_depth(mut self, index_depth: u8) -> Self {
+        self.index_depth = index_depth;
+        self
+    }
+
+    pub fn toc_depth(mut self, toc_depth: u8) -> Self {
+        self.toc_depth = toc_depth;
+        self
+    }
+
+    pub fn theme<S: Into<String>>(mut self, theme: S) -> Self {
+        self.theme = Some(theme.into());
+        self
+    }
+
+    pub fn search_index<S: Into<String>>(mut self, search_index: S) -> Self {
+        self.search_index = Some(search_index.into());
+        self
+    }
+
+    pub fn cache(mut self, cache: Arc<ParseCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+}
+
+// A document of the site and the pages nested under it in the navigation.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct NavEntry {
+    // Relative to the site root.
+    pub source: PathBuf,
+    // Navigation label, the document title when empty.
+    pub label: String,
+    pub children: Vec<NavEntry>,
+}
+
+// Documents to render, relative to `root`, in navigation order.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Site {
+    root: PathBuf,
+    nav: Vec<NavEntry>,
+}
+
+impl Site {
+    // Every path becomes a top level page. Paths must be inside `root`.
+    pub fn from_paths<P: Into<PathBuf>>(root: P, paths: &[PathBuf]) -> Result<Self> {
+        let root = root.into();
+        let mut nav = vec![];
+        for path in paths {
+            let relative = path.strip_prefix(&root).unwrap_or(path);
+            if relative.is_absolute()
+                || relative
+                    .components()
+                    .any(|component| component == Component::ParentDir)
+            {
+                bail!("{} is outside {}", path.display(), root.display());
+            }
+            nav.push(NavEntry {
+                source: relative.to_path_buf(),
+                label: String::new(),
+                children: vec![],
+            });
+        }
+        Ok(Site { root, nav })
+    }
+
+    // Every .adoc file under `root` becomes a top level page, in path order. Files and
+    // directories whose name starts with `_` or `.` are left out, `_` marks partials that are
+    // only included.
+    pub fn from_dir<P: Into<PathBuf>>(root: P) -> Result<Self> {
+        let root = root.into();
+        let mut paths = vec![];
+        collect_pages(&root, &mut paths)?;
+        paths.sort();
+        Site::from_paths(root, &paths)
+    }
+
+    // A navigation manifest is an AsciiDoc file whose lists hold xrefs, like Antora's nav files:
+    //
+    //   * xref:index.adoc[Home]
+    //   * xref:guide/install.adoc[]
+    //   ** xref:guide/upgrade.adoc[Upgrading]
+    //
+    // Targets are relative to the manifest, which is the site root.
+    pub fn from_nav(manifest: &Path) -> Result<Self> {
+        let source = fs::read_to_string(manifest)
+            .with_context(|| format!("failed to read {}", manifest.display()))?;
+        let document = Document::parse(source.trim_end())
+            .with_context(|| format!("invalid manifest {}", manifest.display()))?;
+
+        let mut nav: Vec<NavEntry> = vec![];
+        for item in document.blocks().iter().flat_map(Block::list_items) {
+            let (children, level) = match item {
+                ListItem::Normal { children, level }
+                | ListItem::Check {
+                    children, level, ..
+                } => (children, *level),
+            };
+            let text = inline_text(children);
+            let xref = match find_xrefs(&text).into_iter().next() {
+                Some(xref) => xref,
+                None => bail!("nav item `{}` has no xref", text.trim()),
+            };
+            let (document, _) = split_target(&xref.target);
+            let entry = NavEntry {
+                source: PathBuf::from(document),
+                label: xref.text,
+                children: vec![],
+            };
+            insert_nav(&mut nav, u32::from(level.as_u8()), entry);
+        }
+        let root = manifest.parent().unwrap_or(Path::new("")).to_path_buf();
+        Ok(Site { root, nav })
+    }
+
+    pub fn nav(&self) -> &[NavEntry] {
+        &self.nav
+    }
+
+    fn sources(&self) -> Vec<&Path> {
+        fn collect<'a>(entries: &'a [NavEntry], sources: &mut Vec<&'a Path>) {
+            for entry in entries {
+                sources.push(&entry.source);
+                collect(&entry.children, sources);
+            }
+        }
+        let mut sources = vec![];
+        collect(&self.nav, &mut sources);
+        sources
+    }
+}
+
+fn collect_pages(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
+    let entries =
+        fs::read_dir(dir).with_context(|| format!("failed to read directory {}", dir.display()))?;
+    for entry in entries {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with(['_', '.']) {
+            continue;
+        }
+        let path = entry.path();
+        if path.is_dir() {
+            collect_pages(&path, paths)?;
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "adoc")
+        {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+// Nest `entry` under the last entry of each level above `level`.
+fn insert_nav(siblings: &mut Vec<NavEntry>, level: u32, entry: NavEntry) {
+    if level > 1 && !siblings.is_empty() {
+        insert_nav(&mut siblings.last_mut().unwrap().children, level - 1, entry);
+    } else {
+        siblings.push(entry);
+    }
+}
+
+// An xref whose target is not one of the documents being built.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct UnresolvedXref {
+    pub source: PathBuf,
+    pub target: String,
+}
+
+impl fmt::Display for UnresolvedXref {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: xref to `{}` is not part of the build",
+            self.source.display(),
+            self.target
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct BuildReport {
+    // Output files, relative to the output directory, index last.
+    pub written: Vec<PathBuf>,
+    pub unresolved: Vec<UnresolvedXref>,
+}
+
+struct Page {
+    source: PathBuf,
+    output: PathBuf,
+    title: String,
+    // Level, title and id of the sections listed in the index or the table of contents.
+    sections: Vec<(u8, String, String)>,
+    body: String,
+    // Plain text for the search index.
+    text: String,
+}
+
+// Render every document of `site` to HTML under `out_dir`, keeping the directory layout.
+// Xrefs between documents point at the rendered pages, and an index page lists the pages with
+// their sections in navigation order. Each page links to the pages before and after it in that
+// order.
+pub fn build(site: &Site, out_dir: &Path, options: &BuildOptions) -> Result<BuildReport> {
+    let mut sources = site.sources();
+    let mut seen = HashSet::new();
+    sources.retain(|source| seen.insert(*source));
+    // Pages are rendered on all cores with the `parallel` feature.
+    let rendered = parallel::map(&sources, |source| {
+        render_page(site, source, options)
+            .with_context(|| format!("failed to build {}", source.display()))
+    });
+
+    let mut pages = BTreeMap::new();
+    for (source, page) in sources.iter().zip(rendered) {
+        let page = page?;
+        if page.output == Path::new(&options.index) {
+            bail!(
+                "{} would overwrite the index page {}, choose another index name",
+                source.display(),
+                options.index
+            );
+        }
+        pages.insert(source.to_path_buf(), page);
+    }
+
+    let titles = pages
+        .iter()
+        .map(|(source, page)| (source.clone(), page.title.clone()))
+        .collect::<BTreeMap<_, _>>();
+    let mut report = BuildReport::default();
+    for page in pages.values() {
+        let (body, unresolved) = resolve_xrefs(&page.body, &page.source, &titles);
+        report
+            .unresolved
+            .extend(unresolved.into_iter().map(|target| UnresolvedXref {
+                source: page.source.clone(),
+                target,
+            }));
+        let position = sources
+            .iter()
+            .position(|source| *source == page.source)
+            .unwrap();
+        let previous = position
+            .checked_sub(1)
+            .map(|previous| &pages[sources[previous]]);
+        let next = sources.get(position + 1).map(|next| &pages[*next]);
+        let html = page_html(page, &body, previous, next, options)
+            .with_context(|| format!("failed to build {}", page.source.display()))?;
+        write(out_dir, &page.output, &html)?;
+        report.written.push(page.output.clone());
+    }
+
+    if let Some(search_index) = &options.search_index {
+        let entries = sources
+            .iter()
+            .map(|source| {
+                let page = &pages[*source];
+                json!({
+                    "title": page.title,
+                    "url": path_to_url(&page.output),
+                    "body": page.text,
+                })
+            })
+            .collect::<Vec<_>>();
+        write(
+            out_dir,
+            Path::new(search_index),
+            &serde_json::to_string(&entries)?,
+        )?;
+        report.written.push(PathBuf::from(search_index));
+    }
+
+    let mut index = String::from("<nav class=\"index\">\n");
+    index_entries(site.nav(), &pages, options.index_depth, &mut index);
+    index.push_str("</nav>\n");
+    write(
+        out_dir,
+        Path::new(&options.index),
+        &layout("Index", &index, None),
+    )?;
+    report.written.push(PathBuf::from(&options.index));
+    Ok(report)
+}
+
+fn render_page(site: &Site, source: &Path, options: &BuildOptions) -> Result<Page> {
+    let path = site.root.join(source);
+    let text =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    let parse = options.config.apply(options.parse.clone(), base_dir);
+    let document = match &options.cache {
+        Some(cache) => cache.parse(&path, &text, &parse)?,
+        None => Document::parse_with_options(&text, &parse)?.into_shared(),
+    };
+
+    let blocks = document.blocks();
+    let strategy = options.html.heading_ids.clone().unwrap_or_default();
+    let generated = heading_ids(blocks, &strategy);
+    let mut title = None;
+    let mut sections = vec![];
+    let depth = options.index_depth.max(options.toc_depth);
+    for block in blocks {
+        if let Block::Heading {
+            level, children, ..
+        } = block
+        {
+            let text = inline_text(children);
+            if *level == HeadingLevel::Title {
+                title.get_or_insert(text);
+            } else if level.as_u8() <= depth {
+                let id = block
+                    .id()
+                    .map(str::to_string)
+                    .or_else(|| generated.get(&(block as *const Block)).cloned())
+                    .unwrap_or_default();
+                sections.push((level.as_u8(), text, id));
+            }
+        }
+    }
+    let mut html = options.html.clone();
+    html.heading_ids = Some(strategy);
+    Ok(Page {
+        source: source.to_path_buf(),
+        output: output_path(source),
+        title: title.unwrap_or_else(|| source.display().to_string()),
+        sections,
+        body: html::render_with_options(&document, &html),
+        text: blocks
+            .iter()
+            .map(|block| block_text(block).trim().to_string())
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n"),
+    })
+}
+
+// The page around the rendered `body`, in the theme when there is one.
+fn page_html(
+    page: &Page,
+    body: &str,
+    previous: Option<&Page>,
+    next: Option<&Page>,
+    options: &BuildOptions,
+) -> Result<String> {
+    let root = "../".repeat(page.output.components().count() - 1);
+    let index_href = format!("{}{}", root, options.index);
+
+    let sections = page
+        .sections
+        .iter()
+        .filter(|(level, ..)| *level <= options.toc_depth)
+        .cloned()
+        .collect::<Vec<_>>();
+    let mut toc = String::new();
+    if !sections.is_empty() {
+        toc.push_str("<nav class=\"toc\">\n");
+        section_entries(&sections, "", &mut toc);
+        toc.push_str("</nav>\n");
+    }
+
+    let mut pagination = String::new();
+    if previous.is_some() || next.is_some() {
+        pagination.push_str("<nav class=\"pagination\">\n");
+        for (rel, other) in [("prev", previous), ("next", next)] {
+            if let Some(other) = other {
+                pagination.push_str(&format!(
+                    "<a rel=\"{}\" href=\"{}{}\">{}</a>\n",
+                    rel,
+                    escape(&root),
+                    escape(&path_to_url(&other.output)),
+                    escape(&other.title)
+                ));
+            }
+        }
+        pagination.push_str("</nav>\n");
+    }
+
+    match &options.theme {
+        Some(theme) => fill_theme(theme, |key| match key {
+            "title" => Some(escape(&page.title)),
+            "content" => Some(body.to_string()),
+            "toc" => Some(toc.clone()),
+            "pagination" => Some(pagination.clone()),
+            "index" => Some(escape(&index_href)),
+            "root" => Some(escape(&root)),
+            _ => None,
+        }),
+        None => Ok(layout(
+            &page.title,
+            &format!("{}{}{}", toc, body, pagination),
+            Some(&index_href),
+        )),
+    }
+}
+
+// Replace the `{{key}}` placeholders of `theme`, a key `value` doesn't know is an error so typos
+// don't end up in the pages.
+fn fill_theme<F: Fn(&str) -> Option<String>>(theme: &str, value: F) -> Result<String> {
+    let mut out = String::with_capacity(theme.len());
+    let mut rest = theme;
+    while let Some(start) = rest.find("{{") {
+        let end = match rest[start..].find("}}") {
+            Some(end) => start + end,
+            None => break,
+        };
+        let key = rest[start + 2..end].trim();
+        match value(key) {
+            Some(value) => {
+                out.push_str(&rest[..start]);
+                out.push_str(&value);
+            }
+            None => bail!("unknown placeholder `{{{{{}}}}}` in theme", key),
+        }
+        rest = &rest[end + 2..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn output_path(source: &Path) -> PathBuf {
+    source.with_extension("html")
+}
+
+fn write(out_dir: &Path, relative: &Path, html: &str) -> Result<()> {
+    let path = out_dir.join(relative);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    fs::write(&path, html).with_context(|| format!("failed to write {}", path.display()))
+}
+
+pub(crate) fn layout(title: &str, body: &str, index_href: Option<&str>) -> String {
+    let nav = match index_href {
+        Some(href) => format!(
+            "<nav class=\"site\"><a href=\"{}\">Index</a></nav>\n",
+            escape(href)
+        ),
+        None => String::new(),
+    };
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n{}{}</body>\n</html>\n",
+        escape(title),
+        nav,
+        body
+    )
+}
+
+fn index_entries(
+    entries: &[NavEntry],
+    pages: &BTreeMap<PathBuf, Page>,
+    index_depth: u8,
+    out: &mut String,
+) {
+    if entries.is_empty() {
+        return;
+    }
+    out.push_str("<ul>\n");
+    for entry in entries {
+        let page = &pages[&entry.source];
+        let href = path_to_url(&page.output);
+        let label = if entry.label.is_empty() {
+            &page.title
+        } else {
+            &entry.label
+        };
+        out.push_str(&format!(
+            "<li><a href=\"{}\">{}</a>\n",
+            escape(&href),
+            escape(label)
+        ));
+        let sections = page
+            .sections
+            .iter()
+            .filter(|(level, ..)| *level <= index_depth)
+            .cloned()
+            .collect::<Vec<_>>();
+        section_entries(&sections, &href, out);
+        index_entries(&entry.children, pages, index_depth, out);
+        out.push_str("</li>\n");
+    }
+    out.push_str("</ul>\n");
+}
+
+// Nested lists following the section levels, which may skip levels.
+fn section_entries(sections: &[(u8, String, String)], href: &str, out: &mut String) {
+    let mut open: Vec<u8> = vec![];
+    for (level, title, id) in sections {
+        while open.last().is_some_and(|last| last > level) {
+            out.push_str("</li>\n</ul>\n");
+            open.pop();
+        }
+        if open.last() == Some(level) {
+            out.push_str("</li>\n");
+        } else {
+            out.push_str("<ul class=\"sections\">\n");
+            open.push(*level);
+        }
+        out.push_str(&format!(
+            "<li><a href=\"{}#{}\">{}</a>",
+            escape(href),
+            escape(id),
+            escape(title)
+        ));
+    }
+    for _ in open {
+        out.push_str("</li>\n</ul>\n");
+    }
+}
+
+fn path_to_url(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+struct Xref {
+    // Byte range of the whole reference.
+    start: usize,
+    end: usize,
+    target: String,
+    text: String,
+}
+
+// `xref:target[text]` macros and `<<target,text>>` references in `text`. In HTML output the
+// angle brackets of the latter are escaped.
+fn find_xrefs(text: &str) -> Vec<Xref> {
+    let mut xrefs = vec![];
+    let mut offset = 0;
+    while offset < text.len() {
+        let rest = &text[offset..];
+        let found = [
+            ("xref:", "[", "]"),
+            ("<<", ",", ">>"),
+            ("&lt;&lt;", ",", "&gt;&gt;"),
+        ]
+        .iter()
+        .filter_map(|(open, separator, close)| {
+            rest.find(open)
+                .map(|index| (index, *open, *separator, *close))
+        })
+        .min_by_key(|(index, ..)| *index);
+        let (index, open, separator, close) = match found {
+            Some(found) => found,
+            None => break,
+        };
+        let start = offset + index;
+        let body_start = start + open.len();
+        let body = &text[body_start..];
+        let parsed = body.find(close).and_then(|close_index| {
+            let inner = &body[..close_index];
+            let (target, label) = if open == "xref:" {
+                let (target, label) = inner.split_once(separator)?;
+                (target, label)
+            } else {
+                inner.split_once(separator).unwrap_or((inner, ""))
+            };
+            let valid = !target.is_empty()
+                && !target
+                    .chars()
+                    .any(|c| c.is_whitespace() || c == '<' || c == '>' || c == '"');
+            valid.then(|| Xref {
+                start,
+                end: body_start + close_index + close.len(),
+                target: target.to_string(),
+                text: label.trim().to_string(),
+            })
+        });
+        match parsed {
+            Some(xref) => {
+                offset = xref.end;
+                xrefs.push(xref);
+            }
+            None => offset = body_start,
+        }
+    }
+    xrefs
+}
+
+// The document part of an xref target and the anchor, `guide/setup#install` and
+// `guide/setup.adoc#install` both name `guide/setup.adoc`. Targets without either name an
+// anchor of the same document.
+fn split_target(target: &str) -> (String, String) {
+    let (document, id) = target.split_once('#').unwrap_or((target, ""));
+    if !target.contains('#') && !document.ends_with(".adoc") {
+        return (String::new(), document.to_string());
+    }
+    let document = if document.ends_with(".adoc") || document.is_empty() {
+        document.to_string()
+    } else {
+        format!("{}.adoc", document)
+    };
+    (document, id.to_string())
+}
+
+// Turn the xrefs of a rendered page into links, skipping preformatted blocks. Targets in other
+// documents are relative to `source`, the returned list holds those that are not being built.
+pub(crate) fn resolve_xrefs(
+    html: &str,
+    source: &Path,
+    titles: &BTreeMap<PathBuf, String>,
+) -> (String, Vec<String>) {
+    let mut out = String::with_capacity(html.len());
+    let mut unresolved = vec![];
+    let mut rest = html;
+    while !rest.is_empty() {
+        let (text, pre) = match rest.find("<pre") {
+            Some(start) => {
+                let end = rest[start..]
+                    .find("</pre>")
+                    .map_or(rest.len(), |end| start + end + "</pre>".len());
+                (&rest[..start], &rest[start..end])
+            }
+            None => (rest, ""),
+        };
+        let mut last = 0;
+        for xref in find_xrefs(text) {
+            out.push_str(&text[last..xref.start]);
+            last = xref.end;
+            let (document, id) = split_target(&xref.target);
+            let href = if document.is_empty() {
+                format!("#{}", id)
+            } else {
+                let dir = source.parent().unwrap_or(Path::new(""));
+                let resolved = normalize(&dir.join(&document));
+                match titles.get(&resolved) {
+                    Some(_) => {}
+                    None => unresolved.push(xref.target.clone()),
+                }
+                let mut href = path_to_url(&output_path(Path::new(&document)));
+                if !id.is_empty() {
+                    href.push('#');
+                    href.push_str(&id);
+                }
+                href
+            };
+            let label = if !xref.text.is_empty() {
+                xref.text.clone()
+            } else if id.is_empty() {
+                let dir = source.parent().unwrap_or(Path::new(""));
+                titles
+                    .get(&normalize(&dir.join(&document)))
+                    .cloned()
+                    .unwrap_or_else(|| xref.target.clone())
+            } else {
+                format!("[{}]", id)
+            };
+            // The label comes from already escaped HTML.
+            out.push_str(&format!("<a href=\"{}\">{}</a>", escape(&href), label));
+        }
+        out.push_str(&text[last..]);
+        out.push_str(pre);
+        rest = &rest[text.len() + pre.len()..];
+    }
+    (out, unresolved)
+}
+
+// Resolve `.` and `..` without touching the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn site(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("combine-sandbox-build-{}", name));
+        let _ = fs::remove_dir_all(&root);
+        for (path, source) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, source).unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn test_build() {
+        let root = site(
+            "nav",
+            &[
+                (
+                    "nav.adoc",
+                    "* xref:start.adoc[Home]\n* xref:guide/install.adoc[]\n** xref:guide/upgrade.adoc[Upgrading]",
+                ),
+                (
+                    "start.adoc",
+                    "= Start\n\nsee xref:guide/install.adoc#_linux[] and xref:missing.adoc[gone]\n\n== Next",
+                ),
+                ("guide/install.adoc", "= Install\n\n== Linux\n\nback to <<../start.adoc,home>>"),
+                ("guide/upgrade.adoc", "= Upgrade\n\nfrom xref:install.adoc[]"),
+            ],
+        );
+        let site = Site::from_nav(&root.join("nav.adoc")).unwrap();
+        assert_eq!(
+            site.nav()[1].children[0].source,
+            PathBuf::from("guide/upgrade.adoc")
+        );
+
+        let out = root.join("out");
+        let report = build(&site, &out, &BuildOptions::new()).unwrap();
+        assert_eq!(
+            report.written,
+            vec![
+                PathBuf::from("guide/install.html"),
+                PathBuf::from("guide/upgrade.html"),
+                PathBuf::from("start.html"),
+                PathBuf::from("index.html"),
+            ]
+        );
+        assert_eq!(
+            report
+                .unresolved
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["start.adoc: xref to `missing.adoc` is not part of the build"]
+        );
+
+        let start = fs::read_to_string(out.join("start.html")).unwrap();
+        assert_eq!(
+            start.contains("see <a href=\"guide/install.html#_linux\">[_linux]</a> and <a href=\"missing.html\">gone</a>"),
+            true,
+            "{}",
+            start
+        );
+        let upgrade = fs::read_to_string(out.join("guide/upgrade.html")).unwrap();
+        assert_eq!(
+            upgrade.contains("from <a href=\"install.html\">Install</a>"),
+            true
+        );
+        assert_eq!(
+            upgrade.contains("<a href=\"../index.html\">Index</a>"),
+            true
+        );
+        let install = fs::read_to_string(out.join("guide/install.html")).unwrap();
+        assert_eq!(install.contains("<a href=\"../start.html\">home</a>"), true);
+
+        let index = fs::read_to_string(out.join("index.html")).unwrap();
+        assert_eq!(
+            index.contains("<li><a href=\"start.html\">Home</a>\n<ul class=\"sections\">\n<li><a href=\"start.html#_next\">Next</a></li>\n</ul>\n</li>"),
+            true,
+            "{}",
+            index
+        );
+        assert_eq!(
+            index.contains("<li><a href=\"guide/install.html\">Install</a>"),
+            true
+        );
+    }
+
+    #[test]
+    fn test_build_with_cache() {
+        let root = site("cache", &[("a.adoc", "= A"), ("b.adoc", "= B")]);
+        let site = Site::from_paths(&root, &[root.join("a.adoc"), root.join("b.adoc")]).unwrap();
+        let cache = Arc::new(ParseCache::new());
+        let options = BuildOptions::new().cache(cache.clone());
+        let out = root.join("out");
+
+        build(&site, &out, &options).unwrap();
+        fs::write(root.join("b.adoc"), "= Changed").unwrap();
+        build(&site, &out, &options).unwrap();
+        assert_eq!(cache.len(), 2);
+        let b = fs::read_to_string(out.join("b.html")).unwrap();
+        assert_eq!(b.contains("<title>Changed</title>"), true);
+    }
+
+    #[test]
+    fn test_build_site() {
+        let root = site(
+            "site",
+            &[
+                (
+                    "a.adoc",
+                    "= A\n\n== One\n\ntext of a\n\n=== Deep\n\n==== Deeper",
+                ),
+                ("b/c.adoc", "= C\n\nsee xref:../a.adoc[]"),
+                ("_partial.adoc", "included only"),
+            ],
+        );
+        let site = Site::from_dir(&root).unwrap();
+        assert_eq!(
+            site.sources(),
+            vec![Path::new("a.adoc"), Path::new("b/c.adoc")]
+        );
+
+        let options = BuildOptions::new()
+            .theme("<title>{{title}}</title>\n<link href=\"{{root}}site.css\">\n{{toc}}{{content}}{{pagination}}")
+            .search_index("search.json");
+        let out = root.join("out");
+        let report = build(&site, &out, &options).unwrap();
+        assert_eq!(
+            report.written,
+            vec![
+                PathBuf::from("a.html"),
+                PathBuf::from("b/c.html"),
+                PathBuf::from("search.json"),
+                PathBuf::from("index.html"),
+            ]
+        );
+
+        let a = fs::read_to_string(out.join("a.html")).unwrap();
+        assert_eq!(
+            a.starts_with("<title>A</title>\n<link href=\"site.css\">\n<nav class=\"toc\">\n<ul class=\"sections\">\n<li><a href=\"#_one\">One</a><ul class=\"sections\">\n<li><a href=\"#_deep\">Deep</a></li>\n</ul>\n</li>\n</ul>\n</nav>\n"),
+            true,
+            "{}",
+            a
+        );
+        assert_eq!(
+            a.ends_with(
+                "<nav class=\"pagination\">\n<a rel=\"next\" href=\"b/c.html\">C</a>\n</nav>\n"
+            ),
+            true
+        );
+        let c = fs::read_to_string(out.join("b/c.html")).unwrap();
+        assert_eq!(c.contains("<link href=\"../site.css\">"), true);
+        assert_eq!(c.contains("<a rel=\"prev\" href=\"../a.html\">A</a>"), true);
+
+        let search: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(out.join("search.json")).unwrap()).unwrap();
+        assert_eq!(
+            search[0],
+            json!({
+                "title": "A",
+                "url": "a.html",
+                "body": "A\nOne\ntext of a\nDeep\nDeeper",
+            })
+        );
+        assert_eq!(search[1]["url"], "b/c.html");
+    }
+
+    #[test]
+    fn test_fill_theme() {
+        let value = |key: &str| (key == "title").then(|| "T".to_string());
+        assert_eq!(
+            fill_theme("<h1>{{ title }}</h1>", value).unwrap(),
+            "<h1>T</h1>"
+        );
+        assert_eq!(fill_theme("{{titel}}", value).is_err(), true);
+    }
+
+    #[test]
+    fn test_from_paths() {
+        let site = Site::from_paths("/docs", &[PathBuf::from("/docs/a/b.adoc")]).unwrap();
+        assert_eq!(site.nav()[0].source, PathBuf::from("a/b.adoc"));
+        assert_eq!(
+            Site::from_paths("/docs", &[PathBuf::from("/other/c.adoc")]).is_err(),
+            true
+        );
+    }
+
+    #[test]
+    fn test_split_target() {
+        let split = |target: &str| split_target(target);
+        assert_eq!(split("_intro"), (String::new(), "_intro".to_string()));
+        assert_eq!(
+            split("setup#_x"),
+            ("setup.adoc".to_string(), "_x".to_string())
+        );
+        assert_eq!(
+            split("setup.adoc"),
+            ("setup.adoc".to_string(), String::new())
+        );
+    }
+}
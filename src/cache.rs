@@ -0,0 +1,127 @@
+use crate::document::{ArcDocument, Document};
+use crate::options::ParseOptions;
+use crate::preprocess::{preprocess, Preprocessed};
+use crate::BlockOptions;
+use anyhow::Result;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+// Documents parsed by earlier builds, so a rebuild only parses the files that changed. Includes
+// are expanded every time, that's cheap, and an entry is reused while the expanded text and
+// attributes stay the same. A change to an included file reparses every document including it.
+//
+// Entries are keyed by path and reused only with the same options shaping the blocks. Whether
+// recovery is on is part of the key but its policy isn't, call `clear` when the policy changes.
+#[derive(Default)]
+pub struct ParseCache {
+    entries: Mutex<HashMap<PathBuf, (u64, ArcDocument)>>,
+}
+
+impl ParseCache {
+    pub fn new() -> Self {
+        ParseCache::default()
+    }
+
+    // `source`, read from `path`, parsed with `options`, or the document parsed from the same
+    // text before.
+    pub fn parse(&self, path: &Path, source: &str, options: &ParseOptions) -> Result<ArcDocument> {
+        let preprocessed = preprocess(source, options)?;
+        let hash = content_hash(&preprocessed, options);
+        if let Some((cached, document)) = self.entries.lock().unwrap().get(path) {
+            if *cached == hash {
+                return Ok(document.clone());
+            }
+        }
+        let document = Document::parse_preprocessed(preprocessed, options)?.into_shared();
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), (hash, document.clone()));
+        Ok(document)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+fn content_hash(preprocessed: &Preprocessed, options: &ParseOptions) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    preprocessed.source.hash(&mut hasher);
+    preprocessed.attributes.hash(&mut hasher);
+    options.recovery.is_some().hash(&mut hasher);
+    BlockOptions::from(options).hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::HeadingOverflow;
+    use crate::{HeadingLevel, InMemoryResolver};
+    use pretty_assertions::assert_eq;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_parse_cache() {
+        let mut resolver = InMemoryResolver::new();
+        resolver.insert("part.adoc", "from the part");
+        let options = ParseOptions::new().include_resolver(resolver);
+        let cache = ParseCache::new();
+        let path = Path::new("book.adoc");
+        let source = "= Book\n\ninclude::part.adoc[]\n";
+
+        let first = cache.parse(path, source, &options).unwrap();
+        let second = cache.parse(path, source, &options).unwrap();
+        assert_eq!(ArcDocument::ptr_eq(&first, &second), true);
+
+        let changed = cache.parse(path, "= Book\n", &options).unwrap();
+        assert_eq!(ArcDocument::ptr_eq(&first, &changed), false);
+        assert_eq!(cache.len(), 1);
+
+        let mut resolver = InMemoryResolver::new();
+        resolver.insert("part.adoc", "edited part");
+        let options = ParseOptions {
+            include_resolver: Some(Arc::new(resolver)),
+            ..options
+        };
+        let included = cache.parse(path, source, &options).unwrap();
+        assert_eq!(ArcDocument::ptr_eq(&first, &included), false);
+        assert_eq!(
+            included.blocks().last(),
+            Document::parse_with_options(source, &options)
+                .unwrap()
+                .blocks()
+                .last()
+        );
+    }
+
+    #[test]
+    fn test_parse_cache_options() {
+        let cache = ParseCache::new();
+        let path = Path::new("deep.adoc");
+        let source = "====== Deep\n";
+        let lenient = ParseOptions::new().heading_overflow(HeadingOverflow::Lenient);
+        let compat = ParseOptions::new().heading_overflow(HeadingOverflow::Compat);
+
+        let first = cache.parse(path, source, &lenient).unwrap();
+        let second = cache.parse(path, source, &compat).unwrap();
+        assert_eq!(ArcDocument::ptr_eq(&first, &second), false);
+        assert_eq!(first.blocks()[0].heading_level(), None);
+        assert_eq!(
+            second.blocks()[0].heading_level(),
+            Some(&HeadingLevel::Level4)
+        );
+    }
+}
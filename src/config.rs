@@ -0,0 +1,304 @@
+use crate::include::FileSystemResolver;
+use crate::lint::LintConfig;
+use crate::options::{HeadingOverflow, ParseOptions};
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+// Looked up in this order in each directory. Both are TOML, e.g.
+//
+//   safe-mode = "safe"
+//   include-paths = ["partials"]
+//   tab-size = 8
+//
+//   [attributes]
+//   product = "Sandbox"
+//
+//   [lint]
+//   trailing-whitespace = "off"
+//
+//   [output]
+//   format = "html-github"
+//   width = 100
+pub const CONFIG_FILE_NAMES: [&str; 2] = ["asciidoc.toml", ".asciidocrc"];
+
+// How much of the file system includes may reach, after Asciidoctor's safe modes.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub enum SafeMode {
+    // Any file.
+    Unsafe,
+    // Files inside the project directory.
+    Safe,
+    Server,
+    // No includes at all, the directives are left as written.
+    Secure,
+}
+
+impl SafeMode {
+    pub fn from_name(name: &str) -> Option<SafeMode> {
+        match name {
+            "unsafe" => Some(SafeMode::Unsafe),
+            "safe" => Some(SafeMode::Safe),
+            "server" => Some(SafeMode::Server),
+            "secure" => Some(SafeMode::Secure),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct OutputConfig {
+    // Name of a render format, as the CLI spells it.
+    pub format: Option<String>,
+    // Paragraph fill width of the formatter.
+    pub width: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    // Directory holding the config file, relative paths in the file resolve against it.
+    pub dir: PathBuf,
+    pub attributes: BTreeMap<String, String>,
+    pub safe_mode: Option<SafeMode>,
+    pub include_paths: Vec<PathBuf>,
+    // See `ParseOptions::tab_size`.
+    pub tab_size: Option<usize>,
+    // See `ParseOptions::triple_backtick_code`.
+    pub triple_backtick_code: bool,
+    // See `ParseOptions::heading_overflow`.
+    pub heading_overflow: Option<HeadingOverflow>,
+    pub lint: LintConfig,
+    pub output: OutputConfig,
+}
+
+impl Config {
+    // Nearest config file in `start` or one of its ancestors, the defaults when there is none.
+    pub fn discover(start: &Path) -> Result<Config> {
+        for dir in start.ancestors() {
+            for name in CONFIG_FILE_NAMES.iter() {
+                let path = dir.join(name);
+                if path.is_file() {
+                    return Config::load(&path);
+                }
+            }
+        }
+        Ok(Config::default())
+    }
+
+    pub fn load(path: &Path) -> Result<Config> {
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        Config::parse(&source, dir).with_context(|| format!("invalid config {}", path.display()))
+    }
+
+    pub fn parse<P: Into<PathBuf>>(source: &str, dir: P) -> Result<Config> {
+        let table = source.parse::<Table>()?;
+        let mut config = Config {
+            dir: dir.into(),
+            ..Config::default()
+        };
+        for (key, value) in &table {
+            match key.as_str() {
+                "safe-mode" => {
+                    let name = string(key, value)?;
+                    config.safe_mode = match SafeMode::from_name(name) {
+                        Some(mode) => Some(mode),
+                        None => bail!("unknown safe mode `{}`", name),
+                    };
+                }
+                "include-paths" => {
+                    for path in array(key, value)? {
+                        config
+                            .include_paths
+                            .push(config.dir.join(string(key, path)?));
+                    }
+                }
+                "tab-size" => match value.as_integer() {
+                    Some(size) if size >= 0 => config.tab_size = Some(size as usize),
+                    _ => bail!("`tab-size` must be a non-negative integer"),
+                },
+                "triple-backtick-code" => match value.as_bool() {
+                    Some(enabled) => config.triple_backtick_code = enabled,
+                    None => bail!("`triple-backtick-code` must be a boolean"),
+                },
+                "heading-overflow" => {
+                    let name = string(key, value)?;
+                    config.heading_overflow = match HeadingOverflow::from_name(name) {
+                        Some(mode) => Some(mode),
+                        None => bail!("unknown heading overflow mode `{}`", name),
+                    };
+                }
+                "attributes" => {
+                    for (name, value) in section(key, value)? {
+                        let value = match value {
+                            Value::String(value) => value.clone(),
+                            Value::Boolean(true) => String::new(),
+                            value => value.to_string(),
+                        };
+                        config.attributes.insert(name.clone(), value);
+                    }
+                }
+                "lint" => {
+                    for (rule, level) in section(key, value)? {
+                        let setting = format!("{}={}", rule, string(rule, level)?);
+                        config.lint = config.lint.set(&setting)?;
+                    }
+                }
+                "output" => {
+                    for (name, value) in section(key, value)? {
+                        match name.as_str() {
+                            "format" => config.output.format = Some(string(name, value)?.into()),
+                            "width" => match value.as_integer() {
+                                Some(width) if width > 0 => {
+                                    config.output.width = Some(width as usize)
+                                }
+                                _ => bail!("`output.width` must be a positive integer"),
+                            },
+                            _ => bail!("unknown key `output.{}`", name),
+                        }
+                    }
+                }
+                _ => bail!("unknown key `{}`", key),
+            }
+        }
+        Ok(config)
+    }
+
+    // `options` with the configured attributes and include resolution for a document in
+    // `base_dir`. Attributes already set on `options` win.
+    pub fn apply(&self, mut options: ParseOptions, base_dir: &Path) -> ParseOptions {
+        for (name, value) in &self.attributes {
+            options
+                .attributes
+                .entry(name.clone())
+                .or_insert_with(|| value.clone());
+        }
+        if let Some(tab_size) = self.tab_size {
+            options.tab_size = tab_size;
+        }
+        if self.triple_backtick_code {
+            options.triple_backtick_code = true;
+        }
+        if let Some(mode) = self.heading_overflow {
+            options.heading_overflow = mode;
+        }
+        let mode = self.safe_mode.unwrap_or(SafeMode::Unsafe);
+        if mode == SafeMode::Secure {
+            options.include_resolver = None;
+            return options;
+        }
+        let mut resolver = FileSystemResolver::new(base_dir);
+        for path in &self.include_paths {
+            resolver = resolver.search_path(path);
+        }
+        if mode > SafeMode::Unsafe {
+            // Without a config file the project is the document's own directory.
+            let jail = if self.dir.as_os_str().is_empty() {
+                base_dir
+            } else {
+                &self.dir
+            };
+            resolver = resolver.jail(jail);
+        }
+        options.include_resolver(resolver)
+    }
+}
+
+fn string<'a>(key: &str, value: &'a Value) -> Result<&'a str> {
+    match value.as_str() {
+        Some(value) => Ok(value),
+        None => bail!("`{}` must be a string", key),
+    }
+}
+
+fn array<'a>(key: &str, value: &'a Value) -> Result<&'a Vec<Value>> {
+    match value.as_array() {
+        Some(value) => Ok(value),
+        None => bail!("`{}` must be an array", key),
+    }
+}
+
+fn section<'a>(key: &str, value: &'a Value) -> Result<&'a Table> {
+    match value.as_table() {
+        Some(value) => Ok(value),
+        None => bail!("`[{}]` must be a table", key),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostic::Severity;
+    use crate::lint::LintRule;
+    use crate::Document;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parse() {
+        let config = Config::parse(
+            "safe-mode = \"secure\"\ninclude-paths = [\"partials\"]\ntab-size = 8\ntriple-backtick-code = true\nheading-overflow = \"compat\"\n\n[attributes]\nproduct = \"Sandbox\"\nsectnums = true\nversion = 2\n\n[lint]\ntrailing-whitespace = \"error\"\n\n[output]\nformat = \"html-github\"\nwidth = 80\n",
+            "/project",
+        )
+        .unwrap();
+        assert_eq!(config.safe_mode, Some(SafeMode::Secure));
+        assert_eq!(
+            config.include_paths,
+            vec![PathBuf::from("/project/partials")]
+        );
+        assert_eq!(config.tab_size, Some(8));
+        assert_eq!(config.triple_backtick_code, true);
+        assert_eq!(config.heading_overflow, Some(HeadingOverflow::Compat));
+        assert_eq!(config.attributes["product"], "Sandbox");
+        assert_eq!(config.attributes["sectnums"], "");
+        assert_eq!(config.attributes["version"], "2");
+        assert_eq!(
+            config.lint.severity(LintRule::TrailingWhitespace),
+            Some(Severity::Error)
+        );
+        assert_eq!(config.output.format.as_deref(), Some("html-github"));
+        assert_eq!(config.output.width, Some(80));
+
+        assert_eq!(Config::parse("safe-mode = \"lax\"", "").is_err(), true);
+        assert_eq!(
+            Config::parse("[lint]\nno-such-rule = \"off\"", "").is_err(),
+            true
+        );
+        assert_eq!(Config::parse("colour = true", "").is_err(), true);
+        assert_eq!(Config::parse("tab-size = -1", "").is_err(), true);
+    }
+
+    #[test]
+    fn test_discover() {
+        let base = std::env::temp_dir().join("combine-sandbox-config");
+        let root = base.join("project");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(root.join("docs/guide")).unwrap();
+        fs::write(
+            root.join(".asciidocrc"),
+            "safe-mode = \"safe\"\n[attributes]\nproduct = \"Sandbox\"",
+        )
+        .unwrap();
+        fs::write(root.join("docs/part.adoc"), "part").unwrap();
+        fs::write(base.join("outside.adoc"), "outside").unwrap();
+
+        let config = Config::discover(&root.join("docs/guide")).unwrap();
+        assert_eq!(config.dir, root);
+        let defaults = config.apply(ParseOptions::new(), &root);
+        assert_eq!(defaults.attributes["product"], "Sandbox");
+        let options = config.apply(
+            ParseOptions::new().attribute("product", "Override"),
+            &root.join("docs/guide"),
+        );
+        let document =
+            Document::parse_with_options("{product}\n\ninclude::../part.adoc[]", &options).unwrap();
+        assert_eq!(document.attribute("product"), Some("Override"));
+        assert_eq!(document.dump_tree().contains("part"), true);
+        assert_eq!(
+            Document::parse_with_options("include::../../../outside.adoc[]", &options).is_err(),
+            true
+        );
+    }
+}
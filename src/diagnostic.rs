@@ -0,0 +1,175 @@
+use crate::Span;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub span: Span,
+    // Likely fix, shown below the source excerpt.
+    pub note: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error<S: Into<String>>(message: S, span: Span) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            message: message.into(),
+            span,
+            note: None,
+        }
+    }
+
+    pub fn warning<S: Into<String>>(message: S, span: Span) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            message: message.into(),
+            span,
+            note: None,
+        }
+    }
+
+    pub fn info<S: Into<String>>(message: S, span: Span) -> Self {
+        Diagnostic {
+            severity: Severity::Info,
+            message: message.into(),
+            span,
+            note: None,
+        }
+    }
+
+    pub fn note<S: Into<String>>(mut self, note: S) -> Self {
+        self.note = Some(note.into());
+        self
+    }
+
+    // The diagnostic with the first source line of its span and carets under the span, like
+    //
+    //   error: unclosed `*`
+    //    --> guide.adoc:3:5
+    //     |
+    //   3 | Use *bold text
+    //     |     ^^^^^^^^^^
+    //     = help: did you forget a closing `*`?
+    //
+    // `color` adds ANSI colors for terminals.
+    pub fn snippet(&self, name: &str, source: &str, color: bool) -> String {
+        let paint = |code: &str, text: &str| {
+            if color {
+                format!("\x1b[{}m{}\x1b[0m", code, text)
+            } else {
+                text.to_string()
+            }
+        };
+        let severity_color = match self.severity {
+            Severity::Info => "1;36",
+            Severity::Warning => "1;33",
+            Severity::Error => "1;31",
+        };
+
+        let start = floor_char_boundary(source, self.span.start);
+        let line_start = source[..start].rfind('\n').map_or(0, |index| index + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |index| start + index);
+        let line = source[line_start..line_end].trim_end_matches('\r');
+        let line_number = source[..start].matches('\n').count() + 1;
+        let column = source[line_start..start].chars().count();
+        let end = floor_char_boundary(source, self.span.end.clamp(start, line_end));
+        let width = source[start..end].chars().count().max(1);
+
+        let gutter = " ".repeat(line_number.to_string().len());
+        let bar = paint("1;34", "|");
+        let mut out = format!(
+            "{}{}\n{}{} {}:{}:{}\n",
+            paint(severity_color, &self.severity.to_string()),
+            paint("1", &format!(": {}", self.message)),
+            gutter,
+            paint("1;34", "-->"),
+            name,
+            line_number,
+            column + 1
+        );
+        out.push_str(&format!("{} {}\n", gutter, bar));
+        out.push_str(&format!(
+            "{} {} {}\n",
+            paint("1;34", &line_number.to_string()),
+            bar,
+            line
+        ));
+        out.push_str(&format!(
+            "{} {} {}{}\n",
+            gutter,
+            bar,
+            " ".repeat(column),
+            paint(severity_color, &"^".repeat(width))
+        ));
+        if let Some(note) = &self.note {
+            out.push_str(&format!(
+                "{} {} {}\n",
+                gutter,
+                paint("1;34", "="),
+                paint("1", &format!("help: {}", note))
+            ));
+        }
+        out
+    }
+}
+
+fn floor_char_boundary(s: &str, index: usize) -> usize {
+    let mut index = index.min(s.len());
+    while !s.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} at {}..{}",
+            self.severity, self.message, self.span.start, self.span.end
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_snippet() {
+        let source = "= Title\n\nUse *bold text\nhere";
+        let diagnostic = Diagnostic::error("unclosed `*`", Span { start: 13, end: 27 })
+            .note("did you forget a closing `*`?");
+        assert_eq!(
+            diagnostic.snippet("guide.adoc", source, false),
+            "error: unclosed `*`\n --> guide.adoc:3:5\n  |\n3 | Use *bold text\n  |     ^^^^^^^^^^\n  = help: did you forget a closing `*`?\n"
+        );
+        assert_eq!(
+            diagnostic
+                .snippet("guide.adoc", source, true)
+                .contains("\x1b[1;31merror\x1b[0m"),
+            true
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use crate::document::Document;
+use crate::{Block, BlockMeta};
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiagramKind {
+    PlantUml,
+    Mermaid,
+    Graphviz,
+}
+
+impl DiagramKind {
+    // Block style (`[plantuml]`) or code block language naming the kind.
+    pub fn from_name(name: &str) -> Option<DiagramKind> {
+        match name {
+            "plantuml" | "puml" => Some(DiagramKind::PlantUml),
+            "mermaid" => Some(DiagramKind::Mermaid),
+            "graphviz" | "dot" => Some(DiagramKind::Graphviz),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DiagramKind::PlantUml => "plantuml",
+            DiagramKind::Mermaid => "mermaid",
+            DiagramKind::Graphviz => "graphviz",
+        }
+    }
+}
+
+// What a backend made of a diagram.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiagramOutput {
+    // Markup to embed as is.
+    Svg(String),
+    // Image written elsewhere, referenced by this path or URL.
+    Path(String),
+}
+
+// Rendered diagram attached to a block, see `Document::diagram`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagram {
+    pub kind: DiagramKind,
+    pub output: DiagramOutput,
+}
+
+// Turns the source of a diagram block into an image, by running a tool or with a library.
+pub trait DiagramBackend: Send + Sync {
+    fn render(&self, kind: DiagramKind, source: &str) -> Result<DiagramOutput>;
+}
+
+// Pipes the source to a command and reads SVG from its stdout. By default `plantuml`, `mmdc` and
+// `dot` from the PATH.
+pub struct CommandBackend {
+    commands: HashMap<DiagramKind, Vec<String>>,
+    // Write the images to this directory instead of returning the markup. Files are named after
+    // the source, existing ones are reused without running the command again.
+    output_dir: Option<PathBuf>,
+}
+
+impl Default for CommandBackend {
+    fn default() -> Self {
+        let command = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect();
+        let mut commands = HashMap::new();
+        commands.insert(
+            DiagramKind::PlantUml,
+            command(&["plantuml", "-tsvg", "-pipe"]),
+        );
+        commands.insert(
+            DiagramKind::Mermaid,
+            command(&["mmdc", "-i", "-", "-o", "-", "-e", "svg"]),
+        );
+        commands.insert(DiagramKind::Graphviz, command(&["dot", "-Tsvg"]));
+        CommandBackend {
+            commands,
+            output_dir: None,
+        }
+    }
+}
+
+impl CommandBackend {
+    pub fn new() -> Self {
+        CommandBackend::default()
+    }
+
+    // Program and arguments run for `kind`.
+    pub fn command<S: Into<String>, I: IntoIterator<Item = S>>(
+        mut self,
+        kind: DiagramKind,
+        command: I,
+    ) -> Self {
+        self.commands
+            .insert(kind, command.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn output_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.output_dir = Some(dir.into());
+        self
+    }
+
+    fn run(&self, kind: DiagramKind, source: &str) -> Result<String> {
+        let (program, args) = match self.commands.get(&kind).map(Vec::as_slice) {
+            Some([program, args @ ..]) => (program, args),
+            _ => bail!("no command for {} diagrams", kind.name()),
+        };
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("failed to run `{}`", program))?;
+        // Written from another thread, a tool printing while it reads would block otherwise.
+        let mut stdin = child.stdin.take().unwrap();
+        let input = source.to_string();
+        let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+        let output = child
+            .wait_with_output()
+            .with_context(|| format!("failed to run `{}`", program))?;
+        let _ = writer.join();
+        if !output.status.success() {
+            bail!(
+                "`{}` failed with {}: {}",
+                program,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        String::from_utf8(output.stdout)
+            .with_context(|| format!("`{}` wrote output that isn't UTF-8", program))
+    }
+}
+
+impl DiagramBackend for CommandBackend {
+    fn render(&self, kind: DiagramKind, source: &str) -> Result<DiagramOutput> {
+        let dir = match &self.output_dir {
+            Some(dir) => dir,
+            None => return Ok(DiagramOutput::Svg(self.run(kind, source)?)),
+        };
+        let path = dir.join(format!("{}-{}.svg", kind.name(), digest(source)));
+        if !path.is_file() {
+            let svg = self.run(kind, source)?;
+            fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create {}", dir.display()))?;
+            fs::write(&path, svg).with_context(|| format!("failed to write {}", path.display()))?;
+        }
+        Ok(DiagramOutput::Path(path.to_string_lossy().into_owned()))
+    }
+}
+
+// Kind and source of a diagram block: a paragraph styled `[plantuml]`, `[mermaid]` or
+// `[graphviz]`, or a code block in one of those languages.
+pub fn diagram_source(block: &Block, meta: Option<&BlockMeta>) -> Option<(DiagramKind, String)> {
+    match block {
+        Block::Paragraph { children } => {
+            let style = meta.and_then(|meta| meta.style.as_ref())?;
+            Some((DiagramKind::from_name(style)?, children.source_text()))
+        }
+        Block::CodeBlock {
+            children,
+            file_type: Some(file_type),
+            ..
+        } => Some((
+            DiagramKind::from_name(file_type)?,
+            crate::text::inline_text(children),
+        )),
+        _ => None,
+    }
+}
+
+// Render every diagram block of `document` with `backend` and attach the results for renderers.
+// Stops at the first diagram the backend fails on.
+pub fn render_diagrams(document: &mut Document, backend: &dyn DiagramBackend) -> Result<()> {
+    let mut diagrams = vec![];
+    for (index, block) in document.blocks().iter().enumerate() {
+        if let Some((kind, source)) = diagram_source(block, document.meta(index)) {
+            let output = backend
+                .render(kind, &source)
+                .with_context(|| format!("failed to render {} diagram", kind.name()))?;
+            diagrams.push((index, Diagram { kind, output }));
+        }
+    }
+    for (index, diagram) in diagrams {
+        document.set_diagram(index, diagram);
+    }
+    Ok(())
+}
+
+// FNV-1a of the source, stable so unchanged diagrams keep their file.
+fn digest(source: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in source.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::ParseOptions;
+    use pretty_assertions::assert_eq;
+
+    struct Echo;
+
+    impl DiagramBackend for Echo {
+        fn render(&self, kind: DiagramKind, source: &str) -> Result<DiagramOutput> {
+            Ok(DiagramOutput::Svg(format!(
+                "<svg>{}: {}</svg>",
+                kind.name(),
+                source
+            )))
+        }
+    }
+
+    #[test]
+    fn test_render_diagrams() {
+        let source = "[plantuml]\nAlice -> *Bob*\n\n[source,plantuml]\nnot a diagram\n\n[graphviz]\ndigraph { a -> b }\n";
+        let mut document =
+            Document::parse_with_options(source, &ParseOptions::new().lazy_inlines(true)).unwrap();
+        render_diagrams(&mut document, &Echo).unwrap();
+        assert_eq!(
+            document.diagram(0),
+            Some(&Diagram {
+                kind: DiagramKind::PlantUml,
+                output: DiagramOutput::Svg("<svg>plantuml: Alice -> *Bob*</svg>".to_string()),
+            })
+        );
+        assert_eq!(document.diagram(2), None);
+        assert_eq!(
+            document.diagram(4).map(|diagram| diagram.kind),
+            Some(DiagramKind::Graphviz)
+        );
+    }
+
+    #[test]
+    fn test_command_backend_output_dir() {
+        let dir =
+            std::env::temp_dir().join(format!("combine-sandbox-diagrams-{}", std::process::id()));
+        let backend = CommandBackend::new()
+            .command(DiagramKind::Graphviz, ["cat"])
+            .output_dir(&dir);
+        let output = backend.render(DiagramKind::Graphviz, "<svg/>").unwrap();
+        let path = dir.join(format!("graphviz-{}.svg", digest("<svg/>")));
+        assert_eq!(
+            output,
+            DiagramOutput::Path(path.to_string_lossy().into_owned())
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "<svg/>");
+        assert_eq!(
+            CommandBackend::new()
+                .command(DiagramKind::Mermaid, ["false"])
+                .render(DiagramKind::Mermaid, "graph TD")
+                .is_err(),
+            true
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,380 @@
+use crate::diagnostic::Severity;
+use crate::lines::LineIndex;
+use crate::links::{check_links, resolve_xref, LinkCheckOptions, LinkKind, Project};
+use crate::lint::{self, closest, LintConfig, LintRule};
+use crate::options::ParseOptions;
+use crate::preprocess::attribute_usage;
+use crate::render::images::{ImageOptions, ImageResolver};
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::path::PathBuf;
+
+// Attributes Asciidoctor defines for every document, so references to them are no typos.
+const INTRINSIC_ATTRIBUTES: &[&str] = &[
+    "amp",
+    "apos",
+    "asterisk",
+    "backslash",
+    "backtick",
+    "brvbar",
+    "caret",
+    "cpp",
+    "deg",
+    "docdate",
+    "docdir",
+    "docname",
+    "doctitle",
+    "empty",
+    "endsb",
+    "gt",
+    "ldquo",
+    "localdate",
+    "lsquo",
+    "lt",
+    "nbsp",
+    "plus",
+    "pp",
+    "quot",
+    "rdquo",
+    "rsquo",
+    "sp",
+    "startsb",
+    "tilde",
+    "two-colons",
+    "two-semicolons",
+    "vbar",
+    "wj",
+    "zwsp",
+];
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub enum Check {
+    Lint(LintRule),
+    BrokenLink(LinkKind),
+    UnusedAnchor,
+    MissingImage,
+    UnknownAttribute,
+}
+
+impl Check {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Check::Lint(rule) => rule.name(),
+            Check::BrokenLink(LinkKind::Xref) => "broken-xref",
+            Check::BrokenLink(LinkKind::Include) => "broken-include",
+            Check::BrokenLink(LinkKind::External) => "broken-url",
+            Check::UnusedAnchor => "unused-anchor",
+            Check::MissingImage => "missing-image",
+            Check::UnknownAttribute => "unknown-attribute",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Finding {
+    // Relative to the project root.
+    pub path: PathBuf,
+    pub line: usize,
+    pub check: Check,
+    pub severity: Severity,
+    pub message: String,
+    pub note: Option<String>,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}: {} [{}]",
+            self.path.display(),
+            self.line,
+            self.severity,
+            self.message,
+            self.check.name()
+        )?;
+        if let Some(note) = &self.note {
+            write!(f, "\n  = help: {}", note)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DoctorOptions {
+    pub lint: LintConfig,
+    // Attributes set outside the documents, e.g. by a config file, so references to them
+    // resolve.
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl DoctorOptions {
+    pub fn new() -> Self {
+        DoctorOptions::default()
+    }
+
+    pub fn lint(mut self, lint: LintConfig) -> Self {
+        self.lint = lint;
+        self
+    }
+
+    pub fn attribute<K: Into<String>, V: Into<String>>(mut self, name: K, value: V) -> Self {
+        self.attributes.insert(name.into(), value.into());
+        self
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct DoctorReport {
+    pub files: usize,
+    // Sorted by file and line.
+    pub findings: Vec<Finding>,
+}
+
+impl DoctorReport {
+    pub fn count(&self, severity: Severity) -> usize {
+        self.findings
+            .iter()
+            .filter(|finding| finding.severity == severity)
+            .count()
+    }
+
+    // Number of findings of each check, by check name.
+    pub fn by_check(&self) -> BTreeMap<&'static str, usize> {
+        let mut counts = BTreeMap::new();
+        for finding in &self.findings {
+            *counts.entry(finding.check.name()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    // One line per finding, then a summary like
+    //
+    //   3 files: 1 error, 2 warnings, 0 infos
+    //     broken-xref: 1
+    //     unknown-attribute: 2
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        for finding in &self.findings {
+            out.push_str(&format!("{}\n", finding));
+        }
+        if !self.findings.is_empty() {
+            out.push('\n');
+        }
+        let plural = |count: usize, word: &str| {
+            format!("{} {}{}", count, word, if count == 1 { "" } else { "s" })
+        };
+        out.push_str(&format!(
+            "{}: {}, {}, {}\n",
+            plural(self.files, "file"),
+            plural(self.count(Severity::Error), "error"),
+            plural(self.count(Severity::Warning), "warning"),
+            plural(self.count(Severity::Info), "info")
+        ));
+        for (check, count) in self.by_check() {
+            out.push_str(&format!("  {}: {}\n", check, count));
+        }
+        out
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "files": self.files,
+            "summary": {
+                "errors": self.count(Severity::Error),
+                "warnings": self.count(Severity::Warning),
+                "infos": self.count(Severity::Info),
+                "checks": self.by_check(),
+            },
+            "findings": self.findings.iter().map(|finding| json!({
+                "path": finding.path.display().to_string(),
+                "line": finding.line,
+                "check": finding.check.name(),
+                "severity": finding.severity.to_string(),
+                "message": finding.message,
+                "note": finding.note,
+            })).collect::<Vec<_>>(),
+        })
+    }
+}
+
+// Every check over every file of `project`: the lints, broken xrefs and includes, explicit
+// anchors nothing refers to, images missing on disk and references to attributes that are
+// never set.
+pub fn doctor(project: &Project, options: &DoctorOptions) -> Result<DoctorReport> {
+    let mut findings = vec![];
+
+    // Broken xrefs are reported with the links, which also know the other documents.
+    let lint_config = options.lint.clone().level(LintRule::BrokenXref, None);
+    for (path, file) in &project.files {
+        for lint in lint::lint(&file.document, &file.source, &lint_config) {
+            findings.push(Finding {
+                path: path.clone(),
+                line: lint.line,
+                check: Check::Lint(lint.rule),
+                severity: lint.diagnostic.severity,
+                message: lint.diagnostic.message,
+                note: lint.diagnostic.note,
+            });
+        }
+    }
+
+    for link in check_links(project, &LinkCheckOptions::new())? {
+        let note = match link.kind {
+            LinkKind::Xref if !lint::is_inter_document(&link.target) => {
+                let anchors = lint::anchors(&project.files[&link.path].document);
+                closest(&link.target, anchors.keys())
+                    .map(|anchor| format!("did you mean `{}`?", anchor))
+            }
+            _ => None,
+        };
+        findings.push(Finding {
+            message: format!("broken {} `{}`: {}", link.kind, link.target, link.reason),
+            path: link.path,
+            line: link.line,
+            check: Check::BrokenLink(link.kind),
+            severity: Severity::Error,
+            note,
+        });
+    }
+
+    let lines = project
+        .files
+        .iter()
+        .map(|(path, file)| (path, LineIndex::new(&file.source)))
+        .collect::<HashMap<_, _>>();
+    let line = |path: &PathBuf, offset: usize| lines[path].line_of(offset) + 1;
+
+    let mut referenced = HashSet::new();
+    for (path, file) in &project.files {
+        for (target, _) in lint::xrefs(&file.document) {
+            if lint::is_inter_document(&target) {
+                referenced.insert(resolve_xref(path, &target));
+            } else {
+                referenced.insert((path.clone(), target));
+            }
+        }
+    }
+    for (path, file) in &project.files {
+        for (id, span) in lint::explicit_anchors(&file.document) {
+            if !referenced.contains(&(path.clone(), id.clone())) {
+                findings.push(Finding {
+                    path: path.clone(),
+                    line: line(path, span.start),
+                    check: Check::UnusedAnchor,
+                    severity: Severity::Info,
+                    message: format!("anchor `{}` is never referenced", id),
+                    note: None,
+                });
+            }
+        }
+    }
+
+    let mut parse_options = ParseOptions::new();
+    parse_options.attributes = options.attributes.clone();
+    for (path, file) in &project.files {
+        let usage = attribute_usage(&file.source, &parse_options);
+
+        let mut images = ImageOptions::new();
+        images.images_dir = usage.attributes.get("imagesdir").cloned();
+        let resolver = ImageResolver::new(&images, None);
+        let dir = project.root.join(path.parent().unwrap_or(&PathBuf::new()));
+        for (target, span) in lint::images(&file.document) {
+            // Targets using attribute references can't be resolved without a render.
+            if target.contains('{') {
+                continue;
+            }
+            if let Some(image) = resolver.local_path(&target) {
+                if !dir.join(&image).is_file() {
+                    findings.push(Finding {
+                        path: path.clone(),
+                        line: line(path, span.start),
+                        check: Check::MissingImage,
+                        severity: Severity::Error,
+                        message: format!("image `{}` not found at {}", target, image),
+                        note: None,
+                    });
+                }
+            }
+        }
+
+        for (offset, name) in usage.unresolved {
+            if INTRINSIC_ATTRIBUTES.contains(&name.as_str()) {
+                continue;
+            }
+            let note = closest(&name, usage.defined.iter())
+                .map(|defined| format!("did you mean `{{{}}}`?", defined));
+            findings.push(Finding {
+                path: path.clone(),
+                line: line(path, offset),
+                check: Check::UnknownAttribute,
+                severity: Severity::Warning,
+                message: format!("attribute `{}` is not set", name),
+                note,
+            });
+        }
+    }
+
+    findings.sort_by(|a, b| (&a.path, a.line, a.check).cmp(&(&b.path, b.line, b.check)));
+    Ok(DoctorReport {
+        files: project.files.len(),
+        findings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::fs;
+
+    #[test]
+    fn test_doctor() {
+        let root = std::env::temp_dir().join("combine-sandbox-doctor");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("images")).unwrap();
+        fs::write(root.join("images/logo.png"), "").unwrap();
+        fs::write(
+            root.join("index.adoc"),
+            ":imagesdir: images\n:product: Sandbox\n\n= Index\n\n[[intro]]\nAbout {prodcut} and {nbsp}.\n\nimage::logo.png[]\n\nimage::diagram.png[]\n\nSee <<intr>> and xref:guide.adoc#setup[].\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("guide.adoc"),
+            "[[setup]]\n== Setup\n\n[[spare]]\nText\n",
+        )
+        .unwrap();
+
+        let project = Project::load(&root).unwrap();
+        let report = doctor(&project, &DoctorOptions::new()).unwrap();
+        assert_eq!(
+            report
+                .findings
+                .iter()
+                .map(|finding| (finding.path.to_str().unwrap(), finding.line, finding.check))
+                .collect::<Vec<_>>(),
+            vec![
+                ("guide.adoc", 4, Check::UnusedAnchor),
+                ("index.adoc", 6, Check::UnusedAnchor),
+                ("index.adoc", 7, Check::UnknownAttribute),
+                ("index.adoc", 11, Check::MissingImage),
+                ("index.adoc", 13, Check::BrokenLink(LinkKind::Xref)),
+            ]
+        );
+        assert_eq!(
+            report.findings[2].note.as_deref(),
+            Some("did you mean `{product}`?")
+        );
+        assert_eq!(
+            report.findings[4].note.as_deref(),
+            Some("did you mean `intro`?")
+        );
+        assert_eq!(
+            report.to_text().ends_with(
+                "2 files: 2 errors, 1 warning, 2 infos\n  broken-xref: 1\n  missing-image: 1\n  unknown-attribute: 1\n  unused-anchor: 2\n"
+            ),
+            true
+        );
+    }
+}
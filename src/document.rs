@@ -0,0 +1,746 @@
+use crate::diagnostic::Diagnostic;
+use crate::diagram::Diagram;
+use crate::dump;
+use crate::footprint;
+use crate::lint::closest;
+use crate::options::{HeadingOverflow, ParseOptions};
+use crate::outline::{self, OutlineEntry, DEFAULT_TOC_LEVELS};
+use crate::parallel;
+use crate::preprocess::{preprocess, Preprocessed};
+use crate::recovery::parse_lenient;
+use crate::{
+    parse_spanned, Block, BlockKind, BlockMeta, BlockOptions, HeadingLevel, Inline, ParsedBlock,
+    Span, BLOCK_STYLES, HEADING_LEVELS,
+};
+use anyhow::Result;
+use std::collections::{BTreeMap, HashSet};
+use std::mem::size_of;
+use std::ops::{Deref, Range};
+use std::sync::Arc;
+
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct Document {
+    blocks: Vec<Block>,
+    // Source range of each top level block, `None` for blocks that were not parsed from text.
+    spans: Vec<Option<Span>>,
+    metas: Vec<Option<BlockMeta>>,
+    attributes: BTreeMap<String, String>,
+    diagnostics: Vec<Diagnostic>,
+    // Rendered diagrams by top level block index.
+    diagrams: BTreeMap<usize, Diagram>,
+}
+
+impl Document {
+    pub fn new(blocks: Vec<Block>) -> Self {
+        Document {
+            spans: blocks.iter().map(|_| None).collect(),
+            metas: blocks.iter().map(|_| None).collect(),
+            blocks,
+            attributes: BTreeMap::new(),
+            diagnostics: vec![],
+            diagrams: BTreeMap::new(),
+        }
+    }
+
+    pub(crate) fn from_spanned(parsed: Vec<ParsedBlock>) -> Self {
+        let mut blocks = Vec::with_capacity(parsed.len());
+        let mut spans = Vec::with_capacity(parsed.len());
+        let mut metas = Vec::with_capacity(parsed.len());
+        for parsed in parsed {
+            blocks.push(parsed.block);
+            spans.push(Some(parsed.span));
+            metas.push(parsed.meta);
+        }
+        Document {
+            blocks,
+            spans,
+            metas,
+            attributes: BTreeMap::new(),
+            diagnostics: vec![],
+            diagrams: BTreeMap::new(),
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self> {
+        let mut document = Document::from_spanned(parse_spanned(s, BlockOptions::default())?);
+        document.diagnostics = document.duplicate_attributes(s);
+        document.diagnostics.extend(document.unknown_styles(s));
+        document
+            .diagnostics
+            .extend(document.heading_overflows(s, HeadingOverflow::default()));
+        document
+            .diagnostics
+            .sort_by_key(|diagnostic| diagnostic.span.start);
+        Ok(document)
+    }
+
+    pub fn parse_with_options(s: &str, options: &ParseOptions) -> Result<Self> {
+        Document::parse_preprocessed(preprocess(s, options)?, options)
+    }
+
+    // Like `parse_with_options`, along with the preprocessed source the spans and diagnostics of
+    // the document point into.
+    pub fn parse_with_source(s: &str, options: &ParseOptions) -> Result<(Self, String)> {
+        let preprocessed = preprocess(s, options)?;
+        let source = preprocessed.source.clone();
+        Ok((Document::parse_preprocessed(preprocessed, options)?, source))
+    }
+
+    pub(crate) fn parse_preprocessed(
+        preprocessed: Preprocessed,
+        options: &ParseOptions,
+    ) -> Result<Self> {
+        let source = &preprocessed.source;
+        let blocks = BlockOptions::from(options);
+        let (spanned, diagnostics) = match &options.recovery {
+            Some(policy) => {
+                parallel::parse(source, options, |s| Ok(parse_lenient(s, policy, blocks)))?
+            }
+            None => parallel::parse(source, options, |s| Ok((parse_spanned(s, blocks)?, vec![])))?,
+        };
+        let mut document = Document::from_spanned(spanned);
+        document.diagnostics = diagnostics;
+        document
+            .diagnostics
+            .extend(document.duplicate_attributes(source));
+        document.diagnostics.extend(document.unknown_styles(source));
+        document
+            .diagnostics
+            .extend(document.heading_overflows(source, options.heading_overflow));
+        document
+            .diagnostics
+            .sort_by_key(|diagnostic| diagnostic.span.start);
+        document.attributes = preprocessed.attributes;
+        Ok(document)
+    }
+
+    // A named attribute written twice in one attribute line, `[width=50, width=80]`. The last
+    // value wins like it does in Asciidoctor, the line is reported as a warning.
+    fn duplicate_attributes(&self, source: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+        for (index, meta) in self.metas.iter().enumerate() {
+            let (meta, span) = match (meta, self.span(index)) {
+                (Some(meta), Some(span)) => (meta, span),
+                _ => continue,
+            };
+            let span = attribute_line(source, span);
+            for key in meta.attributes.duplicate_keys() {
+                let value = meta.attributes.get(key).unwrap_or("");
+                diagnostics.push(Diagnostic::warning(
+                    format!(
+                        "attribute `{}` is set more than once, the last value `{}` is used",
+                        key, value
+                    ),
+                    span,
+                ));
+            }
+        }
+        diagnostics
+    }
+
+    // A style no backend knows, like the typo in `[sourec,rust]`. The block is parsed like it had
+    // no style and the attribute line is reported as a warning.
+    fn unknown_styles(&self, source: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+        for (index, meta) in self.metas.iter().enumerate() {
+            let (meta, span) = match (meta, self.span(index)) {
+                (Some(meta), Some(span)) if !meta.has_known_style() => (meta, span),
+                _ => continue,
+            };
+            let style = meta.style.as_deref().unwrap_or("");
+            let diagnostic = Diagnostic::warning(
+                format!("unknown block style `{}`", style),
+                attribute_line(source, span),
+            );
+            diagnostics.push(match closest(style, BLOCK_STYLES.iter()) {
+                Some(known) => diagnostic.note(format!("did you mean `{}`?", known)),
+                None => diagnostic,
+            });
+        }
+        diagnostics
+    }
+
+    // Headings with more marks than there are section levels, `====== Title`, and what `mode`
+    // made of them. Only strict mode reports an error.
+    fn heading_overflows(&self, source: &str, mode: HeadingOverflow) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+        for (index, block) in self.blocks.iter().enumerate() {
+            let overflowed = match block {
+                Block::Heading {
+                    level: HeadingLevel::Level4,
+                    ..
+                } => mode == HeadingOverflow::Compat,
+                Block::Paragraph { children } => {
+                    mode != HeadingOverflow::Compat && overflowed_marks(&children.source_text()) > 0
+                }
+                _ => false,
+            };
+            let span = match self.span(index) {
+                Some(span) if overflowed => span,
+                _ => continue,
+            };
+            // Lines above the heading belong to its attribute line or title.
+            let text = source.get(span.start..span.end).unwrap_or("");
+            let (offset, marks) = match text
+                .split_inclusive('\n')
+                .scan(span.start, |offset, line| {
+                    let start = *offset;
+                    *offset += line.len();
+                    Some((start, overflowed_marks(line)))
+                })
+                .find(|(_, marks)| *marks > 0)
+            {
+                Some(line) => line,
+                None => continue,
+            };
+            let line = &source[offset..];
+            let span = Span {
+                start: offset,
+                end: offset + line.find('\n').unwrap_or(line.len()),
+            };
+            let message = format!(
+                "`{}` is deeper than the last section level `{}`",
+                "=".repeat(marks),
+                "=".repeat(HEADING_LEVELS)
+            );
+            diagnostics.push(match mode {
+                HeadingOverflow::Strict => {
+                    Diagnostic::error(format!("{}, the line is a paragraph", message), span)
+                        .note(format!("use `{}` or less", "=".repeat(HEADING_LEVELS)))
+                }
+                HeadingOverflow::Compat => {
+                    Diagnostic::info(format!("{}, it is read as the last level", message), span)
+                }
+                HeadingOverflow::Lenient => {
+                    Diagnostic::info(format!("{}, the line is a paragraph", message), span)
+                }
+            });
+        }
+        diagnostics
+    }
+
+    pub fn blocks(&self) -> &[Block] {
+        &self.blocks
+    }
+
+    // Level 0 heading opening the document, only blank lines may come before it.
+    pub fn title(&self) -> Option<&[Inline]> {
+        match self.title_index().map(|index| &self.blocks[index]) {
+            Some(Block::Heading { children, .. }) => Some(children),
+            _ => None,
+        }
+    }
+
+    fn title_index(&self) -> Option<usize> {
+        let index = self
+            .blocks
+            .iter()
+            .position(|block| block.kind() != BlockKind::BlankBlock)?;
+        match self.blocks[index].heading_level() {
+            Some(HeadingLevel::Title) => Some(index),
+            _ => None,
+        }
+    }
+
+    // Indexes of the blocks between the title and the first section, without the blank ones at
+    // either end. Like in AsciiDoc, only a document with both a title and sections has one.
+    pub fn preamble(&self) -> Option<Range<usize>> {
+        let title = self.title_index()?;
+        let section = title
+            + 1
+            + self.blocks[title + 1..]
+                .iter()
+                .position(|block| block.heading_level().is_some())?;
+        let is_content = |index: &usize| self.blocks[*index].kind() != BlockKind::BlankBlock;
+        let start = (title + 1..section).find(is_content)?;
+        let end = (start..section).rev().find(is_content)? + 1;
+        Some(start..end)
+    }
+
+    // Spans point into the preprocessed source when the document was parsed with options.
+    pub fn span(&self, index: usize) -> Option<Span> {
+        self.spans.get(index).copied().flatten()
+    }
+
+    // Attribute line written above the top level block at `index`.
+    pub fn meta(&self, index: usize) -> Option<&BlockMeta> {
+        self.metas.get(index).and_then(Option::as_ref)
+    }
+
+    pub fn attributes(&self) -> &BTreeMap<String, String> {
+        &self.attributes
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(String::as_str)
+    }
+
+    // Output of the diagram block at `index`, set by `diagram::render_diagrams`.
+    pub fn diagram(&self, index: usize) -> Option<&Diagram> {
+        self.diagrams.get(&index)
+    }
+
+    pub fn set_diagram(&mut self, index: usize, diagram: Diagram) {
+        self.diagrams.insert(index, diagram);
+    }
+
+    // Problems found while parsing leniently, and warnings such as attributes set twice.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    // Approximate bytes held by the document and everything it owns. Lazily parsed paragraphs
+    // count their source text and are not parsed.
+    pub fn memory_footprint(&self) -> usize {
+        size_of::<Document>()
+            + self.blocks.capacity() * size_of::<Block>()
+            + self.blocks.iter().map(footprint::block).sum::<usize>()
+            + self.spans.capacity() * size_of::<Option<Span>>()
+            + self.metas.capacity() * size_of::<Option<BlockMeta>>()
+            + self
+                .metas
+                .iter()
+                .flatten()
+                .map(footprint::meta)
+                .sum::<usize>()
+            + self
+                .attributes
+                .iter()
+                .map(|(name, value)| {
+                    size_of::<(String, String)>() + name.capacity() + value.capacity()
+                })
+                .sum::<usize>()
+            + self.diagnostics.capacity() * size_of::<Diagnostic>()
+            + self
+                .diagnostics
+                .iter()
+                .map(|diagnostic| {
+                    diagnostic.message.capacity()
+                        + diagnostic.note.as_ref().map_or(0, String::capacity)
+                })
+                .sum::<usize>()
+    }
+
+    pub fn into_blocks(self) -> Vec<Block> {
+        self.blocks
+    }
+
+    // Section tree up to `max_depth`, falling back to the `toclevels` attribute.
+    pub fn outline(&self, max_depth: Option<u8>) -> Vec<OutlineEntry> {
+        let max_depth = max_depth
+            .or_else(|| self.attribute("toclevels").and_then(|v| v.parse().ok()))
+            .unwrap_or(DEFAULT_TOC_LEVELS);
+        outline::outline(&self.blocks, max_depth)
+    }
+
+    pub fn dump_tree(&self) -> String {
+        dump::dump_tree(self)
+    }
+
+    pub fn append(&mut self, other: Document) {
+        self.append_with_leveloffset(other, 0)
+    }
+
+    // Concatenate `other` after this document. Heading levels of `other` are shifted by
+    // `leveloffset`, ids of headings and block anchors (`[#id]`) already used here get a `_2`,
+    // `_3`... suffix and attributes already defined here are kept.
+    pub fn append_with_leveloffset(&mut self, other: Document, leveloffset: i32) {
+        let headings = self.blocks.iter().filter_map(|block| match block {
+            Block::Heading { id: Some(id), .. } => Some(id.clone()),
+            _ => None,
+        });
+        let anchors = self
+            .metas
+            .iter()
+            .filter_map(|meta| meta.as_ref().and_then(|meta| meta.id.clone()));
+        let mut ids = headings.chain(anchors).collect::<HashSet<String>>();
+
+        let offset = self.blocks.len();
+        self.diagrams.extend(
+            other
+                .diagrams
+                .into_iter()
+                .map(|(index, diagram)| (offset + index, diagram)),
+        );
+        for (block, meta) in other.blocks.into_iter().zip(other.metas) {
+            let block = match block {
+                Block::Heading {
+                    level,
+                    children,
+                    id,
+                } => Block::Heading {
+                    level: level.offset(leveloffset),
+                    children,
+                    id: id.map(|id| unique_id(id, &mut ids)),
+                },
+                block => block,
+            };
+            let meta = meta.map(|meta| BlockMeta {
+                id: meta.id.map(|id| unique_id(id, &mut ids)),
+                ..meta
+            });
+            self.blocks.push(block);
+            self.spans.push(None);
+            self.metas.push(meta);
+        }
+
+        for (name, value) in other.attributes {
+            self.attributes.entry(name).or_insert(value);
+        }
+    }
+
+    // Freeze the document so it can be handed out to many threads without cloning the tree.
+    pub fn into_shared(self) -> ArcDocument {
+        ArcDocument::from(self)
+    }
+}
+
+// Cheap to clone handle of an immutable parsed document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArcDocument(Arc<Document>);
+
+impl ArcDocument {
+    pub fn ptr_eq(this: &ArcDocument, other: &ArcDocument) -> bool {
+        Arc::ptr_eq(&this.0, &other.0)
+    }
+}
+
+impl From<Document> for ArcDocument {
+    fn from(document: Document) -> Self {
+        ArcDocument(Arc::new(document))
+    }
+}
+
+impl Deref for ArcDocument {
+    type Target = Document;
+
+    fn deref(&self) -> &Document {
+        &self.0
+    }
+}
+
+fn unique_id(id: String, ids: &mut HashSet<String>) -> String {
+    let mut candidate = id.clone();
+    let mut suffix = 2;
+    while ids.contains(&candidate) {
+        candidate = format!("{}_{}", id, suffix);
+        suffix += 1;
+    }
+    ids.insert(candidate.clone());
+    candidate
+}
+
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Document>();
+    assert_send_sync::<ArcDocument>();
+};
+
+// First line of the block at `span`, where its attribute line is.
+fn attribute_line(source: &str, span: Span) -> Span {
+    let line = source.get(span.start..).unwrap_or("");
+    Span {
+        start: span.start,
+        end: span.start + line.find('\n').unwrap_or(line.len()),
+    }
+}
+
+// Number of marks opening `line` when it is a heading deeper than the section levels, 0 otherwise.
+fn overflowed_marks(line: &str) -> usize {
+    let marks = line.len() - line.trim_start_matches('=').len();
+    if marks > HEADING_LEVELS && line[marks..].starts_with(' ') {
+        marks
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostic::Severity;
+    use crate::{HeadingLevel, InMemoryResolver, Inline, RecoveryPolicy};
+    use pretty_assertions::assert_eq;
+    use smallvec::smallvec;
+    use std::thread;
+
+    #[test]
+    fn test_parse_document() {
+        let document = Document::parse("== Heading").unwrap();
+        assert_eq!(
+            document.blocks(),
+            &[Block::Heading {
+                level: HeadingLevel::Level1,
+                children: smallvec![Inline::Value("Heading".to_string())],
+                id: None
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_with_include_resolver() {
+        let mut resolver = InMemoryResolver::new();
+        resolver.insert("chapter.adoc", "== Chapter");
+        let options = ParseOptions::new().include_resolver(resolver);
+
+        let document = Document::parse_with_options("include::chapter.adoc[]", &options).unwrap();
+        assert_eq!(
+            document.blocks(),
+            &[Block::Heading {
+                level: HeadingLevel::Level1,
+                children: smallvec![Inline::Value("Chapter".to_string())],
+                id: None
+            }]
+        );
+    }
+
+    #[test]
+    fn test_document_attributes() {
+        let document = Document::parse_with_options(
+            ":revnumber: 1.0\nVersion {revnumber}",
+            &ParseOptions::new(),
+        )
+        .unwrap();
+        assert_eq!(document.attribute("revnumber"), Some("1.0"));
+        assert_eq!(
+            document.blocks(),
+            &[Block::Paragraph {
+                children: smallvec![Inline::Value("Version 1.0".to_string())].into()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_with_source() {
+        let (document, source) =
+            Document::parse_with_source(":a: b\n[sourec]\n{a}", &ParseOptions::new()).unwrap();
+        assert_eq!(source, "[sourec]\nb");
+        let span = document.diagnostics()[0].span;
+        assert_eq!(&source[span.start..span.end], "[sourec]");
+    }
+
+    #[test]
+    fn test_append() {
+        let mut book =
+            Document::parse_with_options(":author: himanoa\n= Book", &ParseOptions::new()).unwrap();
+        let chapter = Document::parse_with_options(
+            ":author: someone\n:chapter: 1\n= Chapter",
+            &ParseOptions::new(),
+        )
+        .unwrap();
+
+        book.append_with_leveloffset(chapter, 1);
+        assert_eq!(
+            book.blocks(),
+            &[
+                Block::Heading {
+                    level: HeadingLevel::Title,
+                    children: smallvec![Inline::Value("Book".to_string())],
+                    id: None
+                },
+                Block::Heading {
+                    level: HeadingLevel::Level1,
+                    children: smallvec![Inline::Value("Chapter".to_string())],
+                    id: None
+                },
+            ]
+        );
+        assert_eq!(book.attribute("author"), Some("himanoa"));
+        assert_eq!(book.attribute("chapter"), Some("1"));
+    }
+
+    #[test]
+    fn test_append_remaps_conflicting_ids() {
+        let heading = |id: &str| Block::Heading {
+            level: HeadingLevel::Level1,
+            children: smallvec![],
+            id: Some(id.to_string()),
+        };
+        let mut document = Document::new(vec![heading("intro")]);
+        document.append(Document::new(vec![heading("intro"), heading("intro")]));
+
+        assert_eq!(
+            document.blocks(),
+            &[heading("intro"), heading("intro_2"), heading("intro_3")]
+        );
+    }
+
+    #[test]
+    fn test_append_remaps_conflicting_anchors() {
+        let chapter = || Document::parse("[#setup]\nFirst step.\n\n== Setup").unwrap();
+        let mut book = chapter();
+        book.append(chapter());
+        let ids = (0..book.blocks().len())
+            .filter_map(|index| book.meta(index).and_then(|meta| meta.id.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["setup", "setup_2"]);
+    }
+
+    #[test]
+    fn test_outline_honors_toclevels() {
+        let document =
+            Document::parse_with_options(":toclevels: 1\n== A\n\n=== A-1", &ParseOptions::new())
+                .unwrap();
+        assert_eq!(document.outline(None).len(), 1);
+        assert_eq!(document.outline(None)[0].children.len(), 0);
+        assert_eq!(document.outline(Some(2))[0].children.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_with_recovery() {
+        assert_eq!(Document::parse("=broken\n\nok").is_err(), true);
+
+        let options = ParseOptions::new().recovery(RecoveryPolicy::default());
+        let document = Document::parse_with_options("=broken\n\nok", &options).unwrap();
+        assert_eq!(document.blocks().len(), 3);
+        assert_eq!(document.diagnostics().len(), 1);
+        assert_eq!(document.span(0), Some(Span { start: 0, end: 7 }));
+    }
+
+    #[test]
+    fn test_duplicate_attributes() {
+        let document = Document::parse("intro\n\n[width=50, height=10, width=80]\ntext").unwrap();
+        assert_eq!(
+            document.meta(2).unwrap().attributes.get("width"),
+            Some("80")
+        );
+        assert_eq!(
+            document.diagnostics(),
+            &[Diagnostic::warning(
+                "attribute `width` is set more than once, the last value `80` is used",
+                Span { start: 7, end: 38 },
+            )]
+        );
+
+        let options = ParseOptions::new().recovery(RecoveryPolicy::default());
+        let document =
+            Document::parse_with_options("=broken\n\n[id=a,id=b]\nok", &options).unwrap();
+        assert_eq!(document.diagnostics().len(), 2);
+        assert_eq!(document.diagnostics()[0].severity, Severity::Error);
+        assert_eq!(document.diagnostics()[1].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_title_and_preamble() {
+        let document = Document::parse("\n= Guide\n\nIntro\n\nMore\n\n== Usage\n\ntext").unwrap();
+        assert_eq!(
+            document.title().map(crate::text::inline_text),
+            Some("Guide".to_string())
+        );
+        assert_eq!(document.preamble(), Some(2..5));
+
+        // The preamble needs a section after it and a title before it.
+        assert_eq!(
+            Document::parse("= Guide\n\nIntro").unwrap().preamble(),
+            None
+        );
+        assert_eq!(
+            Document::parse("= Guide\n\n== Usage").unwrap().preamble(),
+            None
+        );
+        let document = Document::parse("Intro\n\n= Guide\n\n== Usage").unwrap();
+        assert_eq!(document.title(), None);
+        assert_eq!(document.preamble(), None);
+    }
+
+    #[test]
+    fn test_unknown_styles() {
+        let source =
+            "[sourec,rust]\nfn main() {}\n\n[plantuml]\na -> b\n\n[frobnicate]\n----\nx\n----";
+        let document = Document::parse(source).unwrap();
+        assert_eq!(document.meta(0).unwrap().style, Some("sourec".into()));
+        assert_eq!(
+            document.diagnostics(),
+            &[
+                Diagnostic::warning("unknown block style `sourec`", Span { start: 0, end: 13 })
+                    .note("did you mean `source`?"),
+                Diagnostic::warning(
+                    "unknown block style `frobnicate`",
+                    Span { start: 47, end: 59 }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_heading_overflow() {
+        let source = "intro\n\n[.deep]\n====== Deep ======\n";
+        let document = Document::parse(source).unwrap();
+        assert_eq!(
+            document.blocks()[2],
+            Block::Paragraph {
+                children: smallvec![Inline::Value("====== Deep ======".to_string())].into(),
+            }
+        );
+        let span = Span { start: 15, end: 33 };
+        assert_eq!(
+            document.diagnostics(),
+            &[Diagnostic::info(
+                "`======` is deeper than the last section level `=====`, the line is a paragraph",
+                span,
+            )]
+        );
+
+        let options = ParseOptions::new().heading_overflow(HeadingOverflow::Compat);
+        let document = Document::parse_with_options(source, &options).unwrap();
+        assert_eq!(
+            document.blocks()[2].heading_level(),
+            Some(&HeadingLevel::Level4)
+        );
+        assert_eq!(document.meta(2).unwrap().has_role("deep"), true);
+        assert_eq!(document.diagnostics()[0].severity, Severity::Info);
+        assert_eq!(document.diagnostics()[0].span, span);
+
+        let options = ParseOptions::new().heading_overflow(HeadingOverflow::Strict);
+        let document = Document::parse_with_options(source, &options).unwrap();
+        assert_eq!(document.blocks()[2].heading_level(), None);
+        assert_eq!(document.diagnostics()[0].severity, Severity::Error);
+
+        let document = Document::parse_with_options(
+            "===== Last
+",
+            &options,
+        )
+        .unwrap();
+        assert_eq!(document.diagnostics(), &[]);
+    }
+
+    #[test]
+    fn test_shared_document_across_threads() {
+        let shared = Document::parse("foo *bar*").unwrap().into_shared();
+
+        let handles = (0..4)
+            .map(|_| {
+                let document = shared.clone();
+                thread::spawn(move || document.blocks().len())
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), 1);
+        }
+        assert_eq!(ArcDocument::ptr_eq(&shared, &shared.clone()), true);
+    }
+
+    #[test]
+    fn test_memory_footprint() {
+        let short = Document::parse("foo").unwrap();
+        let long = Document::parse(&"foo *bar* baz\n\n".repeat(100)).unwrap();
+        assert_eq!(
+            short.memory_footprint() >= std::mem::size_of::<Document>(),
+            true
+        );
+        assert_eq!(
+            long.memory_footprint() > short.memory_footprint() * 10,
+            true
+        );
+
+        let options = ParseOptions::new().lazy_inlines(true);
+        let lazy = Document::parse_with_options("foo *bar*", &options).unwrap();
+        lazy.memory_footprint();
+        match &lazy.blocks()[0] {
+            Block::Paragraph { children } => assert_eq!(children.is_parsed(), false),
+            block => panic!("expected a paragraph, got {:?}", block),
+        }
+    }
+}
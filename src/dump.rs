@@ -0,0 +1,164 @@
+use crate::document::Document;
+use crate::{Block, Inline, ListItem, Span};
+use std::fmt::Write;
+
+const MAX_TEXT_LENGTH: usize = 40;
+
+pub fn dump_tree(document: &Document) -> String {
+    let mut out = String::from("Document\n");
+    for (index, block) in document.blocks().iter().enumerate() {
+        dump_block(&mut out, block, document.span(index), 1);
+    }
+    out
+}
+
+fn dump_block(out: &mut String, block: &Block, span: Option<Span>, depth: usize) {
+    let detail = match block {
+        Block::Heading { level, id, .. } => format!(
+            " level={}{}",
+            level.as_u8(),
+            id.as_ref()
+                .map(|id| format!(" id={}", id))
+                .unwrap_or_default()
+        ),
+        Block::CodeBlock { file_type, .. } => file_type
+            .as_ref()
+            .map(|file_type| format!(" file_type={}", file_type))
+            .unwrap_or_default(),
+        Block::Table { columns, rows, .. } => {
+            format!(" columns={} rows={}", columns.len(), rows.len())
+        }
+        _ => String::new(),
+    };
+    let span = span
+        .map(|span| format!(" @{}..{}", span.start, span.end))
+        .unwrap_or_default();
+    line(out, depth, &format!("{:?}{}{}", block.kind(), detail, span));
+
+    match block {
+        Block::Paragraph { children } => dump_inlines(out, children, depth + 1),
+        Block::Heading { children, .. } | Block::CodeBlock { children, .. } => {
+            dump_inlines(out, children, depth + 1)
+        }
+        Block::Block { children, .. } => {
+            for block in children {
+                dump_block(out, block, None, depth + 1);
+            }
+        }
+        Block::UnorderdList { children } | Block::OrderdList { children } => {
+            for item in children {
+                dump_list_item(out, item, depth + 1);
+            }
+        }
+        Block::Label { children, key } => {
+            line(out, depth + 1, "key:");
+            dump_inlines(out, key, depth + 2);
+            dump_inlines(out, children, depth + 1);
+        }
+        Block::Qanda { question, answer } => {
+            line(out, depth + 1, "question:");
+            dump_inlines(out, question, depth + 2);
+            line(out, depth + 1, "answer:");
+            dump_inlines(out, answer, depth + 2);
+        }
+        _ => {}
+    }
+}
+
+fn dump_list_item(out: &mut String, item: &ListItem, depth: usize) {
+    let children = match item {
+        ListItem::Normal { children, level } => {
+            line(out, depth, &format!("ListItem level={}", level.as_u8()));
+            children
+        }
+        ListItem::Check {
+            children,
+            level,
+            checked,
+        } => {
+            line(
+                out,
+                depth,
+                &format!("CheckItem level={} checked={}", level.as_u8(), checked),
+            );
+            children
+        }
+    };
+    dump_inlines(out, children, depth + 1);
+}
+
+fn dump_inlines(out: &mut String, inlines: &[Inline], depth: usize) {
+    for inline in inlines {
+        dump_inline(out, inline, depth);
+    }
+}
+
+fn dump_inline(out: &mut String, inline: &Inline, depth: usize) {
+    match inline {
+        Inline::Value(value) => line(out, depth, &format!("Value {}", truncate(value))),
+        Inline::Macro {
+            kind,
+            id,
+            attributes,
+        } => {
+            let attributes = attributes.len();
+            line(
+                out,
+                depth,
+                &format!("Macro {}::{} attributes={}", kind, truncate(id), attributes),
+            );
+        }
+        inline => {
+            line(out, depth, &format!("{:?}", inline.kind()));
+            if let Some(child) = inline.child() {
+                dump_inline(out, child, depth + 1);
+            }
+        }
+    }
+}
+
+fn line(out: &mut String, depth: usize, text: &str) {
+    writeln!(out, "{}{}", "  ".repeat(depth), text).unwrap();
+}
+
+fn truncate(text: &str) -> String {
+    if text.chars().count() <= MAX_TEXT_LENGTH {
+        return format!("{:?}", text);
+    }
+    let truncated = text.chars().take(MAX_TEXT_LENGTH).collect::<String>();
+    format!("{:?}...", truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_dump_tree() {
+        let document = Document::parse("== Heading\n\nThis is *bold*\n\n* [x] done").unwrap();
+        assert_eq!(
+            dump_tree(&document),
+            r#"Document
+  Heading level=1 @0..10
+    Value "Heading"
+  BlankBlock @10..12
+  Paragraph @12..26
+    Value "This is "
+    Bold
+      Value "bold"
+  BlankBlock @26..28
+  UnorderdList @28..38
+    CheckItem level=1 checked=true
+      Value "done"
+"#
+        );
+    }
+
+    #[test]
+    fn test_truncate() {
+        let text = "a".repeat(50);
+        assert_eq!(truncate(&text), format!("{:?}...", "a".repeat(40)));
+        assert_eq!(truncate("\n"), r#""\n""#);
+    }
+}
@@ -0,0 +1,395 @@
+use crate::document::Document;
+use crate::symbol::Symbol;
+use crate::text::inline_text;
+use crate::{AttrList, Block, DelimitedKind, FootnoteType, HeadingLevel, Inline, ListItem};
+use std::borrow::Cow;
+use std::collections::VecDeque;
+
+// Container opened by `Event::Start` and closed by the matching `Event::End`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Tag<'a> {
+    Paragraph,
+    Heading {
+        level: &'a HeadingLevel,
+        id: Option<&'a str>,
+    },
+    List {
+        ordered: bool,
+    },
+    // A nested list is the last child of the item it belongs to.
+    Item {
+        checked: Option<bool>,
+    },
+    Label,
+    LabelKey,
+    Qanda,
+    Question,
+    Answer,
+    CodeBlock {
+        language: Option<&'a str>,
+        title: Option<&'a str>,
+    },
+    // Holds the `BlockTitle` and then the nested blocks. Comments produce no events.
+    Block {
+        kind: DelimitedKind,
+    },
+    BlockTitle,
+    Table {
+        title: Option<&'a str>,
+    },
+    TableHead,
+    TableCell,
+    // Holds a `TableCell` for each cell of the row.
+    TableRow,
+    Strong,
+    Emphasis,
+    Monospace,
+    InlineCode,
+    Literal,
+    Mark,
+    Styled {
+        roles: &'a [Symbol],
+    },
+    Lead,
+    Footnote(&'a FootnoteType),
+    // The link text follows as events, it defaults to the URL.
+    Link {
+        url: Cow<'a, str>,
+    },
+    // The alternative text follows as events.
+    Image {
+        url: &'a str,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Event<'a> {
+    Start(Tag<'a>),
+    End(Tag<'a>),
+    Text(Cow<'a, str>),
+    SoftBreak,
+    HardBreak,
+    Rule,
+    PageBreak,
+    // Macros other than links and images, math among them.
+    Macro {
+        kind: &'a str,
+        id: &'a str,
+        attributes: &'a AttrList,
+    },
+}
+
+// Flat stream of events over the blocks of a document, in the shape of pulldown-cmark's
+// `Parser`. Events of a block are produced when the iteration reaches it.
+pub struct Events<'a> {
+    blocks: std::slice::Iter<'a, Block>,
+    pending: VecDeque<Event<'a>>,
+}
+
+impl<'a> Events<'a> {
+    pub fn new(blocks: &'a [Block]) -> Self {
+        Events {
+            blocks: blocks.iter(),
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<'a> Iterator for Events<'a> {
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Event<'a>> {
+        while self.pending.is_empty() {
+            let block = self.blocks.next()?;
+            block_events(&mut self.pending, block);
+        }
+        self.pending.pop_front()
+    }
+}
+
+impl Document {
+    pub fn events(&self) -> Events<'_> {
+        Events::new(self.blocks())
+    }
+}
+
+fn block_events<'a>(out: &mut VecDeque<Event<'a>>, block: &'a Block) {
+    match block {
+        Block::Paragraph { children } => wrap(out, Tag::Paragraph, children),
+        Block::Macro { children } => wrap(out, Tag::Paragraph, std::slice::from_ref(children)),
+        Block::Heading {
+            level,
+            children,
+            id,
+        } => wrap(
+            out,
+            Tag::Heading {
+                level,
+                id: id.as_deref(),
+            },
+            children,
+        ),
+        Block::HorizontalRuledLine => out.push_back(Event::Rule),
+        Block::NextPage => out.push_back(Event::PageBreak),
+        Block::UnorderdList { children } => list_events(out, children, false),
+        Block::OrderdList { children } => list_events(out, children, true),
+        Block::Label { children, key } => {
+            out.push_back(Event::Start(Tag::Label));
+            wrap(out, Tag::LabelKey, key);
+            inline_events(out, children);
+            out.push_back(Event::End(Tag::Label));
+        }
+        Block::Qanda { question, answer } => {
+            out.push_back(Event::Start(Tag::Qanda));
+            wrap(out, Tag::Question, question);
+            wrap(out, Tag::Answer, answer);
+            out.push_back(Event::End(Tag::Qanda));
+        }
+        Block::CodeBlock {
+            children,
+            title,
+            file_type,
+        } => {
+            let tag = Tag::CodeBlock {
+                language: file_type.as_deref(),
+                title: title.as_deref(),
+            };
+            out.push_back(Event::Start(tag.clone()));
+            out.push_back(Event::Text(Cow::Owned(inline_text(children))));
+            out.push_back(Event::End(tag));
+        }
+        Block::Block {
+            kind: DelimitedKind::Comment,
+            ..
+        } => {}
+        Block::Block {
+            kind,
+            children,
+            title,
+            ..
+        } => {
+            out.push_back(Event::Start(Tag::Block { kind: *kind }));
+            if let Some(title) = title {
+                wrap(out, Tag::BlockTitle, title);
+            }
+            for block in children {
+                block_events(out, block);
+            }
+            out.push_back(Event::End(Tag::Block { kind: *kind }));
+        }
+        Block::Table {
+            columns,
+            rows,
+            title,
+        } => {
+            let tag = Tag::Table {
+                title: title.as_deref(),
+            };
+            out.push_back(Event::Start(tag.clone()));
+            if !columns.is_empty() {
+                out.push_back(Event::Start(Tag::TableHead));
+                for column in columns {
+                    out.push_back(Event::Start(Tag::TableCell));
+                    out.push_back(Event::Text(Cow::Borrowed(column.name())));
+                    out.push_back(Event::End(Tag::TableCell));
+                }
+                out.push_back(Event::End(Tag::TableHead));
+            }
+            for row in rows {
+                out.push_back(Event::Start(Tag::TableRow));
+                for cell in row.cells() {
+                    out.push_back(Event::Start(Tag::TableCell));
+                    for block in &cell.blocks {
+                        block_events(out, block);
+                    }
+                    out.push_back(Event::End(Tag::TableCell));
+                }
+                out.push_back(Event::End(Tag::TableRow));
+            }
+            out.push_back(Event::End(tag));
+        }
+        Block::BlankBlock => {}
+    }
+}
+
+fn wrap<'a>(out: &mut VecDeque<Event<'a>>, tag: Tag<'a>, inlines: &'a [Inline]) {
+    out.push_back(Event::Start(tag.clone()));
+    inline_events(out, inlines);
+    out.push_back(Event::End(tag));
+}
+
+// Items carry their level, a deeper item opens a list nested in the item before it.
+fn list_events<'a>(out: &mut VecDeque<Event<'a>>, items: &'a [ListItem], ordered: bool) {
+    let mut items = items.iter().peekable();
+    nested_list_events(out, &mut items, 1, ordered);
+}
+
+fn nested_list_events<'a, I: Iterator<Item = &'a ListItem>>(
+    out: &mut VecDeque<Event<'a>>,
+    items: &mut std::iter::Peekable<I>,
+    level: u32,
+    ordered: bool,
+) {
+    out.push_back(Event::Start(Tag::List { ordered }));
+    while let Some(item) = items.next_if(|item| item.level().max(1) >= level) {
+        let checked = match item {
+            ListItem::Check { checked, .. } => Some(*checked),
+            ListItem::Normal { .. } => None,
+        };
+        out.push_back(Event::Start(Tag::Item { checked }));
+        inline_events(out, item.children());
+        if items.peek().is_some_and(|next| next.level() > level) {
+            nested_list_events(out, items, level + 1, ordered);
+        }
+        out.push_back(Event::End(Tag::Item { checked }));
+    }
+    out.push_back(Event::End(Tag::List { ordered }));
+}
+
+fn inline_events<'a>(out: &mut VecDeque<Event<'a>>, inlines: &'a [Inline]) {
+    for inline in inlines {
+        let (tag, child) = match inline {
+            Inline::Value(value) => {
+                out.push_back(Event::Text(Cow::Borrowed(value)));
+                continue;
+            }
+            Inline::HardBreak => {
+                out.push_back(Event::HardBreak);
+                continue;
+            }
+            Inline::SoftBreak => {
+                out.push_back(Event::SoftBreak);
+                continue;
+            }
+            Inline::Macro {
+                attributes,
+                kind,
+                id,
+            } => {
+                macro_events(out, kind.as_str(), id, attributes);
+                continue;
+            }
+            Inline::Bold { children } => (Tag::Strong, children),
+            Inline::Italic { children } => (Tag::Emphasis, children),
+            Inline::Monospace { children } => (Tag::Monospace, children),
+            Inline::InlineCode { children } => (Tag::InlineCode, children),
+            Inline::Literal { children } => (Tag::Literal, children),
+            Inline::Marker { children } => (Tag::Mark, children),
+            Inline::Styled { roles, children } => (Tag::Styled { roles }, children),
+            Inline::Lead { children } => (Tag::Lead, children),
+            Inline::Footnote { kind, children } => (Tag::Footnote(kind), children),
+        };
+        out.push_back(Event::Start(tag.clone()));
+        inline_events(out, std::slice::from_ref(child.as_ref()));
+        out.push_back(Event::End(tag));
+    }
+}
+
+fn macro_events<'a>(
+    out: &mut VecDeque<Event<'a>>,
+    kind: &'a str,
+    id: &'a str,
+    attributes: &'a AttrList,
+) {
+    let text = attributes.get("alt").or_else(|| attributes.positional(0));
+    let (tag, text) = match kind {
+        "image" => (Tag::Image { url: id }, text),
+        "link" => (
+            Tag::Link {
+                url: Cow::Borrowed(id),
+            },
+            Some(text.unwrap_or(id)),
+        ),
+        "http" | "https" | "mailto" => {
+            let url = format!("{}:{}", kind, id);
+            let text = match text {
+                Some(text) => Cow::Borrowed(text),
+                None => Cow::Owned(url.clone()),
+            };
+            out.push_back(Event::Start(Tag::Link {
+                url: Cow::Owned(url.clone()),
+            }));
+            out.push_back(Event::Text(text));
+            out.push_back(Event::End(Tag::Link {
+                url: Cow::Owned(url),
+            }));
+            return;
+        }
+        _ => {
+            out.push_back(Event::Macro {
+                kind,
+                id,
+                attributes,
+            });
+            return;
+        }
+    };
+    out.push_back(Event::Start(tag.clone()));
+    if let Some(text) = text {
+        out.push_back(Event::Text(Cow::Borrowed(text)));
+    }
+    out.push_back(Event::End(tag));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_events() {
+        let document = Document::parse("== Intro\n\nSome *bold*\n\n* a\n** b\n* c").unwrap();
+        assert_eq!(
+            document.events().collect::<Vec<_>>(),
+            vec![
+                Event::Start(Tag::Heading {
+                    level: &HeadingLevel::Level1,
+                    id: None
+                }),
+                Event::Text("Intro".into()),
+                Event::End(Tag::Heading {
+                    level: &HeadingLevel::Level1,
+                    id: None
+                }),
+                Event::Start(Tag::Paragraph),
+                Event::Text("Some ".into()),
+                Event::Start(Tag::Strong),
+                Event::Text("bold".into()),
+                Event::End(Tag::Strong),
+                Event::End(Tag::Paragraph),
+                Event::Start(Tag::List { ordered: false }),
+                Event::Start(Tag::Item { checked: None }),
+                Event::Text("a".into()),
+                Event::Start(Tag::List { ordered: false }),
+                Event::Start(Tag::Item { checked: None }),
+                Event::Text("b".into()),
+                Event::End(Tag::Item { checked: None }),
+                Event::End(Tag::List { ordered: false }),
+                Event::End(Tag::Item { checked: None }),
+                Event::Start(Tag::Item { checked: None }),
+                Event::Text("c".into()),
+                Event::End(Tag::Item { checked: None }),
+                Event::End(Tag::List { ordered: false }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_link_events() {
+        let attributes = AttrList::from(vec![]);
+        let mut out = VecDeque::new();
+        macro_events(&mut out, "https", "//example.com", &attributes);
+        assert_eq!(
+            out.into_iter().collect::<Vec<_>>(),
+            vec![
+                Event::Start(Tag::Link {
+                    url: "https://example.com".into()
+                }),
+                Event::Text("https://example.com".into()),
+                Event::End(Tag::Link {
+                    url: "https://example.com".into()
+                }),
+            ]
+        );
+    }
+}
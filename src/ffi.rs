@@ -0,0 +1,161 @@
+// C API, declared in `include/combine_sandbox.h`. Strings passed in are NUL terminated UTF-8
+// and are only borrowed for the call. Strings returned are owned by the caller and released
+// with `asciidoc_string_free`. A null return means the call failed, `asciidoc_last_error`
+// then has the reason. Sources are parsed leniently, problems in the document end up in the
+// `diagnostics` of the JSON AST instead of failing the call.
+//
+// Safety: pointer arguments must be null or point to a NUL terminated string, and strings
+// returned by this API must be freed once, with `asciidoc_string_free`.
+#![allow(clippy::missing_safety_doc)]
+
+use crate::document::Document;
+use crate::json::to_json_string;
+use crate::options::ParseOptions;
+use crate::recovery::RecoveryPolicy;
+use crate::render::html::{render_with_options, HtmlOptions, HtmlProfile};
+use crate::slug::SlugStrategy;
+use anyhow::{anyhow, bail, Result};
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+// Flags of `asciidoc_render_html`, combined with `|`.
+pub const ASCIIDOC_HTML_SANITIZE: u32 = 1;
+pub const ASCIIDOC_HTML_ASCIIDOCTOR: u32 = 1 << 1;
+pub const ASCIIDOC_HTML_HEADING_IDS: u32 = 1 << 2;
+pub const ASCIIDOC_HTML_HIDE_TITLE: u32 = 1 << 3;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+// The JSON AST of `input`, null on failure.
+#[no_mangle]
+pub unsafe extern "C" fn asciidoc_parse_to_json(input: *const c_char) -> *mut c_char {
+    call(|| {
+        let document = parse(input)?;
+        Ok(to_json_string(&document))
+    })
+}
+
+// `input` rendered to HTML with the `ASCIIDOC_HTML_*` `flags`, null on failure.
+#[no_mangle]
+pub unsafe extern "C" fn asciidoc_render_html(input: *const c_char, flags: u32) -> *mut c_char {
+    call(|| {
+        let document = parse(input)?;
+        Ok(render_with_options(&document, &html_options(flags)))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn asciidoc_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+// Message of the last failed call on this thread, null when there is none. Valid until the
+// next call on this thread, not to be freed.
+#[no_mangle]
+pub extern "C" fn asciidoc_last_error() -> *const c_char {
+    LAST_ERROR.with(|error| error.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
+
+unsafe fn parse(input: *const c_char) -> Result<Document> {
+    if input.is_null() {
+        bail!("input is null");
+    }
+    let input = CStr::from_ptr(input)
+        .to_str()
+        .map_err(|error| anyhow!("input is not UTF-8: {}", error))?;
+    let options = ParseOptions::new().recovery(RecoveryPolicy::default());
+    Document::parse_with_options(input, &options)
+}
+
+fn html_options(flags: u32) -> HtmlOptions {
+    let mut options = HtmlOptions::new()
+        .sanitize(flags & ASCIIDOC_HTML_SANITIZE != 0)
+        .hide_title(flags & ASCIIDOC_HTML_HIDE_TITLE != 0);
+    if flags & ASCIIDOC_HTML_ASCIIDOCTOR != 0 {
+        options = options.profile(HtmlProfile::Asciidoctor);
+    }
+    if flags & ASCIIDOC_HTML_HEADING_IDS != 0 {
+        options = options.heading_ids(SlugStrategy::default());
+    }
+    options
+}
+
+// Runs `f` and hands its string over to C. Panics must not unwind into the caller, they are
+// reported like errors.
+fn call<F: FnOnce() -> Result<String>>(f: F) -> *mut c_char {
+    let result = match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => result,
+        Err(_) => Err(anyhow!("internal error, the parser panicked")),
+    };
+    let result = result
+        .and_then(|s| CString::new(s).map_err(|_| anyhow!("output contains a NUL character")));
+    match result {
+        Ok(s) => {
+            LAST_ERROR.with(|error| error.borrow_mut().take());
+            s.into_raw()
+        }
+        Err(message) => {
+            let message =
+                CString::new(format!("{:#}", message).replace('\0', "")).unwrap_or_default();
+            LAST_ERROR.with(|error| *error.borrow_mut() = Some(message));
+            ptr::null_mut()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    unsafe fn take(s: *mut c_char) -> Option<String> {
+        if s.is_null() {
+            return None;
+        }
+        let owned = CStr::from_ptr(s).to_str().unwrap().to_string();
+        asciidoc_string_free(s);
+        Some(owned)
+    }
+
+    #[test]
+    fn test_render_html() {
+        let input = CString::new("== Intro\n\nsome *bold*").unwrap();
+        let html = unsafe {
+            take(asciidoc_render_html(
+                input.as_ptr(),
+                ASCIIDOC_HTML_HEADING_IDS,
+            ))
+        };
+        assert_eq!(
+            html.as_deref(),
+            Some("<h2 id=\"_intro\">Intro</h2>\n<p>some <strong>bold</strong></p>\n")
+        );
+        assert_eq!(asciidoc_last_error().is_null(), true);
+    }
+
+    #[test]
+    fn test_errors() {
+        let json = unsafe { take(asciidoc_parse_to_json(ptr::null())) };
+        assert_eq!(json, None);
+        let error = unsafe { CStr::from_ptr(asciidoc_last_error()) };
+        assert_eq!(error.to_str().unwrap(), "input is null");
+
+        let input = CString::new(vec![0xff, 0xfe]).unwrap();
+        assert_eq!(
+            unsafe { asciidoc_parse_to_json(input.as_ptr()) }.is_null(),
+            true
+        );
+
+        let input = CString::new("=broken\n\nok").unwrap();
+        let json = unsafe { take(asciidoc_parse_to_json(input.as_ptr())) }.unwrap();
+        assert_eq!(json.contains("\"diagnostics\""), true);
+        assert_eq!(asciidoc_last_error().is_null(), true);
+    }
+}
@@ -0,0 +1,124 @@
+use crate::{Block, FootnoteType, Inline, TableRow};
+
+// A footnote in document order, numbered from 1.
+#[derive(Debug)]
+pub struct Footnote<'a> {
+    pub number: usize,
+    pub kind: &'a FootnoteType,
+    pub content: &'a Inline,
+    // The `Inline::Footnote` node itself, renderers match on its address to find the number.
+    pub node: &'a Inline,
+}
+
+pub fn collect_footnotes(blocks: &[Block]) -> Vec<Footnote<'_>> {
+    let mut footnotes = vec![];
+    for block in blocks {
+        collect_block(block, &mut footnotes);
+    }
+    footnotes
+}
+
+fn collect_block<'a>(block: &'a Block, footnotes: &mut Vec<Footnote<'a>>) {
+    match block {
+        Block::Paragraph { children } => collect_inlines(children, footnotes),
+        Block::Macro { children } => collect_inlines(std::slice::from_ref(children), footnotes),
+        Block::Heading { children, .. } | Block::CodeBlock { children, .. } => {
+            collect_inlines(children, footnotes)
+        }
+        Block::UnorderdList { children } | Block::OrderdList { children } => {
+            for item in children {
+                collect_inlines(item.children(), footnotes);
+            }
+        }
+        Block::Label { children, key } => {
+            collect_inlines(key, footnotes);
+            collect_inlines(children, footnotes);
+        }
+        Block::Qanda { question, answer } => {
+            collect_inlines(question, footnotes);
+            collect_inlines(answer, footnotes);
+        }
+        Block::Block {
+            children, title, ..
+        } => {
+            if let Some(title) = title {
+                collect_inlines(title, footnotes);
+            }
+            for block in children {
+                collect_block(block, footnotes);
+            }
+        }
+        Block::Table { rows, .. } => {
+            for cell in rows.iter().flat_map(TableRow::cells) {
+                for block in &cell.blocks {
+                    collect_block(block, footnotes);
+                }
+            }
+        }
+        Block::HorizontalRuledLine | Block::NextPage | Block::BlankBlock => {}
+    }
+}
+
+fn collect_inlines<'a>(inlines: &'a [Inline], footnotes: &mut Vec<Footnote<'a>>) {
+    for inline in inlines {
+        collect_inline(inline, footnotes);
+    }
+}
+
+fn collect_inline<'a>(inline: &'a Inline, footnotes: &mut Vec<Footnote<'a>>) {
+    match inline {
+        Inline::Footnote { kind, children } => {
+            footnotes.push(Footnote {
+                number: footnotes.len() + 1,
+                kind,
+                content: children,
+                node: inline,
+            });
+            collect_inline(children, footnotes);
+        }
+        inline => {
+            if let Some(child) = inline.child() {
+                collect_inline(child, footnotes);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ListItem, ListLevel};
+    use pretty_assertions::assert_eq;
+    use smallvec::smallvec;
+
+    #[test]
+    fn test_collect_footnotes() {
+        let footnote = |text: &str| Inline::Footnote {
+            kind: FootnoteType::Note,
+            children: Box::new(Inline::Value(text.to_string())),
+        };
+        let blocks = vec![
+            Block::Paragraph {
+                children: smallvec![Inline::Bold {
+                    children: Box::new(footnote("first")),
+                }]
+                .into(),
+            },
+            Block::UnorderdList {
+                children: vec![ListItem::Normal {
+                    children: smallvec![footnote("second")],
+                    level: ListLevel::Level1,
+                }],
+            },
+        ];
+
+        let footnotes = collect_footnotes(&blocks);
+        assert_eq!(
+            footnotes
+                .iter()
+                .map(|footnote| (footnote.number, footnote.content.as_value()))
+                .collect::<Vec<_>>(),
+            vec![(1, Some("first")), (2, Some("second"))]
+        );
+    }
+}
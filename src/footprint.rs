@@ -0,0 +1,120 @@
+// Heap bytes owned by syntax tree nodes, not counting the node itself. Estimates: allocator
+// overhead isn't counted and hash tables are taken as their capacity of entries. Symbols are
+// shared between documents and left out.
+use crate::symbol::Symbol;
+use crate::{
+    Attr, AttrList, Block, BlockMeta, Inline, InlineVec, ListItem, TableCell, TableColumn, TableRow,
+};
+use std::mem::size_of;
+
+pub(crate) fn block(block: &Block) -> usize {
+    match block {
+        Block::Paragraph { children } => children.heap_size(),
+        Block::Macro { children } => inline(children),
+        Block::Heading { children, id, .. } => {
+            inlines(children) + id.as_ref().map_or(0, String::capacity)
+        }
+        Block::UnorderdList { children } | Block::OrderdList { children } => {
+            children.capacity() * size_of::<ListItem>()
+                + children
+                    .iter()
+                    .map(|item| match item {
+                        ListItem::Normal { children, .. } | ListItem::Check { children, .. } => {
+                            inlines(children)
+                        }
+                    })
+                    .sum::<usize>()
+        }
+        Block::Label { children, key } => inlines(children) + size_of::<InlineVec>() + inlines(key),
+        Block::Qanda { question, answer } => {
+            inlines(question) + size_of::<InlineVec>() + inlines(answer)
+        }
+        Block::CodeBlock {
+            children, title, ..
+        } => inlines(children) + title.as_ref().map_or(0, String::capacity),
+        Block::Block {
+            children,
+            metas,
+            title,
+            ..
+        } => {
+            children.capacity() * size_of::<Block>()
+                + children.iter().map(self::block).sum::<usize>()
+                + self::metas(metas)
+                + title
+                    .as_ref()
+                    .map_or(0, |title| size_of::<InlineVec>() + inlines(title))
+        }
+        Block::Table {
+            columns,
+            rows,
+            title,
+        } => {
+            columns.capacity() * size_of::<TableColumn>()
+                + columns
+                    .iter()
+                    .map(|column| column.name.capacity())
+                    .sum::<usize>()
+                + rows.capacity() * size_of::<TableRow>()
+                + rows
+                    .iter()
+                    .map(|row| {
+                        row.cells.capacity() * size_of::<TableCell>()
+                            + row
+                                .cells
+                                .iter()
+                                .map(|cell| {
+                                    cell.blocks.capacity() * size_of::<Block>()
+                                        + cell.blocks.iter().map(self::block).sum::<usize>()
+                                        + self::metas(&cell.metas)
+                                })
+                                .sum::<usize>()
+                    })
+                    .sum::<usize>()
+                + title.as_ref().map_or(0, String::capacity)
+        }
+        Block::HorizontalRuledLine | Block::NextPage | Block::BlankBlock => 0,
+    }
+}
+
+fn metas(metas: &Vec<Option<BlockMeta>>) -> usize {
+    metas.capacity() * size_of::<Option<BlockMeta>>()
+        + metas.iter().flatten().map(meta).sum::<usize>()
+}
+
+pub(crate) fn inlines(inlines: &InlineVec) -> usize {
+    let spilled = if inlines.spilled() {
+        inlines.capacity() * size_of::<Inline>()
+    } else {
+        0
+    };
+    spilled + inlines.iter().map(inline).sum::<usize>()
+}
+
+fn inline(inline: &Inline) -> usize {
+    match inline {
+        Inline::Value(text) => text.capacity(),
+        Inline::Macro { attributes, id, .. } => {
+            size_of::<AttrList>() + self::attributes(attributes) + id.capacity()
+        }
+        inline => inline
+            .child()
+            .map_or(0, |child| size_of::<Inline>() + self::inline(child)),
+    }
+}
+
+pub(crate) fn meta(meta: &BlockMeta) -> usize {
+    meta.id.as_ref().map_or(0, String::capacity)
+        + (meta.roles.capacity() + meta.options.capacity()) * size_of::<Symbol>()
+        + attributes(&meta.attributes)
+}
+
+fn attributes(attributes: &AttrList) -> usize {
+    attributes.len() * size_of::<Attr>()
+        + attributes
+            .iter()
+            .map(|attr| match attr {
+                Attr::Positional(value) | Attr::Named(_, value) => value.capacity(),
+            })
+            .sum::<usize>()
+}
@@ -0,0 +1,89 @@
+use crate::document::Document;
+use crate::render::asciidoc::{self, AsciiDocOptions};
+use anyhow::Result;
+
+#[derive(Debug, Clone, Default)]
+pub struct FormatOptions {
+    // Paragraph fill width, `None` keeps the author's line breaks.
+    pub width: Option<usize>,
+}
+
+impl FormatOptions {
+    pub fn new() -> Self {
+        FormatOptions::default()
+    }
+
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+}
+
+// Normalize AsciiDoc source: one blank line between blocks, `= ` heading markers, list markers
+// without indentation, attribute lines directly above their block and aligned table headers.
+//
+// The source is not preprocessed, so include directives and attribute references are kept as
+// written.
+pub fn format(source: &str, options: &FormatOptions) -> Result<String> {
+    let document = Document::parse(source)?;
+    Ok(asciidoc::render(
+        &document,
+        &AsciiDocOptions {
+            wrap_width: options.width,
+        },
+    ))
+}
+
+// Whether `format` would leave the source unchanged.
+pub fn is_formatted(source: &str, options: &FormatOptions) -> Result<bool> {
+    Ok(format(source, options)? == source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_format() {
+        let source = "==   Section\n\n\n\n[source,rust]\nThe {product} manual\nis long\n\n:version: 1\n:author: himanoa\n\n*   foo\n**  bar";
+        assert_eq!(
+            format(source, &FormatOptions::new().width(40)).unwrap(),
+            "== Section\n\n[source,rust]\nThe {product} manual is long\n\n:version: 1\n:author: himanoa\n\n* foo\n** bar\n"
+        );
+    }
+
+    #[test]
+    fn test_format_keeps_meaning() {
+        let options = FormatOptions::new().width(5);
+        assert_eq!(
+            format("alpha ---- beta <<< gamma + delta", &options).unwrap(),
+            "alpha ----\nbeta <<<\ngamma + delta\n"
+        );
+        assert_eq!(
+            format("= Title\n:toc:\n:author: me\n\nBody", &options).unwrap(),
+            "= Title\n:toc:\n:author: me\n\nBody\n"
+        );
+        assert_eq!(
+            format("image::logo.png[Logo]\n\nimage:icon.png[]", &options).unwrap(),
+            "image::logo.png[Logo]\n\nimage:icon.png[]\n"
+        );
+        for source in ["* a\n* b\n\npara", "* a\n* b\n\n\n\n. c\n\npara"] {
+            let formatted = format(source, &FormatOptions::new()).unwrap();
+            assert_eq!(
+                format(&formatted, &FormatOptions::new()).unwrap(),
+                formatted
+            );
+        }
+        assert_eq!(
+            format("* a\n* b\n\n\n\n. c\n\npara", &FormatOptions::new()).unwrap(),
+            "* a\n* b\n\n. c\n\npara\n"
+        );
+    }
+
+    #[test]
+    fn test_is_formatted() {
+        assert_eq!(is_formatted("== A\n", &FormatOptions::new()).unwrap(), true);
+        assert_eq!(is_formatted("==  A", &FormatOptions::new()).unwrap(), false);
+    }
+}
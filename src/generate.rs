@@ -0,0 +1,204 @@
+// Synthetic documents for benchmarks. The output only depends on the options, so numbers
+// measured with the same options are comparable across machines and commits.
+
+const WORDS: [&str; 16] = [
+    "parser",
+    "combine",
+    "document",
+    "section",
+    "stream",
+    "value",
+    "block",
+    "inline",
+    "heading",
+    "attribute",
+    "render",
+    "source",
+    "grammar",
+    "list",
+    "token",
+    "span",
+];
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DocumentSize {
+    // A README, about 4 KB.
+    Small,
+    // A chapter, about 100 KB.
+    Medium,
+    // A book, about 2 MB.
+    Large,
+}
+
+impl DocumentSize {
+    pub const ALL: [DocumentSize; 3] = [
+        DocumentSize::Small,
+        DocumentSize::Medium,
+        DocumentSize::Large,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DocumentSize::Small => "small",
+            DocumentSize::Medium => "medium",
+            DocumentSize::Large => "large",
+        }
+    }
+
+    pub fn sections(self) -> usize {
+        match self {
+            DocumentSize::Small => 4,
+            DocumentSize::Medium => 100,
+            DocumentSize::Large => 2000,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GenerateOptions {
+    pub sections: usize,
+    // Changes the words, not the shape of the document.
+    pub seed: u64,
+}
+
+impl Default for GenerateOptions {
+    fn default() -> Self {
+        GenerateOptions {
+            sections: DocumentSize::Medium.sections(),
+            seed: 1,
+        }
+    }
+}
+
+impl GenerateOptions {
+    pub fn new() -> Self {
+        GenerateOptions::default()
+    }
+
+    pub fn size(mut self, size: DocumentSize) -> Self {
+        self.sections = size.sections();
+        self
+    }
+
+    pub fn sections(mut self, sections: usize) -> Self {
+        self.sections = sections;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+// A document mixing the constructs the grammar knows: nested sections, paragraphs with inline
+// formatting, lists, description lists, attribute entries and references, and page breaks.
+pub fn generate(options: &GenerateOptions) -> String {
+    let mut words = Words(options.seed.max(1));
+    let mut out = String::from(":product: Sandbox\n:version: 1.0\n\n= Synthetic document\n\n");
+    for section in 0..options.sections {
+        let level = 1 + section % 3;
+        out.push_str(&format!(
+            "{} {}\n\n",
+            "=".repeat(level + 1),
+            words.sentence(3)
+        ));
+        out.push_str(&words.paragraph());
+        out.push_str("\n\n");
+        match section % 4 {
+            0 => {
+                for item in 0..5 {
+                    let marker = if item % 3 == 2 { "**" } else { "*" };
+                    out.push_str(&format!("{} {}\n", marker, words.sentence(4)));
+                }
+            }
+            1 => {
+                for _ in 0..4 {
+                    out.push_str(&format!(". {}\n", words.sentence(5)));
+                }
+            }
+            2 => {
+                for _ in 0..3 {
+                    out.push_str(&format!("{}:: {}\n", words.word(), words.sentence(6)));
+                }
+            }
+            _ => out.push_str("<<<\n"),
+        }
+        // Paragraphs after the block, a list directly followed by a heading swallows it.
+        for _ in 0..2 {
+            out.push('\n');
+            out.push_str(&words.paragraph());
+            out.push('\n');
+        }
+        out.push('\n');
+    }
+    out
+}
+
+// xorshift, deterministic and good enough to vary the text.
+struct Words(u64);
+
+impl Words {
+    fn next(&mut self) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 as usize
+    }
+
+    fn word(&mut self) -> &'static str {
+        WORDS[self.next() % WORDS.len()]
+    }
+
+    fn sentence(&mut self, length: usize) -> String {
+        (0..length)
+            .map(|_| self.word())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn paragraph(&mut self) -> String {
+        let mut out = vec![];
+        for index in 0..40 {
+            let word = self.word();
+            // Formatting never starts a line, where `*` would be a list marker.
+            let line_start = index % 12 == 0;
+            out.push(match self.next() % 12 {
+                _ if line_start => word.to_string(),
+                0 => format!("*{}*", word),
+                1 => format!("_{}_", word),
+                2 => format!("`{}`", word),
+                3 => "{product}".to_string(),
+                _ => word.to_string(),
+            });
+            // A few lines per paragraph, like wrapped prose.
+            if index % 12 == 11 {
+                out.push("\n".to_string());
+            }
+        }
+        out.join(" ").replace(" \n ", "\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::Metrics;
+    use crate::Document;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_generate() {
+        let options = GenerateOptions::new().size(DocumentSize::Small);
+        let source = generate(&options);
+        assert_eq!(source, generate(&options));
+        assert_eq!(source == generate(&options.clone().seed(2)), false);
+
+        let document = Document::parse_with_options(&source, &Default::default()).unwrap();
+        let metrics = Metrics::of(&document);
+        // The sections and the document title.
+        assert_eq!(metrics.headings, DocumentSize::Small.sections() + 1);
+        assert_eq!(metrics.lists, 2);
+        assert_eq!(crate::render::html::render(&document).contains("<strong>"), true);
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{
+    CHECKBOX_MARKS, HEADING_LEVELS, HORIZONTAL_RULE, INLINE_MARKS, LIST_ITEM_MARKS,
+    ORDERED_LIST_MARK, UNORDERED_LIST_MARK,
+};
+use serde_json::{json, Value};
+
+pub const SCOPE_NAME: &str = "text.asciidoc";
+
+// TextMate grammar (the JSON form VS Code and most editors load) built from the marks the
+// parser accepts, so highlighting matches what ends up in the AST: a span opens with one or two
+// marks, `#` only highlights outside of list items and headings stop at five `=`.
+pub fn textmate_grammar() -> Value {
+    let checkbox = format!("[{}]", regex_escape(CHECKBOX_MARKS));
+    let list_marks = format!(
+        "{}+|{}+",
+        regex_escape(&UNORDERED_LIST_MARK.to_string()),
+        regex_escape(&ORDERED_LIST_MARK.to_string())
+    );
+    json!({
+        "$schema": "https://raw.githubusercontent.com/martinring/tmlanguage/master/tmlanguage.json",
+        "name": "AsciiDoc",
+        "scopeName": SCOPE_NAME,
+        "fileTypes": ["adoc", "asciidoc", "asc"],
+        "patterns": [
+            {"include": "#heading"},
+            {"include": "#horizontal_rule"},
+            {"include": "#list_item"},
+            {"include": "#attribute_line"},
+            {"include": "#inline"},
+        ],
+        "repository": {
+            "heading": {
+                "name": "markup.heading.asciidoc",
+                "begin": format!("^(={{1,{}}})( +)", HEADING_LEVELS),
+                "end": "$",
+                "beginCaptures": {"1": {"name": "punctuation.definition.heading.asciidoc"}},
+                "patterns": [{"include": "#inline"}],
+            },
+            "horizontal_rule": {
+                "name": "meta.separator.asciidoc",
+                "match": format!("^{}", regex_escape(HORIZONTAL_RULE)),
+            },
+            "list_item": {
+                "name": "markup.list.asciidoc",
+                "begin": format!("^({})(?:[ \\t]+(\\[{}\\])(?=[ \\t]))?[ \\t]*", list_marks, checkbox),
+                "end": "$",
+                "beginCaptures": {
+                    "1": {"name": "punctuation.definition.list.begin.asciidoc"},
+                    "2": {"name": "constant.other.checkbox.asciidoc"},
+                },
+                "patterns": [{"include": "#list_item_inline"}],
+            },
+            "attribute_line": {
+                "name": "markup.meta.attribute-list.asciidoc",
+                "match": "^\\[[^\\]\\n]*\\]$",
+            },
+            "inline": {"patterns": inline_patterns(INLINE_MARKS)},
+            "list_item_inline": {"patterns": inline_patterns(LIST_ITEM_MARKS)},
+        },
+    })
+}
+
+// Spans of the formatting marks in `marks`, literal monospace before the monospace span it would
+// otherwise be taken for.
+fn inline_patterns(marks: &str) -> Vec<Value> {
+    let mut patterns = vec![];
+    if marks.contains('`') {
+        patterns.push(json!({
+            "name": "markup.inline.raw.code.asciidoc",
+            "match": "`\\+[^\\n]+?\\+`",
+        }));
+    }
+    for mark in marks.chars() {
+        let scope = match mark {
+            '*' => "markup.bold.asciidoc",
+            '_' => "markup.italic.asciidoc",
+            '`' => "markup.inline.raw.asciidoc",
+            '#' => "markup.highlight.asciidoc",
+            _ => continue,
+        };
+        let mark = regex_escape(&mark.to_string());
+        patterns.push(json!({
+            "name": scope,
+            "match": format!("({0}{{1,2}})[^{0}\\n]+({0}{{1,2}})", mark),
+            "captures": {
+                "1": {"name": "punctuation.definition.begin.asciidoc"},
+                "2": {"name": "punctuation.definition.end.asciidoc"},
+            },
+        }));
+    }
+    patterns.push(json!({
+        "name": "constant.other.hard-break.asciidoc",
+        "match": " \\+$",
+    }));
+    patterns
+}
+
+fn regex_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\^$.|?*+()[]{}#".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_textmate_grammar() {
+        let grammar = textmate_grammar();
+        let repository = &grammar["repository"];
+        assert_eq!(repository["heading"]["begin"], "^(={1,5})( +)");
+        assert_eq!(
+            repository["list_item"]["begin"],
+            "^(\\*+|\\.+)(?:[ \\t]+(\\[[\\*x ]\\])(?=[ \\t]))?[ \\t]*"
+        );
+        let scopes = |name: &str| {
+            repository[name]["patterns"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|pattern| pattern["name"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            scopes("inline").contains(&"markup.highlight.asciidoc".to_string()),
+            true
+        );
+        assert_eq!(
+            scopes("list_item_inline").contains(&"markup.highlight.asciidoc".to_string()),
+            false
+        );
+        assert_eq!(
+            repository["inline"]["patterns"][1]["match"],
+            "(\\*{1,2})[^\\*\\n]+(\\*{1,2})"
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Source of the text referenced by `include::target[]` directives.
+pub trait IncludeResolver: Send + Sync {
+    fn resolve(&self, target: &str) -> Result<String>;
+}
+
+pub struct FileSystemResolver {
+    base_dir: PathBuf,
+    // Tried in order when the target is not found relative to `base_dir`.
+    search_paths: Vec<PathBuf>,
+    // Files outside this directory can't be included.
+    jail: Option<PathBuf>,
+}
+
+impl FileSystemResolver {
+    pub fn new<P: Into<PathBuf>>(base_dir: P) -> Self {
+        FileSystemResolver {
+            base_dir: base_dir.into(),
+            search_paths: vec![],
+            jail: None,
+        }
+    }
+
+    pub fn search_path<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.search_paths.push(dir.into());
+        self
+    }
+
+    pub fn jail<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.jail = Some(dir.into());
+        self
+    }
+
+    fn check_jail(&self, path: &Path) -> Result<()> {
+        let jail = match &self.jail {
+            Some(jail) => jail,
+            None => return Ok(()),
+        };
+        let canonical = |path: &Path| {
+            path.canonicalize()
+                .with_context(|| format!("failed to include {}", path.display()))
+        };
+        if !canonical(path)?.starts_with(canonical(jail)?) {
+            bail!(
+                "refusing to include {}, it is outside {}",
+                path.display(),
+                jail.display()
+            );
+        }
+        Ok(())
+    }
+}
+
+impl IncludeResolver for FileSystemResolver {
+    fn resolve(&self, target: &str) -> Result<String> {
+        let path = std::iter::once(&self.base_dir)
+            .chain(&self.search_paths)
+            .map(|dir| dir.join(target))
+            .find(|path| path.is_file())
+            .unwrap_or_else(|| self.base_dir.join(target));
+        self.check_jail(&path)?;
+        fs::read_to_string(&path).with_context(|| format!("failed to include {}", path.display()))
+    }
+}
+
+#[derive(Default)]
+pub struct InMemoryResolver {
+    files: HashMap<String, String>,
+}
+
+impl InMemoryResolver {
+    pub fn new() -> Self {
+        InMemoryResolver::default()
+    }
+
+    pub fn insert<K: Into<String>, V: Into<String>>(&mut self, target: K, content: V) {
+        self.files.insert(target.into(), content.into());
+    }
+}
+
+impl IncludeResolver for InMemoryResolver {
+    fn resolve(&self, target: &str) -> Result<String> {
+        self.files
+            .get(target)
+            .cloned()
+            .ok_or_else(|| anyhow!("include target not found: {}", target))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_in_memory_resolver() {
+        let mut resolver = InMemoryResolver::new();
+        resolver.insert("chapter.adoc", "== Chapter");
+
+        assert_eq!(resolver.resolve("chapter.adoc").unwrap(), "== Chapter");
+        assert_eq!(resolver.resolve("missing.adoc").is_err(), true);
+    }
+
+    #[test]
+    fn test_file_system_resolver() {
+        let root = std::env::temp_dir().join("combine-sandbox-include");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::create_dir_all(root.join("partials")).unwrap();
+        fs::write(root.join("partials/note.adoc"), "note").unwrap();
+        fs::write(root.join("secret.adoc"), "secret").unwrap();
+
+        let resolver = FileSystemResolver::new(root.join("docs"))
+            .search_path(root.join("partials"))
+            .jail(&root);
+        assert_eq!(resolver.resolve("note.adoc").unwrap(), "note");
+        assert_eq!(resolver.resolve("../secret.adoc").unwrap(), "secret");
+
+        let resolver = resolver.jail(root.join("docs"));
+        assert_eq!(resolver.resolve("../secret.adoc").is_err(), true);
+        assert_eq!(resolver.resolve("note.adoc").is_err(), true);
+    }
+}
@@ -0,0 +1,434 @@
+use crate::diagnostic::Diagnostic;
+use crate::document::Document;
+use crate::symbol::Symbol;
+use crate::{Attr, AttrList, Block, BlockMeta, FootnoteType, Inline, ListItem, Span, TableRow};
+use serde_json::{json, Map, Value};
+
+// Bumped on any incompatible change to the shape described by `JSON_SCHEMA`.
+pub const JSON_AST_VERSION: u32 = 2;
+
+pub const JSON_SCHEMA: &str = include_str!("../schema/ast-v2.schema.json");
+
+pub fn to_json_ast(document: &Document) -> Value {
+    let blocks = document
+        .blocks()
+        .iter()
+        .enumerate()
+        .map(|(index, block)| {
+            let mut value = block_to_json(block);
+            if let Some(meta) = document.meta(index) {
+                value["meta"] = meta_to_json(meta);
+            }
+            if let Some(span) = document.span(index) {
+                value["span"] = span_to_json(span);
+            }
+            value
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "version": JSON_AST_VERSION,
+        "attributes": document.attributes(),
+        "blocks": blocks,
+        "diagnostics": document.diagnostics().iter().map(diagnostic_to_json).collect::<Vec<_>>(),
+    })
+}
+
+pub fn to_json_string(document: &Document) -> String {
+    serde_json::to_string_pretty(&to_json_ast(document)).unwrap()
+}
+
+// Value of the `type` field of a block node.
+pub(crate) fn block_type_name(block: &Block) -> &'static str {
+    match block {
+        Block::Paragraph { .. } => "paragraph",
+        Block::Heading { .. } => "heading",
+        Block::HorizontalRuledLine => "horizontal_rule",
+        Block::NextPage => "page_break",
+        Block::UnorderdList { .. } => "unordered_list",
+        Block::OrderdList { .. } => "ordered_list",
+        Block::Label { .. } => "label",
+        Block::Qanda { .. } => "qanda",
+        Block::CodeBlock { .. } => "code_block",
+        Block::Block { .. } => "open_block",
+        Block::Table { .. } => "table",
+        Block::Macro { .. } => "block_macro",
+        Block::BlankBlock => "blank",
+    }
+}
+
+pub(crate) fn block_to_json(block: &Block) -> Value {
+    match block {
+        Block::Paragraph { children } => json!({
+            "type": block_type_name(block),
+            "children": inlines_to_json(children),
+        }),
+        Block::Macro { children } => json!({
+            "type": block_type_name(block),
+            "macro": inline_to_json(children),
+        }),
+        Block::Heading {
+            level,
+            children,
+            id,
+        } => json!({
+            "type": block_type_name(block),
+            "level": level.as_u8(),
+            "id": id,
+            "children": inlines_to_json(children),
+        }),
+        Block::HorizontalRuledLine => json!({ "type": block_type_name(block) }),
+        Block::NextPage => json!({ "type": block_type_name(block) }),
+        Block::UnorderdList { children } => json!({
+            "type": block_type_name(block),
+            "items": children.iter().map(list_item_to_json).collect::<Vec<_>>(),
+        }),
+        Block::OrderdList { children } => json!({
+            "type": block_type_name(block),
+            "items": children.iter().map(list_item_to_json).collect::<Vec<_>>(),
+        }),
+        Block::Label { children, key } => json!({
+            "type": block_type_name(block),
+            "key": inlines_to_json(key),
+            "children": inlines_to_json(children),
+        }),
+        Block::Qanda { question, answer } => json!({
+            "type": block_type_name(block),
+            "question": inlines_to_json(question),
+            "answer": inlines_to_json(answer),
+        }),
+        Block::CodeBlock {
+            children,
+            title,
+            file_type,
+        } => json!({
+            "type": block_type_name(block),
+            "title": title,
+            "language": file_type.as_deref(),
+            "children": inlines_to_json(children),
+        }),
+        Block::Block {
+            kind,
+            children,
+            metas,
+            title,
+        } => json!({
+            "type": block_type_name(block),
+            "kind": kind.name(),
+            "title": title.as_ref().map(|title| inlines_to_json(title)),
+            "children": blocks_to_json(children, metas),
+        }),
+        Block::Table {
+            columns,
+            rows,
+            title,
+        } => json!({
+            "type": block_type_name(block),
+            "title": title,
+            "columns": columns.iter().map(|column| column.name()).collect::<Vec<_>>(),
+            "rows": rows.iter().map(row_to_json).collect::<Vec<_>>(),
+        }),
+        Block::BlankBlock => json!({ "type": block_type_name(block) }),
+    }
+}
+
+// Nested blocks with the attribute lines written above them.
+fn blocks_to_json(blocks: &[Block], metas: &[Option<BlockMeta>]) -> Vec<Value> {
+    blocks
+        .iter()
+        .enumerate()
+        .map(|(index, block)| {
+            let mut value = block_to_json(block);
+            if let Some(Some(meta)) = metas.get(index) {
+                value["meta"] = meta_to_json(meta);
+            }
+            value
+        })
+        .collect()
+}
+
+fn meta_to_json(meta: &BlockMeta) -> Value {
+    json!({
+        "style": meta.style.as_ref().map(Symbol::as_str),
+        "id": meta.id,
+        "roles": meta.roles.iter().map(Symbol::as_str).collect::<Vec<_>>(),
+        "options": meta.options.iter().map(Symbol::as_str).collect::<Vec<_>>(),
+        "attributes": attributes_to_json(&meta.attributes),
+    })
+}
+
+fn row_to_json(row: &TableRow) -> Value {
+    let cells = row
+        .cells()
+        .iter()
+        .map(|cell| {
+            let mut value = json!({
+                "style": cell.style.as_ref().map(Symbol::as_str),
+                "blocks": blocks_to_json(&cell.blocks, &cell.metas),
+            });
+            if let Some(span) = cell.span {
+                value["span"] = span_to_json(span);
+            }
+            value
+        })
+        .collect::<Vec<_>>();
+    json!({ "cells": cells })
+}
+
+fn list_item_to_json(item: &ListItem) -> Value {
+    let checked = match item {
+        ListItem::Normal { .. } => None,
+        ListItem::Check { checked, .. } => Some(*checked),
+    };
+    json!({
+        "type": "list_item",
+        "level": item.level(),
+        "checked": checked,
+        "children": inlines_to_json(item.children()),
+    })
+}
+
+fn inlines_to_json(inlines: &[Inline]) -> Vec<Value> {
+    inlines.iter().map(inline_to_json).collect()
+}
+
+// Value of the `type` field of an inline node.
+pub(crate) fn inline_type_name(inline: &Inline) -> &'static str {
+    match inline {
+        Inline::Value(_) => "text",
+        Inline::HardBreak => "hard_break",
+        Inline::SoftBreak => "soft_break",
+        Inline::Literal { .. } => "literal",
+        Inline::Footnote { .. } => "footnote",
+        Inline::Lead { .. } => "lead",
+        Inline::Bold { .. } => "bold",
+        Inline::Italic { .. } => "italic",
+        Inline::Monospace { .. } => "monospace",
+        Inline::Marker { .. } => "marker",
+        Inline::Styled { .. } => "styled",
+        Inline::InlineCode { .. } => "inline_code",
+        Inline::Macro { .. } => "macro",
+    }
+}
+
+fn inline_to_json(inline: &Inline) -> Value {
+    let kind = inline_type_name(inline);
+    if let Some(child) = inline.child() {
+        let mut value = json!({
+            "type": kind,
+            "children": [inline_to_json(child)],
+        });
+        if let Inline::Footnote { kind, .. } = inline {
+            value["kind"] = json!(footnote_type_name(kind));
+        }
+        if let Inline::Styled { roles, .. } = inline {
+            value["roles"] = json!(roles.iter().map(Symbol::as_str).collect::<Vec<_>>());
+        }
+        return value;
+    }
+    match inline {
+        Inline::Value(value) => json!({ "type": kind, "value": value }),
+        Inline::Macro {
+            attributes,
+            kind: name,
+            id,
+        } => json!({
+            "type": kind,
+            "name": name.as_str(),
+            "target": id,
+            "attributes": attributes_to_json(attributes),
+        }),
+        _ => json!({ "type": kind }),
+    }
+}
+
+// `named` is keyed by name and loses the order, `entries` has every entry as written.
+fn attributes_to_json(attributes: &AttrList) -> Value {
+    let named = attributes
+        .named()
+        .map(|(key, value)| (key.to_string(), json!(value)))
+        .collect::<Map<_, _>>();
+    let entries = attributes
+        .iter()
+        .map(|attr| match attr {
+            Attr::Positional(value) => json!({ "value": value }),
+            Attr::Named(key, value) => json!({ "name": key.as_str(), "value": value }),
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "positional": attributes.positionals().collect::<Vec<_>>(),
+        "named": named,
+        "entries": entries,
+    })
+}
+
+fn footnote_type_name(kind: &FootnoteType) -> &'static str {
+    match kind {
+        FootnoteType::Note => "note",
+        FootnoteType::Tip => "tip",
+        FootnoteType::Important => "important",
+        FootnoteType::Warning => "warning",
+        FootnoteType::Caution => "caution",
+    }
+}
+
+fn span_to_json(span: Span) -> Value {
+    json!({ "start": span.start, "end": span.end })
+}
+
+fn diagnostic_to_json(diagnostic: &Diagnostic) -> Value {
+    json!({
+        "severity": diagnostic.severity.to_string(),
+        "message": diagnostic.message,
+        "span": span_to_json(diagnostic.span),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TableCell;
+    use pretty_assertions::assert_eq;
+    use smallvec::smallvec;
+
+    #[test]
+    fn test_to_json_ast() {
+        let document = Document::parse("== Head\n\n* [x] *done*").unwrap();
+        assert_eq!(
+            to_json_ast(&document),
+            json!({
+                "version": 2,
+                "attributes": {},
+                "blocks": [
+                    {
+                        "type": "heading",
+                        "level": 1,
+                        "id": null,
+                        "children": [{ "type": "text", "value": "Head" }],
+                        "span": { "start": 0, "end": 7 }
+                    },
+                    { "type": "blank", "span": { "start": 7, "end": 9 } },
+                    {
+                        "type": "unordered_list",
+                        "items": [{
+                            "type": "list_item",
+                            "level": 1,
+                            "checked": true,
+                            "children": [{
+                                "type": "bold",
+                                "children": [{ "type": "text", "value": "done" }]
+                            }]
+                        }],
+                        "span": { "start": 9, "end": 21 }
+                    }
+                ],
+                "diagnostics": []
+            })
+        );
+    }
+
+    #[test]
+    fn test_delimited_block_to_json() {
+        let blocks = crate::parse("____\nquoted\n____").unwrap();
+        assert_eq!(
+            block_to_json(&blocks[0]),
+            json!({
+                "type": "open_block",
+                "kind": "quote",
+                "title": null,
+                "children": [{
+                    "type": "paragraph",
+                    "children": [{ "type": "text", "value": "quoted" }]
+                }]
+            })
+        );
+    }
+
+    #[test]
+    fn test_meta_to_json() {
+        let document =
+            Document::parse("[quote#q.big%open]\n____\n[source,rust]\n----\nfn\n----\n____")
+                .unwrap();
+        let value = to_json_ast(&document);
+        assert_eq!(
+            value["blocks"][0]["meta"],
+            json!({
+                "style": "quote",
+                "id": "q",
+                "roles": ["big"],
+                "options": ["open"],
+                "attributes": {
+                    "positional": ["quote#q.big%open"],
+                    "named": {},
+                    "entries": [{ "value": "quote#q.big%open" }]
+                }
+            })
+        );
+        assert_eq!(
+            value["blocks"][0]["children"][0]["meta"]["attributes"]["positional"],
+            json!(["source", "rust"])
+        );
+    }
+
+    #[test]
+    fn test_attributes_to_json() {
+        let mut attributes = AttrList::new();
+        attributes.push_named("width", "80");
+        attributes.push_positional("Logo");
+        attributes.push_named("alt", "a");
+        assert_eq!(
+            attributes_to_json(&attributes),
+            json!({
+                "positional": ["Logo"],
+                "named": { "alt": "a", "width": "80" },
+                "entries": [
+                    { "name": "width", "value": "80" },
+                    { "value": "Logo" },
+                    { "name": "alt", "value": "a" }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_row_to_json() {
+        let row = TableRow::new(vec![
+            TableCell::new(vec![Block::Paragraph {
+                children: smallvec![Inline::Value("foo".to_string())].into(),
+            }]),
+            TableCell {
+                style: Some("a".into()),
+                span: Some(Span { start: 4, end: 7 }),
+                ..TableCell::new(vec![])
+            },
+        ]);
+        assert_eq!(
+            row_to_json(&row),
+            json!({
+                "cells": [
+                    {
+                        "style": null,
+                        "blocks": [{
+                            "type": "paragraph",
+                            "children": [{ "type": "text", "value": "foo" }]
+                        }]
+                    },
+                    {
+                        "style": "a",
+                        "blocks": [],
+                        "span": { "start": 4, "end": 7 }
+                    }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_schema_is_valid_json() {
+        let schema: Value = serde_json::from_str(JSON_SCHEMA).unwrap();
+        assert_eq!(
+            schema["properties"]["version"]["const"],
+            json!(JSON_AST_VERSION)
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{parse_inlines, Inline, InlineVec};
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::{Deref, DerefMut};
+use std::sync::OnceLock;
+
+// Inline children of a paragraph. Parsed with `ParseOptions::lazy_inlines` a paragraph keeps
+// its source text, the inlines are parsed the first time they are looked at. Tools that only
+// walk the block structure never pay for them. Derefs to the inlines either way.
+pub struct LazyInlines {
+    raw: Option<Box<str>>,
+    triple_backtick_code: bool,
+    parsed: OnceLock<InlineVec>,
+}
+
+impl LazyInlines {
+    // Inlines parsed from `raw` on first access.
+    pub fn raw<S: Into<Box<str>>>(raw: S) -> Self {
+        LazyInlines {
+            raw: Some(raw.into()),
+            triple_backtick_code: false,
+            parsed: OnceLock::new(),
+        }
+    }
+
+    // Parse the text with `ParseOptions::triple_backtick_code`.
+    pub(crate) fn triple_backtick_code(mut self, triple_backtick_code: bool) -> Self {
+        self.triple_backtick_code = triple_backtick_code;
+        self
+    }
+
+    // Source text of inlines that haven't been parsed yet.
+    pub fn source(&self) -> Option<&str> {
+        match self.parsed.get() {
+            Some(_) => None,
+            None => self.raw.as_deref(),
+        }
+    }
+
+    // Verbatim text for blocks whose content isn't AsciiDoc (source, diagrams): the source when
+    // it wasn't parsed, the text of the inlines otherwise.
+    pub fn source_text(&self) -> String {
+        match self.source() {
+            Some(source) => source.to_string(),
+            None => crate::text::inline_text(self),
+        }
+    }
+
+    pub fn is_parsed(&self) -> bool {
+        self.parsed.get().is_some()
+    }
+
+    pub fn into_inner(mut self) -> InlineVec {
+        self.force();
+        self.parsed.take().unwrap_or_default()
+    }
+
+    // Heap bytes held, without parsing: the source text and the inlines if they were parsed.
+    pub(crate) fn heap_size(&self) -> usize {
+        self.raw.as_ref().map_or(0, |raw| raw.len())
+            + self.parsed.get().map_or(0, crate::footprint::inlines)
+    }
+
+    fn force(&self) -> &InlineVec {
+        self.parsed.get_or_init(|| {
+            parse_inlines(self.raw.as_deref().unwrap_or(""), self.triple_backtick_code)
+        })
+    }
+}
+
+impl From<InlineVec> for LazyInlines {
+    fn from(inlines: InlineVec) -> Self {
+        LazyInlines {
+            raw: None,
+            triple_backtick_code: false,
+            parsed: OnceLock::from(inlines),
+        }
+    }
+}
+
+impl From<Vec<Inline>> for LazyInlines {
+    fn from(inlines: Vec<Inline>) -> Self {
+        LazyInlines::from(InlineVec::from(inlines))
+    }
+}
+
+impl FromIterator<Inline> for LazyInlines {
+    fn from_iter<I: IntoIterator<Item = Inline>>(iter: I) -> Self {
+        LazyInlines::from(iter.into_iter().collect::<InlineVec>())
+    }
+}
+
+impl IntoIterator for LazyInlines {
+    type Item = Inline;
+    type IntoIter = smallvec::IntoIter<[Inline; 1]>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_inner().into_iter()
+    }
+}
+
+impl Deref for LazyInlines {
+    type Target = InlineVec;
+
+    fn deref(&self) -> &InlineVec {
+        self.force()
+    }
+}
+
+impl DerefMut for LazyInlines {
+    fn deref_mut(&mut self) -> &mut InlineVec {
+        self.force();
+        self.raw = None;
+        self.parsed.get_mut().unwrap()
+    }
+}
+
+impl PartialEq for LazyInlines {
+    fn eq(&self, other: &Self) -> bool {
+        self.force() == other.force()
+    }
+}
+
+impl Eq for LazyInlines {}
+
+impl fmt::Debug for LazyInlines {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.force(), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InlineKind;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_lazy_inlines() {
+        let inlines = LazyInlines::raw("some *bold*\ntext");
+        assert_eq!(inlines.is_parsed(), false);
+        assert_eq!(inlines.source(), Some("some *bold*\ntext"));
+        assert_eq!(
+            inlines.iter().map(Inline::kind).collect::<Vec<_>>(),
+            vec![
+                InlineKind::Value,
+                InlineKind::Bold,
+                InlineKind::SoftBreak,
+                InlineKind::Value
+            ]
+        );
+        assert_eq!(inlines.is_parsed(), true);
+        assert_eq!(inlines.source(), None);
+
+        let parsed = LazyInlines::from(vec![Inline::Value("a".to_string())]);
+        assert_eq!(LazyInlines::raw("a"), parsed);
+        assert_eq!(parsed.into_inner().len(), 1);
+    }
+}
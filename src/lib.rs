@@ -0,0 +1,3738 @@
+use anyhow::Result;
+use combine::error::{Commit, ParseError, StreamError};
+use combine::parser::char::{newline, space, string};
+use combine::parser::combinator::Either;
+use combine::parser::range::{recognize, take_fn, take_while, take_while1, TakeRange};
+use combine::*;
+use smallvec::{smallvec, SmallVec};
+use std::cell::Cell;
+use std::iter::FromIterator;
+
+pub mod annotations;
+pub mod build;
+pub mod cache;
+pub mod config;
+pub mod diagnostic;
+pub mod diagram;
+pub mod document;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod doctor;
+mod dump;
+pub mod events;
+pub mod footnote;
+mod footprint;
+pub mod format;
+pub mod generate;
+pub mod grammar;
+pub mod include;
+pub mod json;
+pub mod lazy;
+pub mod lines;
+pub mod links;
+pub mod lint;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+#[cfg(feature = "markdown-import")]
+pub mod markdown_import;
+pub mod metrics;
+pub mod options;
+pub mod outline;
+mod parallel;
+mod preprocess;
+pub mod query;
+pub mod reader;
+pub mod recovery;
+pub mod render;
+pub mod scaffold;
+pub mod serve;
+pub mod slug;
+pub mod source_map;
+pub mod subs;
+pub mod symbol;
+pub mod syntax_tree;
+pub mod text;
+pub mod toc;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use cache::ParseCache;
+pub use diagnostic::{Diagnostic, Severity};
+pub use document::{ArcDocument, Document};
+pub use events::{Event, Events, Tag};
+pub use include::{FileSystemResolver, InMemoryResolver, IncludeResolver};
+pub use json::to_json_ast;
+pub use lazy::LazyInlines;
+pub use options::{BlankLines, HeadingOverflow, ParseOptions};
+pub use format::{format, FormatOptions};
+pub use outline::OutlineEntry;
+pub use reader::{parse_reader, parse_reader_with_options, BlockReader};
+pub use recovery::{RecoveryPolicy, SyncPoint};
+pub use render::asciidoc::to_asciidoc;
+pub use render::markdown::to_markdown;
+pub use render::pandoc::to_pandoc_json;
+pub use slug::{SlugStrategy, Slugger};
+pub use source_map::{SourceMap, SourceMapEntry};
+pub use subs::Substitutions;
+pub use symbol::Symbol;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum HeadingLevel {
+    Title,
+    Level1,
+    Level2,
+    Level3,
+    Level4,
+}
+
+impl HeadingLevel {
+    // Section level as written in the source, `=` is 0 and `=====` is 4.
+    pub fn as_u8(&self) -> u8 {
+        match self {
+            HeadingLevel::Title => 0,
+            HeadingLevel::Level1 => 1,
+            HeadingLevel::Level2 => 2,
+            HeadingLevel::Level3 => 3,
+            HeadingLevel::Level4 => 4,
+        }
+    }
+
+    pub fn from_u8(level: u8) -> Option<HeadingLevel> {
+        match level {
+            0 => Some(HeadingLevel::Title),
+            1 => Some(HeadingLevel::Level1),
+            2 => Some(HeadingLevel::Level2),
+            3 => Some(HeadingLevel::Level3),
+            4 => Some(HeadingLevel::Level4),
+            _ => None,
+        }
+    }
+
+    // Shift the level by `offset`, clamping to the levels the grammar supports.
+    pub fn offset(&self, offset: i32) -> HeadingLevel {
+        let level = (self.as_u8() as i32 + offset).clamp(0, 4);
+        HeadingLevel::from_u8(level as u8).unwrap()
+    }
+}
+
+// Nesting of a list item, one mark per level. A run of more marks is text.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ListLevel {
+    Level1,
+    Level2,
+    Level3,
+    Level4,
+    Level5,
+}
+
+impl ListLevel {
+    // Number of marks, `*` is 1 and `*****` is 5.
+    pub fn as_u8(&self) -> u8 {
+        match self {
+            ListLevel::Level1 => 1,
+            ListLevel::Level2 => 2,
+            ListLevel::Level3 => 3,
+            ListLevel::Level4 => 4,
+            ListLevel::Level5 => 5,
+        }
+    }
+
+    pub fn from_u8(level: u8) -> Option<ListLevel> {
+        match level {
+            1 => Some(ListLevel::Level1),
+            2 => Some(ListLevel::Level2),
+            3 => Some(ListLevel::Level3),
+            4 => Some(ListLevel::Level4),
+            5 => Some(ListLevel::Level5),
+            _ => None,
+        }
+    }
+
+    // Level of a list nested `depth` deep, for lists from other sources. Deeper than AsciiDoc
+    // can write stays at the deepest level.
+    pub fn from_depth(depth: u32) -> ListLevel {
+        ListLevel::from_u8(depth.clamp(1, LIST_LEVELS as u32) as u8).unwrap()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum FootnoteType {
+    Note,
+    Tip,
+    Important,
+    Warning,
+    Caution,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum VideoProvider {
+    Youtube,
+    Vimeo,
+    // A video file, played by the browser.
+    Local,
+}
+
+impl VideoProvider {
+    // Provider of a `video` macro. The first positional attribute names it, `video::abc[vimeo]`,
+    // otherwise it's taken from a YouTube or Vimeo URL target. Any other target is a file.
+    pub fn of_macro(target: &str, attributes: &AttrList) -> VideoProvider {
+        let name = attributes.positional(0).map(str::to_ascii_lowercase);
+        match name.as_deref() {
+            Some("youtube") => return VideoProvider::Youtube,
+            Some("vimeo") => return VideoProvider::Vimeo,
+            _ => {}
+        }
+        let host = target
+            .split_once("://")
+            .map_or(target, |(_, rest)| rest)
+            .split('/')
+            .next()
+            .unwrap_or("")
+            .trim_start_matches("www.");
+        match host {
+            "youtube.com" | "m.youtube.com" | "youtu.be" => VideoProvider::Youtube,
+            "vimeo.com" | "player.vimeo.com" => VideoProvider::Vimeo,
+            _ => VideoProvider::Local,
+        }
+    }
+
+    // URL of the player for `target`, a video id or a URL of the provider. Files are their own
+    // URL.
+    pub fn embed_url(self, target: &str) -> String {
+        let id = || {
+            let query = target.split_once("v=").map(|(_, id)| id);
+            let id = query.unwrap_or_else(|| target.rsplit('/').next().unwrap_or(target));
+            id.split(['&', '?', '#']).next().unwrap_or(id).to_string()
+        };
+        match self {
+            VideoProvider::Youtube => format!("https://www.youtube.com/embed/{}", id()),
+            VideoProvider::Vimeo => format!("https://player.vimeo.com/video/{}", id()),
+            VideoProvider::Local => target.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct TableColumn {
+    name: String,
+}
+
+impl TableColumn {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct TableRow {
+    cells: Vec<TableCell>,
+}
+
+impl TableRow {
+    pub fn new(cells: Vec<TableCell>) -> Self {
+        TableRow { cells }
+    }
+
+    pub fn cells(&self) -> &[TableCell] {
+        &self.cells
+    }
+}
+
+// A cell holds blocks, most often one paragraph. AsciiDoc cells (`a|`) hold any number.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TableCell {
+    pub blocks: Vec<Block>,
+    // Attribute lines of the blocks, like `Block::Block` holds them.
+    pub metas: Vec<Option<BlockMeta>>,
+    // Source range of the cell, `None` for cells that were not parsed from text.
+    pub span: Option<Span>,
+    // Cell style written before the `|`, `a` for AsciiDoc or `m` for monospace.
+    pub style: Option<Symbol>,
+}
+
+impl TableCell {
+    pub fn new(blocks: Vec<Block>) -> Self {
+        TableCell {
+            metas: blocks.iter().map(|_| None).collect(),
+            blocks,
+            span: None,
+            style: None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum BlockKind {
+    Paragraph,
+    Heading,
+    HorizontalRuledLine,
+    NextPage,
+    UnorderdList,
+    OrderdList,
+    Label,
+    Qanda,
+    CodeBlock,
+    Block,
+    Table,
+    Macro,
+    BlankBlock,
+}
+
+// The delimiter a container block was written with, which decides how its content is read and
+// rendered. Tables and listings have blocks of their own.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DelimitedKind {
+    Open,
+    Example,
+    Sidebar,
+    Quote,
+    Pass,
+    Comment,
+}
+
+impl DelimitedKind {
+    fn of_delimiter(delimiter: &str) -> Self {
+        match delimiter.as_bytes()[0] {
+            b'=' => DelimitedKind::Example,
+            b'*' => DelimitedKind::Sidebar,
+            b'_' => DelimitedKind::Quote,
+            b'+' => DelimitedKind::Pass,
+            b'/' => DelimitedKind::Comment,
+            _ => DelimitedKind::Open,
+        }
+    }
+
+    pub fn delimiter(self) -> &'static str {
+        match self {
+            DelimitedKind::Open => "--",
+            DelimitedKind::Example => "====",
+            DelimitedKind::Sidebar => "****",
+            DelimitedKind::Quote => "____",
+            DelimitedKind::Pass => "++++",
+            DelimitedKind::Comment => "////",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DelimitedKind::Open => "open",
+            DelimitedKind::Example => "example",
+            DelimitedKind::Sidebar => "sidebar",
+            DelimitedKind::Quote => "quote",
+            DelimitedKind::Pass => "pass",
+            DelimitedKind::Comment => "comment",
+        }
+    }
+
+    // Content of passthroughs and comments is kept as written.
+    pub fn is_verbatim(self) -> bool {
+        matches!(self, DelimitedKind::Pass | DelimitedKind::Comment)
+    }
+}
+
+// Inline children of blocks and list items. Headings, list items and one-line paragraphs
+// usually hold a single inline once adjacent text is merged, it's stored without a separate
+// allocation. Room for more would grow every block by the size of an `Inline`. Build with
+// `smallvec!` or from a `Vec` with `.into()`.
+pub type InlineVec = SmallVec<[Inline; 1]>;
+
+#[derive(Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Block {
+    Paragraph {
+        children: LazyInlines,
+    },
+    Heading {
+        level: HeadingLevel,
+        children: InlineVec,
+        id: Option<String>,
+    },
+    // Horizontal ruled line section
+    HorizontalRuledLine,
+    NextPage,
+    // List section
+    UnorderdList {
+        children: Vec<ListItem>,
+    },
+    OrderdList {
+        children: Vec<ListItem>,
+    },
+    // The second inline sequence of a block is boxed, two inline sequences would double the size
+    // of every block.
+    Label {
+        children: InlineVec,
+        key: Box<InlineVec>,
+    },
+    Qanda {
+        question: InlineVec,
+        answer: Box<InlineVec>,
+    },
+    CodeBlock {
+        children: InlineVec,
+        title: Option<String>,
+        file_type: Option<Symbol>,
+    },
+    // Unsupport CodeBlockWithSpeachBaloon
+    // Open, example, sidebar and quote blocks hold the blocks of their content. Passthroughs and
+    // comments hold their text unparsed, as a single paragraph.
+    // `metas` holds the attribute lines of the children, like `Document` does for the top level.
+    Block {
+        kind: DelimitedKind,
+        children: Vec<Block>,
+        metas: Vec<Option<BlockMeta>>,
+        title: Option<Box<InlineVec>>,
+    },
+    Table {
+        columns: Vec<TableColumn>,
+        rows: Vec<TableRow>,
+        title: Option<String>,
+    },
+    // `image::logo.png[]` or `video::intro.mp4[]` on a line of its own, holding the macro.
+    Macro {
+        children: Inline,
+    },
+    BlankBlock,
+}
+
+impl Block {
+    pub fn kind(&self) -> BlockKind {
+        match self {
+            Block::Paragraph { .. } => BlockKind::Paragraph,
+            Block::Heading { .. } => BlockKind::Heading,
+            Block::HorizontalRuledLine => BlockKind::HorizontalRuledLine,
+            Block::NextPage => BlockKind::NextPage,
+            Block::UnorderdList { .. } => BlockKind::UnorderdList,
+            Block::OrderdList { .. } => BlockKind::OrderdList,
+            Block::Label { .. } => BlockKind::Label,
+            Block::Qanda { .. } => BlockKind::Qanda,
+            Block::CodeBlock { .. } => BlockKind::CodeBlock,
+            Block::Block { .. } => BlockKind::Block,
+            Block::Table { .. } => BlockKind::Table,
+            Block::Macro { .. } => BlockKind::Macro,
+            Block::BlankBlock => BlockKind::BlankBlock,
+        }
+    }
+
+    // Inline content of blocks that hold a single inline sequence, empty for the others. The
+    // inlines of a lazily parsed paragraph are parsed on the first call.
+    pub fn inlines(&self) -> &[Inline] {
+        match self {
+            Block::Paragraph { children } => children,
+            Block::Heading { children, .. }
+            | Block::Label { children, .. }
+            | Block::CodeBlock { children, .. } => children,
+            Block::Macro { children } => std::slice::from_ref(children),
+            _ => &[],
+        }
+    }
+
+    // Blocks nested in a container block, empty for the others.
+    pub fn blocks(&self) -> &[Block] {
+        match self {
+            Block::Block { children, .. } => children,
+            _ => &[],
+        }
+    }
+
+    // Attribute line of the nested block at `index`.
+    pub fn nested_meta(&self, index: usize) -> Option<&BlockMeta> {
+        match self {
+            Block::Block { metas, .. } => metas.get(index).and_then(Option::as_ref),
+            _ => None,
+        }
+    }
+
+    pub fn list_items(&self) -> &[ListItem] {
+        match self {
+            Block::UnorderdList { children } | Block::OrderdList { children } => children,
+            _ => &[],
+        }
+    }
+
+    pub fn heading_level(&self) -> Option<&HeadingLevel> {
+        match self {
+            Block::Heading { level, .. } => Some(level),
+            _ => None,
+        }
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        match self {
+            Block::Heading { id, .. } => id.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum InlineKind {
+    Value,
+    HardBreak,
+    SoftBreak,
+    Literal,
+    Footnote,
+    Lead,
+    Bold,
+    Italic,
+    Monospace,
+    Marker,
+    Styled,
+    Macro,
+    InlineCode,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Inline {
+    // Paragraph section
+    Value(String),
+    HardBreak,
+    SoftBreak,
+    Literal {
+        children: Box<Inline>,
+    },
+    Footnote {
+        kind: FootnoteType,
+        children: Box<Inline>,
+    },
+    Lead {
+        children: Box<Inline>,
+    },
+    // Text format section
+    Bold {
+        children: Box<Inline>,
+    },
+    Italic {
+        children: Box<Inline>,
+    },
+    Monospace {
+        children: Box<Inline>,
+    },
+    Marker {
+        children: Box<Inline>,
+    },
+    // Text with roles, `[.keyword]#fn#`, styled by `render::roles::RoleStyles`.
+    Styled {
+        roles: Vec<Symbol>,
+        children: Box<Inline>,
+    },
+    // Unsupport Superscript
+    // Unsupport Subscript
+    // Unsupport Curvequote
+    // Unsupport Apostorofy
+    // Boxed, attributes are rare and would make every inline twice as large.
+    Macro {
+        attributes: Box<AttrList>,
+        kind: Symbol,
+        id: String,
+    },
+    // Code section
+    InlineCode {
+        children: Box<Inline>,
+    },
+}
+
+impl Inline {
+    pub fn kind(&self) -> InlineKind {
+        match self {
+            Inline::Value(_) => InlineKind::Value,
+            Inline::HardBreak => InlineKind::HardBreak,
+            Inline::SoftBreak => InlineKind::SoftBreak,
+            Inline::Literal { .. } => InlineKind::Literal,
+            Inline::Footnote { .. } => InlineKind::Footnote,
+            Inline::Lead { .. } => InlineKind::Lead,
+            Inline::Bold { .. } => InlineKind::Bold,
+            Inline::Italic { .. } => InlineKind::Italic,
+            Inline::Monospace { .. } => InlineKind::Monospace,
+            Inline::Marker { .. } => InlineKind::Marker,
+            Inline::Styled { .. } => InlineKind::Styled,
+            Inline::Macro { .. } => InlineKind::Macro,
+            Inline::InlineCode { .. } => InlineKind::InlineCode,
+        }
+    }
+
+    // Wrapped inline of formatting nodes.
+    pub fn child(&self) -> Option<&Inline> {
+        match self {
+            Inline::Literal { children }
+            | Inline::Footnote { children, .. }
+            | Inline::Lead { children }
+            | Inline::Bold { children }
+            | Inline::Italic { children }
+            | Inline::Monospace { children }
+            | Inline::Marker { children }
+            | Inline::Styled { children, .. }
+            | Inline::InlineCode { children } => Some(children),
+            _ => None,
+        }
+    }
+
+    pub fn as_value(&self) -> Option<&str> {
+        match self {
+            Inline::Value(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+// Blocks and inlines are stored by the thousand, a large variant makes every one of them pay for
+// it. Box the payload of a variant that outgrows these rather than raising them.
+#[cfg(target_pointer_width = "64")]
+const _: () = {
+    assert!(std::mem::size_of::<Inline>() <= 48);
+    assert!(std::mem::size_of::<Block>() <= 104);
+};
+
+// Collects parsed inlines, merging adjacent text. Characters the formatting parsers reject
+// are taken one at a time, this keeps them in the `Value` around them.
+#[derive(Default)]
+struct Inlines(InlineVec);
+
+impl Extend<Inline> for Inlines {
+    fn extend<I: IntoIterator<Item = Inline>>(&mut self, iter: I) {
+        for inline in iter {
+            if let (Some(Inline::Value(last)), Inline::Value(value)) = (self.0.last_mut(), &inline)
+            {
+                last.push_str(value);
+                continue;
+            }
+            self.0.push(inline);
+        }
+    }
+}
+
+// One entry of an attribute list, `rust` or `indent=0`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Attr {
+    Positional(String),
+    Named(Symbol, String),
+}
+
+// Attribute list in brackets, `[source, rust, linenums, indent=0]`. Positional and named
+// entries mix and keep the order they were written in.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct AttrList(Vec<Attr>);
+
+impl AttrList {
+    pub fn new() -> Self {
+        AttrList::default()
+    }
+
+    pub fn push_positional<S: Into<String>>(&mut self, value: S) {
+        self.0.push(Attr::Positional(value.into()));
+    }
+
+    pub fn push_named<K: Into<Symbol>, S: Into<String>>(&mut self, key: K, value: S) {
+        self.0.push(Attr::Named(key.into(), value.into()));
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Attr> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    // Positional values in order, named entries between them don't count.
+    pub fn positionals(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().filter_map(|attr| match attr {
+            Attr::Positional(value) => Some(value.as_str()),
+            Attr::Named(..) => None,
+        })
+    }
+
+    pub fn positional(&self, index: usize) -> Option<&str> {
+        self.positionals().nth(index)
+    }
+
+    pub fn named(&self) -> impl Iterator<Item = (&Symbol, &str)> {
+        self.0.iter().filter_map(|attr| match attr {
+            Attr::Named(key, value) => Some((key, value.as_str())),
+            Attr::Positional(_) => None,
+        })
+    }
+
+    // Value of the named entry `key`, the last one when it was written more than once.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.named()
+            .filter(|(name, _)| name.as_str() == key)
+            .last()
+            .map(|(_, value)| value)
+    }
+
+    // Keys of named entries written more than once, in the order they first appear.
+    pub fn duplicate_keys(&self) -> Vec<&Symbol> {
+        let mut duplicates: Vec<&Symbol> = vec![];
+        for (index, (key, _)) in self.named().enumerate() {
+            let again = self.named().skip(index + 1).any(|(other, _)| other == key);
+            if again && !duplicates.contains(&key) {
+                duplicates.push(key);
+            }
+        }
+        duplicates
+    }
+}
+
+impl From<Vec<String>> for AttrList {
+    fn from(values: Vec<String>) -> Self {
+        AttrList(values.into_iter().map(Attr::Positional).collect())
+    }
+}
+
+impl FromIterator<Attr> for AttrList {
+    fn from_iter<I: IntoIterator<Item = Attr>>(iter: I) -> Self {
+        AttrList(iter.into_iter().collect())
+    }
+}
+
+impl<'a> IntoIterator for &'a AttrList {
+    type Item = &'a Attr;
+    type IntoIter = std::slice::Iter<'a, Attr>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ListItem {
+    Normal {
+        children: InlineVec,
+        level: ListLevel,
+    },
+    Check {
+        children: InlineVec,
+        level: ListLevel,
+        checked: bool,
+    },
+}
+
+impl ListItem {
+    pub fn children(&self) -> &[Inline] {
+        match self {
+            ListItem::Normal { children, .. } | ListItem::Check { children, .. } => children,
+        }
+    }
+
+    pub fn level(&self) -> u32 {
+        u32::from(self.list_level().as_u8())
+    }
+
+    pub fn list_level(&self) -> ListLevel {
+        match self {
+            ListItem::Normal { level, .. } | ListItem::Check { level, .. } => *level,
+        }
+    }
+}
+
+// Block attribute line (`[source,rust]`, `[.notes]`, `[#intro.lead]`) preceding a block.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BlockMeta {
+    pub style: Option<Symbol>,
+    pub id: Option<String>,
+    pub roles: Vec<Symbol>,
+    pub options: Vec<Symbol>,
+    pub attributes: AttrList,
+}
+
+impl BlockMeta {
+    // Split the `style#id.role%option` shorthand of the first positional attribute. Named `id`,
+    // `role` and `options` entries add to it.
+    pub fn from_attributes(attributes: AttrList) -> Self {
+        let mut meta = BlockMeta {
+            style: None,
+            id: None,
+            roles: vec![],
+            options: vec![],
+            attributes,
+        };
+        let first = match meta.attributes.iter().next() {
+            Some(Attr::Positional(value)) => value.as_str(),
+            _ => "",
+        };
+        let mut marker = None;
+        let mut segment = String::new();
+        for c in first.chars().chain(std::iter::once('.')) {
+            if c != '#' && c != '.' && c != '%' {
+                segment.push(c);
+                continue;
+            }
+            let value = std::mem::take(&mut segment);
+            match marker {
+                None if !value.is_empty() => meta.style = Some(value.into()),
+                Some('#') if !value.is_empty() => meta.id = Some(value),
+                Some('.') if !value.is_empty() => meta.roles.push(value.into()),
+                Some('%') if !value.is_empty() => meta.options.push(value.into()),
+                _ => {}
+            }
+            marker = Some(c);
+        }
+        if let Some(id) = meta.attributes.get("id") {
+            meta.id = Some(id.to_string());
+        }
+        if let Some(roles) = meta.attributes.get("role") {
+            meta.roles.extend(roles.split_whitespace().map(Symbol::new));
+        }
+        let attributes = &meta.attributes;
+        if let Some(options) = attributes.get("options").or_else(|| attributes.get("opts")) {
+            meta.options
+                .extend(options.split_whitespace().map(Symbol::new));
+        }
+        meta
+    }
+
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+
+    // Whether the style is one AsciiDoc or a diagram backend knows. Unknown styles are kept and
+    // reported as warnings, a block with one renders like it had none.
+    pub fn has_known_style(&self) -> bool {
+        self.style.as_deref().is_none_or(|style| {
+            BLOCK_STYLES.contains(&style) || diagram::DiagramKind::from_name(style).is_some()
+        })
+    }
+}
+
+// Styles of the first positional attribute, on delimited blocks, paragraphs, lists and
+// sections.
+pub const BLOCK_STYLES: &[&str] = &[
+    "source",
+    "listing",
+    "literal",
+    "pass",
+    "stem",
+    "latexmath",
+    "asciimath",
+    "comment",
+    "example",
+    "sidebar",
+    "open",
+    "quote",
+    "verse",
+    "normal",
+    "NOTE",
+    "TIP",
+    "IMPORTANT",
+    "CAUTION",
+    "WARNING",
+    "qanda",
+    "horizontal",
+    "glossary",
+    "bibliography",
+    "arabic",
+    "decimal",
+    "loweralpha",
+    "upperalpha",
+    "lowerroman",
+    "upperroman",
+    "lowergreek",
+    "abstract",
+    "partintro",
+    "preface",
+    "appendix",
+    "colophon",
+    "dedication",
+    "acknowledgments",
+    "index",
+    "discrete",
+    "float",
+];
+
+// Byte range of a node in the parsed source.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+// Byte order mark some editors write at the start of UTF-8 files. It's not part of the text.
+pub(crate) const BOM: char = '\u{feff}';
+
+pub fn parse(s: &str) -> Result<Vec<Block>> {
+    let mut parser = document();
+
+    let s = s.strip_prefix(BOM).unwrap_or(s);
+
+    Ok(parser.parse(s).map(|(tokens, _)| tokens)?)
+}
+
+// The parts of `ParseOptions` the block parsers look at.
+#[derive(Debug, Hash, Clone, Copy, Default)]
+pub(crate) struct BlockOptions {
+    // Paragraphs keep their text and parse their inlines when they're first looked at.
+    pub lazy_inlines: bool,
+    pub blank_lines: BlankLines,
+    pub triple_backtick_code: bool,
+    pub heading_overflow: HeadingOverflow,
+}
+
+impl From<&ParseOptions> for BlockOptions {
+    fn from(options: &ParseOptions) -> Self {
+        BlockOptions {
+            lazy_inlines: options.lazy_inlines,
+            blank_lines: options.blank_lines,
+            triple_backtick_code: options.triple_backtick_code,
+            heading_overflow: options.heading_overflow,
+        }
+    }
+}
+
+pub(crate) struct ParsedBlock {
+    pub block: Block,
+    pub span: Span,
+    pub meta: Option<BlockMeta>,
+}
+
+pub(crate) fn parse_spanned(s: &str, options: BlockOptions) -> Result<Vec<ParsedBlock>> {
+    let mut parser = leading_blank_lines().with(many::<Vec<_>, _, _>((
+        position(),
+        block_with_meta(options),
+        position(),
+    )));
+
+    let input = s.strip_prefix(BOM).unwrap_or(s);
+    let offset = s.len() - input.len();
+
+    let (blocks, _) = parser.parse(input)?;
+    Ok(blocks
+        .into_iter()
+        .map(|(start, (meta, block), end)| {
+            let span = Span {
+                start: offset + start.translate_position(input),
+                end: offset + end.translate_position(input),
+            };
+            ParsedBlock { block, span, meta }
+        })
+        .collect())
+}
+
+fn document<Input>() -> impl Parser<Input, Output = Vec<Block>>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    leading_blank_lines().with(many::<Vec<Block>, _, _>(
+        block_with_meta(BlockOptions::default()).map(|(_, block)| block),
+    ))
+}
+
+// Lines holding nothing but spaces before the first block. They are part of no block, spans
+// still count them. Spaces starting the first line with text belong to its block.
+pub(crate) fn leading_blank_lines<Input>() -> impl Parser<Input, Output = ()>
+where
+    Input: Stream<Token = char>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    skip_many(attempt((
+        skip_many(satisfy(|c| c == ' ' || c == '\t')),
+        newline(),
+    )))
+}
+
+// Inlines of a paragraph whose text was kept by a lazy parse.
+pub(crate) fn parse_inlines(s: &str, triple_backtick_code: bool) -> InlineVec {
+    match many1::<Inlines, _, _>(inline(triple_backtick_code)).parse(s) {
+        Ok((Inlines(inlines), "")) => inlines,
+        // A paragraph parsed before, this isn't reached.
+        _ => smallvec![Inline::Value(s.to_string())],
+    }
+}
+
+pub(crate) fn block_with_meta<Input>(
+    options: BlockOptions,
+) -> impl Parser<Input, Output = (Option<BlockMeta>, Block)>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    (optional(attempt(block_attribute_line())), block(options)).map(|(meta, mut block)| {
+        source_language(meta.as_ref(), &mut block);
+        (meta, block)
+    })
+}
+
+// `[source,rust]` names the language of the listing below it, the second positional attribute.
+fn source_language(meta: Option<&BlockMeta>, block: &mut Block) {
+    if let (
+        Some(meta),
+        Block::CodeBlock {
+            file_type: file_type @ None,
+            ..
+        },
+    ) = (meta, block)
+    {
+        if meta.style.as_deref() == Some("source") {
+            *file_type = meta.attributes.positional(1).map(Symbol::from);
+        }
+    }
+}
+
+fn block_attribute_line<Input>() -> impl Parser<Input, Output = BlockMeta>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    (
+        attribute_list(),
+        newline(),
+        look_ahead(satisfy(|c| c != '\n')),
+    )
+        .map(|(attributes, _, _)| BlockMeta::from_attributes(attributes))
+}
+
+pub(crate) fn block<Input>(options: BlockOptions) -> impl Parser<Input, Output = Block>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    // Only the blocks the next character can start are tried, in the order of precedence.
+    look_ahead(any()).then(move |c| {
+        dispatch!(c;
+            '=' => choice((attempt(delimited_block(options)), heading_block(options), paragraph_block(options))),
+            '<' => choice((attempt(horizontal_ruled_line_block()), paragraph_block(options))),
+            '.' => choice((attempt(delimited_block(options)), attempt(ordered_list_block(options)), paragraph_block(options))),
+            '*' => choice((attempt(delimited_block(options)), attempt(unordered_list_block(options)), paragraph_block(options))),
+            '-' | '_' | '+' | '/' | '`' | '|' => choice((attempt(delimited_block(options)), paragraph_block(options))),
+            'i' | 'v' => choice((attempt(block_macro()), paragraph_block(options))),
+            '\n' => choice((paragraph_block(options), blank_block(options.blank_lines))),
+            _ => paragraph_block(options)
+        )
+    })
+    // A block that ends without a blank line hands the line break over to the next one, the
+    // final one of the document belongs to the last block.
+    .skip(optional(attempt(
+        newline().skip(look_ahead(block_start()).or(eof())),
+    )))
+}
+
+parser! {
+    fn inline[Input](triple_backtick_code: bool)(Input) -> Inline
+    where
+        [Input: RangeStream<Token = char>, Input::Range: stream::Range + AsRef<str>] {
+            nested(inline_(*triple_backtick_code))
+        }
+}
+
+// Formatting nested deeper than this is kept as text. Every level is a recursive call of the
+// parser, adversarial input would otherwise overflow the stack.
+pub const MAX_NESTING: usize = 32;
+
+thread_local! {
+    static NESTING: Cell<usize> = const { Cell::new(0) };
+    // Set when a parse hit `MAX_NESTING`, the lenient parser turns it into a diagnostic.
+    static NESTING_EXCEEDED: Cell<bool> = const { Cell::new(false) };
+}
+
+// Restores the nesting level, also when a parser panics.
+struct NestingGuard(usize);
+
+impl Drop for NestingGuard {
+    fn drop(&mut self) {
+        NESTING.with(|nesting| nesting.set(self.0));
+    }
+}
+
+pub(crate) fn take_nesting_exceeded() -> bool {
+    NESTING_EXCEEDED.with(|exceeded| exceeded.replace(false))
+}
+
+// `parser` one formatting level deeper. Past `MAX_NESTING` it fails without consuming input, so
+// the mark that opened the level is taken as text.
+fn nested<Input, P>(mut parser: P) -> impl Parser<Input, Output = P::Output>
+where
+    Input: Stream<Token = char>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+    P: Parser<Input>,
+{
+    combine::parser(move |input: &mut Input| {
+        let level = NESTING.with(Cell::get);
+        // Level 0 is the text of the block itself.
+        if level > MAX_NESTING {
+            NESTING_EXCEEDED.with(|exceeded| exceeded.set(true));
+            let error = Input::Error::from_error(
+                input.position(),
+                StreamError::message_static_message("formatting nested too deeply"),
+            );
+            return Err(Commit::Peek(error.into()));
+        }
+        let _guard = NestingGuard(level);
+        NESTING.with(|nesting| nesting.set(level + 1));
+        parser.parse_stream(input).into_result()
+    })
+}
+
+fn inline_<Input>(triple_backtick_code: bool) -> impl Parser<Input, Output = Inline>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    // Text is the common case and never backtracks. A formatting mark that doesn't open a span,
+    // with no closing mark after it, is kept as text and joins the text around it in one value.
+    look_ahead(any()).then(move |c| {
+        dispatch!(c;
+            '*' => attempt(bold(triple_backtick_code)).or(mark(c)),
+            '_' => attempt(italic(triple_backtick_code)).or(mark(c)),
+            '`' => backtick_span(triple_backtick_code).or(mark(c)),
+            '#' => attempt(marker(triple_backtick_code)).or(mark(c)),
+            '[' => attempt(styled(triple_backtick_code)).or(mark(c)),
+            '\n' => attempt(line_break()),
+            _ if starts_macro_name(c) => macro_or_value::<_, INLINE_TERMINATORS>(),
+            _ => value::<_, INLINE_TERMINATORS>()
+        )
+    })
+}
+
+parser! {
+    fn list_item_inline[Input](triple_backtick_code: bool)(Input) -> Inline
+    where
+        [Input: RangeStream<Token = char>, Input::Range: stream::Range + AsRef<str>] {
+            list_item_inline_(*triple_backtick_code)
+        }
+}
+
+fn list_item_inline_<Input>(triple_backtick_code: bool) -> impl Parser<Input, Output = Inline>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    look_ahead(any()).then(move |c| {
+        dispatch!(c;
+            '*' => attempt(bold(triple_backtick_code)).or(mark(c)),
+            '_' => attempt(italic(triple_backtick_code)).or(mark(c)),
+            '`' => attempt(monospace(triple_backtick_code)).or(mark(c)),
+            '#' => mark(c),
+            '[' => attempt(styled(triple_backtick_code)).or(mark(c)),
+            _ if starts_macro_name(c) => macro_or_value::<_, LIST_ITEM_TERMINATORS>(),
+            _ => value::<_, LIST_ITEM_TERMINATORS>()
+        )
+    })
+}
+
+// The run of marks that failed to open a span, as text in one go instead of trying a span again
+// at each of them.
+fn mark<Input>(c: char) -> impl Parser<Input, Output = Inline>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    take_while1(move |next| next == c)
+        .map(|marks: Input::Range| Inline::Value(marks.as_ref().to_string()))
+}
+
+fn line_break<Input>() -> impl Parser<Input, Output = Inline>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    // Neither goes on to a line that starts a block, the paragraph ends there.
+    let next_block = || not_followed_by(block_start().map(|_| "block"));
+    choice((
+        // The final line break of the document ends the paragraph as well.
+        newline()
+            .and(not_followed_by(newline()))
+            .skip(not_followed_by(eof().map(|_| "end")))
+            .skip(next_block())
+            .map(|_| Inline::SoftBreak),
+        space()
+            .and(string("+\n"))
+            .skip(next_block())
+            .map(|_| Inline::HardBreak),
+    ))
+}
+
+fn bold<Input>(triple_backtick_code: bool) -> impl Parser<Input, Output = Inline>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    let symbol = '*';
+    between(
+        skip_count_min_max(1, 2, token(symbol)),
+        skip_count_min_max(1, 2, token(symbol)),
+        inline(triple_backtick_code),
+    )
+    .map(|children| Inline::Bold {
+        children: Box::new(children),
+    })
+}
+
+pub fn monospace<Input>(triple_backtick_code: bool) -> impl Parser<Input, Output = Inline>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    let symbol = '`';
+    let content =
+        attempt(literal().skip(look_ahead(token(symbol)))).or(inline(triple_backtick_code));
+    between(
+        skip_count_min_max(1, 2, token(symbol)),
+        skip_count_min_max(1, 2, token(symbol)),
+        content,
+    )
+    .map(|children| Inline::Monospace {
+        children: Box::new(children),
+    })
+}
+
+// `+text+` in monospace, the text as written with no formatting in it.
+fn literal<Input>() -> impl Parser<Input, Output = Inline>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    between(
+        token('+'),
+        token('+'),
+        take_while1(|c| c != '+' && c != '\n'),
+    )
+    .map(|text: Input::Range| Inline::Literal {
+        children: Box::new(Inline::Value(text.as_ref().to_string())),
+    })
+}
+
+// A span opened by a backtick. Monospace, and with `triple_backtick_code` the inline code
+// between three backticks that earlier versions read. AsciiDoc has no such syntax.
+fn backtick_span<Input>(triple_backtick_code: bool) -> impl Parser<Input, Output = Inline>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    if triple_backtick_code {
+        return Either::Left(attempt(inline_code(true)).or(attempt(monospace(true))));
+    }
+    Either::Right(attempt(monospace(false)))
+}
+
+fn italic<Input>(triple_backtick_code: bool) -> impl Parser<Input, Output = Inline>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    let symbol = '_';
+    between(
+        skip_count_min_max(1, 2, token(symbol)),
+        skip_count_min_max(1, 2, token(symbol)),
+        inline(triple_backtick_code),
+    )
+    .map(|children| Inline::Italic {
+        children: Box::new(children),
+    })
+}
+
+fn marker<Input>(triple_backtick_code: bool) -> impl Parser<Input, Output = Inline>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    highlighted(triple_backtick_code).map(|children| Inline::Marker {
+        children: Box::new(children),
+    })
+}
+
+// Inline between one or two `#`.
+fn highlighted<Input>(triple_backtick_code: bool) -> impl Parser<Input, Output = Inline>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    let symbol = '#';
+    between(
+        skip_count_min_max(1, 2, token(symbol)),
+        skip_count_min_max(1, 2, token(symbol)),
+        inline(triple_backtick_code),
+    )
+}
+
+// A highlight with roles in front, `[.keyword.bold]#fn#`, is text with those roles.
+fn styled<Input>(triple_backtick_code: bool) -> impl Parser<Input, Output = Inline>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    let role = token('.')
+        .with(take_while1(|c: char| {
+            c.is_alphanumeric() || c == '-' || c == '_'
+        }))
+        .map(|role: Input::Range| Symbol::from(role.as_ref()));
+    (
+        between(token('['), token(']'), many1::<Vec<_>, _, _>(role)),
+        highlighted(triple_backtick_code),
+    )
+        .map(|(roles, children)| Inline::Styled {
+            roles,
+            children: Box::new(children),
+        })
+}
+
+// Inline macros, `footnote:[text]` or `image:logo.png[Logo]`. The name starts a macro only where
+// a word starts, `value` stops in front of it.
+pub(crate) const INLINE_MACROS: &[&str] = &[
+    "footnote",
+    "image",
+    "video",
+    "stem",
+    "latexmath",
+    "asciimath",
+];
+
+fn starts_macro_name(c: char) -> bool {
+    INLINE_MACROS.iter().any(|name| name.starts_with(c))
+}
+
+fn starts_macro(text: &str) -> bool {
+    INLINE_MACROS.iter().any(|name| {
+        text.strip_prefix(name)
+            .is_some_and(|rest| rest.starts_with(':'))
+    })
+}
+
+// A macro, or text when there is no macro name and colon to try one at. Most words starting like
+// a macro name aren't one, they don't pay for a failed attempt.
+fn macro_or_value<Input, const TERMINATORS: u128>() -> impl Parser<Input, Output = Inline>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    combine::parser(
+        |input: &mut Input| match starts_macro(input.range().as_ref()) {
+            true => attempt(inline_macro())
+                .or(value::<_, TERMINATORS>())
+                .parse_stream(input)
+                .into_result(),
+            false => value::<_, TERMINATORS>().parse_stream(input).into_result(),
+        },
+    )
+}
+
+fn inline_macro<Input>() -> impl Parser<Input, Output = Inline>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    choice((
+        attempt(string("footnote:").with(macro_text())).map(|text| footnote(&text)),
+        attempt(target_macro("image", 1)),
+        attempt(target_macro("video", 1)),
+        attempt(formula("stem")),
+        attempt(formula("latexmath")),
+        attempt(formula("asciimath")),
+    ))
+}
+
+// A formula, `stem:[sqrt(4) = 2]`. Its text is the one positional attribute, `,` and `=` are
+// part of it.
+fn formula<Input>(name: &'static str) -> impl Parser<Input, Output = Inline>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    (string(name), token(':'), macro_text()).map(move |(_, _, text)| Inline::Macro {
+        attributes: Box::new(AttrList::from(vec![text])),
+        kind: name.into(),
+        id: String::new(),
+    })
+}
+
+// `name:target[attributes]`, or the block form with two colons, `image::logo.png[]`. In text the
+// block form reads like the inline one.
+fn target_macro<Input>(name: &'static str, colons: usize) -> impl Parser<Input, Output = Inline>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    let attributes = attempt(attribute_list()).or(string("[]").map(|_| AttrList::new()));
+    (
+        string(name),
+        skip_count_min_max(colons, 2, token(':')),
+        take_while1(|c: char| c != '[' && !c.is_whitespace()),
+        attributes,
+    )
+        .map(
+            move |(_, _, target, attributes): (_, _, Input::Range, _)| Inline::Macro {
+                attributes: Box::new(attributes),
+                kind: name.into(),
+                id: target.as_ref().to_string(),
+            },
+        )
+}
+
+// Text of a macro between brackets as written, a backslash keeps a closing bracket in it.
+fn macro_text<Input>() -> impl Parser<Input, Output = String>
+where
+    Input: Stream<Token = char>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    between(
+        token('['),
+        token(']'),
+        many1(choice((
+            attempt(token('\\').with(token(']'))),
+            satisfy(|c| c != ']' && c != '\n'),
+        ))),
+    )
+}
+
+// A footnote, `footnote:[TIP: text]` for one of a kind other than a note. The text is kept as
+// written.
+fn footnote(text: &str) -> Inline {
+    let (kind, text) = match text.split_once(": ") {
+        Some(("NOTE", text)) => (FootnoteType::Note, text),
+        Some(("TIP", text)) => (FootnoteType::Tip, text),
+        Some(("IMPORTANT", text)) => (FootnoteType::Important, text),
+        Some(("WARNING", text)) => (FootnoteType::Warning, text),
+        Some(("CAUTION", text)) => (FootnoteType::Caution, text),
+        _ => (FootnoteType::Note, text),
+    };
+    Inline::Footnote {
+        kind,
+        children: Box::new(Inline::Value(text.to_string())),
+    }
+}
+
+fn inline_code<Input>(triple_backtick_code: bool) -> impl Parser<Input, Output = Inline>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    let symbol = '`';
+    (
+        token(symbol),
+        token(symbol),
+        token(symbol),
+        inline(triple_backtick_code),
+        token(symbol),
+        token(symbol),
+        token(symbol),
+    )
+        .map(|(_, _, _, children, _, _, _)| Inline::InlineCode {
+            children: Box::new(children),
+        })
+}
+
+// Characters that end a run of text so the other inline parsers of a context get to look at
+// them, as a bitset over ASCII. Other characters never end a run. Passed to `value` as a const
+// parameter, the lookup compiles down to the comparisons a `matches!` would give.
+const fn terminators(chars: &str) -> u128 {
+    let bytes = chars.as_bytes();
+    let mut set = 0;
+    let mut index = 0;
+    while index < bytes.len() {
+        assert!(bytes[index].is_ascii());
+        set |= 1 << bytes[index];
+        index += 1;
+    }
+    set
+}
+
+fn is_terminator(terminators: u128, c: char) -> bool {
+    (c as u32) < 128 && terminators & (1 << c as u32) != 0
+}
+
+// Marks that open a formatting span in paragraphs and section titles, and in list items where
+// `#` is never a highlight. The editor grammar of grammar.rs is generated from these too.
+pub(crate) const INLINE_MARKS: &str = "*_`#";
+pub(crate) const LIST_ITEM_MARKS: &str = "*_`";
+
+// Paragraphs and section titles: formatting marks, highlights, roles and line breaks.
+const INLINE_TERMINATORS: u128 = terminators("\n[") | terminators(INLINE_MARKS);
+// A list item ends at its line, roles are read like in paragraphs.
+const LIST_ITEM_TERMINATORS: u128 = terminators("\n[") | terminators(LIST_ITEM_MARKS);
+
+// Deepest heading, more `=` than this is a paragraph.
+pub(crate) const HEADING_LEVELS: usize = 5;
+// Deepest list item, a longer run of marks is text.
+pub(crate) const LIST_LEVELS: usize = 5;
+pub(crate) const HORIZONTAL_RULE: &str = "<<<";
+pub(crate) const UNORDERED_LIST_MARK: char = '*';
+pub(crate) const ORDERED_LIST_MARK: char = '.';
+// Between the brackets of a checklist item, anything but a space checks it.
+pub(crate) const CHECKBOX_MARKS: &str = "*x ";
+
+fn value<Input, const TERMINATORS: u128>() -> impl Parser<Input, Output = Inline>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    // Sliced from the input in one go rather than collected a character at a time.
+    look_ahead(satisfy(|c| !is_terminator(TERMINATORS, c)))
+        .with(take_fn(|text: Input::Range| {
+            TakeRange::Found(value_end::<TERMINATORS>(text.as_ref()))
+        }))
+        .map(|text: Input::Range| Inline::Value(text.as_ref().to_string()))
+}
+
+// Length of the text up to the next terminator or inline macro. A macro is found at the colon
+// after its name, a name at the start has been tried already. Terminators and the colon are
+// ASCII, bytes are compared rather than decoded characters.
+fn value_end<const TERMINATORS: u128>(text: &str) -> usize {
+    for (index, byte) in text.bytes().enumerate() {
+        if !is_terminator(TERMINATORS | terminators(":"), byte as char) {
+            continue;
+        }
+        if byte != b':' {
+            return index;
+        }
+        let word = text[..index].trim_end_matches(char::is_alphanumeric).len();
+        if word > 0 && INLINE_MACROS.contains(&&text[word..index]) {
+            return word;
+        }
+    }
+    text.len()
+}
+
+fn heading_block<Input>(options: BlockOptions) -> impl Parser<Input, Output = Block>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    recognize((skip_many1(token('=')), skip_many1(token(' '))))
+        .and(many1::<Inlines, _, _>(heading_inline(
+            options.triple_backtick_code,
+        )))
+        .skip(end_of_line())
+        .map(move |(marks, Inlines(mut children)): (Input::Range, _)| {
+            let marks = marks.as_ref();
+            let heading = marks.trim_end_matches(' ');
+            // Deeper headings are read as the deepest level in compat mode, see `HeadingOverflow`.
+            if heading.len() > HEADING_LEVELS && options.heading_overflow != HeadingOverflow::Compat
+            {
+                // The marks are text of the paragraph, joined with the text after them.
+                match children.first_mut() {
+                    Some(Inline::Value(text)) => text.insert_str(0, marks),
+                    _ => children.insert(0, Inline::Value(marks.to_string())),
+                }
+                return Block::Paragraph {
+                    children: children.into(),
+                };
+            }
+            let level = match heading.len() {
+                1 => HeadingLevel::Title,
+                2 => HeadingLevel::Level1,
+                3 => HeadingLevel::Level2,
+                4 => HeadingLevel::Level3,
+                _ => HeadingLevel::Level4,
+            };
+            strip_closing_marks(&mut children, heading);
+            Block::Heading {
+                id: None,
+                children,
+                level,
+            }
+        })
+}
+
+// Drop the trailing spaces of a title and the marks closing it when they repeat the opening
+// ones, `== Title ==`. Other marks at the end are part of the title.
+fn strip_closing_marks(children: &mut InlineVec, marks: &str) {
+    if let Some(Inline::Value(last)) = children.last_mut() {
+        let text = last.trim_end_matches(' ');
+        let text = match text.strip_suffix(marks) {
+            Some(rest) if rest.is_empty() || rest.ends_with(' ') => rest.trim_end_matches(' '),
+            _ => text,
+        };
+        last.truncate(text.len());
+        if last.is_empty() && children.len() > 1 {
+            children.pop();
+        }
+    }
+}
+
+// Inlines of a section title, which ends at its line.
+fn heading_inline<Input>(triple_backtick_code: bool) -> impl Parser<Input, Output = Inline>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    look_ahead(any()).then(move |c| {
+        dispatch!(c;
+            '*' => attempt(bold(triple_backtick_code)).or(mark(c)),
+            '_' => attempt(italic(triple_backtick_code)).or(mark(c)),
+            '`' => backtick_span(triple_backtick_code).or(mark(c)),
+            '#' => attempt(marker(triple_backtick_code)).or(mark(c)),
+            '[' => attempt(styled(triple_backtick_code)).or(mark(c)),
+            _ if starts_macro_name(c) => macro_or_value::<_, INLINE_TERMINATORS>(),
+            _ => value::<_, INLINE_TERMINATORS>()
+        )
+    })
+}
+
+// A block macro on a line of its own, `image::logo.png[Logo]`.
+fn block_macro<Input>() -> impl Parser<Input, Output = Block>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    choice((
+        attempt(target_macro("image", 2)),
+        attempt(target_macro("video", 2)),
+    ))
+    .skip(end_of_line())
+    .map(|children| Block::Macro { children })
+}
+
+fn paragraph_block<Input>(options: BlockOptions) -> impl Parser<Input, Output = Block>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    if options.lazy_inlines {
+        return Either::Left(raw_paragraph_block(options.triple_backtick_code));
+    }
+    Either::Right(
+        many1::<Inlines, _, _>(inline(options.triple_backtick_code)).map(|Inlines(children)| {
+            Block::Paragraph {
+                children: children.into(),
+            }
+        }),
+    )
+}
+
+// The text of a paragraph, its inlines left for later. Only a line break followed by another
+// one or by a line starting a block ends the inlines of a paragraph, so it runs up to there.
+fn raw_paragraph_block<Input>(triple_backtick_code: bool) -> impl Parser<Input, Output = Block>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    not_followed_by(string("\n\n"))
+        .skip(not_followed_by(
+            attempt(newline().with(block_start())).map(|_| "block"),
+        ))
+        .with(take_fn(|text: Input::Range| TakeRange::Found(paragraph_end(text.as_ref()))))
+        .map(move |text: Input::Range| Block::Paragraph {
+            children: LazyInlines::raw(text.as_ref()).triple_backtick_code(triple_backtick_code),
+        })
+}
+
+fn paragraph_end(text: &str) -> usize {
+    text.match_indices('\n')
+        .map(|(index, _)| index)
+        .find(|&index| {
+            let next = &text[index + 1..];
+            next.is_empty() || next.starts_with('\n') || block_start::<&str>().parse(next).is_ok()
+        })
+        .unwrap_or(text.len())
+}
+
+// A block between two delimiter lines, or up to the end of the document when the closing one
+// is missing. Listing, literal and fenced code blocks keep their text as code, tables are split
+// into cells, comments and passthroughs keep their text as is and the others hold blocks of
+// their own. The delimiter decides the kind of the block.
+fn delimited_block<Input>(options: BlockOptions) -> impl Parser<Input, Output = Block>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    block_delimiter()
+        .skip(optional(newline()))
+        .then(move |delimiter: Input::Range| {
+            let delimiter = delimiter.as_ref().trim_end().to_string();
+            let closing = closing_delimiter(&delimiter).to_string();
+            take_fn(move |text: Input::Range| {
+                TakeRange::Found(delimited_content(text.as_ref(), &closing).1)
+            })
+            .skip(end_of_line())
+            .map(move |text: Input::Range| {
+                let text = text.as_ref();
+                let content = &text[..delimited_content(text, closing_delimiter(&delimiter)).0];
+                delimited(&delimiter, content, options)
+            })
+        })
+}
+
+// A fenced code block names its language after the opening backticks only.
+fn closing_delimiter(delimiter: &str) -> &str {
+    match delimiter.starts_with("```") {
+        true => "```",
+        false => delimiter,
+    }
+}
+
+// Length of the content of a delimited block in `text`, which starts below the opening
+// delimiter, and the length up to the end of the closing delimiter line.
+fn delimited_content(text: &str, closing: &str) -> (usize, usize) {
+    let mut delimiters = OpenDelimiters::new(closing);
+    let mut start = 0;
+    for line in text.split_inclusive('\n') {
+        let end = start + line.trim_end_matches('\n').len();
+        if delimiters.closes(line) {
+            return (start.saturating_sub(1), end);
+        }
+        start += line.len();
+    }
+    (text.trim_end_matches('\n').len(), text.len())
+}
+
+// The delimiters of a block and of the blocks nested in it, fed the lines of its content one at
+// a time to find the line closing it. The nested ones are kept on a stack, so the content of a
+// nested listing can hold the container's delimiter. Like in AsciiDoc, a container nests one of
+// its own kind only with a delimiter of another length, and its closing delimiter also closes
+// unclosed nested blocks.
+pub(crate) struct OpenDelimiters {
+    closing: String,
+    nested: Vec<String>,
+}
+
+impl OpenDelimiters {
+    // The block opened by the delimiter line `opening`.
+    pub(crate) fn new(opening: &str) -> Self {
+        OpenDelimiters {
+            closing: closing_delimiter(opening.trim_end()).to_string(),
+            nested: vec![],
+        }
+    }
+
+    pub(crate) fn closes(&mut self, line: &str) -> bool {
+        let delimiter = line.trim_end();
+        match self.nested.last() {
+            Some(nested) if delimiter == closing_delimiter(nested) => {
+                self.nested.pop();
+            }
+            Some(nested) if is_verbatim(nested) => {}
+            _ if delimiter == self.closing => return true,
+            _ if !is_verbatim(&self.closing) && is_delimiter(delimiter) => {
+                self.nested.push(delimiter.to_string())
+            }
+            _ => {}
+        }
+        false
+    }
+}
+
+pub(crate) fn is_delimiter(line: &str) -> bool {
+    matches!(block_delimiter().parse(line.trim_end()), Ok((_, "")))
+}
+
+// Content of listing, literal, passthrough and comment blocks and of fenced code isn't parsed.
+fn is_verbatim(delimiter: &str) -> bool {
+    delimiter != "--" && delimiter.starts_with(['-', '.', '+', '/', '`'])
+}
+
+// Blocks of the content of a container with their attribute lines, parsed like a document of
+// their own. Containers nested deeper than `MAX_NESTING` keep their content as a paragraph.
+fn nested_blocks(content: &str, options: BlockOptions) -> (Vec<Block>, Vec<Option<BlockMeta>>) {
+    let paragraph = || {
+        let paragraph = Block::Paragraph {
+            children: parse_inlines(content, options.triple_backtick_code).into(),
+        };
+        (vec![paragraph], vec![None])
+    };
+    let level = NESTING.with(Cell::get);
+    if level > MAX_NESTING {
+        NESTING_EXCEEDED.with(|exceeded| exceeded.set(true));
+        return paragraph();
+    }
+    let _guard = NestingGuard(level);
+    NESTING.with(|nesting| nesting.set(level + 1));
+    let mut parser = leading_blank_lines().with(many::<Vec<_>, _, _>(block_with_meta(options)));
+    match parser.parse(content) {
+        Ok((blocks, "")) => blocks
+            .into_iter()
+            .map(|(meta, block)| (block, meta))
+            .unzip(),
+        _ => paragraph(),
+    }
+}
+
+fn delimited(delimiter: &str, content: &str, options: BlockOptions) -> Block {
+    let text = || match content.is_empty() {
+        true => smallvec![],
+        false => smallvec![Inline::Value(content.to_string())],
+    };
+    match delimiter.as_bytes()[0] {
+        b'`' => Block::CodeBlock {
+            children: text(),
+            title: None,
+            file_type: Some(delimiter.trim_start_matches('`').trim())
+                .filter(|language| !language.is_empty())
+                .map(Symbol::from),
+        },
+        b'-' | b'.' if delimiter != "--" => Block::CodeBlock {
+            children: text(),
+            title: None,
+            file_type: None,
+        },
+        b'|' => table(content, options),
+        _ => {
+            let kind = DelimitedKind::of_delimiter(delimiter);
+            let (children, metas) = match content.is_empty() {
+                true => (vec![], vec![]),
+                false if kind.is_verbatim() => {
+                    let paragraph = Block::Paragraph {
+                        children: text().into(),
+                    };
+                    (vec![paragraph], vec![None])
+                }
+                false => nested_blocks(content, options),
+            };
+            Block::Block {
+                kind,
+                children,
+                metas,
+                title: None,
+            }
+        }
+    }
+}
+
+// Content of a `|===` table. A first line followed by a blank line holds the column names, the
+// number of cells on the first line of the body is the number of columns otherwise. Lines
+// without a `|` continue the cell before them.
+fn table(content: &str, options: BlockOptions) -> Block {
+    let mut lines = content.lines().collect::<Vec<_>>();
+    let mut columns = vec![];
+    if lines.len() > 1 && lines[1].trim().is_empty() && lines[0].trim_start().starts_with('|') {
+        columns = table_cells(lines[0])
+            .1
+            .into_iter()
+            .map(|(_, text)| TableColumn {
+                name: text.trim().to_string(),
+            })
+            .collect();
+        lines.drain(..2);
+    }
+
+    let mut cells: Vec<(Option<&str>, String)> = vec![];
+    let mut width = columns.len();
+    for line in lines {
+        let (continued, line_cells) = table_cells(line);
+        if let Some((_, text)) = cells.last_mut().filter(|_| !continued.is_empty()) {
+            text.push('\n');
+            text.push_str(continued);
+        }
+        if width == 0 {
+            width = line_cells.len();
+        }
+        cells.extend(
+            line_cells
+                .into_iter()
+                .map(|(style, text)| (style, text.to_string())),
+        );
+    }
+
+    let cells = cells.into_iter().map(|(style, text)| {
+        let text = text.trim();
+        let (blocks, metas) = match (style, text.is_empty()) {
+            (_, true) => (vec![], vec![]),
+            (Some("a"), false) => nested_blocks(text, options),
+            _ => {
+                let paragraph = Block::Paragraph {
+                    children: parse_inlines(text, options.triple_backtick_code).into(),
+                };
+                (vec![paragraph], vec![None])
+            }
+        };
+        TableCell {
+            blocks,
+            metas,
+            span: None,
+            style: style.map(Symbol::from),
+        }
+    });
+    let mut rows = vec![];
+    let mut row = vec![];
+    for cell in cells {
+        row.push(cell);
+        if row.len() == width.max(1) {
+            rows.push(TableRow::new(std::mem::take(&mut row)));
+        }
+    }
+    if !row.is_empty() {
+        rows.push(TableRow::new(row));
+    }
+    Block::Table {
+        columns,
+        rows,
+        title: None,
+    }
+}
+
+// Text of a table line that continues the cell before it, and the cells starting on the line with
+// the style written right before their `|`.
+fn table_cells(line: &str) -> (&str, Vec<(Option<&str>, &str)>) {
+    let mut parts = line.split('|');
+    let first = parts.next().unwrap_or("");
+    let (continued, mut style) = match (line.contains('|'), first.trim()) {
+        (false, _) => (first, None),
+        (true, lead) if is_cell_style(lead) => ("", Some(lead)),
+        (true, _) => split_cell_style(first),
+    };
+    let mut cells = vec![];
+    let mut parts = parts.peekable();
+    while let Some(text) = parts.next() {
+        let (text, next_style) = match parts.peek() {
+            Some(_) => split_cell_style(text),
+            None => (text, None),
+        };
+        cells.push((style, text));
+        style = next_style;
+    }
+    (continued, cells)
+}
+
+// Splits the style of the next cell, a letter after a blank, off the end of a cell.
+fn split_cell_style(text: &str) -> (&str, Option<&str>) {
+    match text.rsplit_once(char::is_whitespace) {
+        Some((before, style)) if is_cell_style(style) => (before, Some(style)),
+        _ => (text, None),
+    }
+}
+
+fn is_cell_style(text: &str) -> bool {
+    matches!(text, "a" | "d" | "e" | "h" | "l" | "m" | "s" | "v")
+}
+
+// The line break ending the previous block and the blank lines after it. Paragraphs only start
+// with a line break followed by text, a run of them is always blank lines.
+fn blank_block<Input>(blank_lines: BlankLines) -> impl Parser<Input, Output = Block>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    let blank = match blank_lines {
+        BlankLines::Collapse => newline().with(take_while1(|c| c == '\n')).map(|_| ()).left(),
+        // A line at a time while more than one blank line is left, the last one takes the
+        // line break before the next block with it.
+        BlankLines::Preserve => attempt(newline().skip(look_ahead(string("\n\n"))))
+            .map(|_| ())
+            .or((newline(), newline()).map(|_| ()))
+            .right(),
+    };
+    blank.map(|_| Block::BlankBlock)
+}
+
+fn horizontal_ruled_line_block<Input>() -> impl Parser<Input, Output = Block>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    string(HORIZONTAL_RULE)
+        .skip(skip_many(token(' ')))
+        .skip(end_of_line())
+        .map(|_| Block::HorizontalRuledLine)
+}
+
+// Blocks other than paragraphs take whole lines, so the next block always starts a line and
+// marks in the middle of one (`*`, `=`, `<<<`) are never read as the start of a block. A ruled
+// line with more text on its line is a paragraph.
+fn line_end<Input>() -> impl Parser<Input, Output = ()>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    look_ahead(newline().map(|_| ()).or(eof()))
+}
+
+// The end of a block taking whole lines. Its line break is taken unless a blank line follows,
+// the blank block starts with it then.
+fn end_of_line<Input>() -> impl Parser<Input, Output = ()>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    line_end().skip(optional(attempt(
+        newline().skip(not_followed_by(newline())),
+    )))
+}
+
+// Runs of four or more of these delimit a block: listing, literal, example, sidebar, quote,
+// passthrough and comment blocks.
+const DELIMITER_MARKS: &str = "-.=*_+/";
+
+// A line starting a block of its own, which ends the text above it without a blank line: an
+// attribute line, a block delimiter, `--` around an open block, `|===` around a table, a
+// fenced code block or a ruled line.
+fn block_start<Input>() -> impl Parser<Input, Output = ()>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    choice((
+        attempt(block_attribute_line()).map(|_| ()),
+        attempt(block_delimiter()).map(|_| ()),
+        attempt(horizontal_ruled_line_block()).map(|_| ()),
+    ))
+}
+
+// The delimiter line of a block, without its line break. A fenced code block names its
+// language on it.
+fn block_delimiter<Input>() -> impl Parser<Input, Output = Input::Range>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    choice((
+        attempt(recognize(string("```").with(skip_many(satisfy(|c| c != '\n'))))),
+        attempt(recognize(string("|==="))),
+        attempt(recognize(string("--").skip(not_followed_by(token('-'))))),
+        recognize(
+            satisfy(|c| DELIMITER_MARKS.contains(c))
+                .then(|c| skip_count_min_max(3, usize::MAX, token(c))),
+        ),
+    ))
+    .skip(skip_many(token(' ')))
+    .skip(line_end())
+}
+
+fn unordered_list_block<Input>(options: BlockOptions) -> impl Parser<Input, Output = Block>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    list_items(UNORDERED_LIST_MARK, options.triple_backtick_code)
+        .map(|items| Block::UnorderdList { children: items })
+}
+
+fn ordered_list_block<Input>(options: BlockOptions) -> impl Parser<Input, Output = Block>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    list_items(ORDERED_LIST_MARK, options.triple_backtick_code)
+        .map(|items| Block::OrderdList { children: items })
+}
+
+// Items on consecutive lines. The line break after the last one ends the list like it ends
+// other blocks, see `end_of_line`.
+fn list_items<Input>(
+    list_char: char,
+    triple_backtick_code: bool,
+) -> impl Parser<Input, Output = Vec<ListItem>>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    (
+        list_item(list_char, triple_backtick_code),
+        many::<Vec<_>, _, _>(attempt(
+            newline().with(list_item(list_char, triple_backtick_code)),
+        )),
+    )
+        .skip(end_of_line())
+        .map(|(first, mut items)| {
+            items.insert(0, first);
+            items
+        })
+}
+
+fn list_item<Input>(
+    list_char: char,
+    triple_backtick_code: bool,
+) -> impl Parser<Input, Output = ListItem>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    choice!(
+        attempt(checked_list_item(list_char, triple_backtick_code)),
+        normal_list_item(list_char, triple_backtick_code)
+    )
+}
+
+fn normal_list_item<Input>(
+    list_char: char,
+    triple_backtick_code: bool,
+) -> impl Parser<Input, Output = ListItem>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    // Like the box of a checked item, the text is set off from the marks by blanks.
+    list_marks(list_char)
+        .and(skip_many1(satisfy(|c| c == ' ' || c == '\t')))
+        .and(list_item_text(triple_backtick_code))
+        .map(|((level, _), inline)| ListItem::Normal {
+            level,
+            children: inline,
+        })
+}
+
+fn checked_list_item<Input>(
+    list_char: char,
+    triple_backtick_code: bool,
+) -> impl Parser<Input, Output = ListItem>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    // The box is only read between the marks and the text, set off by blanks on both sides.
+    let blanks = || skip_many1(satisfy(|c| c == ' ' || c == '\t'));
+    list_marks(list_char)
+        .and(blanks())
+        .and(between(
+            token('['),
+            token(']'),
+            satisfy(|c| CHECKBOX_MARKS.contains(c)),
+        ))
+        .and(blanks())
+        .and(list_item_text(triple_backtick_code))
+        .map(
+            |((((level, _), check_box_char), _), inline)| ListItem::Check {
+                level,
+                children: inline,
+                checked: check_box_char != ' ',
+            },
+        )
+}
+
+// The marks starting a list item. A run longer than `LIST_LEVELS` starts none, the line is text.
+fn list_marks<Input>(list_char: char) -> impl Parser<Input, Output = ListLevel>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    attempt(
+        count_min_max::<String, _, _>(1, LIST_LEVELS, token(list_char))
+            .skip(not_followed_by(token(list_char))),
+    )
+    .map(|marks| ListLevel::from_u8(marks.len() as u8).unwrap())
+}
+
+// The text of a list item, which goes on over the lines after its own until a blank line, the
+// next item or a line starting a block of its own.
+fn list_item_text<Input>(triple_backtick_code: bool) -> impl Parser<Input, Output = InlineVec>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    let line = move || many1::<Inlines, _, _>(list_item_inline_(triple_backtick_code));
+    let item_end = choice((
+        newline().map(|_| ()),
+        attempt((list_marks(UNORDERED_LIST_MARK), token(' '))).map(|_| ()),
+        attempt((list_marks(ORDERED_LIST_MARK), token(' '))).map(|_| ()),
+        block_start(),
+    ));
+    (
+        line(),
+        many::<Vec<_>, _, _>(attempt(
+            newline()
+                .skip(not_followed_by(item_end.map(|_| "block")))
+                .with(line()),
+        )),
+    )
+        .map(|(Inlines(mut children), lines)| {
+            for Inlines(line) in lines {
+                children.push(Inline::SoftBreak);
+                children.extend(line);
+            }
+            children
+        })
+}
+
+// `[` entries separated by `,` `]`. An entry with a `=` is named, any other is positional.
+pub(crate) fn attribute_list<Input>() -> impl Parser<Input, Output = AttrList>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: stream::Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    // Blanks around keys, values and the `,` and `=` between them aren't part of them.
+    let blanks = || skip_many(satisfy(|c| c == ' ' || c == '\t'));
+    let key = || {
+        take_while1(|c| c != '=' && c != ']' && c != ',' && c != '\n')
+            .map(|key: Input::Range| key.as_ref().trim_end().to_string())
+    };
+    // Quotes let a value hold `,`, `=` and `]`.
+    let value = || {
+        choice!(
+            quoted_value('"'),
+            quoted_value('\''),
+            take_while(|c| c != '=' && c != ']' && c != ',' && c != '\n')
+                .map(|value: Input::Range| value.as_ref().trim_end().to_string())
+        )
+    };
+    let named = (blanks(), key(), token('='), blanks(), value(), blanks())
+        .map(|(_, key, _, _, value, _)| Attr::Named(key.into(), value));
+    let positional = take_while1(|c| c != ']' && c != '\n' && c != ',')
+        .map(|value: Input::Range| Attr::Positional(value.as_ref().trim().to_string()));
+    let entry = attempt(named.skip(look_ahead(token(',').or(token(']'))))).or(positional);
+
+    between(token('['), token(']'), sep_by1(entry, token(',')))
+        .map(|entries: Vec<Attr>| entries.into_iter().collect())
+}
+
+// Attribute value in `quote`s, read up to the closing one. A backslash before the quote keeps it
+// in the value, other backslashes are text.
+fn quoted_value<Input>(quote: char) -> impl Parser<Input, Output = String>
+where
+    Input: Stream<Token = char>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    between(
+        token(quote),
+        token(quote),
+        many(choice((
+            attempt(token('\\').with(token(quote))),
+            satisfy(move |c| c != quote && c != '\n'),
+        ))),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use combine::error::StringStreamError;
+    use pretty_assertions::assert_eq;
+    // -- utils
+    fn take_parse_result<T, E>(t: (T, E)) -> T {
+        t.0
+    }
+    // --
+
+    #[test]
+    fn test_parse_function() {
+        let asciidoc = "
+== This is a Heading
+
+This is a Paragraph
+
+== Foobar
+
+This is a b**ol**d text
+
+This is a *bold* text
+
+This is a _italic_ text
+
+This is a `monospace` text
+
+This is a #marker# text
+
+This is a `+inline *code*+` text
+
+wrap break *
+a
+
+* foo
+* bar
+
+
+. foo
+. bar
+<<<
+
+";
+
+        let result = parse(asciidoc).unwrap();
+        assert_eq!(
+            result,
+            vec![
+                Block::Heading {
+                    level: HeadingLevel::Level1,
+                    id: None,
+                    children: smallvec![Inline::Value("This is a Heading".to_string())]
+                },
+                Block::BlankBlock,
+                Block::Paragraph {
+                    children: smallvec![Inline::Value("This is a Paragraph".to_string())].into()
+                },
+                Block::BlankBlock,
+                Block::Heading {
+                    level: HeadingLevel::Level1,
+                    id: None,
+                    children: smallvec![Inline::Value("Foobar".to_string())]
+                },
+                Block::BlankBlock,
+                Block::Paragraph {
+                    children: smallvec![
+                        Inline::Value("This is a b".to_string()),
+                        Inline::Bold {
+                            children: Box::new(Inline::Value("ol".to_string()))
+                        },
+                        Inline::Value("d text".to_string()),
+                    ].into()
+                },
+                Block::BlankBlock,
+                Block::Paragraph {
+                    children: smallvec![
+                        Inline::Value("This is a ".to_string()),
+                        Inline::Bold {
+                            children: Box::new(Inline::Value("bold".to_string()))
+                        },
+                        Inline::Value(" text".to_string()),
+                    ].into()
+                },
+                Block::BlankBlock,
+                Block::Paragraph {
+                    children: smallvec![
+                        Inline::Value("This is a ".to_string()),
+                        Inline::Italic {
+                            children: Box::new(Inline::Value("italic".to_string()))
+                        },
+                        Inline::Value(" text".to_string()),
+                    ].into()
+                },
+                Block::BlankBlock,
+                Block::Paragraph {
+                    children: smallvec![
+                        Inline::Value("This is a ".to_string()),
+                        Inline::Monospace {
+                            children: Box::new(Inline::Value("monospace".to_string()))
+                        },
+                        Inline::Value(" text".to_string()),
+                    ].into()
+                },
+                Block::BlankBlock,
+                Block::Paragraph {
+                    children: smallvec![
+                        Inline::Value("This is a ".to_string()),
+                        Inline::Marker {
+                            children: Box::new(Inline::Value("marker".to_string()))
+                        },
+                        Inline::Value(" text".to_string()),
+                    ].into()
+                },
+                Block::BlankBlock,
+                Block::Paragraph {
+                    children: smallvec![
+                        Inline::Value("This is a ".to_string()),
+                        Inline::Monospace {
+                            children: Box::new(Inline::Literal {
+                                children: Box::new(Inline::Value("inline *code*".to_string()))
+                            })
+                        },
+                        Inline::Value(" text".to_string()),
+                    ].into()
+                },
+                Block::BlankBlock,
+                Block::Paragraph {
+                    children: smallvec![
+                        Inline::Value("wrap break *".to_string()),
+                        Inline::SoftBreak,
+                        Inline::Value("a".to_string())
+                    ].into()
+                },
+                Block::BlankBlock,
+                Block::UnorderdList {
+                    children: vec![
+                        ListItem::Normal {
+                            children: smallvec![Inline::Value("foo".to_string())],
+                            level: ListLevel::Level1
+                        },
+                        ListItem::Normal {
+                            children: smallvec![Inline::Value("bar".to_string())],
+                            level: ListLevel::Level1
+                        }
+                    ]
+                },
+                Block::BlankBlock,
+                Block::OrderdList {
+                    children: vec![
+                        ListItem::Normal {
+                            children: smallvec![Inline::Value("foo".to_string())],
+                            level: ListLevel::Level1
+                        },
+                        ListItem::Normal {
+                            children: smallvec![Inline::Value("bar".to_string())],
+                            level: ListLevel::Level1
+                        }
+                    ]
+                },
+                Block::HorizontalRuledLine,
+                Block::BlankBlock,
+            ]
+        )
+    }
+    #[test]
+    fn test_parse_spanned() {
+        let actual = parse_spanned("\n== A\n\nfoo", BlockOptions::default()).unwrap();
+        let spans = actual.iter().map(|parsed| parsed.span).collect::<Vec<_>>();
+        assert_eq!(
+            spans,
+            vec![
+                Span { start: 1, end: 5 },
+                Span { start: 5, end: 7 },
+                Span { start: 7, end: 10 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_spanned_skips_bom() {
+        let actual = parse_spanned("\u{feff}= A", BlockOptions::default()).unwrap();
+        assert_eq!(actual[0].span, Span { start: 3, end: 6 });
+        assert_eq!(
+            parse("\u{feff}text").unwrap(),
+            vec![Block::Paragraph {
+                children: smallvec![Inline::Value("text".to_string())].into()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_spanned_keeps_leading_spaces() {
+        let actual = parse_spanned("\n \n  literal", BlockOptions::default()).unwrap();
+        assert_eq!(actual[0].span, Span { start: 3, end: 12 });
+        assert_eq!(
+            actual[0].block,
+            Block::Paragraph {
+                children: smallvec![Inline::Value("  literal".to_string())].into()
+            }
+        );
+        assert_eq!(parse("\n\n").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_parse_spanned_lazy() {
+        let source = "= A *b*\nc\n\nsome _text_\nmore\n\n\n tail\n";
+        let spans = |parsed: &[ParsedBlock]| parsed.iter().map(|p| p.span).collect::<Vec<_>>();
+        let eager = parse_spanned(source, BlockOptions::default()).unwrap();
+        let options = BlockOptions {
+            lazy_inlines: true,
+            ..BlockOptions::default()
+        };
+        let lazy = parse_spanned(source, options).unwrap();
+        assert_eq!(spans(&lazy), spans(&eager));
+
+        let paragraph = |parsed: &ParsedBlock| match &parsed.block {
+            Block::Paragraph { children } => children.source().map(str::to_string),
+            _ => None,
+        };
+        assert_eq!(
+            lazy.iter().filter_map(paragraph).collect::<Vec<_>>(),
+            vec!["c", "some _text_\nmore", " tail"]
+        );
+        assert_eq!(
+            lazy.into_iter().map(|p| p.block).collect::<Vec<_>>(),
+            eager.into_iter().map(|p| p.block).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_blank_lines() {
+        let source = "a\n\n\n\nb\n\nc";
+        let blocks = |blank_lines| {
+            let options = BlockOptions {
+                blank_lines,
+                ..BlockOptions::default()
+            };
+            parse_spanned(source, options)
+                .unwrap()
+                .into_iter()
+                .map(|parsed| (parsed.block.kind(), parsed.span.start, parsed.span.end))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            blocks(BlankLines::Collapse),
+            vec![
+                (BlockKind::Paragraph, 0, 1),
+                (BlockKind::BlankBlock, 1, 5),
+                (BlockKind::Paragraph, 5, 6),
+                (BlockKind::BlankBlock, 6, 8),
+                (BlockKind::Paragraph, 8, 9),
+            ]
+        );
+        assert_eq!(
+            blocks(BlankLines::Preserve),
+            vec![
+                (BlockKind::Paragraph, 0, 1),
+                (BlockKind::BlankBlock, 1, 2),
+                (BlockKind::BlankBlock, 2, 3),
+                (BlockKind::BlankBlock, 3, 5),
+                (BlockKind::Paragraph, 5, 6),
+                (BlockKind::BlankBlock, 6, 8),
+                (BlockKind::Paragraph, 8, 9),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_block_accessors() {
+        let blocks = parse("== *Head*\n\n* foo").unwrap();
+        assert_eq!(blocks[0].kind(), BlockKind::Heading);
+        assert_eq!(blocks[0].heading_level(), Some(&HeadingLevel::Level1));
+        assert_eq!(blocks[0].inlines()[0].kind(), InlineKind::Bold);
+        assert_eq!(
+            blocks[0].inlines()[0].child().and_then(Inline::as_value),
+            Some("Head")
+        );
+        assert_eq!(blocks[1].kind(), BlockKind::BlankBlock);
+        assert_eq!(blocks[1].inlines(), &[]);
+        assert_eq!(blocks[2].list_items()[0].level(), 1);
+        assert_eq!(
+            blocks[2].list_items()[0].children(),
+            &[Inline::Value("foo".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_block_with_meta() {
+        let (actual, _) = block_with_meta(BlockOptions::default())
+            .parse("[source#main.lead.small%linenums]\nfoo")
+            .unwrap();
+        assert_eq!(
+            actual,
+            (
+                Some(BlockMeta {
+                    style: Some("source".into()),
+                    id: Some("main".to_string()),
+                    roles: vec!["lead".into(), "small".into()],
+                    options: vec!["linenums".into()],
+                    attributes: AttrList::from(vec!["source#main.lead.small%linenums".to_string()]),
+                }),
+                Block::Paragraph {
+                    children: smallvec![Inline::Value("foo".to_string())].into()
+                }
+            )
+        );
+
+        let (actual, _) = block_with_meta(BlockOptions::default()).parse("[.notes]\n* foo").unwrap();
+        assert_eq!(actual.0.map(|meta| meta.roles), Some(vec![Symbol::new("notes")]));
+
+        let (actual, _) = block_with_meta(BlockOptions::default()).parse("[x] is a checkbox").unwrap();
+        assert_eq!(actual.0, None);
+
+        let language = |source: &str| match block_with_meta(BlockOptions::default()).parse(source) {
+            Ok(((_, Block::CodeBlock { file_type, .. }), _)) => file_type,
+            _ => None,
+        };
+        assert_eq!(
+            language("[source,rust]\n----\nlet x;\n----"),
+            Some("rust".into())
+        );
+        assert_eq!(language("[source]\n----\nlet x;\n----"), None);
+        assert_eq!(language("[listing,rust]\n----\nlet x;\n----"), None);
+        // A fence names its own language.
+        assert_eq!(
+            language("[source,rust]\n```toml\nx\n```"),
+            Some("toml".into())
+        );
+    }
+
+    #[test]
+    fn test_inline() {
+        let actual = inline(false).parse(" aadf").map(take_parse_result);
+        assert_eq!(actual, Ok(Inline::Value(" aadf".to_string())));
+    }
+
+    #[test]
+    fn test_block() {
+        let actual = block(BlockOptions::default())
+            .parse("=== Head\n\nHelloWorld")
+            .map(take_parse_result);
+        assert_eq!(
+            actual,
+            Ok(Block::Heading {
+                level: HeadingLevel::Level2,
+                id: None,
+                children: smallvec![Inline::Value("Head".to_string())]
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_heading() {
+        let (actual, _) = heading_block(BlockOptions::default())
+            .parse("= Heading")
+            .unwrap();
+        assert_eq!(
+            actual,
+            Block::Heading {
+                level: HeadingLevel::Title,
+                children: smallvec![Inline::Value("Heading".to_string())],
+                id: None
+            }
+        );
+
+        let (actual, _) = heading_block(BlockOptions::default())
+            .parse("== Heading")
+            .unwrap();
+        assert_eq!(
+            actual,
+            Block::Heading {
+                level: HeadingLevel::Level1,
+                children: smallvec![Inline::Value("Heading".to_string())],
+                id: None
+            }
+        );
+
+        let (actual, _) = heading_block(BlockOptions::default())
+            .parse("=== Heading")
+            .unwrap();
+        assert_eq!(
+            actual,
+            Block::Heading {
+                level: HeadingLevel::Level2,
+                children: smallvec![Inline::Value("Heading".to_string())],
+                id: None
+            }
+        );
+
+        let (actual, _) = heading_block(BlockOptions::default())
+            .parse("==== Heading")
+            .unwrap();
+        assert_eq!(
+            actual,
+            Block::Heading {
+                level: HeadingLevel::Level3,
+                children: smallvec![Inline::Value("Heading".to_string())],
+                id: None
+            }
+        );
+
+        let (actual, _) = heading_block(BlockOptions::default())
+            .parse("===== Heading")
+            .unwrap();
+        assert_eq!(
+            actual,
+            Block::Heading {
+                level: HeadingLevel::Level4,
+                children: smallvec![Inline::Value("Heading".to_string())],
+                id: None
+            }
+        );
+
+        let (actual, _) = heading_block(BlockOptions::default())
+            .parse("== Heading ==  ")
+            .unwrap();
+        assert_eq!(
+            actual,
+            Block::Heading {
+                level: HeadingLevel::Level1,
+                children: smallvec![Inline::Value("Heading".to_string())],
+                id: None
+            }
+        );
+
+        let (actual, _) = heading_block(BlockOptions::default())
+            .parse("=== *Bold* ===\nnext")
+            .unwrap();
+        assert_eq!(
+            actual,
+            Block::Heading {
+                level: HeadingLevel::Level2,
+                children: smallvec![Inline::Bold {
+                    children: Box::new(Inline::Value("Bold".to_string()))
+                }],
+                id: None
+            }
+        );
+
+        let (actual, _) = heading_block(BlockOptions::default())
+            .parse("== a+b==c =")
+            .unwrap();
+        assert_eq!(
+            actual,
+            Block::Heading {
+                level: HeadingLevel::Level1,
+                children: smallvec![Inline::Value("a+b==c =".to_string())],
+                id: None
+            }
+        );
+
+        let (actual, _) = heading_block(BlockOptions::default())
+            .parse("======= Too deep *x* y")
+            .unwrap();
+        assert_eq!(
+            actual,
+            Block::Paragraph {
+                children: (smallvec![
+                    Inline::Value("======= Too deep ".to_string()),
+                    Inline::Bold {
+                        children: Box::new(Inline::Value("x".to_string()))
+                    },
+                    Inline::Value(" y".to_string()),
+                ] as InlineVec)
+                    .into(),
+            }
+        );
+        let (actual, _) = heading_block(BlockOptions::default())
+            .parse("====== *x*")
+            .unwrap();
+        assert_eq!(actual.inlines()[0], Inline::Value("====== ".to_string()));
+    }
+
+    #[test]
+    fn test_bold() {
+        let actual = bold(false).parse("*人間*").map(take_parse_result);
+        assert_eq!(
+            actual,
+            Ok(Inline::Bold {
+                children: Box::new(Inline::Value("人間".to_string()))
+            })
+        );
+
+        // Spaces before the marks are text of their own, the span starts at the mark.
+        assert_eq!(bold(false).parse("  *a*").is_err(), true);
+        assert_eq!(
+            parse_inlines("a  *b* _c_", false).into_vec(),
+            vec![
+                Inline::Value("a  ".to_string()),
+                Inline::Bold {
+                    children: Box::new(Inline::Value("b".to_string()))
+                },
+                Inline::Value(" ".to_string()),
+                Inline::Italic {
+                    children: Box::new(Inline::Value("c".to_string()))
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        let nested = |depth: usize| format!("a {}x{}", "*_".repeat(depth), "_*".repeat(depth));
+        let blocks = parse(&nested(MAX_NESTING / 2)).unwrap();
+        assert_eq!(blocks[0].inlines().len(), 2);
+
+        // Marks past the limit are taken as text instead of overflowing the stack.
+        let blocks = parse(&nested(10_000)).unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(take_nesting_exceeded(), true);
+    }
+
+    #[test]
+    fn test_italic() {
+        let actual = italic(false).parse("_人間_").map(take_parse_result);
+        assert_eq!(
+            actual,
+            Ok(Inline::Italic {
+                children: Box::new(Inline::Value("人間".to_string()))
+            })
+        );
+    }
+
+    #[test]
+    fn test_marker() {
+        let actual = marker(false).parse("#人間#").map(take_parse_result);
+        assert_eq!(
+            actual,
+            Ok(Inline::Marker {
+                children: Box::new(Inline::Value("人間".to_string()))
+            })
+        );
+    }
+
+    #[test]
+    fn test_styled() {
+        let actual = styled(false)
+            .parse("[.keyword.big]#fn#")
+            .map(take_parse_result);
+        assert_eq!(
+            actual,
+            Ok(Inline::Styled {
+                roles: vec!["keyword".into(), "big".into()],
+                children: Box::new(Inline::Value("fn".to_string()))
+            })
+        );
+        let actual = parse_inlines("see [1] and [.x] #y#", false);
+        assert_eq!(
+            actual,
+            smallvec![
+                Inline::Value("see [1] and [.x] ".to_string()),
+                Inline::Marker {
+                    children: Box::new(Inline::Value("y".to_string()))
+                }
+            ] as InlineVec
+        );
+    }
+
+    #[test]
+    fn test_inline_code() {
+        let actual = inline_code(true).parse(r"```npm```").map(take_parse_result);
+        assert_eq!(
+            actual,
+            Ok(Inline::InlineCode {
+                children: Box::new(Inline::Value("npm".to_string()))
+            })
+        );
+
+        let actual = inline_code(true).parse(r"`npm`").map(take_parse_result);
+        assert_eq!(
+            actual,
+            Err(combine::error::StringStreamError::UnexpectedParse)
+        );
+    }
+
+    #[test]
+    fn test_monospace() {
+        let actual = monospace(false).parse("`人間`").map(take_parse_result);
+        assert_eq!(
+            actual,
+            Ok(Inline::Monospace {
+                children: Box::new(Inline::Value("人間".to_string()))
+            })
+        );
+
+        let actual = monospace(false).parse("`*a*`").map(take_parse_result);
+        assert_eq!(
+            actual,
+            Ok(Inline::Monospace {
+                children: Box::new(Inline::Bold {
+                    children: Box::new(Inline::Value("a".to_string()))
+                })
+            })
+        );
+        let actual = monospace(false).parse("`+a+ b`").map(take_parse_result);
+        assert_eq!(
+            actual,
+            Ok(Inline::Monospace {
+                children: Box::new(Inline::Value("+a+ b".to_string()))
+            })
+        );
+
+        // Three backticks are inline code only in compat mode.
+        let source = "a ```npm``` b";
+        assert_eq!(
+            parse(source).unwrap()[0].inlines()[1].kind(),
+            InlineKind::Monospace
+        );
+        let options = BlockOptions {
+            triple_backtick_code: true,
+            ..BlockOptions::default()
+        };
+        let actual = parse_spanned(source, options).unwrap();
+        assert_eq!(
+            actual[0].block.inlines()[1],
+            Inline::InlineCode {
+                children: Box::new(Inline::Value("npm".to_string()))
+            }
+        );
+    }
+
+    #[test]
+    fn test_footnote() {
+        let actual = parse_inlines("A claim.footnote:[TIP: See a\\] b.] and footnote: x", false);
+        assert_eq!(
+            actual,
+            smallvec![
+                Inline::Value("A claim.".to_string()),
+                Inline::Footnote {
+                    kind: FootnoteType::Tip,
+                    children: Box::new(Inline::Value("See a] b.".to_string()))
+                },
+                Inline::Value(" and footnote: x".to_string()),
+            ] as InlineVec
+        );
+        // Only where a word starts.
+        let actual = parse_inlines("afootnote:[x]", false);
+        assert_eq!(
+            actual,
+            smallvec![Inline::Value("afootnote:[x]".to_string())] as InlineVec
+        );
+        let actual = parse("== Title footnote:[x]\n* item footnote:[y]").unwrap();
+        assert_eq!(actual[0].inlines()[1].kind(), InlineKind::Footnote);
+        match &actual[1] {
+            Block::UnorderdList { children } => {
+                assert_eq!(children[0].children()[1].kind(), InlineKind::Footnote)
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_image() {
+        let actual =
+            parse("An image:logo.png[Logo, width=80] icon.\n\nimage::diagram.png[]").unwrap();
+        assert_eq!(
+            actual[0].inlines()[1],
+            Inline::Macro {
+                attributes: Box::new(
+                    vec![
+                        Attr::Positional("Logo".to_string()),
+                        Attr::Named("width".into(), "80".to_string()),
+                    ]
+                    .into_iter()
+                    .collect()
+                ),
+                kind: "image".into(),
+                id: "logo.png".to_string(),
+            }
+        );
+        assert_eq!(actual[2].kind(), BlockKind::Macro);
+        assert_eq!(
+            actual[2].inlines(),
+            &[Inline::Macro {
+                attributes: Box::new(AttrList::new()),
+                kind: "image".into(),
+                id: "diagram.png".to_string(),
+            }]
+        );
+        // Only a macro on a line of its own is a block.
+        let actual = parse("image::a.png[] and text\n\nimage:a.png[]").unwrap();
+        assert_eq!(actual[0].kind(), BlockKind::Paragraph);
+        assert_eq!(actual[2].kind(), BlockKind::Paragraph);
+        let actual = parse_inlines("image: not a macro and image:x.png", false);
+        assert_eq!(
+            actual,
+            smallvec![Inline::Value(
+                "image: not a macro and image:x.png".to_string()
+            )] as InlineVec
+        );
+    }
+
+    #[test]
+    fn test_video() {
+        let actual = parse("video::76979871[vimeo]\n\nSee video:clip.mp4[].").unwrap();
+        assert_eq!(
+            actual[0].inlines(),
+            &[Inline::Macro {
+                attributes: Box::new(AttrList::from(vec!["vimeo".to_string()])),
+                kind: "video".into(),
+                id: "76979871".to_string(),
+            }]
+        );
+        assert_eq!(
+            actual[2].inlines()[1],
+            Inline::Macro {
+                attributes: Box::new(AttrList::new()),
+                kind: "video".into(),
+                id: "clip.mp4".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_formula() {
+        let formula = |kind: &str, text: &str| Inline::Macro {
+            attributes: Box::new(AttrList::from(vec![text.to_string()])),
+            kind: kind.into(),
+            id: String::new(),
+        };
+        let actual = parse_inlines(
+            "stem:[x^2] and latexmath:[f(a, b) = \\frac{a}{b}] or asciimath:[[a\\]]",
+            false,
+        );
+        assert_eq!(
+            actual,
+            smallvec![
+                formula("stem", "x^2"),
+                Inline::Value(" and ".to_string()),
+                formula("latexmath", "f(a, b) = \\frac{a}{b}"),
+                Inline::Value(" or ".to_string()),
+                formula("asciimath", "[a]"),
+            ] as InlineVec
+        );
+    }
+
+    #[test]
+    fn test_value() {
+        let actual = value::<_, INLINE_TERMINATORS>().parse("人間").map(take_parse_result);
+        assert_eq!(actual, Ok(Inline::Value("人間".to_string())));
+
+        let actual = value::<_, INLINE_TERMINATORS>().parse("a#b").map(take_parse_result);
+        assert_eq!(actual, Ok(Inline::Value("a".to_string())));
+        let actual = value::<_, LIST_ITEM_TERMINATORS>().parse("a#b\nc").map(take_parse_result);
+        assert_eq!(actual, Ok(Inline::Value("a#b".to_string())));
+        assert_eq!(value::<_, INLINE_TERMINATORS>().parse("*a").is_err(), true);
+        let actual = value::<_, INLINE_TERMINATORS>()
+            .parse("人間 image:x.png[] a:b")
+            .map(take_parse_result);
+        assert_eq!(actual, Ok(Inline::Value("人間 ".to_string())));
+        let actual = value::<_, INLINE_TERMINATORS>()
+            .parse("image:x.png[]")
+            .map(take_parse_result);
+        assert_eq!(actual, Ok(Inline::Value("image:x.png".to_string())));
+    }
+
+    #[test]
+    fn test_line_break() {
+        let actual = line_break().parse("\nb").map(take_parse_result);
+        assert_eq!(actual, Ok(Inline::SoftBreak));
+
+        let actual = line_break().parse("\n\n").is_err();
+        assert_eq!(actual, true);
+
+        let actual = line_break().parse("\n").is_err();
+        assert_eq!(actual, true);
+
+        let actual = line_break().parse(" +\n").map(take_parse_result);
+        assert_eq!(actual, Ok(Inline::HardBreak));
+    }
+
+    #[test]
+    fn test_paragraph() {
+        let actual = paragraph_block(BlockOptions::default())
+            .parse("人間 *a* 人間")
+            .map(take_parse_result);
+        assert_eq!(
+            actual,
+            Ok(Block::Paragraph {
+                children: smallvec![
+                    Inline::Value("人間 ".to_string()),
+                    Inline::Bold {
+                        children: Box::new(Inline::Value("a".to_string()))
+                    },
+                    Inline::Value(" 人間".to_string())
+                ].into()
+            })
+        );
+
+        let actual = paragraph_block(BlockOptions::default())
+            .parse("人間 ")
+            .map(take_parse_result);
+        assert_eq!(
+            actual,
+            Ok(Block::Paragraph {
+                children: smallvec![Inline::Value("人間 ".to_string()),].into()
+            })
+        );
+        let actual = paragraph_block(BlockOptions::default())
+            .parse("人間")
+            .map(take_parse_result);
+        assert_eq!(
+            actual,
+            Ok(Block::Paragraph {
+                children: smallvec![Inline::Value("人間".to_string())].into()
+            })
+        );
+
+        let actual = paragraph_block(BlockOptions::default())
+            .parse("C# and a_b * c")
+            .map(take_parse_result);
+        assert_eq!(
+            actual,
+            Ok(Block::Paragraph {
+                children: smallvec![Inline::Value("C# and a_b * c".to_string())].into()
+            })
+        );
+
+        let actual = paragraph_block(BlockOptions::default())
+            .parse("a **bold _with no closing")
+            .map(take_parse_result);
+        assert_eq!(
+            actual,
+            Ok(Block::Paragraph {
+                children: smallvec![Inline::Value("a **bold _with no closing".to_string())].into()
+            })
+        );
+
+        let actual = paragraph_block(BlockOptions::default()).parse("人間\n").map(take_parse_result);
+        assert_eq!(
+            actual,
+            Ok(Block::Paragraph {
+                children: smallvec![Inline::Value("人間".to_string())].into()
+            })
+        );
+    }
+
+    #[test]
+    fn test_paragraph_ends_before_block() {
+        let actual = paragraph_block(BlockOptions::default())
+            .parse("a\nb\n[source]\ncode")
+            .unwrap();
+        assert_eq!(
+            actual,
+            (
+                Block::Paragraph {
+                    children: smallvec![
+                        Inline::Value("a".to_string()),
+                        Inline::SoftBreak,
+                        Inline::Value("b".to_string()),
+                    ]
+                    .into()
+                },
+                "\n[source]\ncode"
+            )
+        );
+        assert_eq!(paragraph_end("a\nb\n|===\n|c\n|==="), 3);
+        assert_eq!(paragraph_end("a\n[b] c\n\nd"), 7);
+
+        let source = "Text\n----\nfn main() {\n\n}\n----\nMore\n";
+        let eager = parse(source).unwrap();
+        assert_eq!(
+            eager[1],
+            Block::CodeBlock {
+                children: smallvec![Inline::Value("fn main() {\n\n}".to_string())],
+                title: None,
+                file_type: None,
+            }
+        );
+        let lazy = parse_spanned(
+            source,
+            BlockOptions {
+                lazy_inlines: true,
+                ..BlockOptions::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            lazy.into_iter().map(|parsed| parsed.block).collect::<Vec<_>>(),
+            eager
+        );
+    }
+
+    #[test]
+    fn test_table() {
+        let block = |source: &str| block(BlockOptions::default()).parse(source).unwrap().0;
+        let paragraph = |text: &str| Block::Paragraph {
+            children: smallvec![Inline::Value(text.to_string())].into(),
+        };
+        assert_eq!(
+            block("|===\n|Name |Value\n\n|a |1\nm|b a|* item\n|==="),
+            Block::Table {
+                columns: vec![
+                    TableColumn {
+                        name: "Name".to_string()
+                    },
+                    TableColumn {
+                        name: "Value".to_string()
+                    },
+                ],
+                rows: vec![
+                    TableRow::new(vec![
+                        TableCell::new(vec![paragraph("a")]),
+                        TableCell::new(vec![paragraph("1")]),
+                    ]),
+                    TableRow::new(vec![
+                        TableCell {
+                            style: Some("m".into()),
+                            ..TableCell::new(vec![paragraph("b")])
+                        },
+                        TableCell {
+                            style: Some("a".into()),
+                            ..TableCell::new(vec![Block::UnorderdList {
+                                children: vec![ListItem::Normal {
+                                    level: ListLevel::Level1,
+                                    children: smallvec![Inline::Value("item".to_string())],
+                                }],
+                            }])
+                        },
+                    ]),
+                ],
+                title: None,
+            }
+        );
+        // Without a header, the first line gives the number of columns.
+        match block("|===\n|a |b\n|c\ncontinued |d\n|===") {
+            Block::Table { columns, rows, .. } => {
+                assert_eq!(columns.is_empty(), true);
+                assert_eq!(rows.len(), 2);
+                assert_eq!(
+                    rows[1].cells()[0].blocks,
+                    vec![Block::Paragraph {
+                        children: (smallvec![
+                            Inline::Value("c".to_string()),
+                            Inline::SoftBreak,
+                            Inline::Value("continued".to_string()),
+                        ] as InlineVec)
+                            .into()
+                    }]
+                );
+            }
+            block => panic!("not a table: {:?}", block),
+        }
+    }
+
+    #[test]
+    fn test_delimited_block() {
+        let block = |source: &str| block(BlockOptions::default()).parse(source).unwrap().0;
+        assert_eq!(
+            block("```rust\nlet x;\n```"),
+            Block::CodeBlock {
+                children: smallvec![Inline::Value("let x;".to_string())],
+                title: None,
+                file_type: Some("rust".into()),
+            }
+        );
+        assert_eq!(
+            block("....\nunclosed\n"),
+            Block::CodeBlock {
+                children: smallvec![Inline::Value("unclosed".to_string())],
+                title: None,
+                file_type: None,
+            }
+        );
+        assert_eq!(
+            block("====\nsome *bold*\n===="),
+            Block::Block {
+                kind: DelimitedKind::Example,
+                children: vec![Block::Paragraph {
+                    children: (smallvec![
+                        Inline::Value("some ".to_string()),
+                        Inline::Bold {
+                            children: Box::new(Inline::Value("bold".to_string()))
+                        },
+                    ] as InlineVec)
+                        .into()
+                }],
+                metas: vec![None],
+                title: None,
+            }
+        );
+        assert_eq!(
+            block("////\ncomment *not bold*\n////"),
+            Block::Block {
+                kind: DelimitedKind::Comment,
+                children: vec![Block::Paragraph {
+                    children: smallvec![Inline::Value("comment *not bold*".to_string())].into()
+                }],
+                metas: vec![None],
+                title: None,
+            }
+        );
+        assert_eq!(
+            block("++++\n<b>raw</b>\n++++"),
+            Block::Block {
+                kind: DelimitedKind::Pass,
+                children: vec![Block::Paragraph {
+                    children: smallvec![Inline::Value("<b>raw</b>".to_string())].into()
+                }],
+                metas: vec![None],
+                title: None,
+            }
+        );
+        let kind = |source: &str| match block(source) {
+            Block::Block { kind, .. } => Some(kind),
+            _ => None,
+        };
+        assert_eq!(kind("--\ntext\n--"), Some(DelimitedKind::Open));
+        assert_eq!(kind("****\ntext\n****"), Some(DelimitedKind::Sidebar));
+        assert_eq!(kind("____\ntext\n____"), Some(DelimitedKind::Quote));
+        assert_eq!(kind("****\n****"), Some(DelimitedKind::Sidebar));
+        assert_eq!(block("----").kind(), BlockKind::CodeBlock);
+        assert_eq!(block("--- a").kind(), BlockKind::Paragraph);
+        assert_eq!(
+            parse("----\nx\n----\nfoo").unwrap()[1],
+            Block::Paragraph {
+                children: smallvec![Inline::Value("foo".to_string())].into()
+            }
+        );
+    }
+
+    #[test]
+    fn test_nested_blocks() {
+        let blocks =
+            parse("====\n****\ninner\n\n----\n====\n----\n****\n\nafter\n====\n\nnext").unwrap();
+        assert_eq!(blocks.len(), 3);
+        let outer = blocks[0].blocks();
+        assert_eq!(
+            outer.iter().map(Block::kind).collect::<Vec<_>>(),
+            vec![
+                BlockKind::Block,
+                BlockKind::BlankBlock,
+                BlockKind::Paragraph
+            ]
+        );
+        let inner = outer[0].blocks();
+        assert_eq!(
+            inner.iter().map(Block::kind).collect::<Vec<_>>(),
+            vec![
+                BlockKind::Paragraph,
+                BlockKind::BlankBlock,
+                BlockKind::CodeBlock
+            ]
+        );
+        assert_eq!(
+            inner[2],
+            Block::CodeBlock {
+                children: smallvec![Inline::Value("====".to_string())],
+                title: None,
+                file_type: None,
+            }
+        );
+        assert_eq!(blocks[2].kind(), BlockKind::Paragraph);
+
+        let blocks = parse("====\n****\ninner\n====\n\nafter").unwrap();
+        assert_eq!(
+            blocks[0].blocks()[0].blocks()[0].kind(),
+            BlockKind::Paragraph
+        );
+        assert_eq!(blocks[2].kind(), BlockKind::Paragraph);
+        let blocks = parse("====\n=====\ninner\n=====\n====").unwrap();
+        assert_eq!(
+            blocks[0].blocks()[0].blocks()[0].kind(),
+            BlockKind::Paragraph
+        );
+
+        let blocks = parse("====\n[#first.lead]\none\n\ntwo\n====").unwrap();
+        let meta = blocks[0].nested_meta(0).unwrap();
+        assert_eq!(meta.id.as_deref(), Some("first"));
+        assert_eq!(meta.roles, vec![Symbol::from("lead")]);
+        assert_eq!(blocks[0].nested_meta(2), None);
+    }
+
+    #[test]
+    fn test_horizontal_ruled_line_block() {
+        let actual = horizontal_ruled_line_block()
+            .parse("<<<")
+            .map(take_parse_result);
+        assert_eq!(actual, Ok(Block::HorizontalRuledLine));
+
+        let actual = horizontal_ruled_line_block()
+            .parse("<<")
+            .map(take_parse_result);
+        assert_eq!(actual, Err(StringStreamError::Eoi));
+    }
+
+    #[test]
+    fn test_blocks_start_at_line_start() {
+        assert_eq!(
+            parse("== Heading with *bold* words").unwrap(),
+            vec![Block::Heading {
+                level: HeadingLevel::Level1,
+                id: None,
+                children: smallvec![
+                    Inline::Value("Heading with ".to_string()),
+                    Inline::Bold {
+                        children: Box::new(Inline::Value("bold".to_string()))
+                    },
+                    Inline::Value(" words".to_string()),
+                ]
+            }]
+        );
+        assert_eq!(
+            parse("<<<foo
+
+<<< 
+bar").unwrap(),
+            vec![
+                Block::Paragraph {
+                    children: smallvec![Inline::Value("<<<foo".to_string())].into()
+                },
+                Block::BlankBlock,
+                Block::HorizontalRuledLine,
+                Block::Paragraph {
+                    children: smallvec![Inline::Value("bar".to_string())].into()
+                },
+            ]
+        );
+
+        // The line break ending a heading goes with it, a line below it starts a paragraph.
+        let heading = || Block::Heading {
+            level: HeadingLevel::Level1,
+            id: None,
+            children: smallvec![Inline::Value("Title".to_string())],
+        };
+        assert_eq!(parse("== Title\n").unwrap(), vec![heading()]);
+        assert_eq!(
+            parse("== Title\nfoo bar\n\nbaz").unwrap(),
+            vec![
+                heading(),
+                Block::Paragraph {
+                    children: smallvec![Inline::Value("foo bar".to_string())].into()
+                },
+                Block::BlankBlock,
+                Block::Paragraph {
+                    children: smallvec![Inline::Value("baz".to_string())].into()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unordered_list() {
+        let blocks = "* abc
+* def";
+
+        let actual = unordered_list_block(BlockOptions::default())
+            .parse(blocks)
+            .map(take_parse_result);
+        assert_eq!(
+            actual,
+            Ok(Block::UnorderdList {
+                children: vec![
+                    ListItem::Normal {
+                        level: ListLevel::Level1,
+                        children: smallvec![Inline::Value("abc".to_string())]
+                    },
+                    ListItem::Normal {
+                        level: ListLevel::Level1,
+                        children: smallvec![Inline::Value("def".to_string())]
+                    }
+                ]
+            })
+        );
+
+        let blocks = "* [x] abc";
+
+        let actual = unordered_list_block(BlockOptions::default())
+            .parse(blocks)
+            .map(take_parse_result);
+        assert_eq!(
+            actual,
+            Ok(Block::UnorderdList {
+                children: vec![ListItem::Check {
+                    level: ListLevel::Level1,
+                    children: smallvec![Inline::Value("abc".to_string())],
+                    checked: true
+                },]
+            })
+        );
+
+        let actual = unordered_list_block(BlockOptions::default())
+            .parse("* a [.keyword]#fn# [b]")
+            .map(take_parse_result);
+        assert_eq!(
+            actual,
+            Ok(Block::UnorderdList {
+                children: vec![ListItem::Normal {
+                    level: ListLevel::Level1,
+                    children: smallvec![
+                        Inline::Value("a ".to_string()),
+                        Inline::Styled {
+                            roles: vec![Symbol::from("keyword")],
+                            children: Box::new(Inline::Value("fn".to_string()))
+                        },
+                        Inline::Value(" [b]".to_string())
+                    ]
+                }]
+            })
+        );
+    }
+
+    #[test]
+    fn test_checkbox_after_list_marks() {
+        assert_eq!(
+            parse("[x] described, not checked").unwrap(),
+            vec![Block::Paragraph {
+                children: smallvec![Inline::Value("[x] described, not checked".to_string())].into()
+            }]
+        );
+        let normal = |text: &str| ListItem::Normal {
+            level: ListLevel::Level1,
+            children: smallvec![Inline::Value(text.to_string())],
+        };
+        assert_eq!(
+            parse("* [x]a").unwrap(),
+            vec![Block::UnorderdList {
+                children: vec![normal("[x]a")]
+            }]
+        );
+        // Without a blank after the marks there is no list item at all.
+        for source in ["*[x] a", "*bold* end", ".5 a"] {
+            assert_eq!(
+                parse(source).unwrap(),
+                vec![Block::Paragraph {
+                    children: parse_inlines(source, false).into()
+                }]
+            );
+        }
+        assert_eq!(
+            parse("* a\n[ ] b").unwrap(),
+            vec![Block::UnorderdList {
+                children: vec![ListItem::Normal {
+                    level: ListLevel::Level1,
+                    children: smallvec![
+                        Inline::Value("a".to_string()),
+                        Inline::SoftBreak,
+                        Inline::Value("[ ] b".to_string())
+                    ]
+                }]
+            }]
+        );
+        assert_eq!(
+            parse(". \t[*] b").unwrap(),
+            vec![Block::OrderdList {
+                children: vec![ListItem::Check {
+                    level: ListLevel::Level1,
+                    children: smallvec![Inline::Value("b".to_string())],
+                    checked: true
+                }]
+            }]
+        );
+    }
+
+    #[test]
+    fn test_video_provider() {
+        let named = |name: &str| AttrList::from(vec![name.to_string()]);
+        let none = AttrList::new();
+        assert_eq!(
+            VideoProvider::of_macro("dQw4w9WgXcQ", &named("YouTube")),
+            VideoProvider::Youtube
+        );
+        assert_eq!(
+            VideoProvider::of_macro("76979871", &named("vimeo")),
+            VideoProvider::Vimeo
+        );
+        assert_eq!(
+            VideoProvider::of_macro("https://youtu.be/dQw4w9WgXcQ", &none),
+            VideoProvider::Youtube
+        );
+        assert_eq!(
+            VideoProvider::of_macro("https://vimeo.com/76979871", &none),
+            VideoProvider::Vimeo
+        );
+        assert_eq!(
+            VideoProvider::of_macro("media/intro.mp4", &named("Intro")),
+            VideoProvider::Local
+        );
+
+        assert_eq!(
+            VideoProvider::Youtube.embed_url("https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=1"),
+            "https://www.youtube.com/embed/dQw4w9WgXcQ"
+        );
+        assert_eq!(
+            VideoProvider::Vimeo.embed_url("76979871"),
+            "https://player.vimeo.com/video/76979871"
+        );
+        assert_eq!(
+            VideoProvider::Local.embed_url("media/intro.mp4"),
+            "media/intro.mp4"
+        );
+    }
+
+    #[test]
+    fn test_list_level_bounds() {
+        let actual = unordered_list_block(BlockOptions::default())
+            .parse("***** deep")
+            .map(take_parse_result);
+        assert_eq!(
+            actual,
+            Ok(Block::UnorderdList {
+                children: vec![ListItem::Normal {
+                    level: ListLevel::Level5,
+                    children: smallvec![Inline::Value("deep".to_string())]
+                }]
+            })
+        );
+
+        // Six marks are no list item, the line is text.
+        assert_eq!(
+            parse("****** six").unwrap(),
+            vec![Block::Paragraph {
+                children: smallvec![Inline::Value("****** six".to_string())].into()
+            }]
+        );
+        assert_eq!(
+            parse("* a\n...... b").unwrap(),
+            vec![Block::UnorderdList {
+                children: vec![ListItem::Normal {
+                    level: ListLevel::Level1,
+                    children: smallvec![
+                        Inline::Value("a".to_string()),
+                        Inline::SoftBreak,
+                        Inline::Value("...... b".to_string())
+                    ]
+                }]
+            }]
+        );
+
+        assert_eq!(ListLevel::from_u8(6), None);
+        assert_eq!(ListLevel::from_depth(0), ListLevel::Level1);
+        assert_eq!(ListLevel::from_depth(7), ListLevel::Level5);
+    }
+
+    #[test]
+    fn test_ordered_list() {
+        let blocks = ". abc
+. def";
+
+        let actual = ordered_list_block(BlockOptions::default())
+            .parse(blocks)
+            .map(take_parse_result);
+        assert_eq!(
+            actual,
+            Ok(Block::OrderdList {
+                children: vec![
+                    ListItem::Normal {
+                        level: ListLevel::Level1,
+                        children: smallvec![Inline::Value("abc".to_string())]
+                    },
+                    ListItem::Normal {
+                        level: ListLevel::Level1,
+                        children: smallvec![Inline::Value("def".to_string())]
+                    }
+                ]
+            })
+        )
+    }
+
+    #[test]
+    fn test_ordered_list_item() {
+        let actual = list_item('.', false)
+            .parse(". foobar *foo* bar _foo_")
+            .map(take_parse_result);
+        assert_eq!(
+            actual,
+            Ok(ListItem::Normal {
+                level: ListLevel::Level1,
+                children: smallvec![
+                    Inline::Value("foobar ".to_string()),
+                    Inline::Bold {
+                        children: Box::new(Inline::Value("foo".to_string()))
+                    },
+                    Inline::Value(" bar ".to_string()),
+                    Inline::Italic {
+                        children: Box::new(Inline::Value("foo".to_string()))
+                    },
+                ]
+            })
+        );
+
+        let actual = list_item('.', false)
+            .parse(". foobar\na")
+            .map(take_parse_result);
+        assert_eq!(
+            actual,
+            Ok(ListItem::Normal {
+                level: ListLevel::Level1,
+                children: smallvec![
+                    Inline::Value("foobar".to_string()),
+                    Inline::SoftBreak,
+                    Inline::Value("a".to_string()),
+                ]
+            })
+        );
+
+        let actual = list_item('.', false)
+            .parse(".. foobar\na")
+            .map(take_parse_result);
+        assert_eq!(
+            actual,
+            Ok(ListItem::Normal {
+                level: ListLevel::Level2,
+                children: smallvec![
+                    Inline::Value("foobar".to_string()),
+                    Inline::SoftBreak,
+                    Inline::Value("a".to_string()),
+                ]
+            })
+        );
+
+        // Lines starting a block of their own aren't part of the item.
+        for next in &["\n. b", "\n[source]\ncode", "\n----\ncode", "\n<<<", "\n\nb"] {
+            let source = format!(". a{}", next);
+            let (actual, rest) = list_item('.', false).parse(source.as_str()).unwrap();
+            assert_eq!(actual.children(), &[Inline::Value("a".to_string())][..]);
+            assert_eq!(rest, *next);
+        }
+    }
+
+    #[test]
+    fn test_position_atteributes() {
+        let expect_atteributes = vec!["foo".to_string()];
+
+        let actual = attribute_list().parse(r"[foo]").map(take_parse_result);
+        assert_eq!(actual, Ok(AttrList::from(expect_atteributes)))
+    }
+
+    #[test]
+    fn test_position_atteributes_when_multiple() {
+        let expect_atteributes = vec!["foo".to_string(), "bar".to_string()];
+
+        let actual = attribute_list().parse(r"[foo,bar]").map(take_parse_result);
+        assert_eq!(actual, Ok(AttrList::from(expect_atteributes)))
+    }
+
+    #[test]
+    fn test_named_atteributes() {
+        let mut expect_atteributes = AttrList::new();
+        expect_atteributes.push_named("foo", "bar");
+
+        let actual = attribute_list().parse(r"[foo=bar]").map(take_parse_result);
+        assert_eq!(actual, Ok(expect_atteributes))
+    }
+
+    #[test]
+    fn test_named_atteributes_when_multiple() {
+        let mut expect_atteributes = AttrList::new();
+        expect_atteributes.push_named("foo", "bar");
+        expect_atteributes.push_named("poe", "fuga");
+
+        let actual = attribute_list()
+            .parse(r"[foo=bar, poe=fuga]")
+            .map(take_parse_result);
+        assert_eq!(actual, Ok(expect_atteributes))
+    }
+
+    #[test]
+    fn test_named_atteributes_quoted() {
+        let mut expect_atteributes = AttrList::new();
+        expect_atteributes.push_named("caption", "Figure 1, part A");
+        expect_atteributes.push_named("title", r#"a="b" \ c"#);
+        expect_atteributes.push_named("alt", "it's [here]");
+
+        let actual = attribute_list()
+            .parse(r#"[caption="Figure 1, part A", title="a=\"b\" \ c", alt='it\'s [here]']"#)
+            .map(take_parse_result);
+        assert_eq!(actual, Ok(expect_atteributes));
+
+        let meta = block_with_meta(BlockOptions::default())
+            .parse("[caption=\"Figure 1, part A\"]\ntext")
+            .map(|((meta, _), _)| meta.map(|meta| meta.attributes));
+        let mut expect_atteributes = AttrList::new();
+        expect_atteributes.push_named("caption", "Figure 1, part A");
+        assert_eq!(meta, Ok(Some(expect_atteributes)));
+    }
+
+    #[test]
+    fn test_mixed_atteributes() {
+        let actual = attribute_list()
+            .parse("[source, rust, linenums, indent=0, role=\"a b\"]")
+            .map(take_parse_result)
+            .unwrap();
+        assert_eq!(
+            actual.iter().cloned().collect::<Vec<_>>(),
+            vec![
+                Attr::Positional("source".to_string()),
+                Attr::Positional("rust".to_string()),
+                Attr::Positional("linenums".to_string()),
+                Attr::Named("indent".into(), "0".to_string()),
+                Attr::Named("role".into(), "a b".to_string()),
+            ]
+        );
+        assert_eq!(actual.positional(1), Some("rust"));
+        assert_eq!(actual.get("indent"), Some("0"));
+
+        let meta = BlockMeta::from_attributes(actual);
+        assert_eq!(meta.style, Some("source".into()));
+        assert_eq!(meta.roles, vec![Symbol::from("a"), Symbol::from("b")]);
+    }
+
+    #[test]
+    fn test_atteributes_with_blanks() {
+        let mut expect_atteributes = AttrList::new();
+        expect_atteributes.push_positional("source");
+        expect_atteributes.push_named("cols", "1,2");
+        expect_atteributes.push_named("width", "50%");
+        expect_atteributes.push_named("title", " padded ");
+
+        let actual = attribute_list()
+            .parse("[ source ,cols = \"1,2\" ,\twidth=  50%  , title = ' padded ' ]")
+            .map(take_parse_result);
+        assert_eq!(actual, Ok(expect_atteributes));
+
+        let meta = BlockMeta::from_attributes(attribute_list().parse("[ #intro.lead ]").unwrap().0);
+        assert_eq!(meta.id, Some("intro".to_string()));
+        assert_eq!(meta.roles, vec![Symbol::from("lead")]);
+    }
+}
@@ -0,0 +1,90 @@
+use memchr::memchr_iter;
+
+// Where the lines of a source start, found in one memchr pass. Line numbers and block
+// boundaries are then lookups instead of scans of the text for line breaks.
+#[derive(Debug, Clone)]
+pub struct LineIndex<'a> {
+    source: &'a str,
+    // Byte offset of each line, the first is 0. A source ending with a line break has an empty
+    // last line.
+    starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let mut starts = vec![0];
+        starts.extend(memchr_iter(b'\n', source.as_bytes()).map(|index| index + 1));
+        LineIndex { source, starts }
+    }
+
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.starts.len()
+    }
+
+    // Byte offset where zero-based `line` starts.
+    pub fn start(&self, line: usize) -> usize {
+        self.starts[line]
+    }
+
+    // Zero-based line of the byte `offset`.
+    pub fn line_of(&self, offset: usize) -> usize {
+        match self.starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next) => next - 1,
+        }
+    }
+
+    // One-based line and column of the byte `offset`. Columns count characters, not bytes.
+    pub fn line_column(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.source.len());
+        let line = self.line_of(offset);
+        (
+            line + 1,
+            self.source[self.starts[line]..offset].chars().count() + 1,
+        )
+    }
+
+    // Text of zero-based `line`, without its line break.
+    pub fn line(&self, line: usize) -> &'a str {
+        let end = self
+            .starts
+            .get(line + 1)
+            .map_or(self.source.len(), |next| next - 1);
+        &self.source[self.starts[line]..end]
+    }
+
+    pub fn is_blank(&self, line: usize) -> bool {
+        self.line(line).trim().is_empty()
+    }
+
+    // Lines with nothing but whitespace, where one block ends and the next can start.
+    pub fn blank_lines(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.line_count()).filter(move |line| self.is_blank(*line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_line_index() {
+        let lines = LineIndex::new("= Title\n\nPara ä\ngraph\n");
+        assert_eq!(lines.line_count(), 5);
+        assert_eq!(lines.line(2), "Para ä");
+        assert_eq!(lines.line(4), "");
+        assert_eq!(lines.start(3), 17);
+        assert_eq!(lines.line_of(0), 0);
+        assert_eq!(lines.line_of(8), 1);
+        assert_eq!(lines.line_of(17), 3);
+        assert_eq!(lines.line_column(16), (3, 7));
+        assert_eq!(lines.line_column(100), (5, 1));
+        assert_eq!(lines.blank_lines().collect::<Vec<_>>(), vec![1, 4]);
+        assert_eq!(LineIndex::new("").line(0), "");
+    }
+}
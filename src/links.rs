@@ -0,0 +1,388 @@
+use crate::document::Document;
+use crate::lines::LineIndex;
+use crate::lint::{anchors, is_inter_document, urls, xrefs};
+use crate::parallel;
+use crate::preprocess::include_targets;
+use crate::recovery::{parse_lenient, RecoveryPolicy};
+use crate::{BlockOptions, Span};
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct LinkCheckOptions {
+    // Send a HEAD request to every external URL. Needs the `external-links` feature.
+    pub external: bool,
+    // Requests in flight at the same time.
+    pub concurrency: usize,
+    pub timeout: Duration,
+}
+
+impl Default for LinkCheckOptions {
+    fn default() -> Self {
+        LinkCheckOptions {
+            external: false,
+            concurrency: 8,
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl LinkCheckOptions {
+    pub fn new() -> Self {
+        LinkCheckOptions::default()
+    }
+
+    pub fn external(mut self, external: bool) -> Self {
+        self.external = external;
+        self
+    }
+
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub enum LinkKind {
+    Xref,
+    Include,
+    External,
+}
+
+impl fmt::Display for LinkKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LinkKind::Xref => write!(f, "xref"),
+            LinkKind::Include => write!(f, "include"),
+            LinkKind::External => write!(f, "url"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BrokenLink {
+    // Relative to the project root.
+    pub path: PathBuf,
+    pub line: usize,
+    pub kind: LinkKind,
+    pub target: String,
+    pub reason: String,
+}
+
+impl fmt::Display for BrokenLink {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: broken {} `{}`: {}",
+            self.path.display(),
+            self.line,
+            self.kind,
+            self.target,
+            self.reason
+        )
+    }
+}
+
+pub(crate) struct ProjectFile {
+    pub source: String,
+    pub document: Document,
+}
+
+// Every `.adoc` file under a directory, parsed once.
+pub struct Project {
+    pub(crate) root: PathBuf,
+    pub(crate) files: BTreeMap<PathBuf, ProjectFile>,
+}
+
+impl Project {
+    pub fn load<P: Into<PathBuf>>(root: P) -> Result<Self> {
+        let root = root.into();
+        let mut paths = vec![];
+        collect_adoc_files(&root, &mut paths)?;
+
+        // Files are parsed on all cores with the `parallel` feature.
+        let files = parallel::map(&paths, |path| {
+            let source = fs::read_to_string(path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            let (blocks, _) =
+                parse_lenient(&source, &RecoveryPolicy::default(), BlockOptions::default());
+            let relative = path.strip_prefix(&root).unwrap_or(path).to_path_buf();
+            Ok((
+                relative,
+                ProjectFile {
+                    source,
+                    document: Document::from_spanned(blocks),
+                },
+            ))
+        });
+        Ok(Project {
+            files: files.into_iter().collect::<Result<_>>()?,
+            root,
+        })
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.files.keys().map(PathBuf::as_path)
+    }
+}
+
+fn collect_adoc_files(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
+    let entries =
+        fs::read_dir(dir).with_context(|| format!("failed to read directory {}", dir.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_adoc_files(&path, paths)?;
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "adoc")
+        {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+// Verify the xrefs and includes of every file of `project` against each other, and the external
+// URLs when asked to. The result is sorted by file and line.
+pub fn check_links(project: &Project, options: &LinkCheckOptions) -> Result<Vec<BrokenLink>> {
+    let registry = project
+        .files
+        .iter()
+        .map(|(path, file)| (path.clone(), anchors(&file.document)))
+        .collect::<HashMap<_, _>>();
+
+    let mut broken = vec![];
+    let mut external: BTreeMap<String, Vec<(PathBuf, usize)>> = BTreeMap::new();
+    for (path, file) in &project.files {
+        let lines = LineIndex::new(&file.source);
+        let line = |offset: usize| lines.line_of(offset) + 1;
+        let mut report = |kind, target: &str, span: Span, reason: String| {
+            broken.push(BrokenLink {
+                path: path.clone(),
+                line: line(span.start),
+                kind,
+                target: target.to_string(),
+                reason,
+            })
+        };
+
+        for (target, span) in xrefs(&file.document) {
+            if !is_inter_document(&target) {
+                if !registry[path].contains_key(&target) {
+                    report(LinkKind::Xref, &target, span, "no such anchor".to_string());
+                }
+                continue;
+            }
+            let (resolved, id) = resolve_xref(path, &target);
+            match registry.get(&resolved) {
+                None => report(
+                    LinkKind::Xref,
+                    &target,
+                    span,
+                    format!("no document {}", resolved.display()),
+                ),
+                Some(anchors) if !id.is_empty() && !anchors.contains_key(&id) => report(
+                    LinkKind::Xref,
+                    &target,
+                    span,
+                    format!("no anchor `{}` in {}", id, resolved.display()),
+                ),
+                Some(_) => {}
+            }
+        }
+
+        for (offset, target) in include_targets(&file.source) {
+            // Targets using attribute references can't be resolved without a render.
+            if target.contains('{') {
+                continue;
+            }
+            let included = project
+                .root
+                .join(path.parent().unwrap_or(Path::new("")))
+                .join(&target);
+            if !included.is_file() {
+                let span = Span {
+                    start: offset,
+                    end: offset,
+                };
+                report(LinkKind::Include, &target, span, "no such file".to_string());
+            }
+        }
+
+        if options.external {
+            for (url, span) in urls(&file.document) {
+                external
+                    .entry(url)
+                    .or_default()
+                    .push((path.clone(), line(span.start)));
+            }
+        }
+    }
+
+    if options.external {
+        let urls = external.keys().cloned().collect::<Vec<_>>();
+        for (url, reason) in check_urls(&urls, options)? {
+            for (path, line) in &external[&url] {
+                broken.push(BrokenLink {
+                    path: path.clone(),
+                    line: *line,
+                    kind: LinkKind::External,
+                    target: url.clone(),
+                    reason: reason.clone(),
+                });
+            }
+        }
+    }
+
+    broken.sort_by(|a, b| (&a.path, a.line, a.kind).cmp(&(&b.path, b.line, b.kind)));
+    Ok(broken)
+}
+
+// Document, relative to the project root, and id an inter-document xref in `path` points to.
+// The id is empty for xrefs to the top of a document.
+pub(crate) fn resolve_xref(path: &Path, target: &str) -> (PathBuf, String) {
+    let (document, id) = target.split_once('#').unwrap_or((target, ""));
+    let document = if document.ends_with(".adoc") {
+        document.to_string()
+    } else {
+        format!("{}.adoc", document)
+    };
+    let resolved = normalize(&path.parent().unwrap_or(Path::new("")).join(document));
+    (resolved, id.to_string())
+}
+
+// Resolve `.` and `..` without touching the file system, the files may not exist.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+// URLs that failed, with the reason. Workers take URLs off a shared queue so no more than
+// `concurrency` requests are in flight.
+#[cfg(feature = "external-links")]
+fn check_urls(urls: &[String], options: &LinkCheckOptions) -> Result<Vec<(String, String)>> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(options.timeout))
+        .build()
+        .into();
+    let next = AtomicUsize::new(0);
+    let failed = Mutex::new(vec![]);
+    std::thread::scope(|scope| {
+        for _ in 0..options.concurrency.min(urls.len()) {
+            scope.spawn(|| {
+                while let Some(url) = urls.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    if let Err(error) = agent.head(url).call() {
+                        failed
+                            .lock()
+                            .unwrap()
+                            .push((url.clone(), error.to_string()));
+                    }
+                }
+            });
+        }
+    });
+    let mut failed = failed.into_inner().unwrap();
+    failed.sort();
+    Ok(failed)
+}
+
+#[cfg(not(feature = "external-links"))]
+fn check_urls(_urls: &[String], _options: &LinkCheckOptions) -> Result<Vec<(String, String)>> {
+    anyhow::bail!("checking external links needs the `external-links` feature")
+}
+
+pub fn to_json(broken: &[BrokenLink]) -> Value {
+    broken
+        .iter()
+        .map(|link| {
+            json!({
+                "path": link.path.display().to_string(),
+                "line": link.line,
+                "kind": link.kind.to_string(),
+                "target": link.target,
+                "reason": link.reason,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("combine-sandbox-links-{}", name));
+        let _ = fs::remove_dir_all(&root);
+        for (path, source) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, source).unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn test_check_links() {
+        let root = project(
+            "check",
+            &[
+                (
+                    "index.adoc",
+                    "= Index\n\nsee xref:guide/setup.adoc#_install[] and <<guide/setup#_missing,x>>\n\n<<_nope>> xref:gone.adoc[]\n\ninclude::parts/intro.adoc[]\ninclude::parts/none.adoc[]\n",
+                ),
+                ("guide/setup.adoc", "== Install\n\nback to xref:../index.adoc[]\n"),
+                ("parts/intro.adoc", "intro\n"),
+            ],
+        );
+        let project = Project::load(&root).unwrap();
+        let broken = check_links(&project, &LinkCheckOptions::new()).unwrap();
+
+        assert_eq!(
+            broken
+                .iter()
+                .map(|link| (link.line, link.kind, link.target.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (3, LinkKind::Xref, "guide/setup#_missing"),
+                (5, LinkKind::Xref, "_nope"),
+                (5, LinkKind::Xref, "gone.adoc"),
+                (8, LinkKind::Include, "parts/none.adoc"),
+            ]
+        );
+        assert_eq!(broken[0].reason, "no anchor `_missing` in guide/setup.adoc");
+        assert_eq!(project.paths().count(), 3);
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            normalize(Path::new("guide/../index.adoc")),
+            PathBuf::from("index.adoc")
+        );
+    }
+}
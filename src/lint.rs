@@ -0,0 +1,769 @@
+use crate::annotations::Annotation;
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::document::Document;
+use crate::lines::LineIndex;
+use crate::recovery::{parse_lenient, RecoveryPolicy};
+use crate::render::visitor::{walk_inline, Renderer};
+use crate::slug::{SlugStrategy, Slugger};
+use crate::text::inline_text;
+use crate::{AttrList, Block, BlockOptions, HeadingLevel, Inline, Span, TableRow};
+use anyhow::{bail, Result};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord, Hash)]
+pub enum LintRule {
+    HeadingLevelSkip,
+    TrailingWhitespace,
+    BrokenXref,
+    UnclosedFormatting,
+    TableWithoutHeader,
+}
+
+impl LintRule {
+    pub const ALL: [LintRule; 5] = [
+        LintRule::HeadingLevelSkip,
+        LintRule::TrailingWhitespace,
+        LintRule::BrokenXref,
+        LintRule::UnclosedFormatting,
+        LintRule::TableWithoutHeader,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LintRule::HeadingLevelSkip => "heading-level-skip",
+            LintRule::TrailingWhitespace => "trailing-whitespace",
+            LintRule::BrokenXref => "broken-xref",
+            LintRule::UnclosedFormatting => "unclosed-formatting",
+            LintRule::TableWithoutHeader => "table-without-header",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<LintRule> {
+        LintRule::ALL
+            .iter()
+            .copied()
+            .find(|rule| rule.name() == name)
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            LintRule::HeadingLevelSkip => "Section levels must not skip a level",
+            LintRule::TrailingWhitespace => "Lines must not end with whitespace",
+            LintRule::BrokenXref => "Cross references must point to an anchor in the document",
+            LintRule::UnclosedFormatting => "Formatting marks must be closed",
+            LintRule::TableWithoutHeader => "Tables should have a header row",
+        }
+    }
+
+    pub fn default_severity(&self) -> Severity {
+        match self {
+            LintRule::BrokenXref => Severity::Error,
+            // The parser keeps an unclosed mark as text, the document still renders.
+            LintRule::UnclosedFormatting
+            | LintRule::HeadingLevelSkip
+            | LintRule::TrailingWhitespace => Severity::Warning,
+            LintRule::TableWithoutHeader => Severity::Info,
+        }
+    }
+}
+
+impl fmt::Display for LintRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+// Severity of each rule, `None` turns a rule off. Rules not configured use their default.
+#[derive(Debug, Clone, Default)]
+pub struct LintConfig {
+    pub levels: BTreeMap<LintRule, Option<Severity>>,
+}
+
+impl LintConfig {
+    pub fn new() -> Self {
+        LintConfig::default()
+    }
+
+    pub fn level(mut self, rule: LintRule, severity: Option<Severity>) -> Self {
+        self.levels.insert(rule, severity);
+        self
+    }
+
+    // Apply a `rule=level` setting, the level being one of off, info, warning or error.
+    pub fn set(self, setting: &str) -> Result<Self> {
+        let (name, level) = match setting.split_once('=') {
+            Some(parts) => parts,
+            None => bail!("expected `rule=level`, got `{}`", setting),
+        };
+        let rule = match LintRule::from_name(name.trim()) {
+            Some(rule) => rule,
+            None => bail!("unknown lint rule `{}`", name),
+        };
+        let severity = match level.trim() {
+            "off" => None,
+            "info" => Some(Severity::Info),
+            "warning" => Some(Severity::Warning),
+            "error" => Some(Severity::Error),
+            other => bail!("unknown lint level `{}`", other),
+        };
+        Ok(self.level(rule, severity))
+    }
+
+    pub fn severity(&self, rule: LintRule) -> Option<Severity> {
+        match self.levels.get(&rule) {
+            Some(severity) => *severity,
+            None => Some(rule.default_severity()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Lint {
+    pub rule: LintRule,
+    pub diagnostic: Diagnostic,
+    // 1-based position of the span start.
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}: {} [{}]",
+            self.line, self.column, self.diagnostic.severity, self.diagnostic.message, self.rule
+        )
+    }
+}
+
+// Lints of one file, the unit of the JSON and SARIF reports.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LintReport {
+    pub path: String,
+    pub lints: Vec<Lint>,
+}
+
+impl LintReport {
+    // The lints as CI annotations, titled with their rule.
+    pub fn annotations(&self) -> Vec<Annotation> {
+        self.lints
+            .iter()
+            .map(|lint| Annotation {
+                path: self.path.clone(),
+                line: lint.line,
+                column: lint.column,
+                severity: lint.diagnostic.severity,
+                message: lint.diagnostic.message.clone(),
+                check: lint.rule.name().to_string(),
+            })
+            .collect()
+    }
+}
+
+// Check `document`, which must have been parsed from `source` for the spans to line up.
+pub fn lint(document: &Document, source: &str, config: &LintConfig) -> Vec<Lint> {
+    let mut found: Vec<(LintRule, String, Span, Option<String>)> = vec![];
+
+    if config.severity(LintRule::TrailingWhitespace).is_some() {
+        let mut offset = 0;
+        for line in source.split_inclusive('\n') {
+            let content = line.trim_end_matches(&['\n', '\r'][..]);
+            let trimmed = content.trim_end_matches(&[' ', '\t'][..]);
+            if trimmed.len() < content.len() {
+                found.push((
+                    LintRule::TrailingWhitespace,
+                    "trailing whitespace".to_string(),
+                    Span {
+                        start: offset + trimmed.len(),
+                        end: offset + content.len(),
+                    },
+                    None,
+                ));
+            }
+            offset += line.len();
+        }
+    }
+
+    let anchors = anchors(document);
+    let mut previous_level = HeadingLevel::Title.as_u8();
+    for (index, block) in document.blocks().iter().enumerate() {
+        let span = document.span(index).unwrap_or(Span { start: 0, end: 0 });
+        match block {
+            Block::Heading { level, .. } if *level != HeadingLevel::Title => {
+                if level.as_u8() > previous_level + 1 {
+                    found.push((
+                        LintRule::HeadingLevelSkip,
+                        format!(
+                            "section level {} follows level {}",
+                            level.as_u8(),
+                            previous_level
+                        ),
+                        span,
+                        Some(format!(
+                            "a section below level {} starts with `{} `",
+                            previous_level,
+                            "=".repeat(previous_level as usize + 2)
+                        )),
+                    ));
+                }
+                previous_level = level.as_u8();
+            }
+            Block::Table { columns, .. } if columns.is_empty() => found.push((
+                LintRule::TableWithoutHeader,
+                "table has no header row".to_string(),
+                span,
+                Some("mark the first row as the header with `[%header]`".to_string()),
+            )),
+            _ => {}
+        }
+
+        let inlines = InlineScan::scan(block);
+        for target in inlines.xrefs {
+            // References into other documents are checked by `links::check_links`.
+            if !is_inter_document(&target) && !anchors.contains_key(&target) {
+                found.push((
+                    LintRule::BrokenXref,
+                    format!("no anchor named `{}`", target),
+                    span,
+                    closest(&target, anchors.keys())
+                        .map(|anchor| format!("did you mean `{}`?", anchor)),
+                ));
+            }
+        }
+        for mark in inlines.unclosed {
+            found.push((
+                LintRule::UnclosedFormatting,
+                format!("unclosed `{}`", mark),
+                span,
+                Some(format!("did you forget a closing `{}`?", mark)),
+            ));
+        }
+    }
+
+    let lines = LineIndex::new(source);
+    let mut lints = found
+        .into_iter()
+        .filter_map(|(rule, message, span, note)| {
+            let severity = config.severity(rule)?;
+            let (line, column) = lines.line_column(span.start);
+            Some(Lint {
+                rule,
+                diagnostic: Diagnostic {
+                    severity,
+                    message,
+                    span,
+                    note,
+                },
+                line,
+                column,
+            })
+        })
+        .collect::<Vec<_>>();
+    lints.sort_by_key(|lint| (lint.diagnostic.span.start, lint.rule));
+    lints
+}
+
+// Parse `source` leniently and without preprocessing, so spans point into the file as written.
+pub fn lint_source(source: &str, config: &LintConfig) -> Vec<Lint> {
+    let (blocks, _) = parse_lenient(source, &RecoveryPolicy::default(), BlockOptions::default());
+    lint(&Document::from_spanned(blocks), source, config)
+}
+
+pub fn to_json(reports: &[LintReport]) -> Value {
+    reports
+        .iter()
+        .map(|report| {
+            json!({
+                "path": report.path,
+                "lints": report.lints.iter().map(|lint| json!({
+                    "rule": lint.rule.name(),
+                    "severity": lint.diagnostic.severity.to_string(),
+                    "message": lint.diagnostic.message,
+                    "line": lint.line,
+                    "column": lint.column,
+                    "span": { "start": lint.diagnostic.span.start, "end": lint.diagnostic.span.end },
+                })).collect::<Vec<_>>(),
+            })
+        })
+        .collect()
+}
+
+// SARIF 2.1.0 log with a single run, as understood by code scanning services.
+pub fn to_sarif(reports: &[LintReport]) -> Value {
+    let rules = LintRule::ALL
+        .iter()
+        .map(|rule| {
+            json!({
+                "id": rule.name(),
+                "shortDescription": { "text": rule.description() },
+            })
+        })
+        .collect::<Vec<_>>();
+    let results = reports
+        .iter()
+        .flat_map(|report| {
+            report.lints.iter().map(move |lint| {
+                json!({
+                    "ruleId": lint.rule.name(),
+                    "level": sarif_level(lint.diagnostic.severity),
+                    "message": { "text": lint.diagnostic.message },
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": { "uri": report.path },
+                            "region": { "startLine": lint.line, "startColumn": lint.column },
+                        }
+                    }],
+                })
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        }],
+    })
+}
+
+fn sarif_level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Info => "note",
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+    }
+}
+
+// The candidate within a couple of edits of `target`, for typo suggestions.
+pub(crate) fn closest<'a, S, I>(target: &str, candidates: I) -> Option<&'a str>
+where
+    S: AsRef<str> + ?Sized + 'a,
+    I: Iterator<Item = &'a S>,
+{
+    candidates
+        .map(|candidate| {
+            (
+                edit_distance(target, candidate.as_ref()),
+                candidate.as_ref(),
+            )
+        })
+        .filter(|(distance, _)| *distance <= 2)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+// Levenshtein distance over characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// Explicit ids, the ids generated for sections, and inline `[[id]]` anchors, each with the span
+// of the block defining it. The first definition of an id wins.
+pub(crate) fn anchors(document: &Document) -> HashMap<String, Span> {
+    let mut anchors = HashMap::new();
+    let mut slugger = Slugger::new(SlugStrategy::default());
+    for (index, block) in document.blocks().iter().enumerate() {
+        let span = document.span(index).unwrap_or(Span { start: 0, end: 0 });
+        let mut ids = vec![];
+        if let Some(id) = document.meta(index).and_then(|meta| meta.id.as_ref()) {
+            ids.push(id.clone());
+        }
+        if let Block::Heading { children, id, .. } = block {
+            match id {
+                Some(id) => ids.push(id.clone()),
+                None => ids.push(slugger.slug(&inline_text(children))),
+            };
+        }
+        nested_ids(block, &mut ids);
+        ids.extend(InlineScan::scan(block).anchors);
+        for id in ids {
+            anchors.entry(id).or_insert(span);
+        }
+    }
+    anchors
+}
+
+// Ids of the attribute lines of blocks nested in `block`, at any depth. They take the span of
+// the top level block.
+fn nested_ids(block: &Block, ids: &mut Vec<String>) {
+    let nested = match block {
+        Block::Block {
+            children, metas, ..
+        } => vec![(children, metas)],
+        Block::Table { rows, .. } => rows
+            .iter()
+            .flat_map(TableRow::cells)
+            .map(|cell| (&cell.blocks, &cell.metas))
+            .collect(),
+        _ => vec![],
+    };
+    for (blocks, metas) in nested {
+        for (block, meta) in blocks.iter().zip(metas) {
+            if let Some(id) = meta.as_ref().and_then(|meta| meta.id.as_ref()) {
+                ids.push(id.clone());
+            }
+            nested_ids(block, ids);
+        }
+    }
+}
+
+// Ids given in the source, block and heading ids and inline `[[id]]` anchors, with the span of
+// the block defining them. Generated section ids are left out.
+pub(crate) fn explicit_anchors(document: &Document) -> Vec<(String, Span)> {
+    let mut anchors = vec![];
+    for (index, block) in document.blocks().iter().enumerate() {
+        let span = document.span(index).unwrap_or(Span { start: 0, end: 0 });
+        if let Some(id) = document.meta(index).and_then(|meta| meta.id.as_ref()) {
+            anchors.push((id.clone(), span));
+        }
+        if let Block::Heading { id: Some(id), .. } = block {
+            anchors.push((id.clone(), span));
+        }
+        let mut ids = vec![];
+        nested_ids(block, &mut ids);
+        anchors.extend(ids.into_iter().map(|id| (id, span)));
+        for id in InlineScan::scan(block).anchors {
+            anchors.push((id, span));
+        }
+    }
+    anchors
+}
+
+pub(crate) fn is_inter_document(target: &str) -> bool {
+    target.contains('#') || target.ends_with(".adoc")
+}
+
+// Cross reference targets with the span of the block they appear in.
+pub(crate) fn xrefs(document: &Document) -> Vec<(String, Span)> {
+    scan_blocks(document, |scan| scan.xrefs)
+}
+
+// Targets of image macros, inline and block, with the span of the block they appear in.
+pub(crate) fn images(document: &Document) -> Vec<(String, Span)> {
+    scan_blocks(document, |scan| scan.images)
+}
+
+// External URLs, bare or in link macros, with the span of the block they appear in.
+pub(crate) fn urls(document: &Document) -> Vec<(String, Span)> {
+    scan_blocks(document, |scan| scan.urls)
+}
+
+fn scan_blocks<F: Fn(InlineScan) -> Vec<String>>(document: &Document, f: F) -> Vec<(String, Span)> {
+    document
+        .blocks()
+        .iter()
+        .enumerate()
+        .flat_map(|(index, block)| {
+            let span = document.span(index).unwrap_or(Span { start: 0, end: 0 });
+            f(InlineScan::scan(block))
+                .into_iter()
+                .map(move |target| (target, span))
+        })
+        .collect()
+}
+
+// Collects the references, anchors and stray formatting marks of a block. The grammar splits
+// text into several nodes, so adjacent text is scanned as one run.
+#[derive(Default)]
+struct InlineScan {
+    xrefs: Vec<String>,
+    anchors: Vec<String>,
+    unclosed: Vec<String>,
+    urls: Vec<String>,
+    images: Vec<String>,
+    run: String,
+}
+
+impl InlineScan {
+    fn scan(block: &Block) -> Self {
+        let mut scan = InlineScan::default();
+        scan.block(block);
+        scan.flush();
+        scan
+    }
+
+    fn flush(&mut self) {
+        let run = std::mem::take(&mut self.run);
+        self.unclosed.extend(unclosed_marks(&run));
+        self.xrefs.extend(
+            delimited(&run, "<<", ">>")
+                .into_iter()
+                .map(|target| target.split(',').next().unwrap_or_default().to_string()),
+        );
+        self.xrefs.extend(
+            delimited(&run, "xref:", "[")
+                .into_iter()
+                .map(|target| target.to_string()),
+        );
+        self.anchors.extend(
+            delimited(&run, "[[", "]]")
+                .into_iter()
+                .map(|anchor| anchor.split(',').next().unwrap_or_default().to_string()),
+        );
+        self.images.extend(
+            delimited(&run, "image:", "[")
+                .into_iter()
+                .map(|target| target.trim_start_matches(':').to_string()),
+        );
+        for scheme in &["http://", "https://"] {
+            let mut rest = run.as_str();
+            while let Some(start) = rest.find(scheme) {
+                let url = &rest[start..];
+                let end = url
+                    .find(|c: char| c.is_whitespace() || matches!(c, '[' | '<' | '>' | '"'))
+                    .unwrap_or(url.len());
+                let url = url[..end].trim_end_matches(&['.', ',', ')', ';'][..]);
+                if url.len() > scheme.len() {
+                    self.urls.push(url.to_string());
+                }
+                rest = &rest[start + end..];
+            }
+        }
+    }
+}
+
+impl Renderer for InlineScan {
+    // Code is verbatim, nothing in it is markup.
+    fn code_block(&mut self, _children: &[Inline], _title: Option<&str>, _: Option<&str>) {}
+
+    fn inline(&mut self, inline: &Inline) {
+        match inline {
+            Inline::Value(_) | Inline::SoftBreak | Inline::HardBreak => walk_inline(self, inline),
+            // A span is scanned as a run of its own. In the run around it the span counts as a
+            // word, so a mark in front of it can still close after it, `*bold _n_*`.
+            inline => {
+                let outer = std::mem::take(&mut self.run);
+                walk_inline(self, inline);
+                self.flush();
+                self.run = outer;
+                self.run.push(SPAN_PLACEHOLDER);
+            }
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        self.run.push_str(text);
+    }
+
+    fn soft_break(&mut self) {
+        self.run.push('\n');
+    }
+
+    fn hard_break(&mut self) {
+        self.run.push('\n');
+    }
+
+    fn inline_macro(&mut self, kind: &str, id: &str, _attributes: &AttrList) {
+        match kind {
+            "xref" => self.xrefs.push(id.to_string()),
+            "anchor" => self.anchors.push(id.to_string()),
+            "image" => self.images.push(id.to_string()),
+            "http" | "https" => self.urls.push(format!("{}:{}", kind, id)),
+            "link" if id.starts_with("http://") || id.starts_with("https://") => {
+                self.urls.push(id.to_string())
+            }
+            _ => {}
+        }
+    }
+}
+
+// Stands for a nested span in the text run around it.
+const SPAN_PLACEHOLDER: char = '\u{fffc}';
+
+// Closed pairs are parsed into formatting nodes, so a mark left in the text where a span could
+// open, with no closing mark after it, was never closed. `snake_case` and `<<_id>>` are text.
+fn unclosed_marks(run: &str) -> Vec<String> {
+    let mut unclosed = vec![];
+    let mut previous = None;
+    for (offset, c) in run.char_indices() {
+        let opens = matches!(c, '*' | '_' | '`')
+            && previous.is_none_or(char::is_whitespace)
+            && run[offset + 1..]
+                .chars()
+                .next()
+                .is_some_and(|next| !next.is_whitespace());
+        previous = Some(c);
+        if !opens {
+            continue;
+        }
+        let closes = run[offset + 1..].char_indices().any(|(index, next)| {
+            next == c && !run[offset + 1..offset + 1 + index].ends_with(char::is_whitespace)
+        });
+        if !closes {
+            unclosed.push(c.to_string());
+        }
+    }
+    unclosed
+}
+
+// Text between each `open` and the following `close`, skipping empty or multi-word matches.
+fn delimited<'a>(text: &'a str, open: &str, close: &str) -> Vec<&'a str> {
+    let mut found = vec![];
+    let mut rest = text;
+    while let Some(start) = rest.find(open) {
+        rest = &rest[start + open.len()..];
+        match rest.find(close) {
+            Some(end) => {
+                let inner = &rest[..end];
+                if !inner.is_empty() && !inner.contains(char::is_whitespace) {
+                    found.push(inner);
+                }
+                rest = &rest[end + close.len()..];
+            }
+            None => break,
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TableCell, TableRow};
+    use pretty_assertions::assert_eq;
+    use smallvec::smallvec;
+
+    fn rules(lints: &[Lint]) -> Vec<(LintRule, usize, usize)> {
+        lints
+            .iter()
+            .map(|lint| (lint.rule, lint.line, lint.column))
+            .collect()
+    }
+
+    #[test]
+    fn test_lint() {
+        let source = "= Title\n\n=== Deep \n\nsee <<_deep>> and <<missing,here>>\n\nfoo *bar";
+        let document = Document::parse(source).unwrap();
+        let lints = lint(&document, source, &LintConfig::new());
+
+        assert_eq!(
+            rules(&lints),
+            vec![
+                (LintRule::HeadingLevelSkip, 3, 1),
+                (LintRule::TrailingWhitespace, 3, 9),
+                (LintRule::BrokenXref, 5, 1),
+                (LintRule::UnclosedFormatting, 7, 1),
+            ]
+        );
+        assert_eq!(lints[2].diagnostic.message, "no anchor named `missing`");
+        assert_eq!(lints[2].diagnostic.severity, Severity::Error);
+        assert_eq!(
+            lints[3].diagnostic.note.as_deref(),
+            Some("did you forget a closing `*`?")
+        );
+        assert_eq!(lints[3].diagnostic.severity, Severity::Warning);
+        let anchors = ["_install".to_string(), "_usage".to_string()];
+        assert_eq!(closest("_instal", anchors.iter()), Some("_install"));
+        assert_eq!(closest("_license", anchors.iter()), None);
+    }
+
+    #[test]
+    fn test_unclosed_formatting_around_spans() {
+        let source = "a *bold _n_* and _it `c`_\n\n*open `c` only";
+        let document = Document::parse(source).unwrap();
+
+        assert_eq!(
+            rules(&lint(&document, source, &LintConfig::new())),
+            vec![(LintRule::UnclosedFormatting, 3, 1)]
+        );
+    }
+
+    #[test]
+    fn test_nested_anchors() {
+        let source = "====\n[#inner]\ntext\n====\n\nsee <<inner>>";
+        let document = Document::parse(source).unwrap();
+
+        assert_eq!(rules(&lint(&document, source, &LintConfig::new())), vec![]);
+        assert_eq!(explicit_anchors(&document)[0].0, "inner");
+    }
+
+    #[test]
+    fn test_lint_config() {
+        let source = "=== Deep \n";
+        let document = Document::parse(source).unwrap();
+        let config = LintConfig::new()
+            .set("trailing-whitespace=off")
+            .unwrap()
+            .set("heading-level-skip=error")
+            .unwrap();
+        let lints = lint(&document, source, &config);
+
+        assert_eq!(rules(&lints), vec![(LintRule::HeadingLevelSkip, 1, 1)]);
+        assert_eq!(lints[0].diagnostic.severity, Severity::Error);
+        assert_eq!(LintConfig::new().set("nope=off").is_err(), true);
+        assert_eq!(LintConfig::new().set("broken-xref=loud").is_err(), true);
+    }
+
+    #[test]
+    fn test_urls() {
+        let document =
+            Document::parse("see https://example.com/a_b. or (http://example.org/x), not https://")
+                .unwrap();
+        assert_eq!(
+            urls(&document)
+                .into_iter()
+                .map(|(url, _)| url)
+                .collect::<Vec<_>>(),
+            vec!["http://example.org/x", "https://example.com/a_b"]
+        );
+    }
+
+    #[test]
+    fn test_table_without_header() {
+        let document = Document::new(vec![Block::Table {
+            columns: vec![],
+            rows: vec![TableRow::new(vec![TableCell::new(vec![
+                Block::Paragraph {
+                    children: smallvec![Inline::Value("a".to_string())].into(),
+                },
+            ])])],
+            title: None,
+        }]);
+        let lints = lint(&document, "", &LintConfig::new());
+        assert_eq!(rules(&lints), vec![(LintRule::TableWithoutHeader, 1, 1)]);
+    }
+
+    #[test]
+    fn test_to_sarif() {
+        let reports = vec![LintReport {
+            path: "doc.adoc".to_string(),
+            lints: lint_source(":toc:\na \n", &LintConfig::new()),
+        }];
+        let sarif = to_sarif(&reports);
+
+        assert_eq!(sarif["version"], json!("2.1.0"));
+        assert_eq!(
+            sarif["runs"][0]["results"],
+            json!([{
+                "ruleId": "trailing-whitespace",
+                "level": "warning",
+                "message": { "text": "trailing whitespace" },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": "doc.adoc" },
+                        "region": { "startLine": 2, "startColumn": 2 },
+                    }
+                }],
+            }])
+        );
+    }
+}
@@ -0,0 +1,512 @@
+use crate::diagnostic::{self, Severity};
+use crate::document::Document;
+use crate::lint::anchors;
+use crate::outline::{outline, OutlineEntry};
+use crate::recovery::{parse_lenient, RecoveryPolicy};
+use crate::{Block, BlockOptions, HeadingLevel, Span};
+use anyhow::Result;
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+    PublishDiagnostics,
+};
+use lsp_types::request::{
+    Completion, DocumentSymbolRequest, FoldingRangeRequest, GotoDefinition, Request as _,
+};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, Diagnostic,
+    DiagnosticSeverity, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DocumentSymbol, DocumentSymbolParams, FoldingRange,
+    FoldingRangeKind, FoldingRangeParams, FoldingRangeProviderCapability, GotoDefinitionParams,
+    GotoDefinitionResponse, Location, OneOf, Position, PublishDiagnosticsParams, Range,
+    ServerCapabilities, SymbolKind, TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+};
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+
+// Attributes offered by completion in addition to the ones the document defines.
+const BUILTIN_ATTRIBUTES: &[&str] = &[
+    "author",
+    "description",
+    "doctype",
+    "email",
+    "experimental",
+    "icons",
+    "idprefix",
+    "idseparator",
+    "imagesdir",
+    "keywords",
+    "leveloffset",
+    "revdate",
+    "revnumber",
+    "sectanchors",
+    "sectnums",
+    "source-highlighter",
+    "stem",
+    "toc",
+    "toc-title",
+    "toclevels",
+];
+
+pub fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec!["<".to_string(), ":".to_string(), "{".to_string()]),
+            ..CompletionOptions::default()
+        }),
+        ..ServerCapabilities::default()
+    }
+}
+
+// Serve over stdin and stdout until the client shuts the server down.
+pub fn serve() -> Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+    connection.initialize(serde_json::to_value(capabilities())?)?;
+    Server::default().run(&connection)?;
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+// Open documents are kept as text and parsed again for every request.
+#[derive(Default)]
+struct Server {
+    documents: HashMap<Url, String>,
+}
+
+impl Server {
+    fn run(&mut self, connection: &Connection) -> Result<()> {
+        for message in &connection.receiver {
+            match message {
+                Message::Request(request) => {
+                    if connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+                    let response = self.request(request);
+                    connection.sender.send(Message::Response(response))?;
+                }
+                Message::Notification(notification) => {
+                    if let Some(uri) = self.notification(notification)? {
+                        let diagnostics = self
+                            .documents
+                            .get(&uri)
+                            .map(|source| diagnostics(source))
+                            .unwrap_or_default();
+                        let params = PublishDiagnosticsParams {
+                            uri,
+                            diagnostics,
+                            version: None,
+                        };
+                        connection
+                            .sender
+                            .send(Message::Notification(Notification::new(
+                                PublishDiagnostics::METHOD.to_string(),
+                                params,
+                            )))?;
+                    }
+                }
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    // The document whose diagnostics have to be published again.
+    fn notification(&mut self, notification: Notification) -> Result<Option<Url>> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                let uri = params.text_document.uri;
+                self.documents
+                    .insert(uri.clone(), params.text_document.text);
+                Ok(Some(uri))
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: DidChangeTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                let uri = params.text_document.uri;
+                // Full sync, the last change holds the whole text.
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.documents.insert(uri.clone(), change.text);
+                }
+                Ok(Some(uri))
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                self.documents.remove(&params.text_document.uri);
+                Ok(Some(params.text_document.uri))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn request(&self, request: Request) -> Response {
+        let id = request.id.clone();
+        let method = request.method.clone();
+        match self.dispatch(request) {
+            Ok(Some(value)) => Response::new_ok(id, value),
+            Ok(None) => Response::new_err(
+                id,
+                ErrorCode::MethodNotFound as i32,
+                format!("unsupported request {}", method),
+            ),
+            Err(error) => Response::new_err(id, ErrorCode::InvalidParams as i32, error.to_string()),
+        }
+    }
+
+    // `None` for methods the server does not implement. Requests for documents that are not
+    // open get a null result.
+    fn dispatch(&self, request: Request) -> Result<Option<Value>> {
+        let value = match request.method.as_str() {
+            DocumentSymbolRequest::METHOD => {
+                let params: DocumentSymbolParams = serde_json::from_value(request.params)?;
+                json!(self.source(&params.text_document.uri).map(document_symbols))
+            }
+            FoldingRangeRequest::METHOD => {
+                let params: FoldingRangeParams = serde_json::from_value(request.params)?;
+                json!(self.source(&params.text_document.uri).map(folding_ranges))
+            }
+            GotoDefinition::METHOD => {
+                let params: GotoDefinitionParams = serde_json::from_value(request.params)?;
+                let position = params.text_document_position_params.position;
+                let uri = params.text_document_position_params.text_document.uri;
+                let range = self
+                    .source(&uri)
+                    .and_then(|source| definition(source, position));
+                json!(range.map(|range| GotoDefinitionResponse::Scalar(Location { uri, range })))
+            }
+            Completion::METHOD => {
+                let params: CompletionParams = serde_json::from_value(request.params)?;
+                let position = params.text_document_position;
+                json!(self
+                    .source(&position.text_document.uri)
+                    .map(|source| completions(source, position.position)))
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(value))
+    }
+
+    fn source(&self, uri: &Url) -> Option<&str> {
+        self.documents.get(uri).map(String::as_str)
+    }
+}
+
+// Parsed without preprocessing, so spans point into the text the editor holds.
+fn parse(source: &str) -> (Document, Vec<diagnostic::Diagnostic>) {
+    let (blocks, diagnostics) =
+        parse_lenient(source, &RecoveryPolicy::default(), BlockOptions::default());
+    (Document::from_spanned(blocks), diagnostics)
+}
+
+pub fn diagnostics(source: &str) -> Vec<Diagnostic> {
+    parse(source)
+        .1
+        .into_iter()
+        .map(|diagnostic| Diagnostic {
+            range: range(source, diagnostic.span),
+            severity: Some(match diagnostic.severity {
+                Severity::Info => DiagnosticSeverity::INFORMATION,
+                Severity::Warning => DiagnosticSeverity::WARNING,
+                Severity::Error => DiagnosticSeverity::ERROR,
+            }),
+            source: Some("asciidoc".to_string()),
+            message: diagnostic.message,
+            ..Diagnostic::default()
+        })
+        .collect()
+}
+
+// One symbol per section, nested like the outline.
+pub fn document_symbols(source: &str) -> Vec<DocumentSymbol> {
+    let (document, _) = parse(source);
+    let mut sections = sections(&document, source).into_iter();
+    let entries = outline(document.blocks(), u8::MAX);
+    symbols(&entries, &mut sections, source)
+}
+
+#[allow(deprecated)]
+fn symbols(
+    entries: &[OutlineEntry],
+    sections: &mut impl Iterator<Item = (Span, Span)>,
+    source: &str,
+) -> Vec<DocumentSymbol> {
+    let mut found = vec![];
+    for entry in entries {
+        // The outline lists the section headings in document order.
+        let (heading, section) = match sections.next() {
+            Some(spans) => spans,
+            None => break,
+        };
+        found.push(DocumentSymbol {
+            name: entry.title_text.clone(),
+            detail: entry.id.clone(),
+            kind: SymbolKind::NAMESPACE,
+            tags: None,
+            deprecated: None,
+            range: range(source, section),
+            selection_range: range(source, heading),
+            children: Some(symbols(&entry.children, sections, source)),
+        });
+    }
+    found
+}
+
+pub fn folding_ranges(source: &str) -> Vec<FoldingRange> {
+    let (document, _) = parse(source);
+    sections(&document, source)
+        .into_iter()
+        .filter_map(|(_, section)| {
+            let range = range(source, section);
+            // A section ending at the start of a line does not include that line.
+            let end_line = if range.end.character == 0 {
+                range.end.line.checked_sub(1)?
+            } else {
+                range.end.line
+            };
+            (end_line > range.start.line).then(|| FoldingRange {
+                start_line: range.start.line,
+                end_line,
+                kind: Some(FoldingRangeKind::Region),
+                ..FoldingRange::default()
+            })
+        })
+        .collect()
+}
+
+// The target of the `<<id>>` or `xref:id[]` under the cursor.
+pub fn definition(source: &str, position: Position) -> Option<Range> {
+    let offset = offset(source, position);
+    let line_start = source[..offset].rfind('\n').map_or(0, |index| index + 1);
+    let line_end = source[offset..]
+        .find('\n')
+        .map_or(source.len(), |index| offset + index);
+    let line = &source[line_start..line_end];
+    let cursor = offset - line_start;
+
+    let target = references(line)
+        .into_iter()
+        .find(|(start, end, _)| (*start..=*end).contains(&cursor))?
+        .2;
+    let (document, _) = parse(source);
+    anchors(&document)
+        .get(target)
+        .map(|span| range(source, *span))
+}
+
+// `(start, end, target)` of each reference in `line`, the range covering the whole reference.
+fn references(line: &str) -> Vec<(usize, usize, &str)> {
+    let mut references = vec![];
+    for (open, close) in &[("<<", ">>"), ("xref:", "[")] {
+        let mut from = 0;
+        while let Some(start) = line[from..].find(open).map(|index| from + index) {
+            let inner = start + open.len();
+            let end = match line[inner..].find(close) {
+                Some(index) => inner + index,
+                None => break,
+            };
+            let target = line[inner..end].split(',').next().unwrap_or_default();
+            if !target.is_empty() {
+                references.push((start, end + close.len(), target));
+            }
+            from = end + close.len();
+        }
+    }
+    references
+}
+
+// Anchor ids after `<<` or `xref:`, attribute names after `{` or at the start of an entry.
+pub fn completions(source: &str, position: Position) -> Vec<CompletionItem> {
+    let offset = offset(source, position);
+    let line_start = source[..offset].rfind('\n').map_or(0, |index| index + 1);
+    let before = &source[line_start..offset];
+
+    let word_start = before
+        .rfind(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+        .map_or(0, |index| index + 1);
+    let prefix = &before[..word_start];
+    let word = &before[word_start..];
+
+    if prefix.ends_with("<<") || prefix.ends_with("xref:") {
+        let (document, _) = parse(source);
+        let mut ids = anchors(&document).into_keys().collect::<Vec<_>>();
+        ids.sort();
+        return items(ids, word, CompletionItemKind::REFERENCE);
+    }
+    if prefix.ends_with('{') || prefix == ":" {
+        let mut names = BUILTIN_ATTRIBUTES
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<BTreeSet<_>>();
+        names.extend(defined_attributes(source));
+        return items(names, word, CompletionItemKind::VARIABLE);
+    }
+    vec![]
+}
+
+fn items<I: IntoIterator<Item = String>>(
+    labels: I,
+    word: &str,
+    kind: CompletionItemKind,
+) -> Vec<CompletionItem> {
+    labels
+        .into_iter()
+        .filter(|label| label.starts_with(word))
+        .map(|label| CompletionItem {
+            label,
+            kind: Some(kind),
+            ..CompletionItem::default()
+        })
+        .collect()
+}
+
+fn defined_attributes(source: &str) -> Vec<String> {
+    source
+        .lines()
+        .filter_map(|line| {
+            let name = line.strip_prefix(':')?.split(':').next()?;
+            let name = name.trim_start_matches('!').trim_end_matches('!');
+            (!name.is_empty() && !name.contains(char::is_whitespace)).then(|| name.to_string())
+        })
+        .collect()
+}
+
+// Span of each section heading and of the whole section, which runs until the next heading of
+// the same or a higher level. The document title is not a section.
+fn sections(document: &Document, source: &str) -> Vec<(Span, Span)> {
+    let headings = document
+        .blocks()
+        .iter()
+        .enumerate()
+        .filter_map(|(index, block)| match block {
+            Block::Heading { level, .. } if *level != HeadingLevel::Title => {
+                Some((level.as_u8(), document.span(index)?))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    headings
+        .iter()
+        .enumerate()
+        .map(|(index, (level, heading))| {
+            let end = headings[index + 1..]
+                .iter()
+                .find(|(next_level, _)| next_level <= level)
+                .map_or(source.len(), |(_, next)| next.start);
+            (
+                *heading,
+                Span {
+                    start: heading.start,
+                    end,
+                },
+            )
+        })
+        .collect()
+}
+
+fn range(source: &str, span: Span) -> Range {
+    Range {
+        start: position(source, span.start),
+        end: position(source, span.end),
+    }
+}
+
+// Positions count UTF-16 code units, as the protocol expects by default.
+fn position(source: &str, offset: usize) -> Position {
+    let before = &source[..offset.min(source.len())];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    Position {
+        line: before.matches('\n').count() as u32,
+        character: before[line_start..].encode_utf16().count() as u32,
+    }
+}
+
+fn offset(source: &str, position: Position) -> usize {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        match source[line_start..].find('\n') {
+            Some(index) => line_start += index + 1,
+            None => return source.len(),
+        }
+    }
+    let mut units = 0;
+    for (index, c) in source[line_start..].char_indices() {
+        if units >= position.character as usize || c == '\n' {
+            return line_start + index;
+        }
+        units += c.len_utf16();
+    }
+    source.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const SOURCE: &str = ":product: demo\n= Title\n\n== Intro\n\nsee <<_usage>>\n\n=== Detail\n\nmore\n\n== Usage\n\ntext";
+
+    fn pos(line: u32, character: u32) -> Position {
+        Position { line, character }
+    }
+
+    #[test]
+    fn test_document_symbols() {
+        let symbols = document_symbols(SOURCE);
+        let names = symbols
+            .iter()
+            .map(|symbol| {
+                (
+                    symbol.name.as_str(),
+                    symbol.range.start.line,
+                    symbol.children.as_ref().map_or(0, Vec::len),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec![("Intro", 3, 1), ("Usage", 11, 0)]);
+    }
+
+    #[test]
+    fn test_folding_ranges() {
+        let ranges = folding_ranges(SOURCE)
+            .into_iter()
+            .map(|range| (range.start_line, range.end_line))
+            .collect::<Vec<_>>();
+        assert_eq!(ranges, vec![(3, 10), (7, 10), (11, 13)]);
+    }
+
+    #[test]
+    fn test_definition() {
+        assert_eq!(
+            definition(SOURCE, pos(5, 8)).map(|range| range.start),
+            Some(pos(11, 0))
+        );
+        assert_eq!(definition(SOURCE, pos(5, 1)), None);
+    }
+
+    #[test]
+    fn test_completions() {
+        let source = format!("{}\n\n<<_d", SOURCE);
+        let labels = |items: Vec<CompletionItem>| {
+            items.into_iter().map(|item| item.label).collect::<Vec<_>>()
+        };
+        assert_eq!(labels(completions(&source, pos(15, 4))), vec!["_detail"]);
+
+        let source = format!("{}\n\n{{pro", SOURCE);
+        assert_eq!(labels(completions(&source, pos(15, 4))), vec!["product"]);
+    }
+
+    #[test]
+    fn test_position_counts_utf16() {
+        let source = "a😀b\nc";
+        assert_eq!(position(source, 5), pos(0, 3));
+        assert_eq!(offset(source, pos(0, 3)), 5);
+        assert_eq!(offset(source, pos(1, 0)), 7);
+    }
+}
@@ -1,1071 +1,1266 @@
-use anyhow::Result;
-use combine::error::ParseError;
-use combine::parser::char::{newline, space, spaces, string};
-use combine::*;
-use std::collections::HashMap;
-
-#[derive(Debug, PartialEq, Eq)]
-pub enum HeadingLevel {
-    Title,
-    Level1,
-    Level2,
-    Level3,
-    Level4,
-}
-
-#[derive(Debug, PartialEq, Eq)]
-pub enum ListLevel {
-    Level1,
-    Level2,
-    Level3,
-    Level4,
-    Level5,
-}
-
-#[derive(Debug, PartialEq, Eq)]
-pub enum FootnoteType {
-    Note,
-    Tip,
-    Important,
-    Warning,
-    Caution,
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{ArgAction, Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use combine_sandbox::annotations::{self, Annotation};
+use combine_sandbox::build::{build, BuildOptions, Site};
+use combine_sandbox::config::Config;
+use combine_sandbox::diagnostic::Severity;
+use combine_sandbox::diagram::{render_diagrams, CommandBackend};
+use combine_sandbox::doctor::{doctor, DoctorOptions};
+use combine_sandbox::links::{self, check_links, LinkCheckOptions, Project};
+use combine_sandbox::grammar;
+use combine_sandbox::lint::{self, lint_source, LintConfig, LintReport};
+use combine_sandbox::metrics::{Metrics, WORDS_PER_MINUTE};
+use combine_sandbox::outline::OutlineEntry;
+use combine_sandbox::query::{query_source, Selector};
+use combine_sandbox::render::{
+    asciidoc, html, latex, manpage, markdown, notebook, paged, pandoc, revealjs,
+};
+use combine_sandbox::scaffold::{scaffold, ScaffoldOptions, Template};
+use combine_sandbox::serve::{serve, ServeOptions};
+use combine_sandbox::syntax_tree::syntax_tree;
+use combine_sandbox::toc::update_toc;
+use combine_sandbox::{format, json, Document, FormatOptions, ParseOptions, RecoveryPolicy};
+use serde_json::json;
+use std::io::{IsTerminal, Read, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::{env, fs, io};
+
+// Exit codes, stable for scripts and CI:
+//
+//   0 success
+//   1 the documents have problems: error diagnostics, broken links, unformatted files, and
+//     with --strict also warnings such as unresolved xrefs
+//   2 the command could not run: unreadable or unwritable files, bad arguments or config
+//   3 internal error, a bug in this tool
+const EXIT_PROBLEMS: u8 = 1;
+const EXIT_ERROR: u8 = 2;
+const EXIT_INTERNAL: u8 = 3;
+
+#[derive(Parser)]
+#[command(
+    version,
+    about = "Parse, check, format and render AsciiDoc documents",
+    arg_required_else_help = true
+)]
+struct Cli {
+    #[arg(
+        help = "Print the subcommands and their arguments as JSON for wrapper tools and editors",
+        long,
+        exclusive = true
+    )]
+    help_json: bool,
+    #[arg(
+        help = "Fail on warnings too, e.g. unresolved xrefs, for CI",
+        long,
+        global = true
+    )]
+    strict: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-#[derive(Debug, PartialEq, Eq)]
-pub enum VideoProvider {
-    Youtube,
-}
-
-#[derive(Debug, PartialEq, Eq)]
-pub struct TableColumn {
-    name: String,
-}
-
-#[derive(Debug, PartialEq, Eq)]
-pub struct TableRow {
-    children: Box<Block>,
-}
-
-#[derive(Debug, PartialEq, Eq)]
-pub enum Block {
-    Paragraph {
-        children: Vec<Inline>,
-    },
-    Heading {
-        level: HeadingLevel,
-        children: Vec<Inline>,
-        id: Option<String>,
-    },
-    // Horizontal ruled line section
-    HorizontalRuledLine,
-    NextPage,
-    // List section
-    UnorderdList {
-        children: Vec<ListItem>,
-    },
-    OrderdList {
-        children: Vec<ListItem>,
-    },
-    Label {
-        children: Vec<Inline>,
-        key: Vec<Inline>,
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Render a document to another format")]
+    Render {
+        #[command(flatten)]
+        input: InputArgs,
+        #[arg(
+            help = "Output format, `output.format` of the config file or html by default",
+            short,
+            long,
+            value_enum
+        )]
+        format: Option<RenderFormat>,
+        #[arg(
+            help = "Render [plantuml], [mermaid] and [graphviz] blocks with plantuml, mmdc and dot",
+            long
+        )]
+        diagrams: bool,
+        #[arg(
+            help = "Write the diagrams to this directory instead of inlining the SVG",
+            long,
+            value_name = "DIR",
+            requires = "diagrams"
+        )]
+        diagram_dir: Option<PathBuf>,
+        #[command(flatten)]
+        output: OutputArgs,
     },
-    Qanda {
-        question: Vec<Inline>,
-        answer: Vec<Inline>,
+    #[command(about = "Render a set of documents to a directory of HTML pages with an index")]
+    Build {
+        #[arg(
+            help = "Files or glob patterns of the documents to build",
+            required_unless_present_any = ["nav", "content"]
+        )]
+        patterns: Vec<String>,
+        #[arg(
+            help = "Content directory whose .adoc files are the pages, except those starting with `_`",
+            long,
+            value_name = "DIR",
+            conflicts_with_all = ["patterns", "nav"]
+        )]
+        content: Option<PathBuf>,
+        #[arg(
+            help = "Navigation manifest whose list items are xrefs to the documents, in order",
+            long,
+            value_name = "FILE",
+            conflicts_with = "patterns"
+        )]
+        nav: Option<PathBuf>,
+        #[arg(
+            help = "Directory the output layout mirrors, the manifest's directory with --nav and the content directory with --content",
+            long,
+            default_value = "."
+        )]
+        root: PathBuf,
+        #[arg(
+            help = "Directory to write the pages to",
+            long,
+            default_value = "build"
+        )]
+        out_dir: PathBuf,
+        #[arg(
+            help = "File name of the index page",
+            long,
+            default_value = "index.html"
+        )]
+        index: String,
+        #[arg(
+            help = "Page template with {{title}}, {{content}}, {{toc}}, {{pagination}}, {{index}} and {{root}} placeholders",
+            long,
+            value_name = "FILE"
+        )]
+        theme: Option<PathBuf>,
+        #[arg(
+            help = "Also write a JSON search index for tinysearch or lunr under this name",
+            long,
+            value_name = "FILE"
+        )]
+        search_index: Option<String>,
     },
-    CodeBlock {
-        children: Vec<Inline>,
-        title: Option<String>,
-        file_type: Option<String>,
+    #[command(
+        about = "Preview the documents of a directory over HTTP, pages reload when a file changes"
+    )]
+    Serve {
+        #[arg(help = "Directory to serve", default_value = ".")]
+        dir: PathBuf,
+        #[arg(help = "Address to listen on", long, default_value = "127.0.0.1")]
+        host: String,
+        #[arg(help = "Port to listen on", short, long, default_value_t = 8000)]
+        port: u16,
     },
-    // Unsupport CodeBlockWithSpeachBaloon
-    Block {
-        children: Vec<Inline>,
-        title: Option<Vec<Inline>>,
-    },
-    Table {
-        columns: Vec<TableColumn>,
-        rows: Vec<TableRow>,
-        title: Option<String>,
+    #[command(about = "Report the diagnostics of each file")]
+    Check {
+        #[arg(help = "Files to check, stdin when empty or `-`")]
+        files: Vec<PathBuf>,
+        #[arg(
+            help = "Text excerpts on stderr, or CI annotations on the output",
+            short,
+            long,
+            value_enum,
+            default_value_t = CheckFormat::Text
+        )]
+        format: CheckFormat,
+        #[command(flatten)]
+        output: OutputArgs,
     },
-    BlankBlock,
-}
-
-#[derive(Debug, PartialEq, Eq)]
-pub enum Inline {
-    // Paragraph section
-    Value(String),
-    HardBreak,
-    SoftBreak,
-    Literal {
-        children: Box<Inline>,
+    #[command(about = "Check xrefs and includes across all documents in a directory")]
+    CheckLinks {
+        #[arg(help = "Project directory", default_value = ".")]
+        dir: PathBuf,
+        #[arg(help = "Also send a HEAD request to every external URL", long)]
+        external: bool,
+        #[arg(
+            help = "External requests in flight at once",
+            long,
+            default_value_t = 8
+        )]
+        concurrency: usize,
+        #[arg(short, long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        #[command(flatten)]
+        output: OutputArgs,
     },
-    Footnote {
-        kind: FootnoteType,
-        children: Box<Inline>,
+    #[command(
+        about = "Report lints, broken references, unused anchors, missing images and unknown attributes across a directory"
+    )]
+    Doctor {
+        #[arg(help = "Project directory", default_value = ".")]
+        dir: PathBuf,
+        #[arg(
+            help = "Set the level of a lint rule: off, info, warning or error",
+            short,
+            long = "rule",
+            value_name = "RULE=LEVEL"
+        )]
+        rules: Vec<String>,
+        #[arg(short, long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        #[command(flatten)]
+        output: OutputArgs,
     },
-    Lead {
-        children: Box<Inline>,
+    #[command(about = "Check documents against the lint rules")]
+    Lint {
+        #[arg(help = "Files to lint, stdin when empty or `-`")]
+        files: Vec<PathBuf>,
+        #[arg(
+            help = "Set the level of a rule: off, info, warning or error",
+            short,
+            long = "rule",
+            value_name = "RULE=LEVEL"
+        )]
+        rules: Vec<String>,
+        #[arg(short, long, value_enum, default_value_t = LintFormat::Text)]
+        format: LintFormat,
+        #[command(flatten)]
+        output: OutputArgs,
     },
-    // Text format section
-    Bold {
-        children: Box<Inline>,
+    #[command(about = "Rewrite files in the canonical layout")]
+    Fmt {
+        #[arg(help = "Files to rewrite in place, stdin to stdout when empty or `-`")]
+        files: Vec<PathBuf>,
+        #[arg(
+            help = "List the files that are not formatted instead of rewriting them",
+            long
+        )]
+        check: bool,
+        #[arg(help = "Refill paragraphs to this many characters", long)]
+        width: Option<usize>,
     },
-    Italic {
-        children: Box<Inline>,
+    #[command(about = "Print the parsed syntax tree")]
+    DumpAst {
+        #[command(flatten)]
+        input: InputArgs,
+        #[arg(short, long, value_enum, default_value_t = AstFormat::Json)]
+        format: AstFormat,
+        #[command(flatten)]
+        output: OutputArgs,
     },
-    Monospace {
-        children: Box<Inline>,
+    #[command(
+        about = "Print a TextMate grammar for editors, generated from the marks the parser accepts"
+    )]
+    Grammar {
+        #[command(flatten)]
+        output: OutputArgs,
     },
-    Marker {
-        children: Box<Inline>,
+    #[command(about = "Print a shell completion script")]
+    Completions {
+        #[arg(help = "Shell to complete for", value_enum)]
+        shell: Shell,
     },
-    // Unsupport Superscript
-    // Unsupport Subscript
-    // Unsupport Curvequote
-    // Unsupport Apostorofy
-    Macro {
-        attributes: Attributes,
-        kind: String,
-        id: String,
+    #[command(about = "Create a document skeleton from a template")]
+    New {
+        #[arg(help = "Built-in template", value_enum, default_value_t = NewTemplate::Article)]
+        template: NewTemplate,
+        #[arg(help = "Document title", short, long, default_value = "Untitled")]
+        title: String,
+        #[arg(help = "Author name", short, long)]
+        author: Option<String>,
+        #[arg(help = "Author email", short, long)]
+        email: Option<String>,
+        #[arg(
+            help = "Template file used instead of the built-in one, with {{title}}, {{author}}, {{email}} and {{name}} placeholders",
+            long,
+            value_name = "FILE"
+        )]
+        template_file: Option<PathBuf>,
+        #[arg(help = "Overwrite the output file if it exists", long)]
+        force: bool,
+        #[command(flatten)]
+        output: OutputArgs,
     },
-    // Code section
-    InlineCode {
-        children: Box<Inline>,
+    #[command(
+        about = "Find blocks matching a selector such as 'code[lang=rust]' or 'heading[level<=2]'"
+    )]
+    Query {
+        #[arg(
+            help = "Block type (paragraph, heading, code, list, table, ...) or *, followed by [key op value] conditions on lang, level, id, role, style, title or text"
+        )]
+        selector: Selector,
+        #[arg(help = "Files or glob patterns, stdin when empty or `-`")]
+        patterns: Vec<String>,
+        #[arg(short, long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        #[command(flatten)]
+        output: OutputArgs,
     },
-}
-
-#[derive(Debug, PartialEq, Eq)]
-pub enum Attributes {
-    Position(Vec<String>),
-    Named(HashMap<String, String>),
-}
-
-#[derive(Debug, PartialEq, Eq)]
-pub enum ListItem {
-    Normal {
-        children: Vec<Inline>,
-        level: u32,
+    #[command(about = "Count words, headings and code blocks and estimate the reading time")]
+    Stats {
+        #[arg(help = "Files or glob patterns such as 'docs/**/*.adoc', stdin when empty or `-`")]
+        patterns: Vec<String>,
+        #[arg(help = "Reading speed used for the estimate", long, default_value_t = WORDS_PER_MINUTE)]
+        words_per_minute: usize,
+        #[arg(short, long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        #[command(flatten)]
+        output: OutputArgs,
     },
-    Check {
-        children: Vec<Inline>,
-        level: u32,
-        checked: bool,
+    #[command(about = "Print the table of contents")]
+    Toc {
+        #[command(flatten)]
+        input: InputArgs,
+        #[arg(
+            help = "Rewrite the list of xrefs between the `// toc` and `// tocstop` lines of the document, to the output for stdin",
+            short,
+            long,
+            conflicts_with = "format"
+        )]
+        write: bool,
+        #[arg(
+            help = "Deepest section level to list, `toclevels` or 2 by default",
+            long
+        )]
+        depth: Option<u8>,
+        #[arg(short, long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        #[command(flatten)]
+        output: OutputArgs,
     },
 }
 
-pub fn parse(s: &str) -> Result<Vec<Block>> {
-    let mut parser = document();
-
-    let trim_targets: &[_] = &['\n', ' '];
-    let s = s.trim_start_matches(trim_targets);
-
-    return Ok(parser.parse(s).map(|(tokens, _)| tokens)?);
-}
-
-fn document<Input>() -> impl Parser<Input, Output = Vec<Block>>
-where
-    Input: Stream<Token = char>,
-    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
-{
-    many::<Vec<Block>, _, _>(block())
-}
-
-fn block<Input>() -> impl Parser<Input, Output = Block>
-where
-    Input: Stream<Token = char>,
-    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
-{
-    choice((
-        heading_block(),
-        horizontal_ruled_line_block(),
-        ordered_list_block(),
-        unordered_list_block(),
-        paragraph_block(),
-        blank_block(),
-    ))
-}
-
-parser! {
-    fn inline[Input]()(Input) -> Inline
-    where
-        [Input: Stream<Token = char>] {
-            inline_()
-        }
-}
-
-fn inline_<Input>() -> impl Parser<Input, Output = Inline>
-where
-    Input: Stream<Token = char>,
-    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
-{
-    let inline_combinator = choice((
-        value(),
-        bold(),
-        italic(),
-        attempt(inline_code()).or(monospace()),
-        marker(),
-        line_break(),
-    ));
-    attempt(inline_combinator)
-        .or(satisfy(|c| c != '\n').map(|s: char| Inline::Value(s.to_string())))
-}
-
-parser! {
-    fn list_item_inline[Input]()(Input) -> Inline
-    where
-        [Input: Stream<Token = char>] {
-            list_item_inline_()
-        }
+#[derive(Args)]
+struct InputArgs {
+    #[arg(help = "Document to read, stdin when omitted or `-`")]
+    file: Option<PathBuf>,
+    #[arg(
+        help = "Set a document attribute, `name=value` or `name`",
+        short,
+        long = "attribute",
+        value_name = "NAME[=VALUE]"
+    )]
+    attributes: Vec<String>,
 }
 
-fn list_item_inline_<Input>() -> impl Parser<Input, Output = Inline>
-where
-    Input: Stream<Token = char>,
-    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
-{
-    let inline_combinator = choice((value(), bold(), italic(), monospace()));
-    attempt(inline_combinator)
-        .or(satisfy(|c| c != '\n').map(|s: char| Inline::Value(s.to_string())))
+#[derive(Args)]
+struct OutputArgs {
+    #[arg(help = "File to write, stdout when omitted or `-`", short, long)]
+    output: Option<PathBuf>,
 }
 
-fn line_break<Input>() -> impl Parser<Input, Output = Inline>
-where
-    Input: Stream<Token = char>,
-    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
-{
-    choice((
-        newline()
-            .and(not_followed_by(newline()))
-            .map(|_| Inline::SoftBreak),
-        space().and(string("+\n")).map(|_| Inline::HardBreak),
-    ))
+#[derive(Clone, Copy, ValueEnum)]
+enum RenderFormat {
+    Html,
+    HtmlAsciidoctor,
+    HtmlGithub,
+    Paged,
+    Revealjs,
+    Markdown,
+    Latex,
+    Manpage,
+    Asciidoc,
+    // Pandoc's JSON AST, e.g. `| pandoc -f json -o out.docx`.
+    Pandoc,
+    // Jupyter notebook, `[source,python,role=executable]` blocks become code cells.
+    Notebook,
 }
 
-fn bold<Input>() -> impl Parser<Input, Output = Inline>
-where
-    Input: Stream<Token = char>,
-    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
-{
-    let symbol = '*';
-    skip_many(token(' '))
-        .and(between(skip_count_min_max(1, 2, token(symbol)), skip_count_min_max(1, 2, token(symbol)), inline()))
-        .map(|(_, children)| Inline::Bold {
-            children: Box::new(children),
-        })
+#[derive(Clone, Copy, ValueEnum)]
+enum NewTemplate {
+    Article,
+    Book,
+    Manpage,
+    Readme,
 }
 
-pub fn monospace<Input>() -> impl Parser<Input, Output = Inline>
-where
-    Input: Stream<Token = char>,
-    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
-{
-    let symbol = '`';
-    skip_many(token(' '))
-        .and(between(skip_count_min_max(1, 2, token(symbol)), skip_count_min_max(1, 2, token(symbol)), inline()))
-        .map(|(_, children)| Inline::Monospace {
-            children: Box::new(children),
-        })
+#[derive(Clone, Copy, ValueEnum)]
+enum AstFormat {
+    Json,
+    Tree,
+    Debug,
+    // tree-sitter style nodes with the ranges in the file, for editors.
+    Sexp,
 }
 
-fn italic<Input>() -> impl Parser<Input, Output = Inline>
-where
-    Input: Stream<Token = char>,
-    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
-{
-    let symbol = '_';
-    skip_many(token(' '))
-        .and(between(skip_count_min_max(1, 2, token(symbol)), skip_count_min_max(1, 2, token(symbol)), inline()))
-        .map(|(_, children)| Inline::Italic {
-            children: Box::new(children),
-        })
+#[derive(Clone, Copy, ValueEnum)]
+enum LintFormat {
+    Text,
+    Json,
+    Sarif,
+    // GitHub Actions workflow commands.
+    Github,
+    // GitLab Code Quality report.
+    Gitlab,
 }
 
-fn marker<Input>() -> impl Parser<Input, Output = Inline>
-where
-    Input: Stream<Token = char>,
-    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
-{
-    let symbol = '#';
-    skip_many(token(' '))
-        .and(between(skip_count_min_max(1, 2, token(symbol)), skip_count_min_max(1, 2, token(symbol)), inline()))
-        .map(|(_, children)| Inline::Marker {
-            children: Box::new(children),
-        })
+#[derive(Clone, Copy, ValueEnum)]
+enum CheckFormat {
+    Text,
+    Github,
+    Gitlab,
 }
 
-fn inline_code<Input>() -> impl Parser<Input, Output = Inline>
-where
-    Input: Stream<Token = char>,
-    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
-{
-    let symbol = '`';
-    (
-        token(symbol),
-        token(symbol),
-        token(symbol),
-        inline(),
-        token(symbol),
-        token(symbol),
-        token(symbol),
-    )
-        .map(|(_, _, _, children, _, _, _)| Inline::InlineCode {
-            children: Box::new(children),
-        })
+#[derive(Clone, Copy, ValueEnum)]
+enum ReportFormat {
+    Text,
+    Json,
 }
 
-fn value<Input>() -> impl Parser<Input, Output = Inline>
-where
-    Input: Stream<Token = char>,
-    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
-{
-    let ignore_tokens: &_ = &['\n', '*', '_', '`', '#'];
-    return many1::<String, _, _>(satisfy(move |c| {
-        &ignore_tokens.iter().skip_while(|i| c != **i).count() == &0
-    }))
-    .map(|s| Inline::Value(s));
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let strict = cli.strict;
+    let command = match cli.command {
+        Some(command) if !cli.help_json => command,
+        _ => {
+            let json = serde_json::to_string_pretty(&help_json(&mut Cli::command()));
+            // A closed pipe, e.g. into `head`, is not worth a panic.
+            let _ = writeln!(io::stdout(), "{}", json.unwrap());
+            return ExitCode::SUCCESS;
+        }
+    };
+    // The panic hook has already printed the message.
+    match panic::catch_unwind(AssertUnwindSafe(|| run(command, strict))) {
+        Ok(Ok(true)) => ExitCode::SUCCESS,
+        Ok(Ok(false)) => ExitCode::from(EXIT_PROBLEMS),
+        Ok(Err(error)) => {
+            eprintln!("error: {:#}", error);
+            ExitCode::from(EXIT_ERROR)
+        }
+        Err(_) => ExitCode::from(EXIT_INTERNAL),
+    }
 }
 
-fn heading_block<Input>() -> impl Parser<Input, Output = Block>
-where
-    Input: Stream<Token = char>,
-    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
-{
-    many1::<Vec<char>, _, _>(token('='))
-        .and(many1::<String, _, _>(token(' ')))
-        .and(inline())
-        .map(|((heading, spaces), children)| {
-            if heading.len() > 5 {
-                let heading_raw = heading.iter().collect::<String>();
-                return Block::Paragraph {
-                    children: vec![Inline::Value(heading_raw + spaces.as_str()), children],
+// `Ok(false)` when the command ran but found problems in the documents. `strict` counts
+// warnings as problems.
+fn run(command: Command, strict: bool) -> Result<bool> {
+    let fail_at = if strict {
+        Severity::Warning
+    } else {
+        Severity::Error
+    };
+    match command {
+        Command::Render {
+            input,
+            format,
+            diagrams,
+            diagram_dir,
+            output,
+        } => {
+            let format = match format {
+                Some(format) => format,
+                None => match load_config(input.path())?.output.format {
+                    Some(name) => RenderFormat::from_str(&name, true)
+                        .map_err(|_| anyhow!("unknown output format `{}` in config", name))?,
+                    None => RenderFormat::Html,
+                },
+            };
+            // Notebook code cells and diagrams need the source text, the marks in it aren't
+            // formatting.
+            let mut document = if diagrams || matches!(format, RenderFormat::Notebook) {
+                Document::parse_with_options(
+                    &read_input(input.path())?,
+                    &input.options()?.lazy_inlines(true),
+                )?
+            } else {
+                input.parse()?
+            };
+            if diagrams {
+                let backend = match diagram_dir {
+                    Some(dir) => CommandBackend::new().output_dir(dir),
+                    None => CommandBackend::new(),
                 };
+                render_diagrams(&mut document, &backend)?;
             }
-            let level = match heading.len() {
-                1 => HeadingLevel::Title,
-                2 => HeadingLevel::Level1,
-                3 => HeadingLevel::Level2,
-                4 => HeadingLevel::Level3,
-                5 => HeadingLevel::Level4,
-                _ => {
-                    unreachable!()
+            output.write(&render(&document, format)?)?;
+            // Recovered documents render anyway, strict mode still reports what was recovered.
+            Ok(!strict || document.diagnostics().is_empty())
+        }
+        Command::Build {
+            patterns,
+            content,
+            nav,
+            root,
+            out_dir,
+            index,
+            theme,
+            search_index,
+        } => {
+            let site = match (&nav, &content) {
+                (Some(nav), _) => Site::from_nav(nav)?,
+                (None, Some(content)) => Site::from_dir(content)?,
+                (None, None) => {
+                    let paths = expand_patterns(&patterns)?.into_iter().flatten();
+                    Site::from_paths(&root, &paths.collect::<Vec<_>>())?
                 }
             };
-            Block::Heading {
-                id: None,
-                children: vec![children],
-                level,
+            let config = match &nav {
+                Some(nav) => load_config(Some(nav))?,
+                None => {
+                    let root = content.as_ref().unwrap_or(&root);
+                    Config::discover(&env::current_dir()?.join(root))?
+                }
+            };
+            let mut options = BuildOptions::new()
+                .parse(ParseOptions::new().recovery(RecoveryPolicy::default()))
+                .config(config)
+                .index(index);
+            if let Some(theme) = &theme {
+                let theme = fs::read_to_string(theme)
+                    .with_context(|| format!("failed to read {}", theme.display()))?;
+                options = options.theme(theme);
             }
-        })
-}
-
-fn paragraph_block<Input>() -> impl Parser<Input, Output = Block>
-where
-    Input: Stream<Token = char>,
-    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
-{
-    many1::<Vec<Inline>, _, _>(attempt(inline())).map(|children| Block::Paragraph { children })
-    // many1::<Vec<Inline>, _, _>(inline()).and(look_ahead(count_min_max::<String, _, _>(1, 2, newline())))
-}
-
-fn blank_block<Input>() -> impl Parser<Input, Output = Block>
-where
-    Input: Stream<Token = char>,
-    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
-{
-    count_min_max::<String, _, _>(2, 2, newline()).map(|_| Block::BlankBlock)
-}
-
-fn horizontal_ruled_line_block<Input>() -> impl Parser<Input, Output = Block>
-where
-    Input: Stream<Token = char>,
-    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
-{
-    string("<<<").map(|_| Block::HorizontalRuledLine)
-}
-
-fn unordered_list_block<Input>() -> impl Parser<Input, Output = Block>
-where
-    Input: Stream<Token = char>,
-    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
-{
-    many1::<Vec<ListItem>, _, _>(
-        list_item('*')
-            .and(count_min_max::<Vec<char>, _, _>(0, 1, newline()))
-            .map(|(list_item, _)| list_item),
-    )
-    .map(|items| Block::UnorderdList { children: items })
+            if let Some(search_index) = search_index {
+                options = options.search_index(search_index);
+            }
+            let report = build(&site, &out_dir, &options)?;
+            for unresolved in &report.unresolved {
+                eprintln!("warning: {}", unresolved);
+            }
+            Ok(!strict || report.unresolved.is_empty())
+        }
+        Command::Serve { dir, host, port } => {
+            let options = ServeOptions::new()
+                .parse(ParseOptions::new().recovery(RecoveryPolicy::default()))
+                .config(Config::discover(&env::current_dir()?.join(&dir))?)
+                .host(host)
+                .port(port);
+            eprintln!("Serving {} at http://{}/", dir.display(), options.address());
+            serve(&dir, &options)?;
+            Ok(true)
+        }
+        Command::Check {
+            files,
+            format,
+            output,
+        } => check(&files, format, &output, fail_at),
+        Command::CheckLinks {
+            dir,
+            external,
+            concurrency,
+            format,
+            output,
+        } => {
+            let project = Project::load(dir)?;
+            let options = LinkCheckOptions::new()
+                .external(external)
+                .concurrency(concurrency);
+            let broken = check_links(&project, &options)?;
+            let text = match format {
+                ReportFormat::Text => broken.iter().map(|link| format!("{}\n", link)).collect(),
+                ReportFormat::Json => format!(
+                    "{}\n",
+                    serde_json::to_string_pretty(&links::to_json(&broken))?
+                ),
+            };
+            output.write(&text)?;
+            Ok(broken.is_empty())
+        }
+        Command::Doctor {
+            dir,
+            rules,
+            format,
+            output,
+        } => {
+            let config = Config::discover(&env::current_dir()?.join(&dir))?;
+            let mut options = DoctorOptions::new().lint(config.lint);
+            options.attributes = config.attributes;
+            for rule in &rules {
+                options.lint = options.lint.set(rule)?;
+            }
+            let report = doctor(&Project::load(dir)?, &options)?;
+            let text = match format {
+                ReportFormat::Text => report.to_text(),
+                ReportFormat::Json => {
+                    format!("{}\n", serde_json::to_string_pretty(&report.to_json())?)
+                }
+            };
+            output.write(&text)?;
+            Ok(report
+                .findings
+                .iter()
+                .all(|finding| finding.severity < fail_at))
+        }
+        Command::Lint {
+            files,
+            rules,
+            format,
+            output,
+        } => {
+            // Validate the settings before reading any file.
+            for rule in &rules {
+                LintConfig::new().set(rule)?;
+            }
+            lint(&files, &rules, format, &output, fail_at)
+        }
+        Command::Fmt {
+            files,
+            check,
+            width,
+        } => fmt(&files, check, width),
+        Command::DumpAst {
+            input,
+            format,
+            output,
+        } => {
+            let source = read_input(input.path())?;
+            let text = match format {
+                AstFormat::Json => {
+                    format!("{}\n", json::to_json_string(&input.parse_source(&source)?))
+                }
+                AstFormat::Tree => input.parse_source(&source)?.dump_tree(),
+                AstFormat::Debug => format!("{:#?}\n", input.parse_source(&source)?.blocks()),
+                AstFormat::Sexp => syntax_tree(&source).to_sexp(),
+            };
+            output.write(&text)?;
+            Ok(true)
+        }
+        Command::Grammar { output } => {
+            let grammar = serde_json::to_string_pretty(&grammar::textmate_grammar())?;
+            output.write(&format!("{}\n", grammar))?;
+            Ok(true)
+        }
+        Command::Completions { shell } => {
+            let mut command = Cli::command();
+            let name = command.get_name().to_string();
+            clap_complete::generate(shell, &mut command, name, &mut io::stdout());
+            Ok(true)
+        }
+        Command::New {
+            template,
+            title,
+            author,
+            email,
+            template_file,
+            force,
+            output,
+        } => {
+            let template = match template_file {
+                Some(path) => fs::read_to_string(&path)
+                    .with_context(|| format!("failed to read {}", path.display()))?,
+                None => match template {
+                    NewTemplate::Article => Template::Article,
+                    NewTemplate::Book => Template::Book,
+                    NewTemplate::Manpage => Template::Manpage,
+                    NewTemplate::Readme => Template::Readme,
+                }
+                .source()
+                .to_string(),
+            };
+            let mut options = ScaffoldOptions::new().title(title);
+            options.author = author;
+            options.email = email;
+            if let Some(path) = output.path() {
+                if path.exists() && !force {
+                    bail!(
+                        "{} already exists, pass --force to overwrite it",
+                        path.display()
+                    );
+                }
+            }
+            output.write(&scaffold(&template, &options)?)?;
+            Ok(true)
+        }
+        Command::Query {
+            selector,
+            patterns,
+            format,
+            output,
+        } => query(&selector, &patterns, format, &output),
+        Command::Stats {
+            patterns,
+            words_per_minute,
+            format,
+            output,
+        } => {
+            output.write(&stats(&patterns, words_per_minute, format)?)?;
+            Ok(true)
+        }
+        Command::Toc {
+            input,
+            write,
+            depth,
+            format,
+            output,
+        } => {
+            // Only section titles are needed, paragraphs are left unparsed.
+            let options = input.options()?.lazy_inlines(true);
+            let source = read_input(input.path())?;
+            let document = Document::parse_with_options(&source, &options)?;
+            if write {
+                let updated = update_toc(&source, &document, depth)?;
+                match input.path() {
+                    Some(path) if updated != source => fs::write(path, updated)
+                        .with_context(|| format!("failed to write {}", path.display()))?,
+                    Some(_) => {}
+                    None => output.write(&updated)?,
+                }
+                return Ok(true);
+            }
+            let entries = document.outline(depth);
+            let text = match format {
+                ReportFormat::Text => toc_text(&entries),
+                ReportFormat::Json => {
+                    format!("{}\n", serde_json::to_string_pretty(&toc_json(&entries))?)
+                }
+            };
+            output.write(&text)?;
+            Ok(true)
+        }
+    }
 }
 
-fn ordered_list_block<Input>() -> impl Parser<Input, Output = Block>
-where
-    Input: Stream<Token = char>,
-    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
-{
-    many1::<Vec<ListItem>, _, _>(
-        list_item('.')
-            .and(count_min_max::<Vec<char>, _, _>(0, 1, newline()))
-            .map(|(list_item, _)| list_item),
-    )
-    .map(|items| Block::OrderdList { children: items })
-}
+impl InputArgs {
+    fn path(&self) -> Option<&Path> {
+        self.file.as_deref().and_then(stdio)
+    }
 
-fn list_item<Input>(list_char: char) -> impl Parser<Input, Output = ListItem>
-where
-    Input: Stream<Token = char>,
-    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
-{
-    choice!(
-        attempt(checked_list_item(list_char)),
-        normal_list_item(list_char)
-    )
-}
+    fn parse(&self) -> Result<Document> {
+        self.parse_source(&read_input(self.path())?)
+    }
 
-fn normal_list_item<Input>(list_char: char) -> impl Parser<Input, Output = ListItem>
-where
-    Input: Stream<Token = char>,
-    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
-{
-    many1::<String, _, _>(token(list_char))
-        .and(spaces())
-        .and(many1::<Vec<Inline>, _, _>(attempt(list_item_inline_())))
-        .map(|((list_tokens, _), inline)| ListItem::Normal {
-            level: list_tokens.len() as u32,
-            children: inline,
-        })
-}
+    fn parse_source(&self, source: &str) -> Result<Document> {
+        Document::parse_with_options(source, &self.options()?)
+    }
 
-fn checked_list_item<Input>(list_char: char) -> impl Parser<Input, Output = ListItem>
-where
-    Input: Stream<Token = char>,
-    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
-{
-    many1::<String, _, _>(token(list_char))
-        .and(spaces())
-        .and(between(
-            token('['),
-            token(']'),
-            satisfy(|c| c == '*' || c == 'x' || c == ' '),
-        ))
-        .and(spaces())
-        .and(many1::<Vec<Inline>, _, _>(attempt(list_item_inline_())))
-        .map(
-            |((((list_tokens, _), check_box_char), _), inline)| ListItem::Check {
-                level: list_tokens.len() as u32,
-                children: inline,
-                checked: check_box_char != ' ',
-            },
-        )
+    fn options(&self) -> Result<ParseOptions> {
+        let mut options = parse_options(self.path())?;
+        for attribute in &self.attributes {
+            let (name, value) = match attribute.split_once('=') {
+                Some((name, value)) => (name, value),
+                None => (attribute.as_str(), ""),
+            };
+            options = options.attribute(name, value);
+        }
+        Ok(options)
+    }
 }
 
-fn named_atteributes<Input>() -> impl Parser<Input, Output = Attributes>
-where
-    Input: Stream<Token = char>,
-    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
-{
-    let value = || satisfy(|c| c != '=' && c != ']' && c != ',' && c != '\n');
-    let expression = || (
-        many::<String, _, _>(value()),
-        token('='),
-        many::<String, _, _>(value()),
-    );
-
-    let one_expression = || (
-        token('['),
-        expression(),
-        token(']')
-    ).map(|(_, (key, _, value), _)| {
-        let mut attributes = HashMap::new();
-        attributes.insert(key, value);
-        Attributes::Named(attributes)
-    });
+impl OutputArgs {
+    fn path(&self) -> Option<&Path> {
+        self.output.as_deref().and_then(stdio)
+    }
 
-    let expression_with_delimiter = || (expression(), token(','), skip_many(space()));
-    let many_expressions = || (
-        token('['),
-        many1::<Vec<((String, _, String), _, ())>, _, _>(
-            attempt(expression_with_delimiter())
-        ),
-        expression(),
-        token(']')
-    )
-        .map(|(_, exprs, (last_key, _, last_value), _)| {
-            let mut attributes: HashMap<String, String> = HashMap::new();
+    // ANSI colors only when writing to a terminal, and never when NO_COLOR is set
+    // (https://no-color.org).
+    fn color(&self) -> bool {
+        self.path().is_none() && io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none()
+    }
 
-            for ((key, _, value), _, ()) in exprs.iter() {
-                attributes.insert(key.clone(), value.clone());
+    fn write(&self, text: &str) -> Result<()> {
+        match self.path() {
+            Some(path) => {
+                fs::write(path, text).with_context(|| format!("failed to write {}", path.display()))
             }
-            attributes.insert(last_key, last_value);
-            Attributes::Named(attributes)
-        });
-
-    choice!(attempt(many_expressions()), attempt(one_expression()))
-}
-
-fn position_attributes<Input>() -> impl Parser<Input, Output = Attributes>
-where
-    Input: Stream<Token = char>,
-    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
-{
-    let attribute = || many1::<String, _, _>(satisfy(|c| c != ']' && c != '\n' && c != ','));
-
-    let single_attribute = || (token('['), attribute(), token(']')).map(|(_, attrs, _)| Attributes::Position(vec![attrs]));
-
-    let attribute_with_delimiter = || (attribute(), token(','));
-    let multi_attribute = || (
-        token('['),
-        many::<Vec<(String, _)>, _, _>(attempt(attribute_with_delimiter())),
-        attribute(),
-        token(']')
-        )
-        .map(|(_, attrs, attr, _)| {
-            let mut attributes: Vec<String> = vec![];
-
-            for (attr, _) in attrs.iter() {
-                attributes.push(attr.clone())
+            None => {
+                let mut stdout = io::stdout().lock();
+                stdout.write_all(text.as_bytes())?;
+                stdout.flush()?;
+                Ok(())
             }
-            attributes.push(attr);
-
-            Attributes::Position(attributes)
-        });
+        }
+    }
+}
 
-    choice!(attempt(multi_attribute()), single_attribute())
+// `-` stands for stdin or stdout, like in most command line tools.
+fn stdio(path: &Path) -> Option<&Path> {
+    if path == Path::new("-") {
+        None
+    } else {
+        Some(path)
+    }
 }
 
-fn main() -> () {
-    let asciidoc = "
-    == This is a Heading
-    This is a Paragraph";
+// Includes resolve relative to the document, or to the working directory for stdin.
+fn parse_options(path: Option<&Path>) -> Result<ParseOptions> {
+    let base_dir = path
+        .and_then(Path::parent)
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let options = ParseOptions::new().recovery(RecoveryPolicy::default());
+    Ok(load_config(path)?.apply(options, &base_dir))
+}
 
-    let _result = parse(asciidoc).unwrap();
-    dbg!(_result);
+// Nearest config file above the document, or above the working directory for stdin.
+fn load_config(path: Option<&Path>) -> Result<Config> {
+    let dir = match path.and_then(Path::parent) {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => env::current_dir()?,
+    };
+    Config::discover(&dir)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use combine::error::StringStreamError;
-    use pretty_assertions::assert_eq;
-    // -- utils
-    fn take_parse_result<T, E>(t: (T, E)) -> T {
-        return t.0;
+fn read_input(path: Option<&Path>) -> Result<String> {
+    match path {
+        Some(path) => {
+            fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))
+        }
+        None => {
+            let mut source = String::new();
+            io::stdin()
+                .read_to_string(&mut source)
+                .context("failed to read stdin")?;
+            Ok(source)
+        }
     }
-    // --
-
-    #[test]
-    fn test_parse_function() {
-        let asciidoc = "
-== This is a Heading
-
-This is a Paragraph
-
-== Foobar
-
-This is a b**ol**d text
-
-This is a *bold* text
-
-This is a _italic_ text
-
-This is a `monospace` text
-
-This is a #marker# text
-
-This is a ```inline code``` text
-
-wrap break *
-a
-
-* foo
-* bar
-
-
-. foo
-. bar
-<<<
+}
 
-";
+fn render(document: &Document, format: RenderFormat) -> Result<String> {
+    Ok(match format {
+        RenderFormat::Html => html::render(document),
+        RenderFormat::HtmlAsciidoctor => html::render_with_options(
+            document,
+            &html::HtmlOptions::new().profile(html::HtmlProfile::Asciidoctor),
+        ),
+        RenderFormat::HtmlGithub => {
+            html::render_with_options(document, &html::HtmlOptions::github())
+        }
+        RenderFormat::Paged => paged::render(document, &paged::PagedOptions::default()),
+        RenderFormat::Revealjs => revealjs::render(
+            document,
+            &revealjs::RevealJsOptions::from_document(document),
+        ),
+        RenderFormat::Markdown => markdown::to_markdown(document),
+        RenderFormat::Latex => latex::render(document, &latex::LatexOptions::default()),
+        RenderFormat::Manpage => manpage::render(document)?,
+        RenderFormat::Asciidoc => asciidoc::to_asciidoc(document),
+        RenderFormat::Pandoc => format!("{}\n", pandoc::to_pandoc_json_string(document)),
+        RenderFormat::Notebook => notebook::to_notebook_string(document),
+    })
+}
 
-        let result = parse(asciidoc).unwrap();
-        assert_eq!(
-            result,
-            vec![
-                Block::Heading {
-                    level: HeadingLevel::Level1,
-                    id: None,
-                    children: vec![Inline::Value("This is a Heading".to_string())]
-                },
-                Block::BlankBlock,
-                Block::Paragraph {
-                    children: vec![Inline::Value("This is a Paragraph".to_string())]
-                },
-                Block::BlankBlock,
-                Block::Heading {
-                    level: HeadingLevel::Level1,
-                    id: None,
-                    children: vec![Inline::Value("Foobar".to_string())]
-                },
-                Block::BlankBlock,
-                Block::Paragraph {
-                    children: vec![
-                        Inline::Value("This is a b".to_string()),
-                        Inline::Bold {
-                            children: Box::new(Inline::Value("ol".to_string()))
-                        },
-                        Inline::Value("d text".to_string()),
-                    ]
-                },
-                Block::BlankBlock,
-                Block::Paragraph {
-                    children: vec![
-                        Inline::Value("This is a ".to_string()),
-                        Inline::Bold {
-                            children: Box::new(Inline::Value("bold".to_string()))
-                        },
-                        Inline::Value(" text".to_string()),
-                    ]
-                },
-                Block::BlankBlock,
-                Block::Paragraph {
-                    children: vec![
-                        Inline::Value("This is a ".to_string()),
-                        Inline::Italic {
-                            children: Box::new(Inline::Value("italic".to_string()))
-                        },
-                        Inline::Value(" text".to_string()),
-                    ]
-                },
-                Block::BlankBlock,
-                Block::Paragraph {
-                    children: vec![
-                        Inline::Value("This is a ".to_string()),
-                        Inline::Monospace {
-                            children: Box::new(Inline::Value("monospace".to_string()))
-                        },
-                        Inline::Value(" text".to_string()),
-                    ]
-                },
-                Block::BlankBlock,
-                Block::Paragraph {
-                    children: vec![
-                        Inline::Value("This is a ".to_string()),
-                        Inline::Marker {
-                            children: Box::new(Inline::Value("marker".to_string()))
-                        },
-                        Inline::Value(" text".to_string()),
-                    ]
-                },
-                Block::BlankBlock,
-                Block::Paragraph {
-                    children: vec![
-                        Inline::Value("This is a ".to_string()),
-                        Inline::InlineCode {
-                            children: Box::new(Inline::Value("inline code".to_string()))
-                        },
-                        Inline::Value(" text".to_string()),
-                    ]
-                },
-                Block::BlankBlock,
-                Block::Paragraph {
-                    children: vec![
-                        Inline::Value("wrap break ".to_string()),
-                        Inline::Value("*".to_string()),
-                        Inline::SoftBreak,
-                        Inline::Value("a".to_string())
-                    ]
-                },
-                Block::BlankBlock,
-                Block::UnorderdList {
-                    children: vec![
-                        ListItem::Normal {
-                            children: vec![Inline::Value("foo".to_string())],
-                            level: 1
-                        },
-                        ListItem::Normal {
-                            children: vec![Inline::Value("bar".to_string())],
-                            level: 1
+// Annotations are written once all files are checked, a file that fails to parse is an error
+// annotation at its start.
+fn check(
+    files: &[PathBuf],
+    format: CheckFormat,
+    output: &OutputArgs,
+    fail_at: Severity,
+) -> Result<bool> {
+    let inputs = if files.is_empty() {
+        vec![None]
+    } else {
+        files.iter().map(|file| stdio(file)).collect()
+    };
+
+    let color = io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none();
+    let mut ok = true;
+    let mut found = vec![];
+    for path in inputs {
+        let name = path.map_or("<stdin>".to_string(), |path| path.display().to_string());
+        let source = read_input(path)?;
+        // Diagnostics point into the preprocessed source, the snippets are taken from it.
+        match Document::parse_with_source(&source, &parse_options(path)?) {
+            Ok((document, source)) => {
+                for diagnostic in document.diagnostics() {
+                    match format {
+                        CheckFormat::Text => {
+                            eprint!("{}", diagnostic.snippet(&name, &source, color))
                         }
-                    ]
-                },
-                Block::BlankBlock,
-                Block::OrderdList {
-                    children: vec![
-                        ListItem::Normal {
-                            children: vec![Inline::Value("foo".to_string())],
-                            level: 1
-                        },
-                        ListItem::Normal {
-                            children: vec![Inline::Value("bar".to_string())],
-                            level: 1
-                        }
-                    ]
-                },
-                Block::HorizontalRuledLine,
-                Block::BlankBlock,
-            ]
-        )
-    }
-    #[test]
-    fn test_inline() {
-        let actual = inline().parse(" aadf").map(take_parse_result);
-        assert_eq!(actual, Ok(Inline::Value(" aadf".to_string())));
-    }
-
-    #[test]
-    fn test_block() {
-        let actual = block()
-            .parse("=== Head\n\nHelloWorld")
-            .map(take_parse_result);
-        assert_eq!(
-            actual,
-            Ok(Block::Heading {
-                level: HeadingLevel::Level2,
-                id: None,
-                children: vec![Inline::Value("Head".to_string())]
-            })
-        );
-    }
-
-    #[test]
-    fn test_parse_heading() {
-        let (actual, _) = heading_block().parse("= Heading").unwrap();
-        assert_eq!(
-            actual,
-            Block::Heading {
-                level: HeadingLevel::Title,
-                children: vec![Inline::Value("Heading".to_string())],
-                id: None
-            }
-        );
-
-        let (actual, _) = heading_block().parse("== Heading").unwrap();
-        assert_eq!(
-            actual,
-            Block::Heading {
-                level: HeadingLevel::Level1,
-                children: vec![Inline::Value("Heading".to_string())],
-                id: None
-            }
-        );
-
-        let (actual, _) = heading_block().parse("=== Heading").unwrap();
-        assert_eq!(
-            actual,
-            Block::Heading {
-                level: HeadingLevel::Level2,
-                children: vec![Inline::Value("Heading".to_string())],
-                id: None
-            }
-        );
-
-        let (actual, _) = heading_block().parse("==== Heading").unwrap();
-        assert_eq!(
-            actual,
-            Block::Heading {
-                level: HeadingLevel::Level3,
-                children: vec![Inline::Value("Heading".to_string())],
-                id: None
-            }
-        );
-
-        let (actual, _) = heading_block().parse("===== Heading").unwrap();
-        assert_eq!(
-            actual,
-            Block::Heading {
-                level: HeadingLevel::Level4,
-                children: vec![Inline::Value("Heading".to_string())],
-                id: None
+                        _ => found.push(Annotation::from_diagnostic(
+                            name.as_str(),
+                            &source,
+                            diagnostic,
+                            "parse",
+                        )),
+                    }
+                    ok &= diagnostic.severity < fail_at;
+                }
             }
-        );
-
-        let (actual, _) = heading_block().parse("====== Heading").unwrap();
-        assert_eq!(
-            actual,
-            Block::Paragraph {
-                children: vec![
-                    Inline::Value("====== ".to_string()),
-                    Inline::Value("Heading".to_string())
-                ],
+            Err(error) => {
+                match format {
+                    CheckFormat::Text => eprintln!("{}: error: {:#}", name, error),
+                    _ => found.push(Annotation {
+                        path: name,
+                        line: 1,
+                        column: 1,
+                        severity: Severity::Error,
+                        message: format!("{:#}", error),
+                        check: "parse".to_string(),
+                    }),
+                }
+                ok = false;
             }
-        );
+        }
     }
-
-    #[test]
-    fn test_bold() {
-        let actual = bold().parse("*人間*").map(take_parse_result);
-        assert_eq!(
-            actual,
-            Ok(Inline::Bold {
-                children: Box::new(Inline::Value("人間".to_string()))
-            })
-        );
+    match format {
+        CheckFormat::Text => {}
+        CheckFormat::Github => output.write(&annotations::to_github(&found))?,
+        CheckFormat::Gitlab => output.write(&format!(
+            "{}\n",
+            serde_json::to_string_pretty(&annotations::to_gitlab(&found))?
+        ))?,
     }
+    Ok(ok)
+}
 
-    #[test]
-    fn test_italic() {
-        let actual = italic().parse("_人間_").map(take_parse_result);
-        assert_eq!(
-            actual,
-            Ok(Inline::Italic {
-                children: Box::new(Inline::Value("人間".to_string()))
-            })
-        );
+fn lint(
+    files: &[PathBuf],
+    rules: &[String],
+    format: LintFormat,
+    output: &OutputArgs,
+    fail_at: Severity,
+) -> Result<bool> {
+    let inputs = if files.is_empty() {
+        vec![None]
+    } else {
+        files.iter().map(|file| stdio(file)).collect()
+    };
+
+    let mut reports = vec![];
+    for path in inputs {
+        let source = read_input(path)?;
+        // Rules given on the command line win over the config file.
+        let mut config = load_config(path)?.lint;
+        for rule in rules {
+            config = config.set(rule)?;
+        }
+        reports.push(LintReport {
+            path: path.map_or("<stdin>".to_string(), |path| path.display().to_string()),
+            lints: lint_source(&source, &config),
+        });
     }
 
-    #[test]
-    fn test_marker() {
-        let actual = marker().parse("#人間#").map(take_parse_result);
-        assert_eq!(
-            actual,
-            Ok(Inline::Marker {
-                children: Box::new(Inline::Value("人間".to_string()))
+    let text = match format {
+        LintFormat::Text if output.color() => reports
+            .iter()
+            .flat_map(|report| {
+                report.lints.iter().map(move |lint| {
+                    let severity = match lint.diagnostic.severity {
+                        Severity::Info => "1;36",
+                        Severity::Warning => "1;33",
+                        Severity::Error => "1;31",
+                    };
+                    format!(
+                        "\x1b[1m{}:{}:{}:\x1b[0m \x1b[{}m{}\x1b[0m: {} \x1b[2m[{}]\x1b[0m\n",
+                        report.path,
+                        lint.line,
+                        lint.column,
+                        severity,
+                        lint.diagnostic.severity,
+                        lint.diagnostic.message,
+                        lint.rule
+                    )
+                })
             })
-        );
-    }
-
-    #[test]
-    fn test_inline_code() {
-        let actual = inline_code().parse(r"```npm```").map(take_parse_result);
-        assert_eq!(
-            actual,
-            Ok(Inline::InlineCode {
-                children: Box::new(Inline::Value("npm".to_string()))
+            .collect(),
+        LintFormat::Text => reports
+            .iter()
+            .flat_map(|report| {
+                report
+                    .lints
+                    .iter()
+                    .map(move |lint| format!("{}:{}\n", report.path, lint))
             })
-        );
+            .collect(),
+        LintFormat::Json => format!(
+            "{}\n",
+            serde_json::to_string_pretty(&lint::to_json(&reports))?
+        ),
+        LintFormat::Sarif => format!(
+            "{}\n",
+            serde_json::to_string_pretty(&lint::to_sarif(&reports))?
+        ),
+        LintFormat::Github => {
+            let annotations = reports.iter().flat_map(LintReport::annotations);
+            annotations::to_github(&annotations.collect::<Vec<_>>())
+        }
+        LintFormat::Gitlab => {
+            let annotations = reports.iter().flat_map(LintReport::annotations);
+            format!(
+                "{}\n",
+                serde_json::to_string_pretty(&annotations::to_gitlab(
+                    &annotations.collect::<Vec<_>>()
+                ))?
+            )
+        }
+    };
+    output.write(&text)?;
+    Ok(reports
+        .iter()
+        .flat_map(|report| &report.lints)
+        .all(|lint| lint.diagnostic.severity < fail_at))
+}
 
-        let actual = inline_code().parse(r"`npm`").map(take_parse_result);
-        assert_eq!(
-            actual,
-            Err(combine::error::StringStreamError::UnexpectedParse)
-        );
+// `width` overrides the `output.width` of each file's config.
+fn fmt(files: &[PathBuf], check: bool, width: Option<usize>) -> Result<bool> {
+    let options = |path: Option<&Path>| -> Result<FormatOptions> {
+        let options = FormatOptions::new();
+        Ok(match width.or(load_config(path)?.output.width) {
+            Some(width) => options.width(width),
+            None => options,
+        })
+    };
+    let inputs = if files.is_empty() {
+        vec![None]
+    } else {
+        files.iter().map(|file| stdio(file)).collect()
+    };
+
+    let mut ok = true;
+    for path in inputs {
+        let source = read_input(path)?;
+        let name = path.map_or("<stdin>".to_string(), |path| path.display().to_string());
+        let formatted = format(&source, &options(path)?)
+            .with_context(|| format!("failed to format {}", name))?;
+        match path {
+            // stdin is formatted to stdout, or only compared with --check.
+            None if check => ok &= formatted == source,
+            None => OutputArgs { output: None }.write(&formatted)?,
+            Some(_) if formatted == source => {}
+            Some(_) if check => {
+                println!("{}", name);
+                ok = false;
+            }
+            Some(path) => {
+                fs::write(path, formatted).with_context(|| format!("failed to write {}", name))?
+            }
+        }
     }
+    Ok(ok)
+}
 
-    #[test]
-    fn test_monospace() {
-        let actual = monospace().parse("`人間`").map(take_parse_result);
-        assert_eq!(
-            actual,
-            Ok(Inline::Monospace {
-                children: Box::new(Inline::Value("人間".to_string()))
-            })
-        );
+// Files matching the glob patterns, in pattern order. `-` or no patterns means stdin.
+fn expand_patterns(patterns: &[String]) -> Result<Vec<Option<PathBuf>>> {
+    let mut paths = vec![];
+    for pattern in patterns {
+        if pattern == "-" {
+            paths.push(None);
+            continue;
+        }
+        let matches = glob::glob(pattern)
+            .with_context(|| format!("invalid pattern {}", pattern))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        if matches.is_empty() {
+            bail!("no files match {}", pattern);
+        }
+        paths.extend(matches.into_iter().map(Some));
     }
-
-    #[test]
-    fn test_value() {
-        let actual = value().parse("人間").map(take_parse_result);
-        assert_eq!(actual, Ok(Inline::Value("人間".to_string())));
+    if paths.is_empty() {
+        paths.push(None);
     }
+    Ok(paths)
+}
 
-    #[test]
-    fn test_line_break() {
-        let actual = line_break().parse("\n").map(take_parse_result);
-        assert_eq!(actual, Ok(Inline::SoftBreak));
-
-        let actual = line_break().parse("\n\n").is_err();
-        assert_eq!(actual, true);
-
-        let actual = line_break().parse(" +\n").map(take_parse_result);
-        assert_eq!(actual, Ok(Inline::HardBreak));
+// Exits with 1 when nothing matched, like grep.
+fn query(
+    selector: &Selector,
+    patterns: &[String],
+    format: ReportFormat,
+    output: &OutputArgs,
+) -> Result<bool> {
+    let mut matches = vec![];
+    for path in expand_patterns(patterns)? {
+        let source = read_input(path.as_deref())?;
+        let name = path.map_or("<stdin>".to_string(), |path| path.display().to_string());
+        for found in query_source(&source, selector) {
+            matches.push((name.clone(), found));
+        }
     }
 
-    #[test]
-    fn test_paragraph() {
-        let actual = paragraph_block()
-            .parse("人間 *a* 人間")
-            .map(take_parse_result);
-        assert_eq!(
-            actual,
-            Ok(Block::Paragraph {
-                children: vec![
-                    Inline::Value("人間 ".to_string()),
-                    Inline::Bold {
-                        children: Box::new(Inline::Value("a".to_string()))
-                    },
-                    Inline::Value(" 人間".to_string())
-                ]
-            })
-        );
-
-        let actual = paragraph_block().parse("人間 ").map(take_parse_result);
-        assert_eq!(
-            actual,
-            Ok(Block::Paragraph {
-                children: vec![Inline::Value("人間 ".to_string()),]
-            })
-        );
-        let actual = paragraph_block().parse("人間").map(take_parse_result);
-        assert_eq!(
-            actual,
-            Ok(Block::Paragraph {
-                children: vec![Inline::Value("人間".to_string())]
-            })
-        );
+    let text = match format {
+        ReportFormat::Text => matches
+            .iter()
+            .map(|(name, found)| format!("{}:{}\n", name, found))
+            .collect(),
+        ReportFormat::Json => {
+            let matches = matches
+                .iter()
+                .map(|(name, found)| {
+                    json!({
+                        "path": name,
+                        "line": found.line,
+                        "kind": format!("{:?}", found.kind),
+                        "text": found.text,
+                    })
+                })
+                .collect::<Vec<_>>();
+            format!("{}\n", serde_json::to_string_pretty(&matches)?)
+        }
+    };
+    output.write(&text)?;
+    Ok(!matches.is_empty())
+}
 
-        let actual = paragraph_block().parse("人間\n").map(take_parse_result);
-        assert_eq!(
-            actual,
-            Ok(Block::Paragraph {
-                children: vec![Inline::Value("人間".to_string()), Inline::SoftBreak]
-            })
-        );
+fn stats(patterns: &[String], words_per_minute: usize, format: ReportFormat) -> Result<String> {
+    let mut rows = vec![];
+    for path in expand_patterns(patterns)? {
+        let path = path.as_deref();
+        let source = read_input(path)?;
+        let document = Document::parse_with_options(&source, &parse_options(path)?)?;
+        let name = path.map_or("<stdin>".to_string(), |path| path.display().to_string());
+        rows.push((name, Metrics::of(&document)));
     }
-
-    #[test]
-    fn test_horizontal_ruled_line_block() {
-        let actual = horizontal_ruled_line_block()
-            .parse("<<<")
-            .map(take_parse_result);
-        assert_eq!(actual, Ok(Block::HorizontalRuledLine));
-
-        let actual = horizontal_ruled_line_block()
-            .parse("<<")
-            .map(take_parse_result);
-        assert_eq!(actual, Err(StringStreamError::Eoi));
+    if rows.len() > 1 {
+        let total = rows
+            .iter()
+            .fold(Metrics::default(), |total, (_, metrics)| total + *metrics);
+        rows.push(("total".to_string(), total));
     }
 
-    #[test]
-    fn test_unordered_list() {
-        let blocks = "* abc
-* def";
-
-        let actual = unordered_list_block().parse(blocks).map(take_parse_result);
-        assert_eq!(
-            actual,
-            Ok(Block::UnorderdList {
-                children: vec![
-                    ListItem::Normal {
-                        level: 1,
-                        children: vec![Inline::Value("abc".to_string())]
-                    },
-                    ListItem::Normal {
-                        level: 1,
-                        children: vec![Inline::Value("def".to_string())]
-                    }
-                ]
-            })
-        );
-
-        let blocks = "* [x] abc";
+    let minutes = |metrics: &Metrics| metrics.reading_time(words_per_minute).as_secs() / 60;
+    Ok(match format {
+        ReportFormat::Text => {
+            let width = rows
+                .iter()
+                .map(|(name, _)| name.chars().count())
+                .max()
+                .unwrap_or(0);
+            let mut out = format!(
+                "{:width$} {:>8} {:>10} {:>8} {:>5} {:>7}\n",
+                "file",
+                "words",
+                "characters",
+                "headings",
+                "code",
+                "minutes",
+                width = width
+            );
+            for (name, metrics) in &rows {
+                out.push_str(&format!(
+                    "{:width$} {:>8} {:>10} {:>8} {:>5} {:>7}\n",
+                    name,
+                    metrics.words,
+                    metrics.characters,
+                    metrics.headings,
+                    metrics.code_blocks,
+                    minutes(metrics),
+                    width = width
+                ));
+            }
+            out
+        }
+        ReportFormat::Json => {
+            let rows = rows
+                .iter()
+                .map(|(name, metrics)| {
+                    json!({
+                        "file": name,
+                        "words": metrics.words,
+                        "characters": metrics.characters,
+                        "headings": metrics.headings,
+                        "code_blocks": metrics.code_blocks,
+                        "lists": metrics.lists,
+                        "tables": metrics.tables,
+                        "reading_minutes": minutes(metrics),
+                    })
+                })
+                .collect::<Vec<_>>();
+            format!("{}\n", serde_json::to_string_pretty(&rows)?)
+        }
+    })
+}
 
-        let actual = unordered_list_block().parse(blocks).map(take_parse_result);
-        assert_eq!(
-            actual,
-            Ok(Block::UnorderdList {
-                children: vec![ListItem::Check {
-                    level: 1,
-                    children: vec![Inline::Value("abc".to_string())],
-                    checked: true
-                },]
+// Subcommands, arguments and their values, recursively.
+fn help_json(command: &mut clap::Command) -> serde_json::Value {
+    command.build();
+    let arguments = command
+        .get_arguments()
+        .filter(|argument| !argument.is_hide_set())
+        .map(|argument| {
+            json!({
+                "name": argument.get_id().as_str(),
+                "long": argument.get_long(),
+                "short": argument.get_short(),
+                "help": argument.get_help().map(ToString::to_string),
+                "positional": argument.is_positional(),
+                "required": argument.is_required_set(),
+                "takes_value": argument.get_action().takes_values(),
+                "multiple": matches!(argument.get_action(), ArgAction::Append)
+                    || argument
+                        .get_num_args()
+                        .is_some_and(|range| range.max_values() > 1),
+                "possible_values": argument
+                    .get_possible_values()
+                    .iter()
+                    .map(|value| value.get_name().to_string())
+                    .collect::<Vec<_>>(),
+                "default_values": argument
+                    .get_default_values()
+                    .iter()
+                    .map(|value| value.to_string_lossy().into_owned())
+                    .collect::<Vec<_>>(),
             })
-        );
-    }
-
-    #[test]
-    fn test_ordered_list() {
-        let blocks = ". abc
-. def";
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "name": command.get_name(),
+        "about": command.get_about().map(ToString::to_string),
+        "arguments": arguments,
+        "subcommands": command
+            .get_subcommands_mut()
+            .filter(|subcommand| subcommand.get_name() != "help")
+            .map(help_json)
+            .collect::<Vec<_>>(),
+    })
+}
 
-        let actual = ordered_list_block().parse(blocks).map(take_parse_result);
-        assert_eq!(
-            actual,
-            Ok(Block::OrderdList {
-                children: vec![
-                    ListItem::Normal {
-                        level: 1,
-                        children: vec![Inline::Value("abc".to_string())]
-                    },
-                    ListItem::Normal {
-                        level: 1,
-                        children: vec![Inline::Value("def".to_string())]
-                    }
-                ]
-            })
-        )
+fn toc_text(entries: &[OutlineEntry]) -> String {
+    let mut out = String::new();
+    for entry in entries {
+        out.push_str(&"  ".repeat(entry.level.saturating_sub(1) as usize));
+        out.push_str("- ");
+        out.push_str(&entry.title_text);
+        if let Some(id) = &entry.id {
+            out.push_str(&format!(" (#{})", id));
+        }
+        out.push('\n');
+        out.push_str(&toc_text(&entry.children));
     }
+    out
+}
 
-    #[test]
-    fn test_ordered_list_item() {
-        let actual = list_item('.')
-            .parse(". foobar *foo* bar _foo_")
-            .map(take_parse_result);
-        assert_eq!(
-            actual,
-            Ok(ListItem::Normal {
-                level: 1,
-                children: vec![
-                    Inline::Value("foobar ".to_string()),
-                    Inline::Bold {
-                        children: Box::new(Inline::Value("foo".to_string()))
-                    },
-                    Inline::Value(" bar ".to_string()),
-                    Inline::Italic {
-                        children: Box::new(Inline::Value("foo".to_string()))
-                    },
-                ]
+fn toc_json(entries: &[OutlineEntry]) -> serde_json::Value {
+    entries
+        .iter()
+        .map(|entry| {
+            json!({
+                "title": entry.title_text,
+                "id": entry.id,
+                "level": entry.level,
+                "children": toc_json(&entry.children),
             })
-        );
-
-        let actual = list_item('.').parse(". foobar\na").map(take_parse_result);
-        assert_eq!(
-            actual,
-            Ok(ListItem::Normal {
-                level: 1,
-                children: vec![Inline::Value("foobar".to_string()),]
-            })
-        );
+        })
+        .collect()
+}
 
-        let actual = list_item('.').parse(".. foobar\na").map(take_parse_result);
-        assert_eq!(
-            actual,
-            Ok(ListItem::Normal {
-                level: 2,
-                children: vec![Inline::Value("foobar".to_string()),]
-            })
-        );
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
 
     #[test]
-    fn test_position_atteributes() {
-        let expect_atteributes = vec!["foo".to_string()];
-
-        let actual = position_attributes()
-            .parse(r"[foo]")
-            .map(take_parse_result);
-        assert_eq!(actual, Ok(Attributes::Position(expect_atteributes)))
+    fn test_toc_text() {
+        let document = Document::parse("= Title\n\n== A\n\n=== A-1\n\n== B").unwrap();
+        assert_eq!(toc_text(&document.outline(None)), "- A\n  - A-1\n- B\n");
     }
 
     #[test]
-    fn test_position_atteributes_when_multiple() {
-        let expect_atteributes = vec!["foo".to_string(), "bar".to_string()];
-
-        let actual = position_attributes()
-            .parse(r"[foo,bar]")
-            .map(take_parse_result);
-        assert_eq!(actual, Ok(Attributes::Position(expect_atteributes)))
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+        let cli = Cli::try_parse_from(["combine-sandbox", "check", "--strict", "a.adoc"]).unwrap();
+        assert_eq!(cli.strict, true);
     }
 
     #[test]
-    fn test_named_atteributes() {
-        let mut expect_atteributes = HashMap::new();
-        expect_atteributes.insert("foo".to_string(), "bar".to_string());
-
-        let actual = named_atteributes()
-            .parse(r"[foo=bar]")
-            .map(take_parse_result);
-        assert_eq!(actual, Ok(Attributes::Named(expect_atteributes)))
+    fn test_stdio() {
+        assert_eq!(stdio(Path::new("-")), None);
+        assert_eq!(stdio(Path::new("a.adoc")), Some(Path::new("a.adoc")));
+        assert_eq!(
+            expand_patterns(&["-".to_string()]).unwrap(),
+            vec![None::<PathBuf>]
+        );
     }
 
     #[test]
-    fn test_named_atteributes_when_multiple() {
-        let mut expect_atteributes = HashMap::new();
-        expect_atteributes.insert("foo".to_string(), "bar".to_string());
-        expect_atteributes.insert("poe".to_string(), "fuga".to_string());
-
-        let actual = named_atteributes()
-            .parse(r"[foo=bar, poe=fuga]")
-            .map(take_parse_result);
-        assert_eq!(actual, Ok(Attributes::Named(expect_atteributes)))
+    fn test_help_json() {
+        let help = help_json(&mut Cli::command());
+        let lint = help["subcommands"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|subcommand| subcommand["name"] == "lint")
+            .unwrap();
+        let format = lint["arguments"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|argument| argument["name"] == "format")
+            .unwrap();
+        assert_eq!(format["short"], "f");
+        assert_eq!(format["default_values"], json!(["text"]));
+        assert_eq!(format["possible_values"][0], "text");
     }
 }
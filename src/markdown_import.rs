@@ -0,0 +1,435 @@
+use crate::document::Document;
+use crate::text::inline_text;
+use crate::{
+    AttrList, Block, DelimitedKind, FootnoteType, HeadingLevel, Inline, InlineVec, ListItem,
+    ListLevel, TableCell, TableColumn, TableRow,
+};
+use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel as MarkdownLevel, Options, Parser, Tag};
+use std::collections::HashMap;
+use std::iter::Peekable;
+
+// Read CommonMark with the GFM tables, task lists, footnotes and strikethrough into this crate's
+// AST, the reverse of `to_markdown`. With `to_asciidoc` this converts Markdown to AsciiDoc.
+// Constructs the AST can't hold are simplified: block quotes become open blocks, nested lists
+// take the type of the outer list and raw HTML is kept as text.
+pub fn from_markdown(source: &str) -> Document {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_MATH
+        | Options::ENABLE_HEADING_ATTRIBUTES;
+    let mut importer = Importer {
+        footnotes: footnote_definitions(Parser::new_ext(source, options)),
+    };
+    let mut events = Parser::new_ext(source, options).peekable();
+    Document::new(importer.blocks(&mut events))
+}
+
+type Events<'a> = Peekable<Parser<'a>>;
+
+struct Importer {
+    // Footnote text by label, references may come before their definition.
+    footnotes: HashMap<String, String>,
+}
+
+impl Importer {
+    // Blocks up to the end of the enclosing container, which is consumed.
+    fn blocks(&mut self, events: &mut Events) -> Vec<Block> {
+        let mut blocks = vec![];
+        while let Some(event) = events.next() {
+            let block = match event {
+                Event::End(_) => break,
+                Event::Start(Tag::Paragraph) => Block::Paragraph {
+                    children: self.inlines(events).into(),
+                },
+                Event::Start(Tag::Heading { level, id, .. }) => Block::Heading {
+                    level: heading_level(level),
+                    children: self.inlines(events),
+                    id: id.map(|id| id.to_string()),
+                },
+                Event::Start(Tag::BlockQuote(_)) => {
+                    let children = self.blocks(events);
+                    Block::Block {
+                        kind: DelimitedKind::Quote,
+                        metas: children.iter().map(|_| None).collect(),
+                        children,
+                        title: None,
+                    }
+                }
+                Event::Start(Tag::CodeBlock(kind)) => {
+                    let file_type = match kind {
+                        CodeBlockKind::Fenced(info) => info
+                            .split_whitespace()
+                            .next()
+                            .map(|language| language.into()),
+                        CodeBlockKind::Indented => None,
+                    };
+                    Block::CodeBlock {
+                        children: std::iter::once(Inline::Value(text(events))).collect(),
+                        title: None,
+                        file_type,
+                    }
+                }
+                Event::Start(Tag::List(start)) => {
+                    let mut items = vec![];
+                    self.list(events, 1, &mut items);
+                    match start {
+                        Some(_) => Block::OrderdList { children: items },
+                        None => Block::UnorderdList { children: items },
+                    }
+                }
+                Event::Start(Tag::Table(_)) => self.table(events),
+                Event::Start(Tag::FootnoteDefinition(_)) => {
+                    self.blocks(events);
+                    continue;
+                }
+                Event::Start(Tag::HtmlBlock) => Block::Paragraph {
+                    children: std::iter::once(Inline::Value(text(events).trim_end().to_string()))
+                        .collect::<InlineVec>()
+                        .into(),
+                },
+                Event::Start(Tag::MetadataBlock(_)) => {
+                    text(events);
+                    continue;
+                }
+                Event::Start(_) => {
+                    // Containers without a block of their own, their content is kept.
+                    blocks.extend(self.blocks(events));
+                    continue;
+                }
+                Event::Rule => Block::HorizontalRuledLine,
+                event => {
+                    // Inline content outside of a paragraph, e.g. display math.
+                    let mut children = InlineVec::new();
+                    self.inline(event, events, &mut children);
+                    Block::Paragraph {
+                        children: children.into(),
+                    }
+                }
+            };
+            blocks.push(block);
+        }
+        blocks
+    }
+
+    // Items of the list whose start was just read. A nested list adds its items after the item
+    // it belongs to, one level deeper.
+    fn list(&mut self, events: &mut Events, level: u32, items: &mut Vec<ListItem>) {
+        while let Some(event) = events.next() {
+            match event {
+                Event::Start(Tag::Item) => {}
+                _ => return,
+            }
+            let index = items.len();
+            let mut children = InlineVec::new();
+            let mut checked = None;
+            while let Some(event) = events.next() {
+                match event {
+                    Event::End(_) => break,
+                    Event::TaskListMarker(marker) => checked = Some(marker),
+                    Event::Start(Tag::List(_)) => self.list(events, level + 1, items),
+                    Event::Start(Tag::Paragraph) => {
+                        if !children.is_empty() {
+                            children.push(Inline::SoftBreak);
+                        }
+                        children.extend(self.inlines(events));
+                    }
+                    Event::Start(_) => {
+                        for block in self.blocks(events) {
+                            children.extend(into_inlines(block));
+                        }
+                    }
+                    event => self.inline(event, events, &mut children),
+                }
+            }
+            let level = ListLevel::from_depth(level);
+            let item = match checked {
+                Some(checked) => ListItem::Check {
+                    children,
+                    level,
+                    checked,
+                },
+                None => ListItem::Normal { children, level },
+            };
+            items.insert(index, item);
+        }
+    }
+
+    fn table(&mut self, events: &mut Events) -> Block {
+        let mut columns = vec![];
+        let mut rows = vec![];
+        while let Some(event) = events.next() {
+            match event {
+                Event::End(_) => break,
+                Event::Start(Tag::TableHead) => {
+                    columns = self
+                        .cells(events)
+                        .iter()
+                        .map(|cell| TableColumn {
+                            name: inline_text(cell),
+                        })
+                        .collect();
+                }
+                Event::Start(Tag::TableRow) => {
+                    let cells = self
+                        .cells(events)
+                        .into_iter()
+                        .map(|children| {
+                            TableCell::new(vec![Block::Paragraph {
+                                children: children.into(),
+                            }])
+                        })
+                        .collect();
+                    rows.push(TableRow::new(cells));
+                }
+                _ => {}
+            }
+        }
+        Block::Table {
+            columns,
+            rows,
+            title: None,
+        }
+    }
+
+    // Cells of a table head or row, up to its end.
+    fn cells(&mut self, events: &mut Events) -> Vec<InlineVec> {
+        let mut cells = vec![];
+        while let Some(event) = events.next() {
+            match event {
+                Event::Start(Tag::TableCell) => cells.push(self.inlines(events)),
+                _ => break,
+            }
+        }
+        cells
+    }
+
+    // Inlines up to the end of the enclosing tag, which is consumed.
+    fn inlines(&mut self, events: &mut Events) -> InlineVec {
+        let mut inlines = InlineVec::new();
+        while let Some(event) = events.next() {
+            if let Event::End(_) = event {
+                break;
+            }
+            self.inline(event, events, &mut inlines);
+        }
+        merge_text(inlines)
+    }
+
+    fn inline(&mut self, event: Event, events: &mut Events, out: &mut InlineVec) {
+        match event {
+            Event::Text(text) | Event::Html(text) | Event::InlineHtml(text) => {
+                out.push(Inline::Value(text.to_string()))
+            }
+            Event::Code(code) => out.push(Inline::Monospace {
+                children: Box::new(Inline::Value(code.to_string())),
+            }),
+            Event::InlineMath(math) | Event::DisplayMath(math) => out.push(Inline::Macro {
+                attributes: Box::new(AttrList::from(vec![math.to_string()])),
+                kind: "latexmath".into(),
+                id: String::new(),
+            }),
+            Event::SoftBreak => out.push(Inline::SoftBreak),
+            Event::HardBreak => out.push(Inline::HardBreak),
+            Event::FootnoteReference(label) => {
+                let text = self.footnotes.get(label.as_ref()).cloned();
+                out.push(Inline::Footnote {
+                    kind: FootnoteType::Note,
+                    children: Box::new(Inline::Value(text.unwrap_or_else(|| label.to_string()))),
+                });
+            }
+            Event::TaskListMarker(checked) => out.push(Inline::Value(
+                if checked { "[x] " } else { "[ ] " }.to_string(),
+            )),
+            Event::Start(tag) => {
+                let children = self.inlines(events);
+                match tag {
+                    // The AST wraps a single inline, formatting is applied to each child.
+                    Tag::Strong => out.extend(children.into_iter().map(|child| Inline::Bold {
+                        children: Box::new(child),
+                    })),
+                    Tag::Emphasis => out.extend(children.into_iter().map(|child| Inline::Italic {
+                        children: Box::new(child),
+                    })),
+                    Tag::Link { dest_url, .. } => {
+                        let text = inline_text(&children);
+                        let (kind, id) = match dest_url.split_once(':') {
+                            Some((scheme, rest))
+                                if ["http", "https", "mailto"].contains(&scheme) =>
+                            {
+                                (scheme.to_string(), rest.to_string())
+                            }
+                            _ => ("link".to_string(), dest_url.to_string()),
+                        };
+                        let attributes = if text.is_empty() || text == *dest_url {
+                            vec![]
+                        } else {
+                            vec![text]
+                        };
+                        out.push(Inline::Macro {
+                            attributes: Box::new(AttrList::from(attributes)),
+                            kind: kind.as_str().into(),
+                            id,
+                        });
+                    }
+                    Tag::Image { dest_url, .. } => out.push(Inline::Macro {
+                        attributes: Box::new(AttrList::from(vec![inline_text(&children)])),
+                        kind: "image".into(),
+                        id: dest_url.to_string(),
+                    }),
+                    _ => out.extend(children),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn heading_level(level: MarkdownLevel) -> HeadingLevel {
+    match level {
+        MarkdownLevel::H1 => HeadingLevel::Title,
+        MarkdownLevel::H2 => HeadingLevel::Level1,
+        MarkdownLevel::H3 => HeadingLevel::Level2,
+        MarkdownLevel::H4 => HeadingLevel::Level3,
+        MarkdownLevel::H5 | MarkdownLevel::H6 => HeadingLevel::Level4,
+    }
+}
+
+// Plain text up to the end of the enclosing tag, which is consumed.
+fn text(events: &mut Events) -> String {
+    let mut text = String::new();
+    for event in events.by_ref() {
+        match event {
+            Event::End(_) => break,
+            Event::Text(s) | Event::Html(s) | Event::Code(s) => text.push_str(&s),
+            _ => {}
+        }
+    }
+    text
+}
+
+fn footnote_definitions(parser: Parser) -> HashMap<String, String> {
+    let mut definitions = HashMap::new();
+    let mut current: Option<(String, String)> = None;
+    for event in parser {
+        match event {
+            Event::Start(Tag::FootnoteDefinition(label)) => {
+                current = Some((label.to_string(), String::new()))
+            }
+            Event::End(pulldown_cmark::TagEnd::FootnoteDefinition) => {
+                if let Some((label, text)) = current.take() {
+                    definitions.insert(label, text.trim().to_string());
+                }
+            }
+            Event::Text(s) | Event::Code(s) => {
+                if let Some((_, text)) = &mut current {
+                    text.push_str(&s);
+                }
+            }
+            Event::SoftBreak | Event::HardBreak => {
+                if let Some((_, text)) = &mut current {
+                    text.push(' ');
+                }
+            }
+            _ => {}
+        }
+    }
+    definitions
+}
+
+// Markdown splits text at characters that could have started markup.
+fn merge_text(inlines: InlineVec) -> InlineVec {
+    let mut merged = InlineVec::new();
+    for inline in inlines {
+        match (merged.last_mut(), inline) {
+            (Some(Inline::Value(last)), Inline::Value(value)) => last.push_str(&value),
+            (_, inline) => merged.push(inline),
+        }
+    }
+    merged
+}
+
+// Inline content of a block nested where only inlines fit, list items are put on lines of
+// their own.
+fn into_inlines(block: Block) -> InlineVec {
+    match block {
+        Block::Paragraph { children } => children.into_inner(),
+        Block::Heading { children, .. }
+        | Block::CodeBlock { children, .. }
+        | Block::Label { children, .. }
+        | Block::Qanda {
+            question: children, ..
+        } => children,
+        Block::Block { children, .. } => {
+            let mut inlines = InlineVec::new();
+            for block in children {
+                if !inlines.is_empty() {
+                    inlines.push(Inline::SoftBreak);
+                }
+                inlines.extend(into_inlines(block));
+            }
+            inlines
+        }
+        Block::UnorderdList { children } | Block::OrderdList { children } => {
+            let mut inlines = InlineVec::new();
+            for item in children {
+                if !inlines.is_empty() {
+                    inlines.push(Inline::SoftBreak);
+                }
+                match item {
+                    ListItem::Normal { children, .. } | ListItem::Check { children, .. } => {
+                        inlines.extend(children)
+                    }
+                }
+            }
+            inlines
+        }
+        block => std::iter::once(Inline::Value(crate::text::block_text(&block))).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::asciidoc::to_asciidoc;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_from_markdown() {
+        let document = from_markdown(
+            "# Title\n\n## Section\n\nSome **bold** and `code`, see [docs](https://example.com/docs).\n\n- a\n  - b\n- [x] done\n\n1. one\n2. two\n",
+        );
+        assert_eq!(
+            to_asciidoc(&document),
+            "= Title
+
+== Section
+
+Some *bold* and `code`, see https://example.com/docs[docs].
+
+* a
+** b
+* [x] done
+
+. one
+. two
+"
+        );
+    }
+
+    #[test]
+    fn test_code_and_footnotes() {
+        let document = from_markdown("Note[^1]\n\n```rust\nfn main() {}\n```\n\n[^1]: Details.\n");
+        assert_eq!(
+            to_asciidoc(&document),
+            "Notefootnote:[Details.]
+
+[source,rust]
+----
+fn main() {}
+----
+"
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use crate::document::Document;
+use crate::text::{block_text, cell_text};
+use crate::{Block, TableRow};
+use std::ops::{Add, AddAssign};
+use std::time::Duration;
+
+// Typical silent reading speed for technical prose.
+pub const WORDS_PER_MINUTE: usize = 200;
+
+// Size of a document's prose. Code is counted as blocks, its content is not read as words.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Metrics {
+    pub words: usize,
+    // Characters of the text, whitespace excluded.
+    pub characters: usize,
+    pub headings: usize,
+    pub code_blocks: usize,
+    pub lists: usize,
+    pub tables: usize,
+}
+
+impl Metrics {
+    pub fn of(document: &Document) -> Self {
+        let mut metrics = Metrics::default();
+        for block in document.blocks() {
+            metrics.add_block(block);
+        }
+        metrics
+    }
+
+    fn add_block(&mut self, block: &Block) {
+        match block {
+            Block::Heading { .. } => self.headings += 1,
+            Block::CodeBlock { .. } => {
+                self.code_blocks += 1;
+                return;
+            }
+            Block::UnorderdList { .. } | Block::OrderdList { .. } => self.lists += 1,
+            Block::Table { rows, .. } => {
+                self.tables += 1;
+                for cell in rows.iter().flat_map(TableRow::cells) {
+                    self.add_text(&cell_text(cell));
+                }
+                return;
+            }
+            _ => {}
+        }
+        self.add_text(&block_text(block));
+    }
+
+    fn add_text(&mut self, text: &str) {
+        self.words += text.split_whitespace().count();
+        self.characters += text.chars().filter(|c| !c.is_whitespace()).count();
+    }
+
+    // Rounded up to whole minutes, so any text takes at least one.
+    pub fn reading_time(&self, words_per_minute: usize) -> Duration {
+        let minutes = self.words.div_ceil(words_per_minute.max(1));
+        Duration::from_secs(minutes as u64 * 60)
+    }
+}
+
+impl Add for Metrics {
+    type Output = Metrics;
+
+    fn add(mut self, other: Metrics) -> Metrics {
+        self += other;
+        self
+    }
+}
+
+impl AddAssign for Metrics {
+    fn add_assign(&mut self, other: Metrics) {
+        self.words += other.words;
+        self.characters += other.characters;
+        self.headings += other.headings;
+        self.code_blocks += other.code_blocks;
+        self.lists += other.lists;
+        self.tables += other.tables;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Inline;
+    use pretty_assertions::assert_eq;
+    use smallvec::smallvec;
+
+    #[test]
+    fn test_metrics() {
+        let mut document =
+            Document::parse("= Title\n\n== Intro\n\nSome *bold* words here.\n\n* one item")
+                .unwrap();
+        document.append(Document::new(vec![Block::CodeBlock {
+            children: smallvec![Inline::Value("let x = 1;".to_string())],
+            title: None,
+            file_type: Some("rust".into()),
+        }]));
+        let metrics = Metrics::of(&document);
+
+        assert_eq!(
+            metrics,
+            Metrics {
+                words: 8,
+                characters: 35,
+                headings: 2,
+                code_blocks: 1,
+                lists: 1,
+                tables: 0,
+            }
+        );
+        assert_eq!(metrics.reading_time(WORDS_PER_MINUTE).as_secs(), 60);
+        assert_eq!((metrics + metrics).words, 16);
+    }
+}
@@ -0,0 +1,157 @@
+use crate::include::IncludeResolver;
+use crate::recovery::RecoveryPolicy;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+// What a run of blank lines between blocks parses to.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub enum BlankLines {
+    // A single `BlankBlock`, however long the run.
+    #[default]
+    Collapse,
+    // A `BlankBlock` for every blank line, for tools that write the source back.
+    Preserve,
+}
+
+// What a heading with more marks than there are section levels, `====== Title`, parses to.
+// The path taken is reported as a diagnostic of the document.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub enum HeadingOverflow {
+    // A paragraph like in lenient mode, reported as an error.
+    Strict,
+    // A heading of the deepest level.
+    Compat,
+    // A paragraph keeping the marks as text.
+    #[default]
+    Lenient,
+}
+
+impl HeadingOverflow {
+    pub fn from_name(name: &str) -> Option<HeadingOverflow> {
+        match name {
+            "strict" => Some(HeadingOverflow::Strict),
+            "compat" => Some(HeadingOverflow::Compat),
+            "lenient" => Some(HeadingOverflow::Lenient),
+            _ => None,
+        }
+    }
+}
+
+// Computes the value of a dynamic attribute each time it is referenced.
+pub type AttributeHook = Arc<dyn Fn() -> String + Send + Sync>;
+
+// Columns between tab stops when indentation is expanded.
+pub const DEFAULT_TAB_SIZE: usize = 4;
+
+#[derive(Clone)]
+pub struct ParseOptions {
+    // Include directives are left untouched when no resolver is set.
+    pub include_resolver: Option<Arc<dyn IncludeResolver>>,
+    // Attributes supplied by the caller. They win over entries defined in the document.
+    pub attributes: BTreeMap<String, String>,
+    pub attribute_hooks: HashMap<String, AttributeHook>,
+    // Parse leniently, collecting diagnostics instead of failing on the first invalid block.
+    pub recovery: Option<RecoveryPolicy>,
+    // Keep the text of paragraphs and parse their inlines on first access. For tools that only
+    // look at the block structure.
+    pub lazy_inlines: bool,
+    pub blank_lines: BlankLines,
+    // Tabs of indented lines (list continuations, literal paragraphs) become spaces up to the
+    // next multiple of this column. 0 keeps them.
+    pub tab_size: usize,
+    // Compose the text to Unicode NFC, so the same words typed in different editors compare
+    // equal and give the same anchors. Needs the `nfc` feature.
+    pub nfc: bool,
+    // Read ```` ```code``` ```` as inline code like earlier versions of the parser did, for
+    // documents written for them. AsciiDoc spells it `` `+code+` ``.
+    pub triple_backtick_code: bool,
+    pub heading_overflow: HeadingOverflow,
+    // Parse the level 1 sections of large sources on all cores. The result is the same.
+    #[cfg(feature = "parallel")]
+    pub parallel: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            include_resolver: None,
+            attributes: BTreeMap::new(),
+            attribute_hooks: HashMap::new(),
+            recovery: None,
+            lazy_inlines: false,
+            blank_lines: BlankLines::default(),
+            tab_size: DEFAULT_TAB_SIZE,
+            nfc: false,
+            triple_backtick_code: false,
+            heading_overflow: HeadingOverflow::default(),
+            #[cfg(feature = "parallel")]
+            parallel: false,
+        }
+    }
+}
+
+impl ParseOptions {
+    pub fn new() -> Self {
+        ParseOptions::default()
+    }
+
+    pub fn include_resolver<R: IncludeResolver + 'static>(mut self, resolver: R) -> Self {
+        self.include_resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    pub fn attribute<K: Into<String>, V: Into<String>>(mut self, name: K, value: V) -> Self {
+        self.attributes.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn recovery(mut self, policy: RecoveryPolicy) -> Self {
+        self.recovery = Some(policy);
+        self
+    }
+
+    pub fn lazy_inlines(mut self, lazy_inlines: bool) -> Self {
+        self.lazy_inlines = lazy_inlines;
+        self
+    }
+
+    pub fn blank_lines(mut self, blank_lines: BlankLines) -> Self {
+        self.blank_lines = blank_lines;
+        self
+    }
+
+    pub fn tab_size(mut self, tab_size: usize) -> Self {
+        self.tab_size = tab_size;
+        self
+    }
+
+    pub fn nfc(mut self, nfc: bool) -> Self {
+        self.nfc = nfc;
+        self
+    }
+
+    pub fn triple_backtick_code(mut self, triple_backtick_code: bool) -> Self {
+        self.triple_backtick_code = triple_backtick_code;
+        self
+    }
+
+    pub fn heading_overflow(mut self, heading_overflow: HeadingOverflow) -> Self {
+        self.heading_overflow = heading_overflow;
+        self
+    }
+
+    #[cfg(feature = "parallel")]
+    pub fn parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    pub fn attribute_hook<K, F>(mut self, name: K, hook: F) -> Self
+    where
+        K: Into<String>,
+        F: Fn() -> String + Send + Sync + 'static,
+    {
+        self.attribute_hooks.insert(name.into(), Arc::new(hook));
+        self
+    }
+}
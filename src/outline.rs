@@ -0,0 +1,87 @@
+use crate::text::inline_text;
+use crate::{Block, HeadingLevel};
+
+// Used when neither the caller nor the `toclevels` attribute give a depth.
+pub const DEFAULT_TOC_LEVELS: u8 = 2;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct OutlineEntry {
+    pub title_text: String,
+    pub id: Option<String>,
+    pub level: u8,
+    pub children: Vec<OutlineEntry>,
+}
+
+// Nest the section headings of `blocks` up to `max_depth`. The document title is not part of
+// the outline.
+pub fn outline(blocks: &[Block], max_depth: u8) -> Vec<OutlineEntry> {
+    let mut roots: Vec<OutlineEntry> = vec![];
+    for block in blocks {
+        let (level, children, id) = match block {
+            Block::Heading {
+                level,
+                children,
+                id,
+            } if *level != HeadingLevel::Title && level.as_u8() <= max_depth => {
+                (level.as_u8(), children, id)
+            }
+            _ => continue,
+        };
+        let entry = OutlineEntry {
+            title_text: inline_text(children),
+            id: id.clone(),
+            level,
+            children: vec![],
+        };
+
+        let mut siblings = &mut roots;
+        while siblings.last().is_some_and(|last| last.level < level) {
+            siblings = &mut siblings.last_mut().unwrap().children;
+        }
+        siblings.push(entry);
+    }
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use pretty_assertions::assert_eq;
+
+    fn entry(title_text: &str, level: u8, children: Vec<OutlineEntry>) -> OutlineEntry {
+        OutlineEntry {
+            title_text: title_text.to_string(),
+            id: None,
+            level,
+            children,
+        }
+    }
+
+    #[test]
+    fn test_outline() {
+        let blocks = parse("= Title\n\n== A\n\n=== A-1\n\n==== A-1-a\n\n== B\n\n=== B-1").unwrap();
+
+        assert_eq!(
+            outline(&blocks, 2),
+            vec![
+                entry("A", 1, vec![entry("A-1", 2, vec![])]),
+                entry("B", 1, vec![entry("B-1", 2, vec![])]),
+            ]
+        );
+        assert_eq!(
+            outline(&blocks, 1),
+            vec![entry("A", 1, vec![]), entry("B", 1, vec![])]
+        );
+    }
+
+    #[test]
+    fn test_outline_with_skipped_level() {
+        let blocks = parse("=== Deep\n\n== Shallow").unwrap();
+
+        assert_eq!(
+            outline(&blocks, 4),
+            vec![entry("Deep", 2, vec![]), entry("Shallow", 1, vec![])]
+        );
+    }
+}
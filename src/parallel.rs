@@ -0,0 +1,172 @@
+use crate::diagnostic::Diagnostic;
+use crate::lines::LineIndex;
+use crate::options::ParseOptions;
+use crate::{Block, ParsedBlock, Span};
+use anyhow::Result;
+use std::ops::Range;
+
+// Sources shorter than this are parsed in one piece, splitting them costs more than it saves.
+const MIN_PIECE_LEN: usize = 32 * 1024;
+
+pub(crate) type Parsed = (Vec<ParsedBlock>, Vec<Diagnostic>);
+
+// `f` over `items` on all cores with the `parallel` feature, one after the other without.
+// Results keep the order of `items`.
+#[cfg(feature = "parallel")]
+pub(crate) fn map<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync + Send,
+{
+    use rayon::prelude::*;
+    items.par_iter().map(f).collect()
+}
+
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn map<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    F: Fn(&T) -> R,
+{
+    items.iter().map(f).collect()
+}
+
+#[cfg(feature = "parallel")]
+fn enabled(options: &ParseOptions) -> bool {
+    options.parallel
+}
+
+#[cfg(not(feature = "parallel"))]
+fn enabled(_options: &ParseOptions) -> bool {
+    false
+}
+
+// Blocks of `s` parsed by `parse`, split into sections parsed in parallel when the options
+// ask for it. The result is the one `parse(s)` gives.
+pub(crate) fn parse<F>(s: &str, options: &ParseOptions, parse: F) -> Result<Parsed>
+where
+    F: Fn(&str) -> Result<Parsed> + Sync + Send,
+{
+    if !enabled(options) {
+        return parse(s);
+    }
+    parse_pieces(s, &pieces(s, MIN_PIECE_LEN), parse)
+}
+
+// Ranges of `s` that start at a level 1 section title after a blank line, each at least
+// `min_len` long except the last.
+fn pieces(s: &str, min_len: usize) -> Vec<Range<usize>> {
+    let lines = LineIndex::new(s);
+    let mut pieces = vec![];
+    let mut start = 0;
+    for line in 1..lines.line_count() {
+        let boundary = lines.start(line);
+        if boundary - start >= min_len
+            && lines.line(line - 1).is_empty()
+            && lines.line(line).starts_with("== ")
+        {
+            pieces.push(start..boundary);
+            start = boundary;
+        }
+    }
+    pieces.push(start..s.len());
+    pieces
+}
+
+// No block spans a blank line, so a piece ending with one parses to the blocks the whole source
+// has there. A piece that doesn't, or that needed recovery, which may resume further on, is
+// parsed again together with the next one.
+fn parse_pieces<F>(s: &str, pieces: &[Range<usize>], parse: F) -> Result<Parsed>
+where
+    F: Fn(&str) -> Result<Parsed> + Sync + Send,
+{
+    let mut results = map(pieces, |piece| Some(parse(&s[piece.clone()])));
+    let mut blocks = vec![];
+    let mut diagnostics = vec![];
+    let mut index = 0;
+    while index < pieces.len() {
+        let start = pieces[index].start;
+        let mut result = results[index].take().unwrap();
+        while index + 1 < pieces.len() && !ends_cleanly(&result, pieces[index].end - start) {
+            index += 1;
+            result = parse(&s[start..pieces[index].end]);
+        }
+        let (parsed, found) = result?;
+        let shift = |span: Span| Span {
+            start: start + span.start,
+            end: start + span.end,
+        };
+        blocks.extend(parsed.into_iter().map(|parsed| ParsedBlock {
+            span: shift(parsed.span),
+            ..parsed
+        }));
+        diagnostics.extend(found.into_iter().map(|diagnostic| Diagnostic {
+            span: shift(diagnostic.span),
+            ..diagnostic
+        }));
+        index += 1;
+    }
+    Ok((blocks, diagnostics))
+}
+
+fn ends_cleanly(result: &Result<Parsed>, len: usize) -> bool {
+    match result {
+        Ok((blocks, diagnostics)) => {
+            diagnostics.is_empty()
+                && blocks
+                    .last()
+                    .is_some_and(|last| last.block == Block::BlankBlock && last.span.end == len)
+        }
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recovery::{parse_lenient, RecoveryPolicy};
+    use crate::{parse_spanned, BlockOptions};
+    use pretty_assertions::assert_eq;
+
+    fn summary(parsed: Parsed) -> (Vec<(Block, Span)>, Vec<Span>) {
+        let (blocks, diagnostics) = parsed;
+        (
+            blocks
+                .into_iter()
+                .map(|parsed| (parsed.block, parsed.span))
+                .collect(),
+            diagnostics
+                .into_iter()
+                .map(|diagnostic| diagnostic.span)
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_parse_pieces() {
+        let source = "= Book\n\nIntro\n\n== One\n\nText\n\n* item\n\n== Two\n\n=broken\n\n== Three\n\n*bold* end\n";
+        let ranges = pieces(source, 0);
+        assert_eq!(
+            ranges
+                .iter()
+                .map(|piece| &source[piece.clone()][..6])
+                .collect::<Vec<_>>(),
+            vec!["= Book", "== One", "== Two", "== Thr"]
+        );
+
+        let strict = |s: &str| Ok((parse_spanned(s, BlockOptions::default())?, vec![]));
+        assert_eq!(strict(source).is_err(), true);
+        assert_eq!(parse_pieces(source, &ranges, strict).is_err(), true);
+        let valid = source.replace("=broken", "Para");
+        assert_eq!(
+            summary(parse_pieces(&valid, &pieces(&valid, 0), strict).unwrap()),
+            summary(strict(&valid).unwrap())
+        );
+        let policy = RecoveryPolicy::default();
+        let lenient = |s: &str| Ok(parse_lenient(s, &policy, BlockOptions::default()));
+        assert_eq!(
+            summary(parse_pieces(source, &ranges, lenient).unwrap()),
+            summary(lenient(source).unwrap())
+        );
+    }
+}
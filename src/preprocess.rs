@@ -0,0 +1,565 @@
+use crate::options::ParseOptions;
+use crate::{attribute_list, AttrList, BOM};
+use anyhow::{bail, Result};
+use combine::error::ParseError;
+use combine::parser::char::{spaces, string};
+use combine::stream::Range;
+use combine::*;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+
+// Same limit as asciidoctor's default `max-include-depth`.
+const MAX_INCLUDE_DEPTH: usize = 64;
+
+#[derive(Debug, PartialEq, Eq)]
+struct IncludeDirective {
+    target: String,
+    attributes: AttrList,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum AttributeEntry {
+    Set(String, String),
+    Unset(String),
+}
+
+#[derive(Debug, PartialEq, Eq, Default)]
+pub(crate) struct Preprocessed {
+    pub source: String,
+    pub attributes: BTreeMap<String, String>,
+}
+
+pub(crate) fn preprocess(s: &str, options: &ParseOptions) -> Result<Preprocessed> {
+    let mut leveloffset = options
+        .attributes
+        .get(LEVELOFFSET)
+        .map_or(0, |value| level_offset(value, 0));
+    let expanded = expand_includes(strip_bom(s), options, 0, &mut leveloffset)?;
+    let expanded = expand_tabs(&expanded, options.tab_size);
+    let normalized = if options.nfc {
+        to_nfc(&expanded)?
+    } else {
+        Cow::Borrowed(&*expanded)
+    };
+    Ok(substitute_attributes(&normalized, options))
+}
+
+fn strip_bom(s: &str) -> &str {
+    s.strip_prefix(BOM).unwrap_or(s)
+}
+
+#[cfg(feature = "nfc")]
+fn to_nfc(s: &str) -> Result<Cow<'_, str>> {
+    use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+
+    Ok(match is_nfc_quick(s.chars()) {
+        IsNormalized::Yes => Cow::Borrowed(s),
+        _ => Cow::Owned(s.nfc().collect()),
+    })
+}
+
+#[cfg(not(feature = "nfc"))]
+fn to_nfc(_s: &str) -> Result<Cow<'_, str>> {
+    bail!("NFC normalization needs the `nfc` feature")
+}
+
+// Tabs of lines starting with whitespace, the indentation of list continuations and literal
+// paragraphs, become spaces up to the next tab stop. All tabs of such a line are expanded so a
+// literal paragraph keeps its columns. Other lines are left as written.
+fn expand_tabs(s: &str, tab_size: usize) -> Cow<'_, str> {
+    if tab_size == 0 || !s.contains('\t') {
+        return Cow::Borrowed(s);
+    }
+    let mut output = String::with_capacity(s.len());
+    for line in s.split_inclusive('\n') {
+        if !line.starts_with([' ', '\t']) {
+            output.push_str(line);
+            continue;
+        }
+        let mut column = 0;
+        for c in line.chars() {
+            if c == '\t' {
+                let width = tab_size - column % tab_size;
+                output.extend(std::iter::repeat_n(' ', width));
+                column += width;
+            } else {
+                output.push(c);
+                column += 1;
+            }
+        }
+    }
+    Cow::Owned(output)
+}
+
+// Expands the include directives of `s` and moves its section titles by `leveloffset`, which
+// `:leveloffset:` entries change on the way. Titles of an include with a `leveloffset` attribute
+// move by that offset, the one of the including file is back in effect after it.
+fn expand_includes(
+    s: &str,
+    options: &ParseOptions,
+    depth: usize,
+    leveloffset: &mut i32,
+) -> Result<String> {
+    if depth > MAX_INCLUDE_DEPTH {
+        bail!("maximum include depth of {} exceeded", MAX_INCLUDE_DEPTH);
+    }
+
+    let mut output = String::with_capacity(s.len());
+    // Lines of listing, literal and passthrough blocks aren't titles.
+    let mut delimiter: Option<&str> = None;
+    for line in s.split_inclusive('\n') {
+        let content = line.trim_end_matches('\n');
+        let verbatim = match delimiter {
+            Some(open) => {
+                if content == open {
+                    delimiter = None;
+                }
+                true
+            }
+            None if matches!(content, "----" | "...." | "++++") => {
+                delimiter = Some(content);
+                true
+            }
+            None => false,
+        };
+        if !verbatim && !options.attributes.contains_key(LEVELOFFSET) {
+            match attribute_entry().parse(content) {
+                Ok((AttributeEntry::Set(name, value), "")) if name == LEVELOFFSET => {
+                    *leveloffset = level_offset(&value, *leveloffset);
+                }
+                Ok((AttributeEntry::Unset(name), "")) if name == LEVELOFFSET => *leveloffset = 0,
+                _ => {}
+            }
+        }
+        match (
+            include_directive().parse(content),
+            &options.include_resolver,
+        ) {
+            (Ok((directive, "")), Some(resolver)) => {
+                let included = resolver.resolve(&directive.target)?;
+                let included = strip_bom(&included);
+                let mut offset = *leveloffset;
+                if let Some(value) = directive.attributes.get(LEVELOFFSET) {
+                    offset = level_offset(value, offset);
+                }
+                output.push_str(&expand_includes(included, options, depth + 1, &mut offset)?);
+                // Entries of the included file stay in effect like other attributes do.
+                if directive.attributes.get(LEVELOFFSET).is_none() {
+                    *leveloffset = offset;
+                }
+                if line.ends_with('\n') && !included.ends_with('\n') {
+                    output.push('\n');
+                }
+            }
+            _ if !verbatim => output.push_str(&shift_title(line, *leveloffset)),
+            _ => output.push_str(line),
+        }
+    }
+    Ok(output)
+}
+
+const LEVELOFFSET: &str = "leveloffset";
+
+// Offset set by a `leveloffset` value, relative to `current` when it has a sign.
+fn level_offset(value: &str, current: i32) -> i32 {
+    let value = value.trim();
+    let relative = value.starts_with(['+', '-']);
+    match value.trim_start_matches('+').parse::<i32>() {
+        Ok(offset) if relative => current + offset,
+        Ok(offset) => offset,
+        Err(_) => 0,
+    }
+}
+
+// `line` with `offset` more marks when it's a section title, and as many closing marks when it
+// has them. A title keeps at least one mark, deeper ones than the grammar reads are left to
+// `ParseOptions::heading_overflow`.
+fn shift_title(line: &str, offset: i32) -> Cow<'_, str> {
+    let marks = line.len() - line.trim_start_matches('=').len();
+    if offset == 0 || marks == 0 || !line[marks..].starts_with(' ') {
+        return Cow::Borrowed(line);
+    }
+    let shifted = "=".repeat((marks as i32 + offset).max(1) as usize);
+    let content = line.trim_end_matches('\n');
+    let title = match content.trim_end_matches(' ').strip_suffix(&line[..marks]) {
+        Some(rest) if rest.ends_with(' ') && !rest[marks..].trim().is_empty() => {
+            format!("{}{}", &rest[marks..], shifted)
+        }
+        _ => content[marks..].to_string(),
+    };
+    Cow::Owned(format!("{}{}{}", shifted, title, &line[content.len()..]))
+}
+
+// Applies the attribute entries of `s` and replaces references to them. Lines of listing,
+// literal and passthrough blocks are verbatim and kept as written.
+fn substitute_attributes(s: &str, options: &ParseOptions) -> Preprocessed {
+    let mut preprocessed = Preprocessed {
+        source: String::with_capacity(s.len()),
+        attributes: options.attributes.clone(),
+    };
+
+    let mut delimiter: Option<&str> = None;
+    for line in s.split_inclusive('\n') {
+        let content = line.trim_end_matches('\n');
+        let verbatim = match delimiter {
+            Some(open) => {
+                if content == open {
+                    delimiter = None;
+                }
+                true
+            }
+            None if matches!(content, "----" | "...." | "++++") => {
+                delimiter = Some(content);
+                true
+            }
+            None => false,
+        };
+        if verbatim {
+            preprocessed.source.push_str(line);
+            continue;
+        }
+        match attribute_entry().parse(content) {
+            Ok((entry, "")) => {
+                let attributes = &mut preprocessed.attributes;
+                match entry {
+                    AttributeEntry::Set(name, _) | AttributeEntry::Unset(name)
+                        if options.attributes.contains_key(&name) => {}
+                    AttributeEntry::Set(name, value) => {
+                        let value = replace_references(&value, attributes, options);
+                        attributes.insert(name, value);
+                    }
+                    AttributeEntry::Unset(name) => {
+                        attributes.remove(&name);
+                    }
+                }
+            }
+            _ => {
+                let replaced = replace_references(line, &preprocessed.attributes, options);
+                preprocessed.source.push_str(&replaced);
+            }
+        }
+    }
+    preprocessed
+}
+
+fn replace_references(
+    s: &str,
+    attributes: &BTreeMap<String, String>,
+    options: &ParseOptions,
+) -> String {
+    let mut output = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(start) = rest.find('{') {
+        let escaped = rest[..start].ends_with('\\');
+        output.push_str(&rest[..if escaped { start - 1 } else { start }]);
+        rest = &rest[start..];
+
+        let reference = rest[1..]
+            .find('}')
+            .map(|end| &rest[1..end + 1])
+            .filter(|name| !name.is_empty() && name.chars().all(is_attribute_name_char));
+        let name = match reference {
+            Some(name) => name,
+            None => {
+                output.push('{');
+                rest = &rest[1..];
+                continue;
+            }
+        };
+
+        let value = if escaped {
+            None
+        } else if let Some(hook) = options.attribute_hooks.get(name) {
+            Some(hook())
+        } else {
+            attributes.get(name).cloned()
+        };
+        match value {
+            Some(value) => output.push_str(&value),
+            None => output.push_str(&rest[..name.len() + 2]),
+        }
+        rest = &rest[name.len() + 2..];
+    }
+    output.push_str(rest);
+    output
+}
+
+fn is_attribute_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+fn attribute_entry<Input>() -> impl Parser<Input, Output = AttributeEntry>
+where
+    Input: Stream<Token = char>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    let name = || many1::<String, _, _>(satisfy(is_attribute_name_char));
+    let unset = (token(':'), name(), string("!:")).map(|(_, name, _)| AttributeEntry::Unset(name));
+    let set = (
+        token(':'),
+        name(),
+        token(':'),
+        spaces(),
+        many::<String, _, _>(satisfy(|c| c != '\n')),
+    )
+        .map(|(_, name, _, _, value)| AttributeEntry::Set(name, value.trim_end().to_string()));
+
+    choice!(attempt(unset), set)
+}
+
+#[derive(Debug, PartialEq, Eq, Default)]
+pub(crate) struct AttributeUsage {
+    // Values at the end of the document.
+    pub attributes: BTreeMap<String, String>,
+    // Every name set anywhere, including the options' attributes and hooks.
+    pub defined: BTreeSet<String>,
+    // Byte offset and name of references to attributes not set at that point.
+    pub unresolved: Vec<(usize, String)>,
+}
+
+// Attribute entries and references of `s` without expanding includes. References in listing,
+// literal and passthrough blocks are verbatim and not counted.
+pub(crate) fn attribute_usage(s: &str, options: &ParseOptions) -> AttributeUsage {
+    let mut usage = AttributeUsage {
+        attributes: options.attributes.clone(),
+        defined: options
+            .attributes
+            .keys()
+            .chain(options.attribute_hooks.keys())
+            .cloned()
+            .collect(),
+        unresolved: vec![],
+    };
+    let mut delimiter: Option<&str> = None;
+    let mut offset = 0;
+    for line in s.split_inclusive('\n') {
+        let content = line.trim_end_matches('\n');
+        match delimiter {
+            Some(open) if content == open => delimiter = None,
+            Some(_) => {}
+            None if matches!(content, "----" | "...." | "++++") => delimiter = Some(content),
+            None => match attribute_entry().parse(content) {
+                Ok((AttributeEntry::Set(name, value), "")) => {
+                    let value = replace_references(&value, &usage.attributes, options);
+                    usage.defined.insert(name.clone());
+                    usage.attributes.insert(name, value);
+                }
+                Ok((AttributeEntry::Unset(name), "")) => {
+                    usage.attributes.remove(&name);
+                }
+                _ => {
+                    let mut rest = content;
+                    while let Some(start) = rest.find('{') {
+                        let escaped = rest[..start].ends_with('\\');
+                        let reference = rest[start + 1..]
+                            .find('}')
+                            .map(|end| &rest[start + 1..start + 1 + end])
+                            .filter(|name| {
+                                !name.is_empty() && name.chars().all(is_attribute_name_char)
+                            });
+                        if let Some(name) = reference.filter(|_| !escaped) {
+                            if !usage.attributes.contains_key(name)
+                                && !options.attribute_hooks.contains_key(name)
+                            {
+                                let position = offset + content.len() - rest.len() + start;
+                                usage.unresolved.push((position, name.to_string()));
+                            }
+                        }
+                        rest = &rest[start + 1..];
+                    }
+                }
+            },
+        }
+        offset += line.len();
+    }
+    usage
+}
+
+// Byte offset of the line and target of each include directive in `s`, without resolving them.
+pub(crate) fn include_targets(s: &str) -> Vec<(usize, String)> {
+    let mut targets = vec![];
+    let mut offset = 0;
+    for line in s.split_inclusive('\n') {
+        if let Ok((directive, "")) = include_directive().parse(line.trim_end_matches('\n')) {
+            targets.push((offset, directive.target));
+        }
+        offset += line.len();
+    }
+    targets
+}
+
+fn include_directive<Input>() -> impl Parser<Input, Output = IncludeDirective>
+where
+    Input: RangeStream<Token = char>,
+    Input::Range: Range + AsRef<str>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    (
+        string("include::"),
+        many1::<String, _, _>(satisfy(|c| c != '[' && c != '\n')),
+        attempt(string("[]").map(|_| AttrList::new())).or(attribute_list()),
+    )
+        .map(|(_, target, attributes)| IncludeDirective { target, attributes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::include::InMemoryResolver;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_include_directive() {
+        let actual = include_directive()
+            .parse("include::chapter.adoc[]")
+            .map(|t| t.0);
+        assert_eq!(
+            actual,
+            Ok(IncludeDirective {
+                target: "chapter.adoc".to_string(),
+                attributes: AttrList::new()
+            })
+        );
+        let actual = include_directive()
+            .parse("include::chapter.adoc[leveloffset=+1]")
+            .map(|t| t.0.attributes.get(LEVELOFFSET).map(str::to_string));
+        assert_eq!(actual, Ok(Some("+1".to_string())));
+    }
+
+    #[test]
+    fn test_expand_includes() {
+        let mut resolver = InMemoryResolver::new();
+        resolver.insert("a.adoc", "== A\ninclude::b.adoc[]");
+        resolver.insert("b.adoc", "from b");
+        let options = ParseOptions::new().include_resolver(resolver);
+
+        let actual = preprocess("= Book\ninclude::a.adoc[]\nend", &options).unwrap();
+        assert_eq!(actual.source, "= Book\n== A\nfrom b\nend");
+    }
+
+    #[test]
+    fn test_leveloffset() {
+        let mut resolver = InMemoryResolver::new();
+        resolver.insert("a.adoc", "= A\n\n== A.1 ==\n----\n= code\n----\n");
+        resolver.insert("b.adoc", ":leveloffset: -1\n== B\n");
+        let options = ParseOptions::new().include_resolver(resolver);
+
+        let source = "= Book\n\ninclude::a.adoc[leveloffset=+1]\n== Back\n:leveloffset: 1\ninclude::b.adoc[]\n= Last\n:leveloffset!:\n= Title\n";
+        let actual = preprocess(source, &options).unwrap();
+        assert_eq!(
+            actual.source,
+            "= Book\n\n== A\n\n=== A.1 ===\n----\n= code\n----\n== Back\n== B\n= Last\n= Title\n"
+        );
+
+        let options = ParseOptions::new().attribute(LEVELOFFSET, "2");
+        let actual = preprocess(":leveloffset: 0\n= Title\n", &options).unwrap();
+        assert_eq!(actual.source, "=== Title\n");
+    }
+
+    #[test]
+    fn test_expand_tabs() {
+        let source = "* item\n\tcontinued\n\n  a\tb\n\tab\tc\nkeep\ttab";
+        let actual = preprocess(source, &ParseOptions::new()).unwrap();
+        assert_eq!(
+            actual.source,
+            "* item\n    continued\n\n  a b\n    ab  c\nkeep\ttab"
+        );
+        let actual = preprocess(source, &ParseOptions::new().tab_size(0)).unwrap();
+        assert_eq!(actual.source, source);
+    }
+
+    #[test]
+    fn test_strip_bom() {
+        let mut resolver = InMemoryResolver::new();
+        resolver.insert("a.adoc", "\u{feff}from a\n");
+        let options = ParseOptions::new().include_resolver(resolver);
+
+        let actual = preprocess("\u{feff}:title: T\ninclude::a.adoc[]\n", &options).unwrap();
+        assert_eq!(actual.source, "from a\n");
+        assert_eq!(actual.attributes["title"], "T");
+    }
+
+    #[test]
+    fn test_nfc() {
+        // "é" as `e` and a combining acute accent.
+        let options = ParseOptions::new().nfc(true);
+        let actual = preprocess("Caf\u{65}\u{301}", &options);
+        #[cfg(feature = "nfc")]
+        assert_eq!(actual.unwrap().source, "Caf\u{e9}");
+        #[cfg(not(feature = "nfc"))]
+        assert_eq!(actual.is_err(), true);
+    }
+
+    #[test]
+    fn test_expand_includes_without_resolver() {
+        let actual = preprocess("include::a.adoc[]", &ParseOptions::new()).unwrap();
+        assert_eq!(actual.source, "include::a.adoc[]");
+    }
+
+    #[test]
+    fn test_attribute_entry() {
+        let actual = attribute_entry().parse(":author: himanoa").map(|t| t.0);
+        assert_eq!(
+            actual,
+            Ok(AttributeEntry::Set(
+                "author".to_string(),
+                "himanoa".to_string()
+            ))
+        );
+
+        let actual = attribute_entry().parse(":toc!:").map(|t| t.0);
+        assert_eq!(actual, Ok(AttributeEntry::Unset("toc".to_string())));
+    }
+
+    #[test]
+    fn test_substitute_attributes() {
+        let actual = preprocess(
+            ":product: combine\n:version: 4\nUse {product} {version}, not {unknown} or \\{product}",
+            &ParseOptions::new(),
+        )
+        .unwrap();
+        assert_eq!(actual.source, "Use combine 4, not {unknown} or {product}");
+        assert_eq!(
+            actual.attributes.get("product").map(String::as_str),
+            Some("combine")
+        );
+    }
+
+    #[test]
+    fn test_substitute_attributes_in_verbatim_blocks() {
+        let source = ":product: combine\n----\n:product: other\n{product}\n----\n....\n{product}\n....\n++++\n{product}\n++++\n{product}";
+        let actual = preprocess(source, &ParseOptions::new()).unwrap();
+        assert_eq!(
+            actual.source,
+            "----\n:product: other\n{product}\n----\n....\n{product}\n....\n++++\n{product}\n++++\ncombine"
+        );
+        assert_eq!(
+            actual.attributes.get("product").map(String::as_str),
+            Some("combine")
+        );
+    }
+
+    #[test]
+    fn test_options_attributes_override_document() {
+        let options = ParseOptions::new().attribute("product", "cli");
+        let actual = preprocess(":product: combine\n{product}", &options).unwrap();
+        assert_eq!(actual.source, "cli");
+    }
+
+    #[test]
+    fn test_attribute_hooks() {
+        let options = ParseOptions::new().attribute_hook("gitsha", || "abc123".to_string());
+        let actual = preprocess("built from {gitsha}", &options).unwrap();
+        assert_eq!(actual.source, "built from abc123");
+    }
+
+    #[test]
+    fn test_recursive_include_is_rejected() {
+        let mut resolver = InMemoryResolver::new();
+        resolver.insert("self.adoc", "include::self.adoc[]");
+        let options = ParseOptions::new().include_resolver(resolver);
+
+        assert_eq!(preprocess("include::self.adoc[]", &options).is_err(), true);
+    }
+}
@@ -0,0 +1,326 @@
+use crate::document::Document;
+use crate::lines::LineIndex;
+use crate::recovery::{parse_lenient, RecoveryPolicy};
+use crate::text::{block_text, inline_text};
+use crate::{Block, BlockKind, BlockMeta, BlockOptions};
+use anyhow::{bail, Result};
+use std::fmt;
+use std::str::FromStr;
+
+// Structural selector over top level blocks, e.g. `block:code[lang=rust]` or
+// `heading[level<=2]`. A selector names a block type (`*` for any) followed by any number of
+// `[key op value]` conditions, which must all hold. Commas inside brackets also separate
+// conditions.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Selector {
+    kind: Option<SelectorKind>,
+    conditions: Vec<Condition>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum SelectorKind {
+    Paragraph,
+    Heading,
+    Code,
+    List,
+    UnorderdList,
+    OrderdList,
+    Label,
+    Qanda,
+    Block,
+    Table,
+    Rule,
+    PageBreak,
+}
+
+impl SelectorKind {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "paragraph" => SelectorKind::Paragraph,
+            "heading" | "section" => SelectorKind::Heading,
+            "code" | "listing" => SelectorKind::Code,
+            "list" => SelectorKind::List,
+            "ulist" => SelectorKind::UnorderdList,
+            "olist" => SelectorKind::OrderdList,
+            "label" | "dlist" => SelectorKind::Label,
+            "qanda" => SelectorKind::Qanda,
+            "block" => SelectorKind::Block,
+            "table" => SelectorKind::Table,
+            "rule" => SelectorKind::Rule,
+            "pagebreak" => SelectorKind::PageBreak,
+            _ => return None,
+        })
+    }
+
+    fn matches(self, kind: BlockKind) -> bool {
+        match self {
+            SelectorKind::Paragraph => kind == BlockKind::Paragraph,
+            SelectorKind::Heading => kind == BlockKind::Heading,
+            SelectorKind::Code => kind == BlockKind::CodeBlock,
+            SelectorKind::List => kind == BlockKind::UnorderdList || kind == BlockKind::OrderdList,
+            SelectorKind::UnorderdList => kind == BlockKind::UnorderdList,
+            SelectorKind::OrderdList => kind == BlockKind::OrderdList,
+            SelectorKind::Label => kind == BlockKind::Label,
+            SelectorKind::Qanda => kind == BlockKind::Qanda,
+            SelectorKind::Block => kind == BlockKind::Block,
+            SelectorKind::Table => kind == BlockKind::Table,
+            SelectorKind::Rule => kind == BlockKind::HorizontalRuledLine,
+            SelectorKind::PageBreak => kind == BlockKind::NextPage,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Operator {
+    Exists,
+    Equal,
+    NotEqual,
+    Contains,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+// The earliest operator in a condition wins, the longest one when several start there.
+const OPERATORS: [(&str, Operator); 7] = [
+    ("!=", Operator::NotEqual),
+    ("*=", Operator::Contains),
+    ("<=", Operator::LessOrEqual),
+    (">=", Operator::GreaterOrEqual),
+    ("=", Operator::Equal),
+    ("<", Operator::Less),
+    (">", Operator::Greater),
+];
+
+const KEYS: [&str; 7] = ["lang", "level", "id", "role", "style", "title", "text"];
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Condition {
+    key: String,
+    operator: Operator,
+    value: String,
+}
+
+impl Condition {
+    fn parse(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let found = OPERATORS
+            .iter()
+            .filter_map(|(token, operator)| s.find(token).map(|index| (index, *token, *operator)))
+            .min_by_key(|(index, token, _)| (*index, std::cmp::Reverse(token.len())));
+        let (key, operator, value) = match found {
+            Some((index, token, operator)) => (&s[..index], operator, &s[index + token.len()..]),
+            None => (s, Operator::Exists, ""),
+        };
+        let key = key.trim();
+        if !KEYS.contains(&key) {
+            bail!(
+                "unknown key `{}` in condition `{}`, expected one of {}",
+                key,
+                s,
+                KEYS.join(", ")
+            );
+        }
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .unwrap_or(value);
+        Ok(Condition {
+            key: key.to_string(),
+            operator,
+            value: value.to_string(),
+        })
+    }
+
+    fn matches(&self, block: &Block, meta: Option<&BlockMeta>) -> bool {
+        if self.key == "role" {
+            let roles = meta.map_or(&[][..], |meta| &meta.roles[..]);
+            return match self.operator {
+                Operator::Exists => !roles.is_empty(),
+                Operator::NotEqual => !roles.iter().any(|role| *role == *self.value),
+                _ => roles.iter().any(|role| self.compare(role)),
+            };
+        }
+        let actual = match self.key.as_str() {
+            "lang" => match block {
+                Block::CodeBlock { file_type, .. } => file_type.as_deref().map(str::to_string),
+                _ => None,
+            },
+            "level" => block.heading_level().map(|level| level.as_u8().to_string()),
+            "id" => meta
+                .and_then(|meta| meta.id.clone())
+                .or_else(|| block.id().map(str::to_string)),
+            "style" => meta.and_then(|meta| meta.style.as_deref().map(str::to_string)),
+            "title" => match block {
+                Block::CodeBlock { title, .. } | Block::Table { title, .. } => title.clone(),
+                Block::Block { title, .. } => title.as_ref().map(|title| inline_text(title)),
+                _ => None,
+            },
+            _ => Some(block_text(block)),
+        };
+        match (&actual, self.operator) {
+            (None, Operator::NotEqual) => true,
+            (None, _) => false,
+            (Some(_), Operator::Exists) => true,
+            (Some(actual), _) => self.compare(actual),
+        }
+    }
+
+    // Numbers compare as numbers, anything else only supports equality and containment.
+    fn compare(&self, actual: &str) -> bool {
+        let ordering = match (actual.parse::<i64>(), self.value.parse::<i64>()) {
+            (Ok(actual), Ok(value)) => Some(actual.cmp(&value)),
+            _ => None,
+        };
+        match self.operator {
+            Operator::Exists => true,
+            Operator::Equal => actual == self.value,
+            Operator::NotEqual => actual != self.value,
+            Operator::Contains => actual.contains(&self.value),
+            Operator::Less => ordering.is_some_and(|ordering| ordering.is_lt()),
+            Operator::LessOrEqual => ordering.is_some_and(|ordering| ordering.is_le()),
+            Operator::Greater => ordering.is_some_and(|ordering| ordering.is_gt()),
+            Operator::GreaterOrEqual => ordering.is_some_and(|ordering| ordering.is_ge()),
+        }
+    }
+}
+
+impl Selector {
+    pub fn parse(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let (name, mut rest) = match s.find('[') {
+            Some(index) => (&s[..index], &s[index..]),
+            None => (s, ""),
+        };
+        let name = name.strip_prefix("block:").unwrap_or(name).trim();
+        let kind = match name {
+            "*" | "" => None,
+            name => match SelectorKind::from_name(name) {
+                Some(kind) => Some(kind),
+                None => bail!("unknown block type `{}` in selector `{}`", name, s),
+            },
+        };
+
+        let mut conditions = vec![];
+        while !rest.is_empty() {
+            let close = match (rest.strip_prefix('['), rest.find(']')) {
+                (Some(_), Some(close)) => close,
+                _ => bail!("expected `[condition]` at `{}` in selector `{}`", rest, s),
+            };
+            for condition in rest[1..close].split(',') {
+                conditions.push(Condition::parse(condition)?);
+            }
+            rest = rest[close + 1..].trim_start();
+        }
+        Ok(Selector { kind, conditions })
+    }
+
+    pub fn matches(&self, block: &Block, meta: Option<&BlockMeta>) -> bool {
+        self.kind.is_none_or(|kind| kind.matches(block.kind()))
+            && block.kind() != BlockKind::BlankBlock
+            && self
+                .conditions
+                .iter()
+                .all(|condition| condition.matches(block, meta))
+    }
+}
+
+impl FromStr for Selector {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Selector::parse(s)
+    }
+}
+
+// Indexes of the top level blocks of `document` matching `selector`.
+pub fn query(document: &Document, selector: &Selector) -> Vec<usize> {
+    document
+        .blocks()
+        .iter()
+        .enumerate()
+        .filter(|(index, block)| selector.matches(block, document.meta(*index)))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct QueryMatch {
+    pub kind: BlockKind,
+    pub line: usize,
+    // First line of the block's text.
+    pub text: String,
+}
+
+impl fmt::Display for QueryMatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.line, self.text)
+    }
+}
+
+// Like `query`, with the source lines of the matches. The source is not preprocessed so the
+// lines are the ones of the file.
+pub fn query_source(source: &str, selector: &Selector) -> Vec<QueryMatch> {
+    let (blocks, _) = parse_lenient(source, &RecoveryPolicy::default(), BlockOptions::default());
+    let document = Document::from_spanned(blocks);
+    let lines = LineIndex::new(source);
+    query(&document, selector)
+        .into_iter()
+        .map(|index| {
+            let block = &document.blocks()[index];
+            let offset = document.span(index).map_or(0, |span| span.start);
+            QueryMatch {
+                kind: block.kind(),
+                line: lines.line_of(offset) + 1,
+                text: block_text(block).lines().next().unwrap_or("").to_string(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use smallvec::smallvec;
+
+    #[test]
+    fn test_selector() {
+        let selector = Selector::parse("block:code[lang=rust]").unwrap();
+        let code = |file_type: &str| Block::CodeBlock {
+            children: smallvec![],
+            title: None,
+            file_type: Some(file_type.into()),
+        };
+        assert_eq!(selector.matches(&code("rust"), None), true);
+        assert_eq!(selector.matches(&code("ruby"), None), false);
+
+        let heading = |source: &str| Document::parse(source).unwrap().into_blocks().remove(0);
+        let selector = Selector::parse("heading[level>=1][level<=2]").unwrap();
+        assert_eq!(selector.matches(&heading("= Title"), None), false);
+        assert_eq!(selector.matches(&heading("=== Section"), None), true);
+        assert_eq!(selector.matches(&heading("==== Deep"), None), false);
+        assert_eq!(
+            Selector::parse("*[text*=Sec]")
+                .unwrap()
+                .matches(&heading("== Section"), None),
+            true
+        );
+
+        assert_eq!(Selector::parse("heading[size=2]").is_err(), true);
+        assert_eq!(Selector::parse("chapter").is_err(), true);
+        assert_eq!(Selector::parse("heading[level=2").is_err(), true);
+    }
+
+    #[test]
+    fn test_query_source() {
+        let source = "= Title\n\n== Install\n\nrun it\n\n=== Details\n\nmore";
+        let matches = query_source(source, &Selector::parse("heading[level<=2]").unwrap());
+        assert_eq!(
+            matches.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec!["1: Title", "3: Install", "7: Details"]
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use crate::options::ParseOptions;
+use crate::{is_delimiter, parse_spanned, Block, BlockOptions, OpenDelimiters, ParsedBlock};
+use anyhow::Result;
+use std::collections::VecDeque;
+use std::io::BufRead;
+
+// Blocks of a document read from `reader`, parsed as they are read. Only the text since the
+// last blank line that ends a block is kept in memory, or a whole delimited block when one is
+// open, so generated documents of any size can be processed. Attribute entries and includes are
+// left as text, like `Document::parse` does.
+pub fn parse_reader<R: BufRead>(reader: R) -> BlockReader<R> {
+    parse_reader_with_options(reader, &ParseOptions::default())
+}
+
+// Like `parse_reader`, with the options that shape the blocks. Preprocessing options don't
+// apply.
+pub fn parse_reader_with_options<R: BufRead>(reader: R, options: &ParseOptions) -> BlockReader<R> {
+    BlockReader {
+        reader,
+        options: BlockOptions::from(options),
+        pending: String::new(),
+        open: None,
+        blocks: VecDeque::new(),
+        done: false,
+    }
+}
+
+pub struct BlockReader<R> {
+    reader: R,
+    options: BlockOptions,
+    // Text read but not parsed yet. It starts where the parser starts a block.
+    pending: String,
+    // The delimited block the last line of `pending` is in.
+    open: Option<OpenDelimiters>,
+    blocks: VecDeque<Block>,
+    done: bool,
+}
+
+impl<R: BufRead> BlockReader<R> {
+    // Reads lines up to the next point the parse can be cut at, or the end of the input, and
+    // parses them.
+    fn fill(&mut self) -> Result<()> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.reader.read_line(&mut line)? == 0 {
+                self.done = true;
+                let parsed = parse_spanned(&self.pending, self.options)?;
+                self.blocks
+                    .extend(parsed.into_iter().map(|parsed| parsed.block));
+                self.pending.clear();
+                return Ok(());
+            }
+            // Only delimited blocks span a blank line, outside of them a line after one can start
+            // the next piece. Blank lines would be skipped at the start of a piece, those lines
+            // can't. The delimiters are followed line by line, so the text is parsed once a cut
+            // point is known and never while a block is still open.
+            if self.open.is_none()
+                && self.pending.ends_with("\n\n")
+                && !line.starts_with(['\n', ' '])
+            {
+                let parsed = parse_spanned(&self.pending, self.options)?;
+                if ends_with_blank_block(&parsed, self.pending.len()) {
+                    self.blocks
+                        .extend(parsed.into_iter().map(|parsed| parsed.block));
+                    self.pending.clear();
+                    self.push_line(&line);
+                    return Ok(());
+                }
+            }
+            self.push_line(&line);
+        }
+    }
+
+    fn push_line(&mut self, line: &str) {
+        match self.open.as_mut().map(|open| open.closes(line)) {
+            Some(true) => self.open = None,
+            None if is_delimiter(line) => self.open = Some(OpenDelimiters::new(line)),
+            _ => {}
+        }
+        self.pending.push_str(line);
+    }
+}
+
+// A piece whose blank line the parser left to another block, e.g. the paragraph after a list,
+// may parse differently once the next lines are read.
+fn ends_with_blank_block(parsed: &[ParsedBlock], len: usize) -> bool {
+    parsed
+        .last()
+        .is_some_and(|last| last.block == Block::BlankBlock && last.span.end == len)
+}
+
+impl<R: BufRead> Iterator for BlockReader<R> {
+    type Item = Result<Block>;
+
+    // An error ends the iteration.
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(block) = self.blocks.pop_front() {
+                return Some(Ok(block));
+            }
+            if self.done {
+                return None;
+            }
+            if let Err(error) = self.fill() {
+                self.done = true;
+                return Some(Err(error));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::{generate, GenerateOptions};
+    use crate::parse;
+    use pretty_assertions::assert_eq;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_parse_reader() {
+        let source = generate(&GenerateOptions::new().sections(20));
+        let blocks = parse_reader(Cursor::new(&source))
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(blocks, parse(&source).unwrap());
+
+        for source in &[
+            "\n\n= Title\n\n* item\n\nText\n\n\n== Next\n  indented\n",
+            "Text\n\n----\na\n\nb\n----\n\nMore\n",
+        ] {
+            let blocks = parse_reader(Cursor::new(source))
+                .collect::<Result<Vec<_>>>()
+                .unwrap();
+            assert_eq!(blocks, parse(source).unwrap());
+        }
+
+        let lazy = ParseOptions::new().lazy_inlines(true);
+        let source = "Intro *text*\n\n====\nA\n\n----\n====\n\n----\n\nB\n====\n\nEnd\n";
+        let blocks = parse_reader_with_options(Cursor::new(source), &lazy)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        match &blocks[0] {
+            Block::Paragraph { children } => assert_eq!(children.source(), Some("Intro *text*")),
+            _ => unreachable!(),
+        }
+        assert_eq!(blocks, parse(source).unwrap());
+
+        let mut blocks = parse_reader(Cursor::new("Text\n\n=broken\n"));
+        assert_eq!(blocks.next().unwrap().is_ok(), true);
+        assert_eq!(blocks.next().unwrap().is_ok(), true);
+        assert_eq!(blocks.next().unwrap().is_err(), true);
+        assert_eq!(blocks.next().is_none(), true);
+    }
+}
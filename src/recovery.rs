@@ -0,0 +1,339 @@
+use crate::diagnostic::Diagnostic;
+use crate::lines::LineIndex;
+use crate::{
+    block_with_meta, leading_blank_lines, take_nesting_exceeded, Block, BlockKind, BlockOptions,
+    Inline, ParsedBlock, Span, MAX_NESTING,
+};
+use combine::easy::{Error, Errors};
+use combine::stream::PointerOffset;
+use combine::*;
+use smallvec::smallvec;
+use std::collections::HashMap;
+
+// Where the lenient parser resumes after a block fails to parse.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SyncPoint {
+    NextLine,
+    NextBlankLine,
+    // Delimiter lines such as `----`, `====`, `|===` or `<<<`.
+    NextDelimiterLine,
+    NextHeading,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RecoveryPolicy {
+    pub default: SyncPoint,
+    pub per_block: HashMap<BlockKind, SyncPoint>,
+}
+
+impl Default for RecoveryPolicy {
+    fn default() -> Self {
+        RecoveryPolicy::new(SyncPoint::NextBlankLine)
+    }
+}
+
+impl RecoveryPolicy {
+    pub fn new(default: SyncPoint) -> Self {
+        RecoveryPolicy {
+            default,
+            per_block: HashMap::new(),
+        }
+    }
+
+    pub fn with(mut self, kind: BlockKind, sync_point: SyncPoint) -> Self {
+        self.per_block.insert(kind, sync_point);
+        self
+    }
+
+    pub fn sync_point(&self, kind: BlockKind) -> SyncPoint {
+        self.per_block.get(&kind).copied().unwrap_or(self.default)
+    }
+}
+
+// Parse block by block. Text of a block that fails to parse is kept as a plain paragraph up to
+// the sync point chosen by `policy`, and reported as a diagnostic. With lazy inlines, nesting
+// too deep is only found once the inlines of a paragraph are parsed and isn't reported.
+pub(crate) fn parse_lenient(
+    s: &str,
+    policy: &RecoveryPolicy,
+    options: BlockOptions,
+) -> (Vec<ParsedBlock>, Vec<Diagnostic>) {
+    let mut blocks = vec![];
+    let mut diagnostics = vec![];
+    let lines = LineIndex::new(s);
+    // Left over from an earlier parse on this thread.
+    take_nesting_exceeded();
+
+    let mut offset = match leading_blank_lines().parse(s) {
+        Ok(((), rest)) => s.len() - rest.len(),
+        Err(_) => 0,
+    };
+
+    while offset < s.len() {
+        let input = &s[offset..];
+        let result = (position(), block_with_meta(options), position()).easy_parse(input);
+        let nesting_exceeded = take_nesting_exceeded();
+        match result {
+            Ok(((start, (meta, block), end), _)) if end.translate_position(input) > 0 => {
+                let span = Span {
+                    start: offset + start.translate_position(input),
+                    end: offset + end.translate_position(input),
+                };
+                if nesting_exceeded {
+                    diagnostics.push(Diagnostic::warning(
+                        format!(
+                            "formatting nested deeper than {} levels is kept as text",
+                            MAX_NESTING
+                        ),
+                        span,
+                    ));
+                }
+                blocks.push(ParsedBlock { block, span, meta });
+                offset = span.end;
+            }
+            result => {
+                let kind = guess_block_kind(input);
+                let sync = find_sync_point(&lines, offset, policy.sync_point(kind));
+                let span = Span {
+                    start: offset,
+                    end: sync,
+                };
+                let message = match result {
+                    Err(errors) => describe(&errors),
+                    Ok(_) => "block parser made no progress".to_string(),
+                };
+                let mut diagnostic =
+                    Diagnostic::error(format!("invalid {:?}: {}", kind, message), span);
+                if let Some(note) = suggest(kind, input) {
+                    diagnostic = diagnostic.note(note);
+                }
+                diagnostics.push(diagnostic);
+
+                let text = s[offset..sync].trim_end_matches('\n').to_string();
+                blocks.push(ParsedBlock {
+                    block: Block::Paragraph {
+                        children: smallvec![Inline::Value(text)].into(),
+                    },
+                    span,
+                    meta: None,
+                });
+                offset = sync;
+            }
+        }
+    }
+    (blocks, diagnostics)
+}
+
+// What combine expected, in words. The location is the diagnostic's span.
+fn describe(errors: &Errors<char, &str, PointerOffset<str>>) -> String {
+    let mut unexpected = vec![];
+    let mut expected = vec![];
+    for error in &errors.errors {
+        match error {
+            Error::Unexpected(info) => unexpected.push(token(&info.to_string())),
+            Error::Expected(info) => {
+                let token = token(&info.to_string());
+                if !expected.contains(&token) {
+                    expected.push(token);
+                }
+            }
+            Error::Message(info) => unexpected.push(info.to_string()),
+            Error::Other(error) => unexpected.push(error.to_string()),
+        }
+    }
+    let mut message = match unexpected.first() {
+        Some(unexpected) => format!("unexpected {}", unexpected),
+        None => "unexpected input".to_string(),
+    };
+    if !expected.is_empty() {
+        message.push_str(&format!(", expected {}", expected.join(" or ")));
+    }
+    message
+}
+
+fn token(token: &str) -> String {
+    match token {
+        " " | "whitespace" => "a space".to_string(),
+        "\n" => "a line break".to_string(),
+        "end of input" => "the end of the input".to_string(),
+        token => format!("`{}`", token),
+    }
+}
+
+// Likely fix for a block of `kind` that failed to parse.
+fn suggest(kind: BlockKind, input: &str) -> Option<String> {
+    let line = input.lines().next().unwrap_or("");
+    match kind {
+        BlockKind::Heading => {
+            let markers = line.chars().take_while(|c| *c == '=').count();
+            let rest = &line[markers..];
+            Some(if markers > 5 {
+                "section titles have at most five `=` marks".to_string()
+            } else if rest.trim().is_empty() {
+                "a section title needs text after its `=` marks".to_string()
+            } else {
+                format!(
+                    "did you mean `{} {}`? the `=` marks are followed by a space",
+                    &line[..markers],
+                    rest.trim_start()
+                )
+            })
+        }
+        BlockKind::UnorderdList | BlockKind::OrderdList => {
+            Some("a list item needs text after its marker".to_string())
+        }
+        BlockKind::HorizontalRuledLine if line.starts_with("<<") && !line.starts_with("<<<") => {
+            Some("an xref is closed with `>>`, e.g. `<<id,text>>`".to_string())
+        }
+        _ => None,
+    }
+}
+
+fn guess_block_kind(input: &str) -> BlockKind {
+    match input.chars().next() {
+        Some('=') => BlockKind::Heading,
+        Some('<') => BlockKind::HorizontalRuledLine,
+        Some('*') => BlockKind::UnorderdList,
+        Some('.') => BlockKind::OrderdList,
+        _ => BlockKind::Paragraph,
+    }
+}
+
+// Offset where parsing resumes after the block failing at `offset`, searched from the next
+// line on. A blank sync line is kept so it still separates blocks.
+fn find_sync_point(lines: &LineIndex, offset: usize, sync_point: SyncPoint) -> usize {
+    let end = lines.source().len();
+    for line in lines.line_of(offset) + 1..lines.line_count() {
+        let start = lines.start(line);
+        // The empty line after a final line break.
+        if start == end {
+            break;
+        }
+        let text = lines.line(line);
+        let matched = match sync_point {
+            SyncPoint::NextLine => true,
+            SyncPoint::NextBlankLine => lines.is_blank(line),
+            SyncPoint::NextDelimiterLine => is_delimiter_line(text),
+            SyncPoint::NextHeading => is_heading_line(text),
+        };
+        if matched {
+            return if lines.is_blank(line) {
+                start - 1
+            } else {
+                start
+            };
+        }
+    }
+    end
+}
+
+fn is_delimiter_line(line: &str) -> bool {
+    let line = line.trim_end();
+    if line == "|===" || line == "<<<" || line == "'''" {
+        return true;
+    }
+    let mut chars = line.chars();
+    match chars.next() {
+        Some(c) if "-=*_.+/".contains(c) => line.len() >= 4 && chars.all(|d| d == c),
+        _ => false,
+    }
+}
+
+fn is_heading_line(line: &str) -> bool {
+    let markers = line.chars().take_while(|c| *c == '=').count();
+    (1..=5).contains(&markers) && line[markers..].starts_with(' ')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostic::Severity;
+    use crate::HeadingLevel;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_find_sync_point() {
+        let lines = LineIndex::new("=broken\nline\n\n== Next\n----");
+        assert_eq!(find_sync_point(&lines, 0, SyncPoint::NextLine), 8);
+        assert_eq!(find_sync_point(&lines, 0, SyncPoint::NextBlankLine), 12);
+        assert_eq!(find_sync_point(&lines, 0, SyncPoint::NextHeading), 14);
+        assert_eq!(find_sync_point(&lines, 0, SyncPoint::NextDelimiterLine), 22);
+        assert_eq!(find_sync_point(&lines, 8, SyncPoint::NextLine), 12);
+        let lines = LineIndex::new("=broken");
+        assert_eq!(find_sync_point(&lines, 0, SyncPoint::NextLine), 7);
+        let lines = LineIndex::new("=broken\n");
+        assert_eq!(find_sync_point(&lines, 0, SyncPoint::NextBlankLine), 8);
+    }
+
+    #[test]
+    fn test_parse_lenient() {
+        let (blocks, diagnostics) = parse_lenient(
+            "=broken\nline\n\n== Next",
+            &RecoveryPolicy::default(),
+            BlockOptions::default(),
+        );
+
+        assert_eq!(
+            blocks
+                .into_iter()
+                .map(|parsed| parsed.block)
+                .collect::<Vec<_>>(),
+            vec![
+                Block::Paragraph {
+                    children: smallvec![Inline::Value("=broken\nline".to_string())].into()
+                },
+                Block::BlankBlock,
+                Block::Heading {
+                    level: HeadingLevel::Level1,
+                    children: smallvec![Inline::Value("Next".to_string())],
+                    id: None
+                },
+            ]
+        );
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].span, Span { start: 0, end: 12 });
+        assert_eq!(
+            diagnostics[0].message,
+            "invalid Heading: unexpected `b`, expected `=` or a space"
+        );
+        assert_eq!(
+            diagnostics[0].note.as_deref(),
+            Some("did you mean `= broken`? the `=` marks are followed by a space")
+        );
+    }
+
+    #[test]
+    fn test_parse_lenient_per_block_policy() {
+        let policy = RecoveryPolicy::default().with(BlockKind::Heading, SyncPoint::NextLine);
+        let (blocks, _) = parse_lenient("=broken\nline", &policy, BlockOptions::default());
+
+        assert_eq!(
+            blocks
+                .into_iter()
+                .map(|parsed| parsed.block)
+                .collect::<Vec<_>>(),
+            vec![
+                Block::Paragraph {
+                    children: smallvec![Inline::Value("=broken".to_string())].into()
+                },
+                Block::Paragraph {
+                    children: smallvec![Inline::Value("line".to_string())].into()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_lenient_nesting_limit() {
+        let source = format!("Text\n\n{}x\n", "*_".repeat(1000));
+        let (_, diagnostics) =
+            parse_lenient(&source, &RecoveryPolicy::default(), BlockOptions::default());
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|diagnostic| (diagnostic.severity, diagnostic.span.start))
+                .collect::<Vec<_>>(),
+            vec![(Severity::Warning, 6)]
+        );
+    }
+}
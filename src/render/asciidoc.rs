@@ -0,0 +1,550 @@
+use crate::document::Document;
+use crate::is_delimiter;
+use crate::text::cell_text;
+use crate::{
+    Attr, AttrList, Block, BlockMeta, FootnoteType, HeadingLevel, Inline, ListItem, TableColumn,
+    TableRow,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct AsciiDocOptions {
+    // Refill paragraphs to lines of at most this many characters, `None` keeps the line breaks.
+    pub wrap_width: Option<usize>,
+}
+
+// Write the document back as AsciiDoc that parses to the same tree.
+pub fn to_asciidoc(document: &Document) -> String {
+    render(document, &AsciiDocOptions::default())
+}
+
+// Attribute entries go right below the document title, where they belong to the header, or at
+// the top when there is no title.
+pub fn render(document: &Document, options: &AsciiDocOptions) -> String {
+    let entries = document
+        .attributes()
+        .iter()
+        .map(|(name, value)| attribute_entry(name, value))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let blocks = document.blocks();
+    let has_title = blocks.first().is_some_and(is_title);
+    let mut out = match has_title {
+        true => String::new(),
+        false => entries.clone(),
+    };
+
+    let renderer = AsciiDocRenderer { options };
+    for (index, block) in blocks.iter().enumerate() {
+        let text = renderer.block(block, document.meta(index));
+        // Stray line breaks the grammar leaves behind as paragraphs are dropped.
+        if text.trim().is_empty() {
+            continue;
+        }
+        if !out.is_empty() {
+            let header = index > 0 && is_title(&blocks[index - 1]) && is_attribute_entry(block);
+            out.push_str(if header { "\n" } else { "\n\n" });
+        }
+        out.push_str(&text);
+        if index == 0 && has_title && !entries.is_empty() {
+            out.push('\n');
+            out.push_str(&entries);
+        }
+    }
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+fn is_title(block: &Block) -> bool {
+    block.heading_level() == Some(&HeadingLevel::Title)
+}
+
+fn is_attribute_entry(block: &Block) -> bool {
+    matches!(block, Block::Paragraph { .. })
+        && matches!(block.inlines().first(), Some(Inline::Value(value)) if value.starts_with(':'))
+}
+
+fn attribute_entry(name: &str, value: &str) -> String {
+    if value.is_empty() {
+        format!(":{}:", name)
+    } else {
+        format!(":{}: {}", name, value)
+    }
+}
+
+struct AsciiDocRenderer<'a> {
+    options: &'a AsciiDocOptions,
+}
+
+impl<'a> AsciiDocRenderer<'a> {
+    fn block(&self, block: &Block, meta: Option<&BlockMeta>) -> String {
+        let text = match block {
+            Block::Paragraph { children } => self.paragraph(children),
+            Block::Heading {
+                level, children, ..
+            } => format!(
+                "{} {}",
+                "=".repeat(level.as_u8() as usize + 1),
+                inlines(children)
+            ),
+            // The grammar reads `<<<` as a ruled line, there is no separate page break syntax.
+            Block::HorizontalRuledLine | Block::NextPage => "<<<".to_string(),
+            Block::UnorderdList { children } => list(children, '*'),
+            Block::OrderdList { children } => list(children, '.'),
+            Block::Label { children, key } => format!("{}:: {}", inlines(key), inlines(children)),
+            Block::Qanda { question, answer } => {
+                format!("[qanda]\n{}::\n  {}", inlines(question), inlines(answer))
+            }
+            Block::CodeBlock {
+                children,
+                title,
+                file_type,
+            } => {
+                let mut lines = vec![];
+                if let Some(title) = title {
+                    lines.push(format!(".{}", title));
+                }
+                // The language goes on the attribute line, or on a fence when the block has an
+                // attribute line that doesn't name it.
+                let (open, close) = match (file_type, meta) {
+                    (Some(file_type), None) => {
+                        lines.push(format!("[source,{}]", file_type));
+                        ("----".to_string(), "----")
+                    }
+                    (Some(file_type), Some(meta)) if !names_language(meta, file_type) => {
+                        (format!("```{}", file_type), "```")
+                    }
+                    _ => ("----".to_string(), "----"),
+                };
+                lines.push(open);
+                lines.push(inlines(children).trim_end_matches('\n').to_string());
+                lines.push(close.to_string());
+                lines.join("\n")
+            }
+            Block::Block {
+                kind,
+                children,
+                metas,
+                title,
+            } => {
+                let mut lines = vec![];
+                if let Some(title) = title {
+                    lines.push(format!(".{}", inlines(title)));
+                }
+                lines.push(kind.delimiter().to_string());
+                // Verbatim content is written back without refilling.
+                let blocks = children
+                    .iter()
+                    .enumerate()
+                    .map(|(index, block)| match kind.is_verbatim() {
+                        true => inlines(block.inlines()),
+                        false => self.block(block, metas.get(index).and_then(Option::as_ref)),
+                    })
+                    .filter(|text| !text.is_empty())
+                    .collect::<Vec<_>>();
+                if !blocks.is_empty() {
+                    lines.push(blocks.join("\n\n"));
+                }
+                lines.push(kind.delimiter().to_string());
+                lines.join("\n")
+            }
+            Block::Table {
+                columns,
+                rows,
+                title,
+            } => self.table(columns, rows, title.as_deref()),
+            Block::Macro {
+                children:
+                    Inline::Macro {
+                        attributes,
+                        kind,
+                        id,
+                    },
+            } => format!("{}::{}{}", kind, id, attribute_list(attributes)),
+            Block::Macro { children } => inline(children),
+            Block::BlankBlock => String::new(),
+        };
+        if text.is_empty() {
+            return text;
+        }
+
+        let attribute_line = match (meta, block) {
+            (Some(meta), _) => Some(attribute_list(&meta.attributes)),
+            (None, Block::Heading { id: Some(id), .. }) => Some(format!("[#{}]", id)),
+            _ => None,
+        };
+        match attribute_line {
+            Some(attribute_line) => format!("{}\n{}", attribute_line, text),
+            None => text,
+        }
+    }
+
+    // Header cells are padded so the columns line up.
+    fn table(&self, columns: &[TableColumn], rows: &[TableRow], title: Option<&str>) -> String {
+        let cells = rows
+            .iter()
+            .map(|row| {
+                row.cells()
+                    .iter()
+                    .map(|cell| {
+                        let style = cell.style.as_ref().map_or("", |style| style.as_str());
+                        // AsciiDoc cells hold blocks of their own.
+                        let text = match style {
+                            "a" => cell
+                                .blocks
+                                .iter()
+                                .enumerate()
+                                .map(|(index, block)| {
+                                    let meta = cell.metas.get(index).and_then(Option::as_ref);
+                                    self.block(block, meta)
+                                })
+                                .collect::<Vec<_>>()
+                                .join("\n\n"),
+                            _ => cell_text(cell),
+                        };
+                        format!("{}|{}", style, text)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>();
+        let width = columns
+            .iter()
+            .map(|column| column.name().chars().count())
+            .max()
+            .unwrap_or(0);
+
+        let mut lines = vec![];
+        if let Some(title) = title {
+            lines.push(format!(".{}", title));
+        }
+        lines.push("|===".to_string());
+        if !columns.is_empty() {
+            let header = columns
+                .iter()
+                .map(|column| format!("|{:width$}", column.name(), width = width))
+                .collect::<Vec<_>>()
+                .join(" ");
+            lines.push(header.trim_end().to_string());
+            lines.push(String::new());
+        }
+        lines.extend(cells);
+        lines.push("|===".to_string());
+        lines.join("\n")
+    }
+
+    fn paragraph(&self, children: &[Inline]) -> String {
+        match self.options.wrap_width {
+            Some(width) if !has_line_directives(children) => wrap(children, width),
+            _ => inlines(children),
+        }
+    }
+}
+
+fn names_language(meta: &BlockMeta, file_type: &str) -> bool {
+    meta.style.as_deref() == Some("source") && meta.attributes.positional(1) == Some(file_type)
+}
+
+fn list(items: &[ListItem], marker: char) -> String {
+    items
+        .iter()
+        .map(|item| {
+            let checkbox = match item {
+                ListItem::Check { checked: true, .. } => "[x] ",
+                ListItem::Check { checked: false, .. } => "[ ] ",
+                ListItem::Normal { .. } => "",
+            };
+            format!(
+                "{} {}{}",
+                marker.to_string().repeat(item.level().max(1) as usize),
+                checkbox,
+                inlines(item.children())
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Attribute list as written in the source, `[source,rust]` or `[source,rust, indent=0]`. A named
+// entry after another one is set off by a space.
+pub(crate) fn attribute_list(attributes: &AttrList) -> String {
+    let mut out = String::from("[");
+    for (index, attr) in attributes.iter().enumerate() {
+        match attr {
+            Attr::Positional(value) => {
+                if index > 0 {
+                    out.push(',');
+                }
+                out.push_str(value);
+            }
+            Attr::Named(key, value) => {
+                if index > 0 {
+                    out.push_str(", ");
+                }
+                out.push_str(&format!("{}={}", key, attribute_value(value)));
+            }
+        }
+    }
+    out.push(']');
+    out
+}
+
+// Named attribute value, in double quotes when it wouldn't read back as written.
+pub(crate) fn attribute_value(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value.trim() == value
+        && !value.starts_with(['"', '\''])
+        && !value.contains([',', '=', ']', '\n']);
+    if plain {
+        value.to_string()
+    } else {
+        format!("\"{}\"", value.replace('"', "\\\""))
+    }
+}
+
+fn inlines(inlines: &[Inline]) -> String {
+    inlines.iter().map(inline).collect()
+}
+
+fn inline(inline: &Inline) -> String {
+    match inline {
+        Inline::Value(value) => value.clone(),
+        Inline::HardBreak => " +\n".to_string(),
+        Inline::SoftBreak => "\n".to_string(),
+        Inline::Literal { children } => format!("+{}+", self::inline(children)),
+        Inline::Footnote { kind, children } => match kind {
+            FootnoteType::Note => format!("footnote:[{}]", self::inline(children)),
+            kind => format!(
+                "footnote:[{}: {}]",
+                footnote_label(kind),
+                self::inline(children)
+            ),
+        },
+        Inline::Lead { children } => format!("[.lead]#{}#", self::inline(children)),
+        Inline::Bold { children } => format!("*{}*", self::inline(children)),
+        Inline::Italic { children } => format!("_{}_", self::inline(children)),
+        Inline::Monospace { children } => format!("`{}`", self::inline(children)),
+        Inline::Marker { children } => format!("#{}#", self::inline(children)),
+        Inline::Styled { roles, children } => {
+            let roles = roles
+                .iter()
+                .map(|role| format!(".{}", role))
+                .collect::<String>();
+            format!("[{}]#{}#", roles, self::inline(children))
+        }
+        // Written the AsciiDoc way, the three backticks only read as code in compat mode.
+        Inline::InlineCode { children } => format!("`+{}+`", self::inline(children)),
+        Inline::Macro {
+            attributes,
+            kind,
+            id,
+        } => format!("{}:{}{}", kind, id, attribute_list(attributes)),
+    }
+}
+
+fn footnote_label(kind: &FootnoteType) -> &'static str {
+    match kind {
+        FootnoteType::Note => "NOTE",
+        FootnoteType::Tip => "TIP",
+        FootnoteType::Important => "IMPORTANT",
+        FootnoteType::Warning => "WARNING",
+        FootnoteType::Caution => "CAUTION",
+    }
+}
+
+// Attribute entries and preprocessor directives have to stay on their own lines.
+fn has_line_directives(children: &[Inline]) -> bool {
+    let mut line_start = true;
+    for inline in children {
+        if line_start {
+            if let Inline::Value(value) = inline {
+                if value.starts_with(':') || value.starts_with("include::") {
+                    return true;
+                }
+            }
+        }
+        line_start = matches!(inline, Inline::SoftBreak | Inline::HardBreak);
+    }
+    false
+}
+
+// Words that start a block or a line directive when they start a line: list, heading and
+// delimiter marks, attribute lines and entries, block titles, comments, tables and block macros.
+fn starts_block(word: &str) -> bool {
+    word.chars().all(|c| "*.-=+".contains(c))
+        || is_delimiter(word)
+        || word.starts_with(['[', ':', '|'])
+        || word.starts_with("<<<")
+        || word.starts_with("//")
+        || word.starts_with("```")
+        || (word.starts_with('.') && word.len() > 1)
+        || word.contains("::")
+}
+
+// Greedy fill. Formatted inlines are never split because their delimiters have to stay on one
+// line, hard breaks are kept.
+fn wrap(children: &[Inline], width: usize) -> String {
+    let mut lines: Vec<String> = vec![];
+    let mut line = String::new();
+    let mut word = String::new();
+
+    let push_word = |line: &mut String, lines: &mut Vec<String>, word: &mut String| {
+        if word.is_empty() {
+            return;
+        }
+        let fits = line.chars().count() + 1 + word.chars().count() <= width;
+        // A hard break or a block starting on the next line would change what the text means,
+        // the line runs long instead.
+        let ends_with_break = line == "+" || line.ends_with(" +");
+        if !line.is_empty() && !fits && !ends_with_break && !starts_block(word) {
+            lines.push(std::mem::take(line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+        word.clear();
+    };
+
+    for child in children {
+        match child {
+            Inline::Value(value) => {
+                for c in value.chars() {
+                    if c == ' ' {
+                        push_word(&mut line, &mut lines, &mut word);
+                    } else {
+                        word.push(c);
+                    }
+                }
+            }
+            Inline::SoftBreak => push_word(&mut line, &mut lines, &mut word),
+            Inline::HardBreak => {
+                push_word(&mut line, &mut lines, &mut word);
+                line.push_str(" +");
+                lines.push(std::mem::take(&mut line));
+            }
+            child => word.push_str(&inline(child)),
+        }
+    }
+    push_word(&mut line, &mut lines, &mut word);
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ParseOptions, TableCell};
+    use pretty_assertions::assert_eq;
+    use smallvec::smallvec;
+
+    #[test]
+    fn test_to_asciidoc_round_trip() {
+        let source = "= Title\n\n[#intro.lead]\nThis is *bold*, _italic_ and `code`\nsecond line\n\n* foo\n** [x] bar\n\n. one\n. two\n\n<<<";
+        let document = Document::parse(source).unwrap();
+        let emitted = to_asciidoc(&document);
+
+        assert_eq!(emitted, format!("{}\n", source));
+        assert_eq!(Document::parse(emitted.trim_end()).unwrap(), document);
+    }
+
+    #[test]
+    fn test_to_asciidoc_attributes() {
+        let document =
+            Document::parse_with_options(":toc:\n:author: himanoa\n\n== A", &ParseOptions::new())
+                .unwrap();
+        assert_eq!(to_asciidoc(&document), ":author: himanoa\n:toc:\n\n== A\n");
+    }
+
+    #[test]
+    fn test_attribute_line_quotes_values() {
+        let source = "[caption=\"Figure 1, part A\", title=\"say, \\\"hi\\\"\", width=50]\ntext";
+        let document = Document::parse(source).unwrap();
+        let emitted = to_asciidoc(&document);
+
+        assert_eq!(emitted, format!("{}\n", source));
+        assert_eq!(Document::parse(emitted.trim_end()).unwrap(), document);
+    }
+
+    #[test]
+    fn test_attribute_line_keeps_order() {
+        let source = "[source,rust, linenums=1, indent=0]\ntext";
+        let document = Document::parse(source).unwrap();
+
+        assert_eq!(to_asciidoc(&document), format!("{}\n", source));
+    }
+
+    #[test]
+    fn test_delimited_blocks_round_trip() {
+        let source = "////\na *comment*\n////\n\n****\nside\n****\n\n____\nquoted\n____\n\n++++\n<b>raw</b>\n++++\n\n====\n[#first.lead]\n--\nopen\n--\n====\n\n|===\n|a |b\n\n|1 a|* 2\n|===\n";
+        let document = Document::parse(source).unwrap();
+        let emitted = to_asciidoc(&document);
+
+        assert_eq!(emitted, source);
+        assert_eq!(Document::parse(&emitted).unwrap(), document);
+    }
+
+    #[test]
+    fn test_code_block_language() {
+        for source in [
+            "[source,rust]\n----\nlet x;\n----\n",
+            "[#main]\n```rust\nlet x;\n```\n",
+            "[source]\n```rust\nlet x;\n```\n",
+        ] {
+            let document = Document::parse(source).unwrap();
+            let emitted = to_asciidoc(&document);
+
+            assert_eq!(Document::parse(&emitted).unwrap(), document);
+        }
+        let document = Document::parse("[source,rust]\n----\nlet x;\n----").unwrap();
+        assert_eq!(
+            to_asciidoc(&document),
+            "[source,rust]\n----\nlet x;\n----\n"
+        );
+    }
+
+    #[test]
+    fn test_render_table() {
+        let document = Document::new(vec![Block::Table {
+            columns: vec![
+                TableColumn {
+                    name: "Name".to_string(),
+                },
+                TableColumn {
+                    name: "Description".to_string(),
+                },
+            ],
+            rows: vec![TableRow::new(vec![
+                TableCell::new(vec![Block::Paragraph {
+                    children: smallvec![Inline::Value("foo".to_string())].into(),
+                }]),
+                TableCell {
+                    style: Some("a".into()),
+                    ..TableCell::new(vec![Block::Paragraph {
+                        children: smallvec![Inline::Value("bar".to_string())].into(),
+                    }])
+                },
+            ])],
+            title: Some("Options".to_string()),
+        }]);
+        assert_eq!(
+            to_asciidoc(&document),
+            ".Options\n|===\n|Name        |Description\n\n|foo a|bar\n|===\n"
+        );
+    }
+
+    #[test]
+    fn test_wrap() {
+        let document = Document::parse("one two *three four* five\nsix seven eight\nnine").unwrap();
+        let options = AsciiDocOptions {
+            wrap_width: Some(12),
+        };
+        assert_eq!(
+            render(&document, &options),
+            "one two\n*three four*\nfive six\nseven eight\nnine\n"
+        );
+    }
+}
@@ -0,0 +1,74 @@
+// Turns the source of a code block into highlighted HTML.
+//
+// Implementations return `None` for languages they don't know so the renderer can fall back to
+// plain escaped text. The returned markup goes inside `<pre><code>` as is and must already be
+// escaped.
+pub trait Highlighter: Send + Sync {
+    fn highlight(&self, code: &str, language: &str) -> Option<String>;
+}
+
+#[cfg(feature = "syntect")]
+pub use self::syntect_highlighter::SyntectHighlighter;
+
+#[cfg(feature = "syntect")]
+mod syntect_highlighter {
+    use super::Highlighter;
+    use syntect::html::{ClassStyle, ClassedHTMLGenerator};
+    use syntect::parsing::SyntaxSet;
+    use syntect::util::LinesWithEndings;
+
+    // Emits `<span class="...">` around tokens, the colors come from a stylesheet generated with
+    // `syntect::html::css_for_theme_with_class_style`.
+    pub struct SyntectHighlighter {
+        syntax_set: SyntaxSet,
+    }
+
+    impl Default for SyntectHighlighter {
+        fn default() -> Self {
+            SyntectHighlighter::new(SyntaxSet::load_defaults_newlines())
+        }
+    }
+
+    impl SyntectHighlighter {
+        pub fn new(syntax_set: SyntaxSet) -> Self {
+            SyntectHighlighter { syntax_set }
+        }
+    }
+
+    impl Highlighter for SyntectHighlighter {
+        fn highlight(&self, code: &str, language: &str) -> Option<String> {
+            let syntax = self.syntax_set.find_syntax_by_token(language)?;
+            let mut generator = ClassedHTMLGenerator::new_with_class_style(
+                syntax,
+                &self.syntax_set,
+                ClassStyle::Spaced,
+            );
+            for line in LinesWithEndings::from(code) {
+                generator
+                    .parse_html_for_line_which_includes_newline(line)
+                    .ok()?;
+            }
+            Some(generator.finalize())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn test_syntect_highlighter() {
+            let highlighter = SyntectHighlighter::default();
+            let html = highlighter.highlight("fn main() {}\n", "rust").unwrap();
+            assert_eq!(
+                html.contains("<span class=\"storage type function rust\">fn</span>"),
+                true
+            );
+            assert_eq!(
+                highlighter.highlight("fn main() {}\n", "no-such-language"),
+                None
+            );
+        }
+    }
+}
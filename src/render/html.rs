@@ -0,0 +1,1525 @@
+use crate::diagram::{Diagram, DiagramOutput};
+use crate::document::Document;
+use crate::footnote::{collect_footnotes, Footnote};
+use crate::render::asciidoc::attribute_list;
+use crate::render::highlight::Highlighter;
+use crate::render::images::{ImageOptions, ImageResolver};
+use crate::render::math::{inline_math, MathEngine, MathNotation};
+use crate::render::roles::RoleStyles;
+#[cfg(feature = "templates")]
+use crate::render::templates::TemplateRegistry;
+use crate::slug::{SlugStrategy, Slugger};
+use crate::source_map::SourceMap;
+use crate::subs::{escape_attribute, escape_special_chars, Substitutions};
+use crate::text::{block_text, inline_text};
+use crate::{
+    AttrList, Block, BlockMeta, DelimitedKind, HeadingLevel, Inline, InlineVec, ListItem, Span,
+    VideoProvider,
+};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Arc;
+
+// Markup flavour of the HTML renderer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HtmlProfile {
+    // Plain HTML5 elements with as few wrappers as possible.
+    #[default]
+    Semantic,
+    // The element structure and class names of asciidoctor's html5 backend, for its stylesheets.
+    Asciidoctor,
+}
+
+#[derive(Clone, Default)]
+pub struct HtmlOptions {
+    pub profile: HtmlProfile,
+    // Used for code blocks with a `file_type`, unknown languages are written as plain text.
+    pub highlighter: Option<Arc<dyn Highlighter>>,
+    // Drop link and image targets with schemes that can run script, for untrusted input.
+    pub sanitize: bool,
+    // Give headings without an explicit id one derived from their text.
+    pub heading_ids: Option<SlugStrategy>,
+    // Leave out the document title, for layouts that show it elsewhere.
+    pub hide_title: bool,
+    pub images: ImageOptions,
+    pub math: MathEngine,
+    // Macros outside this list are written out as their source text. `None` supports all.
+    pub allowed_macros: Option<Vec<String>>,
+    // Wrap occurrences of these terms in `<mark>`, ignoring ASCII case.
+    pub search_terms: Vec<String>,
+    // Classes written for roles on text, next to the roles themselves.
+    pub roles: RoleStyles,
+    // Templates replacing the built-in markup of the node kinds they are registered for.
+    #[cfg(feature = "templates")]
+    pub templates: Option<Arc<TemplateRegistry>>,
+}
+
+impl HtmlOptions {
+    pub fn new() -> Self {
+        HtmlOptions::default()
+    }
+
+    pub fn profile(mut self, profile: HtmlProfile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    // Matches what github.com shows for `.adoc` files: asciidoctor markup, `user-content-`
+    // anchors, no raw HTML or script URLs, and macros GitHub doesn't support left as text.
+    pub fn github() -> Self {
+        HtmlOptions::new()
+            .profile(HtmlProfile::Asciidoctor)
+            .heading_ids(SlugStrategy::Separated {
+                prefix: "user-content-".to_string(),
+                separator: '-',
+            })
+            .sanitize(true)
+            .allowed_macros(GITHUB_MACROS.iter().copied())
+    }
+
+    pub fn allowed_macros<S: Into<String>, I: IntoIterator<Item = S>>(mut self, macros: I) -> Self {
+        self.allowed_macros = Some(macros.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn highlighter<H: Highlighter + 'static>(mut self, highlighter: H) -> Self {
+        self.highlighter = Some(Arc::new(highlighter));
+        self
+    }
+
+    pub fn sanitize(mut self, sanitize: bool) -> Self {
+        self.sanitize = sanitize;
+        self
+    }
+
+    pub fn images(mut self, images: ImageOptions) -> Self {
+        self.images = images;
+        self
+    }
+
+    pub fn math(mut self, math: MathEngine) -> Self {
+        self.math = math;
+        self
+    }
+
+    pub fn search_terms<S: Into<String>, I: IntoIterator<Item = S>>(mut self, terms: I) -> Self {
+        self.search_terms = terms.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn roles(mut self, roles: RoleStyles) -> Self {
+        self.roles = roles;
+        self
+    }
+
+    pub fn hide_title(mut self, hide_title: bool) -> Self {
+        self.hide_title = hide_title;
+        self
+    }
+
+    pub fn heading_ids(mut self, strategy: SlugStrategy) -> Self {
+        self.heading_ids = Some(strategy);
+        self
+    }
+
+    #[cfg(feature = "templates")]
+    pub fn templates(mut self, templates: TemplateRegistry) -> Self {
+        self.templates = Some(Arc::new(templates));
+        self
+    }
+}
+
+const GITHUB_MACROS: &[&str] = &["image", "link", "http", "https", "mailto"];
+
+pub fn render(document: &Document) -> String {
+    render_with_options(document, &HtmlOptions::default())
+}
+
+pub fn render_with_options(document: &Document, options: &HtmlOptions) -> String {
+    render_with_source_map(document, options).0
+}
+
+// Render and record which part of the output each top level block produced.
+pub fn render_with_source_map(document: &Document, options: &HtmlOptions) -> (String, SourceMap) {
+    let mut renderer = HtmlRenderer::for_document(options, document);
+    let mut source_map = SourceMap::from_document(document);
+    // Asciidoctor wraps the blocks between the title and the first section.
+    let preamble = match options.profile {
+        HtmlProfile::Asciidoctor => document.preamble(),
+        HtmlProfile::Semantic => None,
+    };
+
+    for (index, block) in document.blocks().iter().enumerate() {
+        if options.hide_title && block.heading_level() == Some(&HeadingLevel::Title) {
+            continue;
+        }
+        if preamble
+            .as_ref()
+            .is_some_and(|preamble| preamble.start == index)
+        {
+            renderer
+                .out
+                .push_str("<div id=\"preamble\">\n<div class=\"sectionbody\">\n");
+        }
+        let start = renderer.out.len();
+        renderer.subs = Substitutions::from_meta(document.meta(index));
+        match document.diagram(index) {
+            Some(diagram) => renderer.diagram(diagram),
+            None => renderer.block(block),
+        }
+        let end = renderer.out.len();
+        if end > start {
+            source_map.set_output(index, Span { start, end });
+        }
+        if preamble
+            .as_ref()
+            .is_some_and(|preamble| preamble.end == index + 1)
+        {
+            renderer.out.push_str("</div>\n</div>\n");
+        }
+    }
+    renderer.close_sections(0);
+    renderer.footnotes(&collect_footnotes(document.blocks()));
+    (renderer.out, source_map)
+}
+
+pub(crate) fn render_block(block: &Block, options: &HtmlOptions) -> String {
+    let mut renderer = HtmlRenderer::new(options, std::slice::from_ref(block));
+    renderer.block(block);
+    renderer.close_sections(0);
+    renderer.out
+}
+
+pub(crate) fn render_inlines(inlines: &[Inline], options: &HtmlOptions) -> String {
+    let mut renderer = HtmlRenderer::new(options, &[]);
+    renderer.inlines(inlines);
+    renderer.out
+}
+
+// Ids and numbers assigned before rendering, keyed by node address. Rendering a node twice (for
+// templates) gives the same markup.
+#[derive(Default)]
+struct Prepared {
+    heading_ids: HashMap<*const Block, String>,
+    footnote_numbers: HashMap<*const Inline, usize>,
+    images: ImageResolver,
+    // The `stem` document attribute.
+    stem: Option<String>,
+}
+
+impl Prepared {
+    fn new(options: &HtmlOptions, blocks: &[Block], document: Option<&Document>) -> Self {
+        let mut prepared = Prepared {
+            images: ImageResolver::new(&options.images, document),
+            stem: document
+                .and_then(|document| document.attribute("stem"))
+                .map(str::to_string),
+            ..Prepared::default()
+        };
+        if let Some(strategy) = &options.heading_ids {
+            prepared.heading_ids = heading_ids(blocks, strategy);
+        }
+        for footnote in collect_footnotes(blocks) {
+            prepared
+                .footnote_numbers
+                .insert(footnote.node as *const Inline, footnote.number);
+        }
+        prepared
+    }
+}
+
+// Ids generated for the headings without an explicit one, keyed by node address. Explicit ids
+// are reserved first so generated ones never collide with them.
+pub(crate) fn heading_ids(
+    blocks: &[Block],
+    strategy: &SlugStrategy,
+) -> HashMap<*const Block, String> {
+    let mut slugger = Slugger::new(strategy.clone());
+    for id in blocks.iter().filter_map(Block::id) {
+        slugger.reserve(id);
+    }
+    let mut ids = HashMap::new();
+    for block in blocks {
+        if let Block::Heading {
+            children, id: None, ..
+        } = block
+        {
+            ids.insert(block as *const Block, slugger.slug(&inline_text(children)));
+        }
+    }
+    ids
+}
+
+struct HtmlRenderer<'a> {
+    out: String,
+    options: &'a HtmlOptions,
+    prepared: Rc<Prepared>,
+    // Levels of the asciidoctor `sectN` divs still open.
+    sections: Vec<u8>,
+    // Substitutions of the top level block being rendered.
+    subs: Substitutions,
+    // Search term matches as byte ranges into the plain text of the inlines being rendered, and
+    // how far into that text rendering is.
+    marks: Vec<(usize, usize)>,
+    text_offset: usize,
+}
+
+impl<'a> HtmlRenderer<'a> {
+    fn new(options: &'a HtmlOptions, blocks: &[Block]) -> Self {
+        HtmlRenderer::with_prepared(options, Prepared::new(options, blocks, None))
+    }
+
+    fn for_document(options: &'a HtmlOptions, document: &Document) -> Self {
+        HtmlRenderer::with_prepared(
+            options,
+            Prepared::new(options, document.blocks(), Some(document)),
+        )
+    }
+
+    fn with_prepared(options: &'a HtmlOptions, prepared: Prepared) -> Self {
+        HtmlRenderer {
+            out: String::new(),
+            options,
+            prepared: Rc::new(prepared),
+            sections: vec![],
+            subs: Substitutions::default(),
+            marks: vec![],
+            text_offset: 0,
+        }
+    }
+
+    #[cfg(feature = "templates")]
+    fn fork<F: FnOnce(&mut HtmlRenderer<'a>)>(&self, f: F) -> String {
+        let mut renderer = HtmlRenderer {
+            out: String::new(),
+            options: self.options,
+            prepared: self.prepared.clone(),
+            sections: vec![],
+            subs: self.subs,
+            marks: vec![],
+            text_offset: 0,
+        };
+        f(&mut renderer);
+        renderer.out
+    }
+
+    fn block(&mut self, block: &Block) {
+        let section = match (self.options.profile, block.heading_level()) {
+            (HtmlProfile::Asciidoctor, Some(level)) if *level != HeadingLevel::Title => {
+                Some(level.as_u8())
+            }
+            _ => None,
+        };
+        if let Some(level) = section {
+            self.close_sections(level);
+            self.out
+                .push_str(&format!("<div class=\"sect{}\">\n", level));
+        }
+        self.node(block);
+        if let Some(level) = section {
+            if level == 1 {
+                self.out.push_str("<div class=\"sectionbody\">\n");
+            }
+            self.sections.push(level);
+        }
+    }
+
+    // Close the sections at `level` and deeper.
+    fn close_sections(&mut self, level: u8) {
+        while self.sections.last().is_some_and(|last| *last >= level) {
+            if self.sections.pop() == Some(1) {
+                self.out.push_str("</div>\n");
+            }
+            self.out.push_str("</div>\n");
+        }
+    }
+
+    // Rendered in place of the diagram's source. Sanitized output embeds SVG as an image, where
+    // script in it doesn't run.
+    fn diagram(&mut self, diagram: &Diagram) {
+        let alt = format!("{} diagram", diagram.kind.name());
+        let content = match &diagram.output {
+            DiagramOutput::Svg(svg) if self.options.sanitize => format!(
+                "<img src=\"data:image/svg+xml;base64,{}\" alt=\"{}\">",
+                STANDARD.encode(svg),
+                alt
+            ),
+            DiagramOutput::Svg(svg) => svg.trim().to_string(),
+            DiagramOutput::Path(path) => {
+                format!("<img src=\"{}\" alt=\"{}\">", escape(path), alt)
+            }
+        };
+        self.out.push_str(&match self.options.profile {
+            HtmlProfile::Semantic => format!(
+                "<figure class=\"diagram {}\">\n{}\n</figure>\n",
+                diagram.kind.name(),
+                content
+            ),
+            HtmlProfile::Asciidoctor => format!(
+                "<div class=\"imageblock {}\">\n<div class=\"content\">\n{}\n</div>\n</div>\n",
+                diagram.kind.name(),
+                content
+            ),
+        });
+    }
+
+    fn node(&mut self, block: &Block) {
+        #[cfg(feature = "templates")]
+        if let Some(templates) = &self.options.templates {
+            if templates.has_template_for(block) {
+                let content = self.fork(|renderer| renderer.inlines(block.inlines()));
+                let default = self.fork(|renderer| renderer.default_block(block));
+                let html = templates.render_block(block, content, default);
+                self.out.push_str(&html);
+                return;
+            }
+        }
+        self.default_block(block)
+    }
+
+    fn default_block(&mut self, block: &Block) {
+        match self.options.profile {
+            HtmlProfile::Semantic => self.semantic_block(block),
+            HtmlProfile::Asciidoctor => self.asciidoctor_block(block),
+        }
+    }
+
+    fn semantic_block(&mut self, block: &Block) {
+        match block {
+            Block::Paragraph { children } => {
+                self.out.push_str("<p>");
+                self.inlines(children);
+                self.out.push_str("</p>\n");
+            }
+            Block::Macro { children } => {
+                self.out.push_str("<figure>");
+                self.inline(children);
+                self.out.push_str("</figure>\n");
+            }
+            Block::Heading {
+                level,
+                children,
+                id,
+            } => {
+                let tag = heading_tag(level);
+                self.out.push('<');
+                self.out.push_str(tag);
+                let id = id
+                    .as_ref()
+                    .or_else(|| self.prepared.heading_ids.get(&(block as *const Block)));
+                if let Some(id) = id {
+                    self.out.push_str(&format!(" id=\"{}\"", escape(id)));
+                }
+                self.out.push('>');
+                self.inlines(children);
+                self.out.push_str(&format!("</{}>\n", tag));
+            }
+            Block::HorizontalRuledLine => self.out.push_str("<hr>\n"),
+            Block::NextPage => self
+                .out
+                .push_str("<div style=\"page-break-after: always\"></div>\n"),
+            Block::UnorderdList { children } => self.list("ul", children),
+            Block::OrderdList { children } => self.list("ol", children),
+            Block::Label { children, key } => {
+                self.out.push_str("<dl>\n<dt>");
+                self.inlines(key);
+                self.out.push_str("</dt>\n<dd>");
+                self.inlines(children);
+                self.out.push_str("</dd>\n</dl>\n");
+            }
+            Block::Qanda { question, answer } => {
+                self.out.push_str("<ol class=\"qanda\">\n<li>\n<p><em>");
+                self.inlines(question);
+                self.out.push_str("</em></p>\n<p>");
+                self.inlines(answer);
+                self.out.push_str("</p>\n</li>\n</ol>\n");
+            }
+            Block::CodeBlock {
+                children,
+                title,
+                file_type,
+            } => {
+                self.out.push_str("<figure class=\"listingblock\">\n");
+                if let Some(title) = title {
+                    self.out
+                        .push_str(&format!("<figcaption>{}</figcaption>\n", escape(title)));
+                }
+                match file_type {
+                    Some(file_type) => self.out.push_str(&format!(
+                        "<pre><code class=\"language-{}\">",
+                        escape(file_type)
+                    )),
+                    None => self.out.push_str("<pre><code>"),
+                }
+                match self.highlight(children, file_type.as_deref()) {
+                    Some(highlighted) => self.out.push_str(&highlighted),
+                    None => self.inlines(children),
+                }
+                self.out.push_str("</code></pre>\n</figure>\n");
+            }
+            Block::Block {
+                kind,
+                children,
+                metas,
+                title,
+            } => self.delimited_block(*kind, children, metas, title.as_deref()),
+            Block::Table {
+                columns,
+                rows,
+                title,
+            } => {
+                self.out.push_str("<table>\n");
+                if let Some(title) = title {
+                    self.out
+                        .push_str(&format!("<caption>{}</caption>\n", escape(title)));
+                }
+                if !columns.is_empty() {
+                    self.out.push_str("<thead>\n<tr>");
+                    for column in columns {
+                        self.out
+                            .push_str(&format!("<th>{}</th>", escape(column.name())));
+                    }
+                    self.out.push_str("</tr>\n</thead>\n");
+                }
+                self.out.push_str("<tbody>\n");
+                for row in rows {
+                    self.out.push_str("<tr>");
+                    for cell in row.cells() {
+                        self.out.push_str("<td>\n");
+                        self.nested_blocks(&cell.blocks, &cell.metas);
+                        self.out.push_str("</td>");
+                    }
+                    self.out.push_str("</tr>\n");
+                }
+                self.out.push_str("</tbody>\n</table>\n");
+            }
+            Block::BlankBlock => {}
+        }
+    }
+
+    fn highlight(&self, children: &[Inline], language: Option<&str>) -> Option<String> {
+        let highlighter = self.options.highlighter.as_ref()?;
+        highlighter.highlight(&inline_text(children), language?)
+    }
+
+    // Footnote texts at the end of the document, each linking back to its reference.
+    fn footnotes(&mut self, footnotes: &[Footnote]) {
+        if footnotes.is_empty() {
+            return;
+        }
+        self.out.push_str("<div id=\"footnotes\">\n<hr>\n");
+        for footnote in footnotes {
+            self.out.push_str(&format!(
+                "<div class=\"footnote\" id=\"_footnotedef_{0}\">\n<a href=\"#_footnoteref_{0}\">{0}</a>. ",
+                footnote.number
+            ));
+            self.inlines(std::slice::from_ref(footnote.content));
+            self.out.push_str("\n</div>\n");
+        }
+        self.out.push_str("</div>\n");
+    }
+
+    // Items deeper than the current level open a nested list inside the previous item.
+    fn list(&mut self, tag: &str, items: &[ListItem]) {
+        let (open, close) = self.list_tags(tag, items);
+        let mut levels: Vec<u32> = vec![];
+        for item in items {
+            let level = item.level();
+            while levels.last().is_some_and(|last| *last > level) {
+                levels.pop();
+                self.out.push_str("</li>\n");
+                self.out.push_str(&close);
+            }
+            if levels.last().is_some_and(|last| *last == level) {
+                self.out.push_str("</li>\n");
+            } else {
+                levels.push(level);
+                self.out.push_str(&open);
+            }
+
+            match self.options.profile {
+                HtmlProfile::Semantic => {
+                    self.out.push_str("<li>");
+                    if let ListItem::Check { checked, .. } = item {
+                        let checked = if *checked { " checked" } else { "" };
+                        self.out
+                            .push_str(&format!("<input type=\"checkbox\" disabled{}> ", checked));
+                    }
+                    self.inlines(item.children());
+                }
+                HtmlProfile::Asciidoctor => {
+                    self.out.push_str("<li>\n<p>");
+                    match item {
+                        ListItem::Check { checked: true, .. } => self.out.push_str("&#10003; "),
+                        ListItem::Check { checked: false, .. } => self.out.push_str("&#10063; "),
+                        ListItem::Normal { .. } => {}
+                    }
+                    self.inlines(item.children());
+                    self.out.push_str("</p>\n");
+                }
+            }
+        }
+        for _ in levels {
+            self.out.push_str("</li>\n");
+            self.out.push_str(&close);
+        }
+    }
+
+    // Passthroughs are written as they are, unless sanitizing, and comments not at all.
+    fn delimited_block(
+        &mut self,
+        kind: DelimitedKind,
+        children: &[Block],
+        metas: &[Option<BlockMeta>],
+        title: Option<&InlineVec>,
+    ) {
+        match kind {
+            DelimitedKind::Comment => return,
+            DelimitedKind::Pass => {
+                let text = children
+                    .iter()
+                    .map(block_text)
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                match self.options.sanitize {
+                    true => self.out.push_str(&escape_special_chars(&text)),
+                    false => self.out.push_str(&text),
+                }
+                self.out.push('\n');
+                return;
+            }
+            _ => {}
+        }
+        let wrapper = format!("<div class=\"{}block\">\n", kind.name());
+        let (open, close, content_open, content_close) = match (self.options.profile, kind) {
+            (HtmlProfile::Semantic, DelimitedKind::Quote) => {
+                ("<blockquote>\n".to_string(), "</blockquote>\n", "", "")
+            }
+            (HtmlProfile::Semantic, DelimitedKind::Sidebar) => (
+                "<aside class=\"sidebarblock\">\n".to_string(),
+                "</aside>\n",
+                "",
+                "",
+            ),
+            (HtmlProfile::Semantic, _) => (wrapper, "</div>\n", "", ""),
+            (HtmlProfile::Asciidoctor, DelimitedKind::Quote) => {
+                (wrapper, "</div>\n", "<blockquote>\n", "</blockquote>\n")
+            }
+            (HtmlProfile::Asciidoctor, _) => {
+                (wrapper, "</div>\n", "<div class=\"content\">\n", "</div>\n")
+            }
+        };
+        self.out.push_str(&open);
+        if let Some(title) = title {
+            self.out.push_str("<div class=\"title\">");
+            self.inlines(title);
+            self.out.push_str("</div>\n");
+        }
+        self.out.push_str(content_open);
+        self.nested_blocks(children, metas);
+        self.out.push_str(content_close);
+        self.out.push_str(close);
+    }
+
+    // Nested blocks take the substitutions of their own attribute line, the ones of the block
+    // around them otherwise.
+    fn nested_blocks(&mut self, blocks: &[Block], metas: &[Option<BlockMeta>]) {
+        let subs = self.subs;
+        for (index, block) in blocks.iter().enumerate() {
+            if let Some(meta) = metas.get(index).and_then(Option::as_ref) {
+                self.subs = Substitutions::from_meta(Some(meta));
+            }
+            self.block(block);
+            self.subs = subs;
+        }
+    }
+
+    fn list_tags(&self, tag: &str, items: &[ListItem]) -> (String, String) {
+        match self.options.profile {
+            HtmlProfile::Semantic => (format!("<{}>\n", tag), format!("</{}>\n", tag)),
+            HtmlProfile::Asciidoctor => {
+                let checklist = items
+                    .iter()
+                    .any(|item| matches!(item, ListItem::Check { .. }));
+                let (wrapper, class) = match tag {
+                    "ol" => ("olist arabic", " class=\"arabic\""),
+                    _ if checklist => ("ulist checklist", " class=\"checklist\""),
+                    _ => ("ulist", ""),
+                };
+                (
+                    format!("<div class=\"{}\">\n<{}{}>\n", wrapper, tag, class),
+                    format!("</{}>\n</div>\n", tag),
+                )
+            }
+        }
+    }
+
+    // Markup of asciidoctor's html5 converter, see `HtmlProfile::Asciidoctor`.
+    fn asciidoctor_block(&mut self, block: &Block) {
+        match block {
+            Block::Paragraph { children } => {
+                self.out.push_str("<div class=\"paragraph\">\n<p>");
+                self.inlines(children);
+                self.out.push_str("</p>\n</div>\n");
+            }
+            Block::Macro { children } => {
+                let kind = match children {
+                    Inline::Macro { kind, .. } => kind.as_str(),
+                    _ => "macro",
+                };
+                self.out.push_str(&format!(
+                    "<div class=\"{}block\">\n<div class=\"content\">\n",
+                    escape(kind)
+                ));
+                self.inline(children);
+                self.out.push_str("\n</div>\n</div>\n");
+            }
+            Block::Heading {
+                level: HeadingLevel::Title,
+                children,
+                ..
+            } => {
+                self.out.push_str("<div id=\"header\">\n<h1>");
+                self.inlines(children);
+                self.out.push_str("</h1>\n</div>\n");
+            }
+            Block::Heading { .. } | Block::UnorderdList { .. } | Block::OrderdList { .. } => {
+                self.semantic_block(block)
+            }
+            Block::HorizontalRuledLine => self.out.push_str("<hr>\n"),
+            Block::NextPage => self
+                .out
+                .push_str("<div style=\"page-break-after: always;\"></div>\n"),
+            Block::Label { children, key } => {
+                self.out
+                    .push_str("<div class=\"dlist\">\n<dl>\n<dt class=\"hdlist1\">");
+                self.inlines(key);
+                self.out.push_str("</dt>\n<dd>\n<p>");
+                self.inlines(children);
+                self.out.push_str("</p>\n</dd>\n</dl>\n</div>\n");
+            }
+            Block::Qanda { question, answer } => {
+                self.out
+                    .push_str("<div class=\"qlist qanda\">\n<ol>\n<li>\n<p><em>");
+                self.inlines(question);
+                self.out.push_str("</em></p>\n<p>");
+                self.inlines(answer);
+                self.out.push_str("</p>\n</li>\n</ol>\n</div>\n");
+            }
+            Block::CodeBlock {
+                children,
+                title,
+                file_type,
+            } => {
+                self.out.push_str("<div class=\"listingblock\">\n");
+                if let Some(title) = title {
+                    self.out
+                        .push_str(&format!("<div class=\"title\">{}</div>\n", escape(title)));
+                }
+                self.out.push_str("<div class=\"content\">\n");
+                match file_type {
+                    Some(file_type) => self.out.push_str(&format!(
+                        "<pre class=\"highlight\"><code class=\"language-{0}\" data-lang=\"{0}\">",
+                        escape(file_type)
+                    )),
+                    None => self.out.push_str("<pre>"),
+                }
+                match self.highlight(children, file_type.as_deref()) {
+                    Some(highlighted) => self.out.push_str(&highlighted),
+                    None => self.inlines(children),
+                }
+                match file_type {
+                    Some(_) => self.out.push_str("</code></pre>\n"),
+                    None => self.out.push_str("</pre>\n"),
+                }
+                self.out.push_str("</div>\n</div>\n");
+            }
+            Block::Block {
+                kind,
+                children,
+                metas,
+                title,
+            } => self.delimited_block(*kind, children, metas, title.as_deref()),
+            Block::Table {
+                columns,
+                rows,
+                title,
+            } => {
+                let cell = "tableblock halign-left valign-top";
+                self.out
+                    .push_str("<table class=\"tableblock frame-all grid-all stretch\">\n");
+                if let Some(title) = title {
+                    self.out.push_str(&format!(
+                        "<caption class=\"title\">{}</caption>\n",
+                        escape(title)
+                    ));
+                }
+                if !columns.is_empty() {
+                    self.out.push_str("<thead>\n<tr>\n");
+                    for column in columns {
+                        self.out.push_str(&format!(
+                            "<th class=\"{}\">{}</th>\n",
+                            cell,
+                            escape(column.name())
+                        ));
+                    }
+                    self.out.push_str("</tr>\n</thead>\n");
+                }
+                self.out.push_str("<tbody>\n");
+                for row in rows {
+                    self.out.push_str("<tr>\n");
+                    for row_cell in row.cells() {
+                        self.out.push_str(&format!("<td class=\"{}\">", cell));
+                        for block in &row_cell.blocks {
+                            self.out.push_str("<p class=\"tableblock\">");
+                            self.inlines(block.inlines());
+                            self.out.push_str("</p>");
+                        }
+                        self.out.push_str("</td>\n");
+                    }
+                    self.out.push_str("</tr>\n");
+                }
+                self.out.push_str("</tbody>\n</table>\n");
+            }
+            Block::BlankBlock => {}
+        }
+    }
+
+    fn inlines(&mut self, inlines: &[Inline]) {
+        if !self.options.search_terms.is_empty() {
+            self.marks = find_terms(&inline_text(inlines), &self.options.search_terms);
+            self.text_offset = 0;
+        }
+        for inline in inlines {
+            self.inline(inline);
+        }
+    }
+
+    fn inline(&mut self, inline: &Inline) {
+        match inline {
+            Inline::Value(value) => self.text(value),
+            Inline::HardBreak => {
+                self.out.push_str("<br>\n");
+                self.text_offset += 1;
+            }
+            Inline::SoftBreak => {
+                self.out.push('\n');
+                self.text_offset += 1;
+            }
+            Inline::Literal { children } => self.inline(children),
+            Inline::Footnote { children, .. } => {
+                match self
+                    .prepared
+                    .footnote_numbers
+                    .get(&(inline as *const Inline))
+                {
+                    Some(number) => {
+                        self.out.push_str(&format!(
+                            "<sup class=\"footnote\">[<a id=\"_footnoteref_{0}\" class=\"footnote\" href=\"#_footnotedef_{0}\" title=\"View footnote.\">{0}</a>]</sup>",
+                            number
+                        ));
+                        // The text is part of the plain text the marks were found in.
+                        self.text_offset += inline_text(std::slice::from_ref(children)).len();
+                    }
+                    None => self.wrap("<sup class=\"footnote\">", children, "</sup>"),
+                }
+            }
+            Inline::Lead { children } => self.wrap("<span class=\"lead\">", children, "</span>"),
+            Inline::Bold { children } => self.wrap("<strong>", children, "</strong>"),
+            Inline::Italic { children } => self.wrap("<em>", children, "</em>"),
+            Inline::Monospace { children } | Inline::InlineCode { children } => {
+                self.wrap("<code>", children, "</code>")
+            }
+            Inline::Marker { children } => self.wrap("<mark>", children, "</mark>"),
+            Inline::Styled { roles, children } => {
+                let open = format!(
+                    "<span class=\"{}\">",
+                    escape(&self.options.roles.html_classes(roles))
+                );
+                self.wrap(&open, children, "</span>")
+            }
+            Inline::Macro {
+                attributes,
+                kind,
+                id,
+            } => self.inline_macro(kind, id, attributes),
+        }
+    }
+
+    // Text is only written unescaped in passthrough blocks, and never when sanitizing.
+    // Text inside a search term match is wrapped in `<mark>`.
+    fn text(&mut self, value: &str) {
+        let start = self.text_offset;
+        let end = start + value.len();
+        self.text_offset = end;
+
+        let mut position = start;
+        for (mark_start, mark_end) in self.marks.clone() {
+            if mark_end <= start || mark_start >= end {
+                continue;
+            }
+            let mark_start = mark_start.max(start);
+            let mark_end = mark_end.min(end);
+            self.escaped_text(&value[position - start..mark_start - start]);
+            self.out.push_str("<mark>");
+            self.escaped_text(&value[mark_start - start..mark_end - start]);
+            self.out.push_str("</mark>");
+            position = mark_end;
+        }
+        self.escaped_text(&value[position - start..]);
+    }
+
+    fn escaped_text(&mut self, value: &str) {
+        if self.subs.special_chars || self.options.sanitize {
+            self.out.push_str(&escape_special_chars(value));
+        } else {
+            self.out.push_str(value);
+        }
+    }
+
+    fn wrap(&mut self, open: &str, inline: &Inline, close: &str) {
+        self.out.push_str(open);
+        self.inline(inline);
+        self.out.push_str(close);
+    }
+
+    fn inline_macro(&mut self, kind: &str, id: &str, attributes: &AttrList) {
+        let text = attributes
+            .get("alt")
+            .or_else(|| attributes.positional(0))
+            .map(str::to_string);
+        if let Some(allowed) = &self.options.allowed_macros {
+            if !allowed.iter().any(|allowed| allowed == kind) {
+                let source = format!("{}:{}{}", kind, id, attribute_list(attributes));
+                self.escaped_text(&source);
+                return;
+            }
+        }
+        if let Some(notation) = MathNotation::of_macro(kind, self.prepared.stem.as_deref()) {
+            let source = attributes.positionals().collect::<Vec<_>>().join(",");
+            self.out
+                .push_str(&inline_math(self.options.math, notation, &source));
+            return;
+        }
+        match kind {
+            "image" => {
+                let src = self.prepared.images.src(id);
+                let alt = escape(&text.unwrap_or_default());
+                if self.is_unsafe_url(&src, true) {
+                    self.out.push_str(&alt);
+                } else {
+                    self.out
+                        .push_str(&format!("<img src=\"{}\" alt=\"{}\">", escape(&src), alt));
+                }
+            }
+            "video" => {
+                let provider = VideoProvider::of_macro(id, attributes);
+                let src = provider.embed_url(id);
+                if self.is_unsafe_url(&src, false) {
+                    self.escaped_text(id);
+                    return;
+                }
+                let src = escape(&src);
+                match provider {
+                    VideoProvider::Local => self
+                        .out
+                        .push_str(&format!("<video src=\"{}\" controls></video>", src)),
+                    VideoProvider::Youtube | VideoProvider::Vimeo => self.out.push_str(&format!(
+                        "<iframe src=\"{}\" frameborder=\"0\" allowfullscreen></iframe>",
+                        src
+                    )),
+                }
+            }
+            "link" | "http" | "https" | "mailto" => {
+                let href = if kind == "link" {
+                    id.to_string()
+                } else {
+                    format!("{}:{}", kind, id)
+                };
+                let text = text.unwrap_or_else(|| href.clone());
+                if self.is_unsafe_url(&href, false) {
+                    self.out.push_str(&escape(&text));
+                    return;
+                }
+                self.out.push_str(&format!(
+                    "<a href=\"{}\">{}</a>",
+                    escape(&href),
+                    escape(&text)
+                ));
+            }
+            _ => self.out.push_str(&format!(
+                "<span class=\"{}\">{}</span>",
+                escape(kind),
+                escape(id)
+            )),
+        }
+    }
+
+    fn is_unsafe_url(&self, url: &str, image: bool) -> bool {
+        if !self.options.sanitize {
+            return false;
+        }
+        match url_scheme(url) {
+            None => false,
+            Some(scheme) if SAFE_URL_SCHEMES.contains(&scheme.as_str()) => false,
+            // Inline images are common in user content and can't run script as `<img>`.
+            Some(scheme) if scheme == "data" && image => {
+                let url = url.trim_start().to_ascii_lowercase();
+                !SAFE_DATA_IMAGE_TYPES
+                    .iter()
+                    .any(|media_type| url.starts_with(&format!("data:{}", media_type)))
+            }
+            Some(_) => true,
+        }
+    }
+}
+
+const SAFE_URL_SCHEMES: &[&str] = &["http", "https", "mailto", "ftp", "tel", "irc"];
+const SAFE_DATA_IMAGE_TYPES: &[&str] = &["image/png", "image/gif", "image/jpeg", "image/webp"];
+
+// Scheme of an absolute URL, lowercased. Browsers ignore control characters and whitespace
+// inside the scheme (`java\tscript:`), so they are ignored here too.
+fn url_scheme(url: &str) -> Option<String> {
+    let mut scheme = String::new();
+    for c in url.chars() {
+        match c {
+            ':' => {
+                return if scheme.is_empty() {
+                    None
+                } else {
+                    Some(scheme)
+                }
+            }
+            c if c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.' => {
+                scheme.push(c.to_ascii_lowercase())
+            }
+            c if c.is_ascii_control() || c == ' ' => {}
+            _ => return None,
+        }
+    }
+    None
+}
+
+// Non-overlapping byte ranges of `terms` in `text`, in order.
+fn find_terms(text: &str, terms: &[String]) -> Vec<(usize, usize)> {
+    let haystack = text.to_ascii_lowercase();
+    let mut ranges = vec![];
+    for term in terms.iter().filter(|term| !term.is_empty()) {
+        let needle = term.to_ascii_lowercase();
+        ranges.extend(
+            haystack
+                .match_indices(&needle)
+                .map(|(start, matched)| (start, start + matched.len())),
+        );
+    }
+    ranges.sort_unstable();
+
+    let mut merged: Vec<(usize, usize)> = vec![];
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+fn heading_tag(level: &HeadingLevel) -> &'static str {
+    match level {
+        HeadingLevel::Title => "h1",
+        HeadingLevel::Level1 => "h2",
+        HeadingLevel::Level2 => "h3",
+        HeadingLevel::Level3 => "h4",
+        HeadingLevel::Level4 => "h5",
+    }
+}
+
+pub(crate) fn escape(s: &str) -> String {
+    escape_attribute(s).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::roles::RoleStyle;
+    use crate::{FootnoteType, ParseOptions};
+    use pretty_assertions::assert_eq;
+    use smallvec::smallvec;
+
+    #[test]
+    fn test_render() {
+        let document =
+            Document::parse("= Title\n\nThis is *bold* & <raw>\n\n* foo\n** bar\n* [x] baz")
+                .unwrap();
+        assert_eq!(
+            render(&document),
+            "<h1>Title</h1>
+<p>This is <strong>bold</strong> &amp; &lt;raw&gt;</p>
+<ul>
+<li>foo<ul>
+<li>bar</li>
+</ul>
+</li>
+<li><input type=\"checkbox\" disabled checked> baz</li>
+</ul>
+"
+        );
+    }
+
+    #[test]
+    fn test_render_inline_macro() {
+        let options = HtmlOptions::default();
+        let mut renderer = HtmlRenderer::new(&options, &[]);
+        renderer.inline(&Inline::Macro {
+            attributes: Box::new(AttrList::from(vec!["Example".to_string()])),
+            kind: "https".into(),
+            id: "//example.com".to_string(),
+        });
+        assert_eq!(renderer.out, "<a href=\"https://example.com\">Example</a>");
+
+        let video = |target: &str, attributes: Vec<String>| {
+            let mut renderer = HtmlRenderer::new(&options, &[]);
+            renderer.inline(&Inline::Macro {
+                attributes: Box::new(AttrList::from(attributes)),
+                kind: "video".into(),
+                id: target.to_string(),
+            });
+            renderer.out
+        };
+        assert_eq!(
+            video("76979871", vec!["vimeo".to_string()]),
+            "<iframe src=\"https://player.vimeo.com/video/76979871\" frameborder=\"0\" allowfullscreen></iframe>"
+        );
+        assert_eq!(
+            video("intro.mp4", vec![]),
+            "<video src=\"intro.mp4\" controls></video>"
+        );
+    }
+
+    #[test]
+    fn test_render_asciidoctor_profile() {
+        let document = Document::parse(
+            "= Title\n\nIntro\n\n== Section\n\nText\n\n=== Sub\n\n* foo\n** [x] bar\n\n\n== Next",
+        )
+        .unwrap();
+        let options = HtmlOptions::new()
+            .profile(HtmlProfile::Asciidoctor)
+            .heading_ids(SlugStrategy::Unicode);
+        assert_eq!(
+            render_with_options(&document, &options),
+            "<div id=\"header\">
+<h1>Title</h1>
+</div>
+<div id=\"preamble\">
+<div class=\"sectionbody\">
+<div class=\"paragraph\">
+<p>Intro</p>
+</div>
+</div>
+</div>
+<div class=\"sect1\">
+<h2 id=\"_section\">Section</h2>
+<div class=\"sectionbody\">
+<div class=\"paragraph\">
+<p>Text</p>
+</div>
+<div class=\"sect2\">
+<h3 id=\"_sub\">Sub</h3>
+<div class=\"ulist checklist\">
+<ul class=\"checklist\">
+<li>
+<p>foo</p>
+<div class=\"ulist checklist\">
+<ul class=\"checklist\">
+<li>
+<p>&#10003; bar</p>
+</li>
+</ul>
+</div>
+</li>
+</ul>
+</div>
+</div>
+</div>
+</div>
+<div class=\"sect1\">
+<h2 id=\"_next\">Next</h2>
+<div class=\"sectionbody\">
+</div>
+</div>
+"
+        );
+    }
+
+    #[test]
+    fn test_render_footnotes() {
+        let document = Document::new(vec![Block::Paragraph {
+            children: smallvec![
+                Inline::Value("See".to_string()),
+                Inline::Footnote {
+                    kind: FootnoteType::Note,
+                    children: Box::new(Inline::Bold {
+                        children: Box::new(Inline::Value("details".to_string())),
+                    }),
+                },
+            ]
+            .into(),
+        }]);
+        assert_eq!(
+            render(&document),
+            "<p>See<sup class=\"footnote\">[<a id=\"_footnoteref_1\" class=\"footnote\" href=\"#_footnotedef_1\" title=\"View footnote.\">1</a>]</sup></p>
+<div id=\"footnotes\">
+<hr>
+<div class=\"footnote\" id=\"_footnotedef_1\">
+<a href=\"#_footnoteref_1\">1</a>. <strong>details</strong>
+</div>
+</div>
+"
+        );
+    }
+
+    #[test]
+    fn test_render_parsed_footnotes() {
+        let document = Document::parse("A claim.footnote:[See the source.]").unwrap();
+        assert_eq!(
+            render(&document),
+            "<p>A claim.<sup class=\"footnote\">[<a id=\"_footnoteref_1\" class=\"footnote\" href=\"#_footnotedef_1\" title=\"View footnote.\">1</a>]</sup></p>
+<div id=\"footnotes\">
+<hr>
+<div class=\"footnote\" id=\"_footnotedef_1\">
+<a href=\"#_footnoteref_1\">1</a>. See the source.
+</div>
+</div>
+"
+        );
+    }
+
+    #[test]
+    fn test_heading_ids() {
+        let document = Document::new(vec![
+            Block::Heading {
+                level: HeadingLevel::Level1,
+                children: smallvec![Inline::Value("Install".to_string())],
+                id: None,
+            },
+            Block::Heading {
+                level: HeadingLevel::Level1,
+                children: smallvec![Inline::Value("Usage".to_string())],
+                id: Some("_install_2".to_string()),
+            },
+            Block::Heading {
+                level: HeadingLevel::Level1,
+                children: smallvec![Inline::Value("Install".to_string())],
+                id: None,
+            },
+        ]);
+        assert_eq!(
+            render_with_options(&document, &HtmlOptions::new().heading_ids(SlugStrategy::Unicode)),
+            "<h2 id=\"_install\">Install</h2>\n<h2 id=\"_install_2\">Usage</h2>\n<h2 id=\"_install_3\">Install</h2>\n"
+        );
+        assert_eq!(
+            render(&document),
+            "<h2>Install</h2>\n<h2 id=\"_install_2\">Usage</h2>\n<h2>Install</h2>\n"
+        );
+    }
+
+    #[test]
+    fn test_passthrough_block() {
+        let document = Document::parse("[pass]\nx <b>raw</b>\n\nx <b>text</b>").unwrap();
+        assert_eq!(
+            render(&document),
+            "<p>x <b>raw</b></p>\n<p>x &lt;b&gt;text&lt;/b&gt;</p>\n"
+        );
+        assert_eq!(
+            render_with_options(&document, &HtmlOptions::new().sanitize(true)),
+            "<p>x &lt;b&gt;raw&lt;/b&gt;</p>\n<p>x &lt;b&gt;text&lt;/b&gt;</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_render_delimited_kinds() {
+        let document = Document::parse(
+            "////\n<b>\n////\n\n****\nside\n****\n\n____\nquoted\n____\n\n++++\n<b>raw</b>\n++++",
+        )
+        .unwrap();
+        assert_eq!(
+            render(&document),
+            "<aside class=\"sidebarblock\">\n<p>side</p>\n</aside>\n<blockquote>\n<p>quoted</p>\n</blockquote>\n<b>raw</b>\n"
+        );
+        assert_eq!(
+            render_with_options(&document, &HtmlOptions::new().profile(HtmlProfile::Asciidoctor)),
+            "<div class=\"sidebarblock\">\n<div class=\"content\">\n<div class=\"paragraph\">\n<p>side</p>\n</div>\n</div>\n</div>\n<div class=\"quoteblock\">\n<blockquote>\n<div class=\"paragraph\">\n<p>quoted</p>\n</div>\n</blockquote>\n</div>\n<b>raw</b>\n"
+        );
+        assert_eq!(
+            render_with_options(&document, &HtmlOptions::new().sanitize(true)),
+            "<aside class=\"sidebarblock\">\n<p>side</p>\n</aside>\n<blockquote>\n<p>quoted</p>\n</blockquote>\n&lt;b&gt;raw&lt;/b&gt;\n"
+        );
+    }
+
+    #[test]
+    fn test_render_nested_passthrough() {
+        let document = Document::parse("====\n[pass]\n<b>raw</b>\n\n<b>text</b>\n====").unwrap();
+        assert_eq!(
+            render(&document),
+            "<div class=\"exampleblock\">\n<p><b>raw</b></p>\n<p>&lt;b&gt;text&lt;/b&gt;</p>\n</div>\n"
+        );
+    }
+
+    #[test]
+    fn test_render_nested_blocks() {
+        let document = Document::parse("====\nsome\n\n----\n====\n----\n====").unwrap();
+        assert_eq!(
+            render(&document),
+            "<div class=\"exampleblock\">\n<p>some</p>\n<figure class=\"listingblock\">\n<pre><code>====</code></pre>\n</figure>\n</div>\n"
+        );
+    }
+
+    #[test]
+    fn test_render_image_with_imagesdir() {
+        let mut document =
+            Document::parse_with_options(":imagesdir: img\nx", &ParseOptions::new()).unwrap();
+        document.append(Document::new(vec![Block::Paragraph {
+            children: smallvec![Inline::Macro {
+                attributes: Box::new(AttrList::from(vec!["Logo".to_string()])),
+                kind: "image".into(),
+                id: "logo.png".to_string(),
+            }]
+            .into(),
+        }]));
+        assert_eq!(
+            render(&document),
+            "<p>x</p>\n<p><img src=\"img/logo.png\" alt=\"Logo\"></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_render_parsed_images() {
+        let document = Document::parse_with_options(
+            ":imagesdir: img\nAn image:logo.png[Logo] icon.\n\nimage::diagram.png[]",
+            &ParseOptions::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            render(&document),
+            "<p>An <img src=\"img/logo.png\" alt=\"Logo\"> icon.</p>\n<figure><img src=\"img/diagram.png\" alt=\"\"></figure>\n"
+        );
+        assert_eq!(
+            render_with_options(&document, &HtmlOptions::new().profile(HtmlProfile::Asciidoctor)),
+            "<div class=\"paragraph\">\n<p>An <img src=\"img/logo.png\" alt=\"Logo\"> icon.</p>\n</div>\n<div class=\"imageblock\">\n<div class=\"content\">\n<img src=\"img/diagram.png\" alt=\"\">\n</div>\n</div>\n"
+        );
+    }
+
+    #[test]
+    fn test_render_parsed_videos() {
+        let document =
+            Document::parse("video::76979871[vimeo]\n\nvideo::https://youtu.be/abc[]").unwrap();
+        assert_eq!(
+            render(&document),
+            "<figure><iframe src=\"https://player.vimeo.com/video/76979871\" frameborder=\"0\" allowfullscreen></iframe></figure>
+<figure><iframe src=\"https://www.youtube.com/embed/abc\" frameborder=\"0\" allowfullscreen></iframe></figure>
+"
+        );
+    }
+
+    #[test]
+    fn test_render_diagram() {
+        let mut document = Document::parse("[graphviz]\ndigraph { a -> b }").unwrap();
+        document.set_diagram(
+            0,
+            Diagram {
+                kind: crate::diagram::DiagramKind::Graphviz,
+                output: DiagramOutput::Svg("<svg></svg>\n".to_string()),
+            },
+        );
+        assert_eq!(
+            render(&document),
+            "<figure class=\"diagram graphviz\">\n<svg></svg>\n</figure>\n"
+        );
+        assert_eq!(
+            render_with_options(&document, &HtmlOptions::new().sanitize(true)),
+            "<figure class=\"diagram graphviz\">\n<img src=\"data:image/svg+xml;base64,PHN2Zz48L3N2Zz4K\" alt=\"graphviz diagram\">\n</figure>\n"
+        );
+    }
+
+    #[test]
+    fn test_render_stem() {
+        let mut document =
+            Document::parse_with_options(":stem: latexmath\nx", &ParseOptions::new()).unwrap();
+        document.append(Document::new(vec![Block::Paragraph {
+            children: smallvec![Inline::Macro {
+                attributes: Box::new(AttrList::from(vec!["a_1".to_string(), "b".to_string()])),
+                kind: "stem".into(),
+                id: "".to_string(),
+            }]
+            .into(),
+        }]));
+        assert_eq!(render(&document), "<p>x</p>\n<p>\\(a_1,b\\)</p>\n");
+    }
+
+    #[test]
+    fn test_render_parsed_stem() {
+        let document = Document::parse_with_options(
+            ":stem: latexmath\nstem:[a_1, b] and asciimath:[sqrt(4) = 2]",
+            &ParseOptions::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            render(&document),
+            "<p>\\(a_1, b\\) and \\$sqrt(4) = 2\\$</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_search_terms() {
+        let document = Document::new(vec![Block::Paragraph {
+            children: smallvec![
+                Inline::Value("Parse the ".to_string()),
+                Inline::Bold {
+                    children: Box::new(Inline::Value("doc".to_string())),
+                },
+                Inline::Value("ument & PARSER".to_string()),
+            ]
+            .into(),
+        }]);
+        let options = HtmlOptions::new().search_terms(vec!["parse", "document"]);
+        assert_eq!(
+            render_with_options(&document, &options),
+            "<p><mark>Parse</mark> the <strong><mark>doc</mark></strong><mark>ument</mark> &amp; <mark>PARSE</mark>R</p>\n"
+        );
+        assert_eq!(find_terms("aaa", &["aa".to_string()]), vec![(0, 2)]);
+        assert_eq!(
+            find_terms("abc", &["ab".to_string(), "bc".to_string()]),
+            vec![(0, 3)]
+        );
+    }
+
+    #[test]
+    fn test_render_roles() {
+        let document = Document::parse("[.keyword]#fn# and [.underline]#*u*#").unwrap();
+        let options = HtmlOptions::new()
+            .roles(RoleStyles::new().role("keyword", RoleStyle::new().class("hljs-keyword")));
+        assert_eq!(
+            render_with_options(&document, &options),
+            "<p><span class=\"keyword hljs-keyword\">fn</span> and <span class=\"underline\"><strong>u</strong></span></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_github_options() {
+        let document = Document::new(vec![
+            Block::Heading {
+                level: HeadingLevel::Level1,
+                children: smallvec![Inline::Value("Getting Started".to_string())],
+                id: None,
+            },
+            Block::Paragraph {
+                children: smallvec![
+                    Inline::Macro {
+                        attributes: Box::new(AttrList::from(vec!["x^2".to_string()])),
+                        kind: "stem".into(),
+                        id: "".to_string(),
+                    },
+                    Inline::Macro {
+                        attributes: Box::new(AttrList::from(vec![])),
+                        kind: "link".into(),
+                        id: "javascript:alert(1)".to_string(),
+                    },
+                ]
+                .into(),
+            },
+        ]);
+        assert_eq!(
+            render_with_options(&document, &HtmlOptions::github()),
+            "<div class=\"sect1\">
+<h2 id=\"user-content-getting-started\">Getting Started</h2>
+<div class=\"sectionbody\">
+<div class=\"paragraph\">
+<p>stem:[x^2]javascript:alert(1)</p>
+</div>
+</div>
+</div>
+"
+        );
+    }
+
+    #[test]
+    fn test_github_options_parsed() {
+        // Formulas and videos are left as written, images still render.
+        let document =
+            Document::parse("Area stem:[x^2] of image:square.png[Square] video:abc[]").unwrap();
+        assert_eq!(
+            render_with_options(&document, &HtmlOptions::github()),
+            "<div class=\"paragraph\">\n<p>Area stem:[x^2] of <img src=\"square.png\" alt=\"Square\"> video:abc[]</p>\n</div>\n"
+        );
+    }
+
+    #[test]
+    fn test_sanitize() {
+        let link = |target: &str| Inline::Macro {
+            attributes: Box::new(AttrList::from(vec!["click".to_string()])),
+            kind: "link".into(),
+            id: target.to_string(),
+        };
+        let image = |target: &str| Inline::Macro {
+            attributes: Box::new(AttrList::from(vec!["alt".to_string()])),
+            kind: "image".into(),
+            id: target.to_string(),
+        };
+        let render = |inline: Inline, sanitize: bool| {
+            render_inlines(&[inline], &HtmlOptions::new().sanitize(sanitize))
+        };
+
+        assert_eq!(
+            render(link("javascript:alert(1)"), false),
+            "<a href=\"javascript:alert(1)\">click</a>"
+        );
+        assert_eq!(render(link("javascript:alert(1)"), true), "click");
+        assert_eq!(render(link("Java\tScript:alert(1)"), true), "click");
+        assert_eq!(render(link("vbscript:msgbox"), true), "click");
+        assert_eq!(
+            render(link("https://example.com"), true),
+            "<a href=\"https://example.com\">click</a>"
+        );
+        assert_eq!(
+            render(link("docs/a:b.html"), true),
+            "<a href=\"docs/a:b.html\">click</a>"
+        );
+        assert_eq!(
+            render(image("data:image/png;base64,AAAA"), true),
+            "<img src=\"data:image/png;base64,AAAA\" alt=\"alt\">"
+        );
+        assert_eq!(render(image("data:text/html,<script>"), true), "alt");
+        assert_eq!(render(link("data:image/png;base64,AAAA"), true), "click");
+    }
+
+    #[test]
+    fn test_render_highlighted_code_block() {
+        struct Upper;
+        impl Highlighter for Upper {
+            fn highlight(&self, code: &str, language: &str) -> Option<String> {
+                if language == "shout" {
+                    Some(escape(&code.to_uppercase()))
+                } else {
+                    None
+                }
+            }
+        }
+        let options = HtmlOptions::new().highlighter(Upper);
+        let code_block = |file_type: &str| Block::CodeBlock {
+            children: smallvec![Inline::Value("a < b".to_string())],
+            title: None,
+            file_type: Some(file_type.into()),
+        };
+
+        assert_eq!(
+            render_block(&code_block("shout"), &options),
+            "<figure class=\"listingblock\">\n<pre><code class=\"language-shout\">A &lt; B</code></pre>\n</figure>\n"
+        );
+        assert_eq!(
+            render_block(&code_block("unknown"), &options),
+            "<figure class=\"listingblock\">\n<pre><code class=\"language-unknown\">a &lt; b</code></pre>\n</figure>\n"
+        );
+    }
+}
@@ -0,0 +1,262 @@
+use crate::document::Document;
+use crate::render::visitor::{render_with, Renderer};
+use crate::AttrList;
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Default)]
+pub struct ImageOptions {
+    // Overrides the document's `imagesdir` attribute.
+    pub images_dir: Option<String>,
+    // Directory local images are read from, normally the one holding the document.
+    pub base_dir: Option<PathBuf>,
+    // Embed local images of at most this many bytes as data URIs.
+    pub data_uri_max_bytes: Option<u64>,
+}
+
+impl ImageOptions {
+    pub fn new() -> Self {
+        ImageOptions::default()
+    }
+
+    pub fn images_dir<S: Into<String>>(mut self, images_dir: S) -> Self {
+        self.images_dir = Some(images_dir.into());
+        self
+    }
+
+    pub fn base_dir<P: Into<PathBuf>>(mut self, base_dir: P) -> Self {
+        self.base_dir = Some(base_dir.into());
+        self
+    }
+
+    pub fn data_uri_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.data_uri_max_bytes = Some(max_bytes);
+        self
+    }
+}
+
+// Turns image macro targets into `src` values.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ImageResolver {
+    images_dir: Option<String>,
+    base_dir: Option<PathBuf>,
+    data_uri_max_bytes: Option<u64>,
+}
+
+impl ImageResolver {
+    pub(crate) fn new(options: &ImageOptions, document: Option<&Document>) -> Self {
+        ImageResolver {
+            images_dir: options.images_dir.clone().or_else(|| {
+                document
+                    .and_then(|document| document.attribute("imagesdir"))
+                    .map(str::to_string)
+            }),
+            base_dir: options.base_dir.clone(),
+            data_uri_max_bytes: options.data_uri_max_bytes,
+        }
+    }
+
+    // Target relative to the document, `None` for URLs and absolute paths.
+    pub(crate) fn local_path(&self, target: &str) -> Option<String> {
+        if is_remote(target) || target.starts_with('/') {
+            return None;
+        }
+        Some(match self.images_dir.as_deref() {
+            Some(dir) if !dir.is_empty() => format!("{}/{}", dir.trim_end_matches('/'), target),
+            _ => target.to_string(),
+        })
+    }
+
+    pub(crate) fn src(&self, target: &str) -> String {
+        if is_remote(target) {
+            return target.to_string();
+        }
+        let path = match self.local_path(target) {
+            Some(path) => path,
+            None => return target.to_string(),
+        };
+        self.data_uri(&path).unwrap_or(path)
+    }
+
+    // Images that are missing or too large are linked instead.
+    fn data_uri(&self, path: &str) -> Option<String> {
+        let max_bytes = self.data_uri_max_bytes?;
+        let media_type = media_type(path)?;
+        let file = self
+            .base_dir
+            .as_deref()
+            .unwrap_or_else(|| Path::new("."))
+            .join(path);
+        if fs::metadata(&file).ok()?.len() > max_bytes {
+            return None;
+        }
+        let data = fs::read(&file).ok()?;
+        Some(format!(
+            "data:{};base64,{}",
+            media_type,
+            STANDARD.encode(data)
+        ))
+    }
+}
+
+fn is_remote(target: &str) -> bool {
+    target.starts_with("data:") || target.contains("://")
+}
+
+fn media_type(path: &str) -> Option<&'static str> {
+    let extension = Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        "svg" => Some("image/svg+xml"),
+        _ => None,
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Asset {
+    // Target as written in the image macro.
+    pub target: String,
+    // Path relative to the document after applying `imagesdir`.
+    pub path: String,
+}
+
+// Local images a document references, for copying next to the rendered output.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct AssetManifest {
+    pub assets: Vec<Asset>,
+}
+
+impl AssetManifest {
+    pub fn collect(document: &Document, options: &ImageOptions) -> Self {
+        let collector = render_with(
+            document,
+            AssetCollector {
+                resolver: ImageResolver::new(options, Some(document)),
+                manifest: AssetManifest::default(),
+            },
+        );
+        collector.manifest
+    }
+
+    // Copy every asset from `base_dir` to the same relative path under `out_dir`.
+    pub fn copy_to(&self, base_dir: &Path, out_dir: &Path) -> Result<Vec<PathBuf>> {
+        let mut copied = vec![];
+        for asset in &self.assets {
+            let from = base_dir.join(&asset.path);
+            let to = out_dir.join(&asset.path);
+            if let Some(parent) = to.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(&from, &to).with_context(|| format!("cannot copy {}", from.display()))?;
+            copied.push(to);
+        }
+        Ok(copied)
+    }
+
+    pub fn to_json(&self) -> String {
+        let assets = self
+            .assets
+            .iter()
+            .map(|asset| json!({ "target": asset.target, "path": asset.path }))
+            .collect::<Vec<_>>();
+        serde_json::to_string_pretty(&json!({ "assets": assets })).unwrap()
+    }
+}
+
+struct AssetCollector {
+    resolver: ImageResolver,
+    manifest: AssetManifest,
+}
+
+impl Renderer for AssetCollector {
+    fn inline_macro(&mut self, kind: &str, id: &str, _attributes: &AttrList) {
+        if kind != "image" {
+            return;
+        }
+        if let Some(path) = self.resolver.local_path(id) {
+            if self.manifest.assets.iter().all(|asset| asset.path != path) {
+                self.manifest.assets.push(Asset {
+                    target: id.to_string(),
+                    path,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Block, Inline, ParseOptions};
+    use pretty_assertions::assert_eq;
+    use std::env;
+
+    fn image(target: &str) -> Inline {
+        Inline::Macro {
+            attributes: Box::new(AttrList::from(vec![])),
+            kind: "image".into(),
+            id: target.to_string(),
+        }
+    }
+
+    fn document_with_images(targets: &[&str]) -> Document {
+        let mut document =
+            Document::parse_with_options(":imagesdir: images\nx", &ParseOptions::new()).unwrap();
+        document.append(Document::new(vec![Block::Paragraph {
+            children: targets.iter().map(|target| image(target)).collect(),
+        }]));
+        document
+    }
+
+    #[test]
+    fn test_image_src() {
+        let document = document_with_images(&[]);
+        let resolver = ImageResolver::new(&ImageOptions::new(), Some(&document));
+        assert_eq!(resolver.src("a.png"), "images/a.png");
+        assert_eq!(
+            resolver.src("https://example.com/a.png"),
+            "https://example.com/a.png"
+        );
+        assert_eq!(resolver.src("/abs/a.png"), "/abs/a.png");
+
+        let resolver =
+            ImageResolver::new(&ImageOptions::new().images_dir("static/"), Some(&document));
+        assert_eq!(resolver.src("a.png"), "static/a.png");
+    }
+
+    #[test]
+    fn test_data_uri() {
+        let dir = env::temp_dir().join(format!("combine-sandbox-images-{}", std::process::id()));
+        fs::create_dir_all(dir.join("images")).unwrap();
+        fs::write(dir.join("images/dot.png"), b"png").unwrap();
+        fs::write(dir.join("images/large.png"), vec![0; 64]).unwrap();
+
+        let document = document_with_images(&[]);
+        let options = ImageOptions::new().base_dir(&dir).data_uri_max_bytes(16);
+        let resolver = ImageResolver::new(&options, Some(&document));
+        assert_eq!(resolver.src("dot.png"), "data:image/png;base64,cG5n");
+        assert_eq!(resolver.src("large.png"), "images/large.png");
+        assert_eq!(resolver.src("missing.png"), "images/missing.png");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_asset_manifest() {
+        let document = document_with_images(&["a.png", "https://example.com/b.png", "a.png"]);
+        let manifest = AssetManifest::collect(&document, &ImageOptions::new());
+        assert_eq!(
+            manifest.assets,
+            vec![Asset {
+                target: "a.png".to_string(),
+                path: "images/a.png".to_string(),
+            }]
+        );
+    }
+}
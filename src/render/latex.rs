@@ -0,0 +1,385 @@
+use crate::document::Document;
+use crate::render::roles::RoleStyles;
+use crate::text::{cell_text, inline_text};
+use crate::{Block, DelimitedKind, HeadingLevel, Inline, ListItem};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CodeListing {
+    Listings,
+    // Needs `-shell-escape` and pygments when compiling.
+    Minted,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LatexOptions {
+    pub code_listing: CodeListing,
+    // Wrap the body in a complete `article` document with the packages it needs.
+    pub standalone: bool,
+    // Commands wrapping text with roles.
+    pub roles: RoleStyles,
+}
+
+impl Default for LatexOptions {
+    fn default() -> Self {
+        LatexOptions {
+            code_listing: CodeListing::Listings,
+            standalone: true,
+            roles: RoleStyles::default(),
+        }
+    }
+}
+
+pub fn render(document: &Document, options: &LatexOptions) -> String {
+    let mut body = String::new();
+    for block in document.blocks() {
+        render_block(&mut body, block, options);
+    }
+    if !options.standalone {
+        return body;
+    }
+
+    let code_package = match options.code_listing {
+        CodeListing::Listings => "\\usepackage{listings}",
+        CodeListing::Minted => "\\usepackage{minted}",
+    };
+    format!(
+        "\\documentclass{{article}}\n\\usepackage[utf8]{{inputenc}}\n\\usepackage{{amssymb}}\n\\usepackage{{hyperref}}\n\\usepackage{{soul}}\n{}\n\\begin{{document}}\n{}\\end{{document}}\n",
+        code_package, body
+    )
+}
+
+fn render_block(out: &mut String, block: &Block, options: &LatexOptions) {
+    match block {
+        Block::Paragraph { children } => {
+            out.push_str(&inlines(children, options));
+            out.push_str("\n\n");
+        }
+        Block::Macro { children } => {
+            out.push_str(&inlines(std::slice::from_ref(children), options));
+            out.push_str("\n\n");
+        }
+        Block::Heading {
+            level,
+            children,
+            id,
+        } => {
+            let title = inlines(children, options);
+            match level {
+                HeadingLevel::Title => {
+                    out.push_str(&format!("\\title{{{}}}\n\\maketitle\n\n", title));
+                    return;
+                }
+                HeadingLevel::Level1 => out.push_str(&format!("\\section{{{}}}", title)),
+                HeadingLevel::Level2 => out.push_str(&format!("\\subsection{{{}}}", title)),
+                HeadingLevel::Level3 => out.push_str(&format!("\\subsubsection{{{}}}", title)),
+                HeadingLevel::Level4 => out.push_str(&format!("\\paragraph{{{}}}", title)),
+            }
+            if let Some(id) = id {
+                out.push_str(&format!("\\label{{{}}}", escape(id)));
+            }
+            out.push_str("\n\n");
+        }
+        Block::HorizontalRuledLine => out.push_str("\\noindent\\rule{\\linewidth}{0.4pt}\n\n"),
+        Block::NextPage => out.push_str("\\newpage\n\n"),
+        Block::UnorderdList { children } => list(out, "itemize", children, options),
+        Block::OrderdList { children } => list(out, "enumerate", children, options),
+        Block::Label { children, key } => out.push_str(&format!(
+            "\\begin{{description}}\n\\item[{}] {}\n\\end{{description}}\n\n",
+            inlines(key, options),
+            inlines(children, options)
+        )),
+        Block::Qanda { question, answer } => out.push_str(&format!(
+            "\\textit{{{}}}\n\n{}\n\n",
+            inlines(question, options),
+            inlines(answer, options)
+        )),
+        Block::CodeBlock {
+            children,
+            title,
+            file_type,
+        } => {
+            let code = inline_text(children);
+            let code = code.trim_end_matches('\n');
+            match options.code_listing {
+                CodeListing::Listings => {
+                    let mut settings = vec![];
+                    if let Some(file_type) = file_type {
+                        settings.push(format!("language={}", file_type));
+                    }
+                    if let Some(title) = title {
+                        settings.push(format!("caption={{{}}}", escape(title)));
+                    }
+                    let settings = if settings.is_empty() {
+                        String::new()
+                    } else {
+                        format!("[{}]", settings.join(","))
+                    };
+                    out.push_str(&format!(
+                        "\\begin{{lstlisting}}{}\n{}\n\\end{{lstlisting}}\n\n",
+                        settings, code
+                    ));
+                }
+                CodeListing::Minted => {
+                    if let Some(title) = title {
+                        out.push_str(&format!("\\noindent\\textbf{{{}}}\n", escape(title)));
+                    }
+                    out.push_str(&format!(
+                        "\\begin{{minted}}{{{}}}\n{}\n\\end{{minted}}\n\n",
+                        file_type.as_deref().unwrap_or("text"),
+                        code
+                    ));
+                }
+            }
+        }
+        Block::Block {
+            kind: DelimitedKind::Comment,
+            ..
+        } => {}
+        Block::Block {
+            kind,
+            children,
+            title,
+            ..
+        } => {
+            if let Some(title) = title {
+                out.push_str(&format!(
+                    "\\noindent\\textbf{{{}}}\n\n",
+                    inlines(title, options)
+                ));
+            }
+            let quote = *kind == DelimitedKind::Quote;
+            if quote {
+                out.push_str("\\begin{quote}\n");
+            }
+            for block in children {
+                render_block(out, block, options);
+            }
+            if quote {
+                out.push_str("\\end{quote}\n\n");
+            }
+        }
+        Block::Table {
+            columns,
+            rows,
+            title,
+        } => {
+            let column_count = columns.len().max(1);
+            out.push_str("\\begin{table}[h]\n\\centering\n");
+            if let Some(title) = title {
+                out.push_str(&format!("\\caption{{{}}}\n", escape(title)));
+            }
+            out.push_str(&format!(
+                "\\begin{{tabular}}{{|{}}}\n\\hline\n",
+                "l|".repeat(column_count)
+            ));
+            if !columns.is_empty() {
+                let header = columns
+                    .iter()
+                    .map(|column| format!("\\textbf{{{}}}", escape(column.name())))
+                    .collect::<Vec<_>>();
+                out.push_str(&format!("{} \\\\\n\\hline\n", header.join(" & ")));
+            }
+            for row in rows {
+                let cells = row
+                    .cells()
+                    .iter()
+                    .map(|cell| escape(&cell_text(cell)))
+                    .collect::<Vec<_>>();
+                out.push_str(&format!("{} \\\\\n\\hline\n", cells.join(" & ")));
+            }
+            out.push_str("\\end{tabular}\n\\end{table}\n\n");
+        }
+        Block::BlankBlock => {}
+    }
+}
+
+fn list(out: &mut String, environment: &str, items: &[ListItem], options: &LatexOptions) {
+    let mut depth = 0;
+    for item in items {
+        let level = item.level().max(1);
+        while depth < level {
+            out.push_str(&format!("\\begin{{{}}}\n", environment));
+            depth += 1;
+        }
+        while depth > level {
+            out.push_str(&format!("\\end{{{}}}\n", environment));
+            depth -= 1;
+        }
+        let label = match item {
+            ListItem::Check { checked: true, .. } => "[$\\boxtimes$]",
+            ListItem::Check { checked: false, .. } => "[$\\square$]",
+            ListItem::Normal { .. } => "",
+        };
+        out.push_str(&format!(
+            "\\item{} {}\n",
+            label,
+            inlines(item.children(), options)
+        ));
+    }
+    for _ in 0..depth {
+        out.push_str(&format!("\\end{{{}}}\n", environment));
+    }
+    out.push('\n');
+}
+
+fn inlines(inlines: &[Inline], options: &LatexOptions) -> String {
+    inlines
+        .iter()
+        .map(|inline| self::inline(inline, options))
+        .collect()
+}
+
+fn inline(inline: &Inline, options: &LatexOptions) -> String {
+    match inline {
+        Inline::Value(value) => escape(value),
+        Inline::HardBreak => "\\\\\n".to_string(),
+        Inline::SoftBreak => "\n".to_string(),
+        Inline::Bold { children } => format!("\\textbf{{{}}}", self::inline(children, options)),
+        Inline::Italic { children } => format!("\\emph{{{}}}", self::inline(children, options)),
+        Inline::Monospace { children }
+        | Inline::InlineCode { children }
+        | Inline::Literal { children } => {
+            format!("\\texttt{{{}}}", self::inline(children, options))
+        }
+        Inline::Marker { children } => format!("\\hl{{{}}}", self::inline(children, options)),
+        Inline::Styled { roles, children } => {
+            options.roles.latex(roles, &self::inline(children, options))
+        }
+        Inline::Lead { children } => format!("{{\\large {}}}", self::inline(children, options)),
+        Inline::Footnote { children, .. } => {
+            format!("\\footnote{{{}}}", self::inline(children, options))
+        }
+        Inline::Macro {
+            attributes,
+            kind,
+            id,
+        } => {
+            let first = attributes
+                .get("alt")
+                .or_else(|| attributes.positional(0))
+                .map(str::to_string);
+            match kind.as_str() {
+                // Math is already LaTeX, pass it through untouched.
+                "stem" | "latexmath" => format!("${}$", first.unwrap_or_default()),
+                "image" => format!("\\includegraphics{{{}}}", id),
+                "link" => format!(
+                    "\\href{{{}}}{{{}}}",
+                    escape_url(id),
+                    escape(&first.unwrap_or_else(|| id.clone()))
+                ),
+                "http" | "https" | "mailto" => {
+                    let url = format!("{}:{}", kind, id);
+                    match first {
+                        Some(text) => {
+                            format!("\\href{{{}}}{{{}}}", escape_url(&url), escape(&text))
+                        }
+                        None => format!("\\url{{{}}}", escape_url(&url)),
+                    }
+                }
+                _ => escape(&format!("{}:{}", kind, id)),
+            }
+        }
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '{' | '}' | '$' | '&' | '#' | '%' | '_' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn escape_url(s: &str) -> String {
+    s.replace('%', "\\%").replace('#', "\\#")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::roles::RoleStyle;
+    use crate::AttrList;
+    use pretty_assertions::assert_eq;
+    use smallvec::smallvec;
+
+    #[test]
+    fn test_render() {
+        let document =
+            Document::parse("= Title\n\n== Intro\n\n50% of *bold* & `code`\n\n* a\n** b").unwrap();
+        let options = LatexOptions {
+            standalone: false,
+            ..LatexOptions::default()
+        };
+        assert_eq!(
+            render(&document, &options),
+            "\\title{Title}
+\\maketitle
+
+\\section{Intro}
+
+50\\% of \\textbf{bold} \\& \\texttt{code}
+
+\\begin{itemize}
+\\item a
+\\begin{itemize}
+\\item b
+\\end{itemize}
+\\end{itemize}
+
+"
+        );
+    }
+
+    #[test]
+    fn test_code_block_and_math() {
+        let code = Block::CodeBlock {
+            children: smallvec![Inline::Value("fn main() {}".to_string())],
+            title: None,
+            file_type: Some("rust".into()),
+        };
+        let mut out = String::new();
+        render_block(
+            &mut out,
+            &code,
+            &LatexOptions {
+                code_listing: CodeListing::Minted,
+                standalone: false,
+                ..LatexOptions::default()
+            },
+        );
+        assert_eq!(
+            out,
+            "\\begin{minted}{rust}\nfn main() {}\n\\end{minted}\n\n"
+        );
+
+        let math = Inline::Macro {
+            attributes: Box::new(AttrList::from(vec!["x^2".to_string()])),
+            kind: "stem".into(),
+            id: "".to_string(),
+        };
+        assert_eq!(inline(&math, &LatexOptions::default()), "$x^2$");
+    }
+
+    #[test]
+    fn test_styled() {
+        let document = Document::parse("[.underline.keyword]#fn# and [.aside]#note#").unwrap();
+        let options = LatexOptions {
+            standalone: false,
+            roles: RoleStyles::new().role("keyword", RoleStyle::new().latex("textbf")),
+            ..LatexOptions::default()
+        };
+        assert_eq!(
+            render(&document, &options),
+            "\\underline{\\textbf{fn}} and note\n\n"
+        );
+    }
+}
@@ -0,0 +1,320 @@
+use crate::document::Document;
+use crate::render::math::MathNotation;
+use crate::render::roles::RoleStyles;
+use crate::text::inline_text;
+use crate::{Block, BlockKind, DelimitedKind, HeadingLevel, Inline, ListItem};
+use anyhow::{bail, Result};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ManPageHeader {
+    pub name: String,
+    pub volnum: String,
+    pub purpose: String,
+}
+
+// The title must read `name(volnum)` and a `NAME` section must hold `name - purpose`, like
+// asciidoctor's manpage doctype.
+pub fn header(document: &Document) -> Result<ManPageHeader> {
+    let title = document.title().map(inline_text).unwrap_or_default();
+    let (name, volnum) = match (title.rfind('('), title.ends_with(')')) {
+        (Some(open), true) if open > 0 => (
+            title[..open].trim().to_string(),
+            title[open + 1..title.len() - 1].to_string(),
+        ),
+        _ => bail!(
+            "manpage title must be in the form `name(volnum)`, got `{}`",
+            title
+        ),
+    };
+
+    let mut blocks = document.blocks().iter();
+    blocks
+        .find(|block| match block {
+            Block::Heading {
+                level: HeadingLevel::Level1,
+                children,
+                ..
+            } => inline_text(children).trim().eq_ignore_ascii_case("name"),
+            _ => false,
+        })
+        .ok_or_else(|| anyhow::anyhow!("manpage must have a NAME section"))?;
+    let purpose = match blocks.find(|block| block.kind() == BlockKind::Paragraph) {
+        Some(paragraph) => inline_text(paragraph.inlines()),
+        None => bail!("NAME section must contain `name - purpose`"),
+    };
+    let purpose = match purpose.split_once(" - ") {
+        Some((_, purpose)) => purpose.trim().to_string(),
+        None => bail!(
+            "NAME section must contain `name - purpose`, got `{}`",
+            purpose
+        ),
+    };
+
+    Ok(ManPageHeader {
+        name,
+        volnum,
+        purpose,
+    })
+}
+
+pub fn render(document: &Document) -> Result<String> {
+    let header = header(document)?;
+    let mut out = format!(
+        ".TH \"{}\" \"{}\" \"{}\" \"{}\" \"{}\"\n.ie \\n(.g .ds Aq \\(aq\n.el .ds Aq '\n.nh\n.ad l\n",
+        quoted(&header.name.to_uppercase()),
+        quoted(&header.volnum),
+        quoted(document.attribute("revdate").unwrap_or("")),
+        quoted(document.attribute("mansource").unwrap_or("")),
+        quoted(document.attribute("manmanual").unwrap_or("")),
+    );
+
+    let mut in_name_section = false;
+    for block in document.blocks() {
+        match block {
+            Block::Heading {
+                level: HeadingLevel::Title,
+                ..
+            } => {}
+            Block::Heading {
+                level, children, ..
+            } => {
+                let title = inline_text(children);
+                in_name_section =
+                    *level == HeadingLevel::Level1 && title.trim().eq_ignore_ascii_case("name");
+                let macro_name = if *level == HeadingLevel::Level1 {
+                    ".SH"
+                } else {
+                    ".SS"
+                };
+                out.push_str(&format!(
+                    "{} \"{}\"\n",
+                    macro_name,
+                    quoted(&title.to_uppercase())
+                ));
+            }
+            Block::Paragraph { .. } if in_name_section => {
+                out.push_str(&format!(
+                    "{} \\- {}\n",
+                    escape(&header.name),
+                    escape(&header.purpose)
+                ));
+                in_name_section = false;
+            }
+            block => render_block(&mut out, block),
+        }
+    }
+    Ok(out)
+}
+
+fn render_block(out: &mut String, block: &Block) {
+    match block {
+        Block::Paragraph { children } => {
+            out.push_str(".sp\n");
+            push_text(out, &inlines(children));
+        }
+        Block::Macro { children } => {
+            out.push_str(".sp\n");
+            push_text(out, &inlines(std::slice::from_ref(children)));
+        }
+        Block::UnorderdList { children } | Block::OrderdList { children } => {
+            let ordered = block.kind() == BlockKind::OrderdList;
+            let mut counters = vec![0];
+            let mut depth = 1;
+            for item in children {
+                let level = item.level().max(1) as usize;
+                while depth < level {
+                    out.push_str(".RS 4\n");
+                    counters.push(0);
+                    depth += 1;
+                }
+                while depth > level {
+                    out.push_str(".RE\n");
+                    counters.pop();
+                    depth -= 1;
+                }
+                let counter = counters.last_mut().unwrap();
+                *counter += 1;
+                let bullet = if ordered {
+                    format!("\" {}.\" 4", counter)
+                } else {
+                    "\\(bu 2".to_string()
+                };
+                out.push_str(&format!(".sp\n.IP {}\n", bullet));
+                let checkbox = match item {
+                    ListItem::Check { checked: true, .. } => "[x] ",
+                    ListItem::Check { checked: false, .. } => "[ ] ",
+                    ListItem::Normal { .. } => "",
+                };
+                push_text(out, &format!("{}{}", checkbox, inlines(item.children())));
+            }
+            for _ in 1..depth {
+                out.push_str(".RE\n");
+            }
+        }
+        Block::CodeBlock { children, .. } => {
+            out.push_str(".sp\n.if n .RS 4\n.nf\n.fam C\n");
+            for line in inline_text(children).trim_end_matches('\n').split('\n') {
+                push_text(out, &escape(line));
+            }
+            out.push_str(".fam\n.fi\n.if n .RE\n");
+        }
+        Block::Label { children, key } => {
+            out.push_str(&format!(".sp\n{}\n.RS 4\n", inlines(key)));
+            push_text(out, &inlines(children));
+            out.push_str(".RE\n");
+        }
+        Block::Qanda { question, answer } => {
+            out.push_str(&format!(".sp\n\\fI{}\\fP\n.RS 4\n", inlines(question)));
+            push_text(out, &inlines(answer));
+            out.push_str(".RE\n");
+        }
+        Block::Block {
+            kind: DelimitedKind::Comment,
+            ..
+        } => {}
+        Block::Block {
+            kind,
+            children,
+            title,
+            ..
+        } => {
+            if let Some(title) = title {
+                out.push_str(&format!(".sp\n\\fB{}\\fP\n", inlines(title)));
+            }
+            let quote = *kind == DelimitedKind::Quote;
+            if quote {
+                out.push_str(".RS 4\n");
+            }
+            for block in children {
+                render_block(out, block);
+            }
+            if quote {
+                out.push_str(".RE\n");
+            }
+        }
+        Block::HorizontalRuledLine | Block::NextPage => out.push_str(".sp\n"),
+        Block::Heading { .. } | Block::Table { .. } | Block::BlankBlock => {}
+    }
+}
+
+fn inlines(inlines: &[Inline]) -> String {
+    inlines.iter().map(inline).collect()
+}
+
+fn inline(inline: &Inline) -> String {
+    match inline {
+        Inline::Value(value) => escape(value),
+        Inline::HardBreak => "\n.br\n".to_string(),
+        Inline::SoftBreak => "\n".to_string(),
+        Inline::Bold { children } => format!("\\fB{}\\fP", self::inline(children)),
+        Inline::Italic { children } => format!("\\fI{}\\fP", self::inline(children)),
+        Inline::Monospace { children } | Inline::InlineCode { children } => {
+            format!("\\f(CR{}\\fP", self::inline(children))
+        }
+        Inline::Literal { children } | Inline::Lead { children } | Inline::Marker { children } => {
+            self::inline(children)
+        }
+        Inline::Styled { roles, children } => {
+            RoleStyles::default().roff(roles, &self::inline(children))
+        }
+        Inline::Footnote { children, .. } => format!(" [{}]", self::inline(children)),
+        // Roff has no math, a formula is kept as written whatever its notation.
+        Inline::Macro {
+            attributes, kind, ..
+        } if MathNotation::of_macro(kind, None).is_some() => {
+            escape(&attributes.positionals().collect::<Vec<_>>().join(","))
+        }
+        Inline::Macro { kind, id, .. } => escape(&format!("{}:{}", kind, id)),
+    }
+}
+
+// Lines must not start with a control character.
+fn push_text(out: &mut String, text: &str) {
+    for line in text.split('\n') {
+        if line.starts_with('.') || line.starts_with('\'') {
+            out.push_str("\\&");
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\e").replace('-', "\\-")
+}
+
+fn quoted(s: &str) -> String {
+    escape(s).replace('"', "\\(dq")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ParseOptions;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_render() {
+        let document = Document::parse_with_options(
+            ":mansource: combine-sandbox 0.1\n= adoc(1)\n\n== Name\n\nadoc - convert *AsciiDoc*\n\n== Synopsis\n\n_adoc_ [*OPTION*]... FILE\n\n== Options\n\n* --help\n* .hidden",
+            &ParseOptions::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            render(&document).unwrap(),
+            r#".TH "ADOC" "1" "" "combine\-sandbox 0.1" ""
+.ie \n(.g .ds Aq \(aq
+.el .ds Aq '
+.nh
+.ad l
+.SH "NAME"
+adoc \- convert AsciiDoc
+.SH "SYNOPSIS"
+.sp
+\fIadoc\fP [\fBOPTION\fP]... FILE
+.SH "OPTIONS"
+.sp
+.IP \(bu 2
+\-\-help
+.sp
+.IP \(bu 2
+\&.hidden
+"#
+        );
+    }
+
+    #[test]
+    fn test_render_math() {
+        let document = Document::parse("Area stem:[x^2] of latexmath:[a_1, b]").unwrap();
+        let mut actual = String::new();
+        render_block(&mut actual, &document.blocks()[0]);
+        assert_eq!(actual, ".sp\nArea x^2 of a_1, b\n");
+    }
+
+    #[test]
+    fn test_render_roles() {
+        let document = Document::parse("[.underline]#see# [.keyword]#fn#").unwrap();
+        let mut actual = String::new();
+        render_block(&mut actual, &document.blocks()[0]);
+        assert_eq!(actual, ".sp\n\\fIsee\\fP fn\n");
+    }
+
+    #[test]
+    fn test_header_requires_manpage_conventions() {
+        let document = Document::parse("= adoc\n\n== NAME\n\nadoc - convert").unwrap();
+        assert_eq!(header(&document).is_err(), true);
+
+        let document = Document::parse("= adoc(1)\n\n== SYNOPSIS\n\nadoc").unwrap();
+        assert_eq!(header(&document).is_err(), true);
+
+        let document = Document::parse("= adoc(1)\n\n== NAME\n\nadoc - convert").unwrap();
+        assert_eq!(
+            header(&document).unwrap(),
+            ManPageHeader {
+                name: "adoc".to_string(),
+                volnum: "1".to_string(),
+                purpose: "convert".to_string()
+            }
+        );
+    }
+}
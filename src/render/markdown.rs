@@ -0,0 +1,344 @@
+use crate::document::Document;
+use crate::render::math::MathNotation;
+use crate::render::roles::RoleStyles;
+use crate::text::{block_text, cell_text, inline_text};
+use crate::{Block, DelimitedKind, Inline, ListItem};
+
+// Convert to CommonMark with the GFM extensions for tables, task lists and footnotes.
+pub fn to_markdown(document: &Document) -> String {
+    let mut renderer = MarkdownRenderer::new(document);
+    let blocks = document
+        .blocks()
+        .iter()
+        .map(|block| renderer.block(block))
+        .collect();
+    renderer.finish(blocks)
+}
+
+#[derive(Default)]
+pub(crate) struct MarkdownRenderer {
+    footnotes: Vec<String>,
+    // The `stem` document attribute.
+    stem: Option<String>,
+}
+
+impl MarkdownRenderer {
+    pub(crate) fn new(document: &Document) -> Self {
+        MarkdownRenderer {
+            footnotes: vec![],
+            stem: document.attribute("stem").map(str::to_string),
+        }
+    }
+
+    // Rendered `blocks` separated by blank lines, followed by the footnotes they referenced.
+    pub(crate) fn finish(&self, blocks: Vec<String>) -> String {
+        let blocks = blocks
+            .into_iter()
+            .filter(|block| !block.is_empty())
+            .collect::<Vec<_>>();
+        let mut out = blocks.join("\n\n");
+        if !self.footnotes.is_empty() {
+            out.push_str("\n\n");
+            let footnotes = self
+                .footnotes
+                .iter()
+                .enumerate()
+                .map(|(index, footnote)| format!("[^{}]: {}", index + 1, footnote))
+                .collect::<Vec<_>>();
+            out.push_str(&footnotes.join("\n"));
+        }
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out
+    }
+
+    pub(crate) fn block(&mut self, block: &Block) -> String {
+        match block {
+            Block::Paragraph { children } => self.inlines(children),
+            Block::Macro { children } => self.inlines(std::slice::from_ref(children)),
+            Block::Heading {
+                level, children, ..
+            } => format!(
+                "{} {}",
+                "#".repeat(level.as_u8() as usize + 1),
+                self.inlines(children)
+            ),
+            Block::HorizontalRuledLine => "---".to_string(),
+            Block::NextPage => "<!-- page break -->".to_string(),
+            Block::UnorderdList { children } => self.list(children, false),
+            Block::OrderdList { children } => self.list(children, true),
+            Block::Label { children, key } => {
+                format!("**{}**  \n{}", self.inlines(key), self.inlines(children))
+            }
+            Block::Qanda { question, answer } => {
+                format!("_{}_\n\n{}", self.inlines(question), self.inlines(answer))
+            }
+            Block::CodeBlock {
+                children,
+                title,
+                file_type,
+            } => {
+                let code = inline_text(children);
+                let fence = fence_for(&code, '`', 3);
+                let code_block = format!(
+                    "{}{}\n{}\n{}",
+                    fence,
+                    file_type.as_deref().unwrap_or(""),
+                    code.trim_end_matches('\n'),
+                    fence
+                );
+                match title {
+                    Some(title) => format!("**{}**\n\n{}", escape(title), code_block),
+                    None => code_block,
+                }
+            }
+            Block::Block {
+                kind: DelimitedKind::Comment,
+                ..
+            } => String::new(),
+            // Markdown passes HTML through as well.
+            Block::Block {
+                kind: DelimitedKind::Pass,
+                ..
+            } => block_text(block),
+            Block::Block {
+                kind,
+                children,
+                title,
+                ..
+            } => {
+                let mut blocks = children
+                    .iter()
+                    .map(|block| self.block(block))
+                    .filter(|text| !text.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("\n\n");
+                if *kind == DelimitedKind::Quote {
+                    blocks = blocks
+                        .lines()
+                        .map(|line| format!("> {}", line).trim_end().to_string())
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                }
+                match title {
+                    Some(title) => format!("**{}**\n\n{}", self.inlines(title), blocks),
+                    None => blocks,
+                }
+            }
+            Block::Table {
+                columns,
+                rows,
+                title,
+            } => {
+                let header = if columns.is_empty() {
+                    vec![String::new()]
+                } else {
+                    columns
+                        .iter()
+                        .map(|column| escape_cell(column.name()))
+                        .collect()
+                };
+                let mut lines = vec![
+                    format!("| {} |", header.join(" | ")),
+                    format!("|{}", " --- |".repeat(header.len())),
+                ];
+                for row in rows {
+                    let cells = row
+                        .cells()
+                        .iter()
+                        .map(|cell| escape_cell(&cell_text(cell)))
+                        .collect::<Vec<_>>();
+                    lines.push(format!("| {} |", cells.join(" | ")));
+                }
+                let table = lines.join("\n");
+                match title {
+                    Some(title) => format!("**{}**\n\n{}", escape(title), table),
+                    None => table,
+                }
+            }
+            Block::BlankBlock => String::new(),
+        }
+    }
+
+    fn list(&mut self, items: &[ListItem], ordered: bool) -> String {
+        let indent_width = if ordered { 3 } else { 2 };
+        items
+            .iter()
+            .map(|item| {
+                let indent = " ".repeat(indent_width * (item.level().max(1) as usize - 1));
+                let marker = if ordered { "1." } else { "-" };
+                let checkbox = match item {
+                    ListItem::Check { checked: true, .. } => "[x] ",
+                    ListItem::Check { checked: false, .. } => "[ ] ",
+                    ListItem::Normal { .. } => "",
+                };
+                format!(
+                    "{}{} {}{}",
+                    indent,
+                    marker,
+                    checkbox,
+                    self.inlines(item.children())
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn inlines(&mut self, inlines: &[Inline]) -> String {
+        inlines.iter().map(|inline| self.inline(inline)).collect()
+    }
+
+    fn inline(&mut self, inline: &Inline) -> String {
+        match inline {
+            Inline::Value(value) => escape(value),
+            Inline::HardBreak => "\\\n".to_string(),
+            Inline::SoftBreak => "\n".to_string(),
+            Inline::Bold { children } => format!("**{}**", self.inline(children)),
+            Inline::Italic { children } | Inline::Lead { children } => {
+                format!("_{}_", self.inline(children))
+            }
+            Inline::Monospace { children }
+            | Inline::InlineCode { children }
+            | Inline::Literal { children } => {
+                code_span(&inline_text(std::slice::from_ref(children.as_ref())))
+            }
+            Inline::Marker { children } => format!("<mark>{}</mark>", self.inline(children)),
+            // Markdown has no spans, the HTML one keeps the roles for stylesheets.
+            Inline::Styled { roles, children } => format!(
+                "<span class=\"{}\">{}</span>",
+                RoleStyles::default().html_classes(roles),
+                self.inline(children)
+            ),
+            Inline::Footnote { children, .. } => {
+                let footnote = self.inline(children);
+                self.footnotes.push(footnote);
+                format!("[^{}]", self.footnotes.len())
+            }
+            Inline::Macro {
+                attributes,
+                kind,
+                id,
+            } => {
+                if let Some(notation) = MathNotation::of_macro(kind, self.stem.as_deref()) {
+                    let formula = attributes.positionals().collect::<Vec<_>>().join(",");
+                    return match notation {
+                        MathNotation::LatexMath => format!("${}$", formula),
+                        // GFM only typesets LaTeX, AsciiMath is left as code.
+                        MathNotation::AsciiMath => code_span(&formula),
+                    };
+                }
+                let text = attributes
+                    .get("alt")
+                    .or_else(|| attributes.positional(0))
+                    .map(str::to_string);
+                match kind.as_str() {
+                    "image" => format!("![{}]({})", escape(&text.unwrap_or_default()), id),
+                    "link" => format!("[{}]({})", escape(&text.unwrap_or_else(|| id.clone())), id),
+                    "http" | "https" | "mailto" => {
+                        let href = format!("{}:{}", kind, id);
+                        match text {
+                            Some(text) => format!("[{}]({})", escape(&text), href),
+                            None => format!("<{}>", href),
+                        }
+                    }
+                    _ => escape(&format!("{}:{}", kind, id)),
+                }
+            }
+        }
+    }
+}
+
+// A backtick run longer than any run inside `code`.
+fn fence_for(code: &str, c: char, min: usize) -> String {
+    let mut longest = 0;
+    let mut current = 0;
+    for d in code.chars() {
+        current = if d == c { current + 1 } else { 0 };
+        longest = longest.max(current);
+    }
+    c.to_string().repeat(min.max(longest + 1))
+}
+
+fn code_span(code: &str) -> String {
+    let fence = fence_for(code, '`', 1);
+    if code.starts_with('`') || code.ends_with('`') {
+        format!("{} {} {}", fence, code, fence)
+    } else {
+        format!("{}{}{}", fence, code, fence)
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if "\\*_`[]<".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn escape_cell(s: &str) -> String {
+    escape(s).replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ParseOptions;
+    use pretty_assertions::assert_eq;
+    use smallvec::smallvec;
+
+    #[test]
+    fn test_to_markdown() {
+        let document = Document::parse(
+            "= Title\n\n== Section\n\nThis is *bold*, _italic_ and `code`\n\n* foo\n** bar\n* [x] done\n\n\n. one\n. two",
+        )
+        .unwrap();
+        assert_eq!(
+            to_markdown(&document),
+            "# Title
+
+## Section
+
+This is **bold**, _italic_ and `code`
+
+- foo
+  - bar
+- [x] done
+
+1. one
+1. two
+"
+        );
+    }
+
+    #[test]
+    fn test_code_block() {
+        let mut renderer = MarkdownRenderer::default();
+        let actual = renderer.block(&Block::CodeBlock {
+            children: smallvec![Inline::Value("let a = \"```\";".to_string())],
+            title: None,
+            file_type: Some("rust".into()),
+        });
+        assert_eq!(actual, "````rust\nlet a = \"```\";\n````");
+    }
+
+    #[test]
+    fn test_math() {
+        let document = Document::parse_with_options(
+            ":stem: latexmath\nstem:[a_1, b] and asciimath:[sqrt(4) = 2]",
+            &ParseOptions::new(),
+        )
+        .unwrap();
+        assert_eq!(to_markdown(&document), "$a_1, b$ and `sqrt(4) = 2`\n");
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("2 * 3 [x]"), "2 \\* 3 \\[x\\]");
+        assert_eq!(code_span("a`b"), "``a`b``");
+    }
+}
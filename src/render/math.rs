@@ -0,0 +1,133 @@
+use crate::document::Document;
+use crate::render::html::escape;
+use crate::render::visitor::{render_with, Renderer};
+use crate::AttrList;
+
+// Client side library typesetting `stem`, `latexmath` and `asciimath` content in HTML output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MathEngine {
+    #[default]
+    MathJax,
+    // KaTeX has no AsciiMath input, such formulas are left as text.
+    KaTeX,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MathNotation {
+    LatexMath,
+    AsciiMath,
+}
+
+impl MathNotation {
+    // Notation of a math macro. `stem` follows the `stem` document attribute, which defaults to
+    // AsciiMath like asciidoctor.
+    pub fn of_macro(kind: &str, stem: Option<&str>) -> Option<MathNotation> {
+        match kind {
+            "latexmath" => Some(MathNotation::LatexMath),
+            "asciimath" => Some(MathNotation::AsciiMath),
+            "stem" => match stem.map(str::trim) {
+                Some("latexmath") | Some("latex") | Some("tex") => Some(MathNotation::LatexMath),
+                _ => Some(MathNotation::AsciiMath),
+            },
+            _ => None,
+        }
+    }
+}
+
+// Inline formula wrapped in the delimiters `engine` is configured to look for.
+pub(crate) fn inline_math(engine: MathEngine, notation: MathNotation, source: &str) -> String {
+    let source = escape(source);
+    match (engine, notation) {
+        (_, MathNotation::LatexMath) => format!("\\({}\\)", source),
+        (MathEngine::MathJax, MathNotation::AsciiMath) => format!("\\${}\\$", source),
+        (MathEngine::KaTeX, MathNotation::AsciiMath) => {
+            format!("<code class=\"asciimath\">{}</code>", source)
+        }
+    }
+}
+
+// Tags for `<head>` loading `engine`, empty when the document has no math.
+pub fn head_includes(document: &Document, engine: MathEngine) -> String {
+    if !render_with(document, MathFinder::default()).found {
+        return String::new();
+    }
+    match engine {
+        MathEngine::MathJax => r#"<script>
+window.MathJax = {
+  loader: { load: ["input/asciimath"] },
+  tex: { inlineMath: [["\\(", "\\)"]] },
+  asciimath: { delimiters: [["\\$", "\\$"]] }
+};
+</script>
+<script src="https://cdn.jsdelivr.net/npm/mathjax@3/es5/tex-mml-chtml.js" async></script>
+"#
+        .to_string(),
+        MathEngine::KaTeX => r#"<link rel="stylesheet" href="https://cdn.jsdelivr.net/npm/katex@0.16/dist/katex.min.css">
+<script defer src="https://cdn.jsdelivr.net/npm/katex@0.16/dist/katex.min.js"></script>
+<script defer src="https://cdn.jsdelivr.net/npm/katex@0.16/dist/contrib/auto-render.min.js" onload="renderMathInElement(document.body, { delimiters: [{ left: '\\(', right: '\\)', display: false }] });"></script>
+"#
+        .to_string(),
+    }
+}
+
+#[derive(Default)]
+struct MathFinder {
+    found: bool,
+}
+
+impl Renderer for MathFinder {
+    fn inline_macro(&mut self, kind: &str, _id: &str, _attributes: &AttrList) {
+        self.found |= MathNotation::of_macro(kind, None).is_some();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Block, Inline};
+    use pretty_assertions::assert_eq;
+    use smallvec::smallvec;
+
+    #[test]
+    fn test_inline_math() {
+        assert_eq!(
+            inline_math(MathEngine::MathJax, MathNotation::LatexMath, "a < b"),
+            "\\(a &lt; b\\)"
+        );
+        assert_eq!(
+            inline_math(MathEngine::MathJax, MathNotation::AsciiMath, "sqrt(x)"),
+            "\\$sqrt(x)\\$"
+        );
+        assert_eq!(
+            inline_math(MathEngine::KaTeX, MathNotation::AsciiMath, "sqrt(x)"),
+            "<code class=\"asciimath\">sqrt(x)</code>"
+        );
+        assert_eq!(
+            MathNotation::of_macro("stem", Some("latexmath")),
+            Some(MathNotation::LatexMath)
+        );
+        assert_eq!(
+            MathNotation::of_macro("stem", Some("")),
+            Some(MathNotation::AsciiMath)
+        );
+    }
+
+    #[test]
+    fn test_head_includes_only_with_math() {
+        let text = Document::parse("no math").unwrap();
+        assert_eq!(head_includes(&text, MathEngine::KaTeX), "");
+
+        let math = Document::new(vec![Block::Paragraph {
+            children: smallvec![Inline::Macro {
+                attributes: Box::new(AttrList::from(vec!["x^2".to_string()])),
+                kind: "latexmath".into(),
+                id: "".to_string(),
+            }]
+            .into(),
+        }]);
+        assert_eq!(
+            head_includes(&math, MathEngine::KaTeX).contains("katex.min.js"),
+            true
+        );
+    }
+}
@@ -0,0 +1,18 @@
+pub mod asciidoc;
+pub mod highlight;
+pub mod html;
+pub mod images;
+pub mod latex;
+pub mod manpage;
+pub mod markdown;
+pub mod math;
+pub mod notebook;
+pub mod paged;
+pub mod pandoc;
+pub mod revealjs;
+pub mod roles;
+#[cfg(feature = "templates")]
+pub mod templates;
+pub mod visitor;
+
+pub use visitor::{render_with, walk_block, walk_inline, Renderer};
@@ -0,0 +1,178 @@
+use crate::document::Document;
+use crate::render::markdown::MarkdownRenderer;
+use crate::symbol::Symbol;
+use crate::text::inline_text;
+use crate::{Block, BlockMeta, Inline};
+use serde_json::{json, Value};
+use smallvec::smallvec;
+
+pub const EXECUTABLE_ROLE: &str = "executable";
+
+// Jupyter notebook (nbformat 4.5) for literate programming. Source blocks with the executable
+// role, `[source,python,role=executable]`, become code cells and the blocks between them a
+// markdown cell each run. The kernel is the language of the first code cell.
+//
+// Without delimited blocks the code is the paragraph below the attribute line. It keeps its
+// exact text when the document was parsed with `ParseOptions::lazy_inlines`, formatting marks
+// in it are read as AsciiDoc otherwise.
+pub fn to_notebook(document: &Document) -> Value {
+    let mut cells = vec![];
+    let mut language = None;
+    let mut prose = MarkdownRenderer::new(document);
+    let mut pending = vec![];
+    for (index, block) in document.blocks().iter().enumerate() {
+        let meta = document.meta(index);
+        match code(block, meta) {
+            Some((lang, source)) if is_executable(meta) => {
+                push_markdown(&mut cells, &mut prose, &mut pending);
+                if language.is_none() {
+                    language = lang;
+                }
+                cells.push(json!({
+                    "cell_type": "code",
+                    "execution_count": null,
+                    "id": format!("cell-{}", cells.len() + 1),
+                    "metadata": {},
+                    "outputs": [],
+                    "source": lines(&source),
+                }));
+            }
+            // Other source blocks stay in the prose as fenced code.
+            Some((file_type, source)) => pending.push(prose.block(&Block::CodeBlock {
+                children: smallvec![Inline::Value(source)],
+                title: None,
+                file_type: file_type.map(Symbol::from),
+            })),
+            None => pending.push(prose.block(block)),
+        }
+    }
+    push_markdown(&mut cells, &mut prose, &mut pending);
+
+    let language = language.unwrap_or_else(|| "python".to_string());
+    let kernelspec = match language.as_str() {
+        "python" => json!({"display_name": "Python 3", "language": "python", "name": "python3"}),
+        _ => json!({"display_name": language, "language": language, "name": language}),
+    };
+    json!({
+        "cells": cells,
+        "metadata": {
+            "kernelspec": kernelspec,
+            "language_info": {"name": language},
+        },
+        "nbformat": 4,
+        "nbformat_minor": 5,
+    })
+}
+
+// Indented like the files Jupyter writes.
+pub fn to_notebook_string(document: &Document) -> String {
+    format!(
+        "{}\n",
+        serde_json::to_string_pretty(&to_notebook(document)).unwrap()
+    )
+}
+
+fn push_markdown(cells: &mut Vec<Value>, prose: &mut MarkdownRenderer, pending: &mut Vec<String>) {
+    let markdown = std::mem::take(prose).finish(std::mem::take(pending));
+    if markdown.is_empty() {
+        return;
+    }
+    cells.push(json!({
+        "cell_type": "markdown",
+        "id": format!("cell-{}", cells.len() + 1),
+        "metadata": {},
+        "source": lines(markdown.trim_end()),
+    }));
+}
+
+// Language and text of a source block, a code block or a paragraph styled `source`.
+fn code(block: &Block, meta: Option<&BlockMeta>) -> Option<(Option<String>, String)> {
+    let language = meta.and_then(|meta| {
+        let attributes = &meta.attributes;
+        attributes
+            .get("language")
+            .or_else(|| attributes.positional(1))
+            .map(str::to_string)
+    });
+    match block {
+        Block::CodeBlock {
+            children,
+            file_type,
+            ..
+        } => Some((
+            file_type
+                .as_ref()
+                .map(|file_type| file_type.to_string())
+                .or(language),
+            inline_text(children),
+        )),
+        Block::Paragraph { children }
+            if meta
+                .and_then(|meta| meta.style.as_ref())
+                .map(|style| style.as_str())
+                == Some("source") =>
+        {
+            Some((language, children.source_text()))
+        }
+        _ => None,
+    }
+}
+
+fn is_executable(meta: Option<&BlockMeta>) -> bool {
+    meta.is_some_and(|meta| meta.has_role(EXECUTABLE_ROLE))
+}
+
+// Cell source as nbformat stores it, one string per line with its line break.
+fn lines(text: &str) -> Vec<&str> {
+    text.trim_end_matches('\n').split_inclusive('\n').collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::ParseOptions;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_to_notebook() {
+        let source = "= Squares\n\nSome *prose*.\n\n[source,python,role=executable]\nx = 2 ** 3\nprint(x * x)\n\n[source,shell]\npip install numpy\n\nMore prose.\n\n[source,python,role=executable]\nprint(x)\n";
+        let document =
+            Document::parse_with_options(source, &ParseOptions::new().lazy_inlines(true)).unwrap();
+        let notebook = to_notebook(&document);
+        assert_eq!(
+            notebook["cells"],
+            json!([
+                {
+                    "cell_type": "markdown",
+                    "id": "cell-1",
+                    "metadata": {},
+                    "source": ["# Squares\n", "\n", "Some **prose**."],
+                },
+                {
+                    "cell_type": "code",
+                    "execution_count": null,
+                    "id": "cell-2",
+                    "metadata": {},
+                    "outputs": [],
+                    "source": ["x = 2 ** 3\n", "print(x * x)"],
+                },
+                {
+                    "cell_type": "markdown",
+                    "id": "cell-3",
+                    "metadata": {},
+                    "source": ["```shell\n", "pip install numpy\n", "```\n", "\n", "More prose."],
+                },
+                {
+                    "cell_type": "code",
+                    "execution_count": null,
+                    "id": "cell-4",
+                    "metadata": {},
+                    "outputs": [],
+                    "source": ["print(x)"],
+                },
+            ])
+        );
+        assert_eq!(notebook["metadata"]["kernelspec"]["name"], "python3");
+        assert_eq!(notebook["nbformat_minor"], 5);
+    }
+}
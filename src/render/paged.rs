@@ -0,0 +1,227 @@
+use crate::document::Document;
+use crate::render::html::{self, escape, HtmlOptions};
+use crate::text::inline_text;
+
+// Content of a running header or footer. `{title}`, `{section}`, `{page}` and `{pages}` are
+// replaced with the document title, the current level 1 section and the page counters.
+#[derive(Debug, Clone, Default)]
+pub struct RunningContent {
+    pub left: Option<String>,
+    pub center: Option<String>,
+    pub right: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct PagedOptions {
+    pub html: HtmlOptions,
+    // CSS `@page` size, e.g. `A4` or `letter landscape`.
+    pub page_size: String,
+    pub margin: String,
+    // Put the document title, author and revision on a page of their own.
+    pub title_page: bool,
+    pub header: Option<RunningContent>,
+    pub footer: Option<RunningContent>,
+}
+
+impl Default for PagedOptions {
+    fn default() -> Self {
+        PagedOptions {
+            html: HtmlOptions::default(),
+            page_size: "A4".to_string(),
+            margin: "20mm".to_string(),
+            title_page: true,
+            header: None,
+            footer: Some(RunningContent {
+                center: Some("{page} / {pages}".to_string()),
+                ..RunningContent::default()
+            }),
+        }
+    }
+}
+
+// Standalone HTML with CSS paged media rules for print engines (weasyprint, paged.js, browsers).
+// `Block::NextPage` becomes a forced page break.
+pub fn render(document: &Document, options: &PagedOptions) -> String {
+    let title = document.title().map(inline_text);
+
+    let mut body = String::new();
+    if options.title_page {
+        if let Some(title) = &title {
+            body.push_str(&title_page(document, title));
+        }
+    }
+    let html_options = options.html.clone().hide_title(options.title_page);
+    body.push_str(&html::render_with_options(document, &html_options));
+
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+{style}</style>
+</head>
+<body>
+{body}</body>
+</html>
+"#,
+        title = escape(title.as_deref().unwrap_or("")),
+        style = page_style(options, title.as_deref().unwrap_or("")),
+        body = body
+    )
+}
+
+fn title_page(document: &Document, title: &str) -> String {
+    let mut page = format!(
+        "<section class=\"title-page\">\n<h1>{}</h1>\n",
+        escape(title)
+    );
+    for name in &["author", "revnumber", "revdate"] {
+        if let Some(value) = document.attribute(name) {
+            page.push_str(&format!("<p class=\"{}\">{}</p>\n", name, escape(value)));
+        }
+    }
+    page.push_str("</section>\n");
+    page
+}
+
+fn page_style(options: &PagedOptions, title: &str) -> String {
+    let mut page = format!(
+        "@page {{\n  size: {};\n  margin: {};\n",
+        options.page_size, options.margin
+    );
+    for (position, content) in [("top", &options.header), ("bottom", &options.footer)] {
+        let content = match content {
+            Some(content) => content,
+            None => continue,
+        };
+        for (side, value) in [
+            ("left", &content.left),
+            ("center", &content.center),
+            ("right", &content.right),
+        ] {
+            if let Some(value) = value {
+                page.push_str(&format!(
+                    "  @{}-{} {{ content: {}; }}\n",
+                    position,
+                    side,
+                    css_content(value, title)
+                ));
+            }
+        }
+    }
+    page.push_str("}\n");
+    if options.title_page {
+        page.push_str("@page :first {\n");
+        for margin_box in ["top-left", "top-center", "top-right"]
+            .iter()
+            .chain(["bottom-left", "bottom-center", "bottom-right"].iter())
+        {
+            page.push_str(&format!("  @{} {{ content: none; }}\n", margin_box));
+        }
+        page.push_str("}\n.title-page { break-after: page; }\n");
+    }
+    page.push_str("h2 { string-set: section content(text); }\n");
+    page.push_str("div[style*=\"page-break-after\"] { break-after: page; }\n");
+    page
+}
+
+// `"Page " counter(page)` style CSS `content` value for a header or footer template.
+fn css_content(template: &str, title: &str) -> String {
+    let mut parts = vec![];
+    let mut text = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        let part = match &rest[start + 1..end] {
+            "page" => Some("counter(page)".to_string()),
+            "pages" => Some("counter(pages)".to_string()),
+            "section" => Some("string(section)".to_string()),
+            "title" => {
+                text.push_str(&rest[..start]);
+                text.push_str(title);
+                None
+            }
+            _ => {
+                text.push_str(&rest[..=end]);
+                None
+            }
+        };
+        if let Some(part) = part {
+            text.push_str(&rest[..start]);
+            if !text.is_empty() {
+                parts.push(css_string(&std::mem::take(&mut text)));
+            }
+            parts.push(part);
+        }
+        rest = &rest[end + 1..];
+    }
+    text.push_str(rest);
+    if !text.is_empty() || parts.is_empty() {
+        parts.push(css_string(&text));
+    }
+    parts.join(" ")
+}
+
+fn css_string(s: &str) -> String {
+    format!(
+        "\"{}\"",
+        s.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\A ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Block, ParseOptions};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_css_content() {
+        assert_eq!(
+            css_content("Page {page} of {pages}", "T"),
+            "\"Page \" counter(page) \" of \" counter(pages)"
+        );
+        assert_eq!(
+            css_content("{title} \"draft\"", "Guide"),
+            "\"Guide \\\"draft\\\"\""
+        );
+        assert_eq!(css_content("{section}", "T"), "string(section)");
+        assert_eq!(css_content("{unknown}", "T"), "\"{unknown}\"");
+    }
+
+    #[test]
+    fn test_render() {
+        let mut document = Document::parse_with_options(
+            ":author: himanoa\n= Guide\n\n== Intro\n\nText",
+            &ParseOptions::new(),
+        )
+        .unwrap();
+        document.append(Document::new(vec![Block::NextPage]));
+        let options = PagedOptions {
+            header: Some(RunningContent {
+                right: Some("{section}".to_string()),
+                ..RunningContent::default()
+            }),
+            ..PagedOptions::default()
+        };
+
+        let html = render(&document, &options);
+        assert_eq!(
+            html.contains(
+                "<body>\n<section class=\"title-page\">\n<h1>Guide</h1>\n<p class=\"author\">himanoa</p>\n</section>\n<h2>Intro</h2>\n<p>Text</p>\n<div style=\"page-break-after: always\"></div>\n</body>"
+            ),
+            true
+        );
+        assert_eq!(
+            html.contains("  @top-right { content: string(section); }\n  @bottom-center { content: counter(page) \" / \" counter(pages); }\n"),
+            true
+        );
+    }
+}
@@ -0,0 +1,429 @@
+use crate::document::Document;
+use crate::render::math::MathNotation;
+use crate::render::roles::RoleStyles;
+use crate::text::{block_text, cell_text, inline_text};
+use crate::{Block, DelimitedKind, HeadingLevel, Inline, ListItem};
+use serde_json::{json, Map, Value};
+
+// Version of the pandoc-types data model the output follows, pandoc 3.x reads it.
+pub const PANDOC_API_VERSION: [u32; 3] = [1, 23, 1];
+
+// Convert to Pandoc's JSON AST, for `pandoc -f json` and its writers. The document title and
+// attributes go to the metadata, the rest maps to the closest Pandoc element, with classes
+// recording what has no equivalent.
+pub fn to_pandoc_json(document: &Document) -> Value {
+    let converter = Converter {
+        stem: document.attribute("stem"),
+    };
+    let mut meta = Map::new();
+    for (name, value) in document.attributes() {
+        meta.insert(name.clone(), json!({"t": "MetaString", "c": value}));
+    }
+    let mut blocks = vec![];
+    for block in document.blocks() {
+        match block {
+            Block::Heading {
+                level: HeadingLevel::Title,
+                children,
+                ..
+            } => {
+                meta.insert(
+                    "title".to_string(),
+                    json!({"t": "MetaInlines", "c": converter.inlines(children)}),
+                );
+            }
+            block => blocks.extend(converter.block(block)),
+        }
+    }
+    json!({
+        "pandoc-api-version": PANDOC_API_VERSION,
+        "meta": meta,
+        "blocks": blocks,
+    })
+}
+
+pub fn to_pandoc_json_string(document: &Document) -> String {
+    serde_json::to_string(&to_pandoc_json(document)).unwrap()
+}
+
+struct Converter<'a> {
+    // Notation of `stem` macros, from the document attribute.
+    stem: Option<&'a str>,
+}
+
+impl Converter<'_> {
+    fn block(&self, block: &Block) -> Option<Value> {
+        Some(match block {
+            Block::Paragraph { children } => node("Para", self.inlines(children)),
+            Block::Macro { children } => node("Para", self.inlines(std::slice::from_ref(children))),
+            Block::Heading {
+                level,
+                children,
+                id,
+            } => node(
+                "Header",
+                json!([
+                    level.as_u8(),
+                    attr(id.as_deref().unwrap_or(""), &[]),
+                    self.inlines(children)
+                ]),
+            ),
+            Block::HorizontalRuledLine => json!({"t": "HorizontalRule"}),
+            Block::NextPage => node("Div", json!([attr("", &["page-break"]), []])),
+            Block::UnorderdList { children } => self.list(children, false),
+            Block::OrderdList { children } => self.list(children, true),
+            Block::Label { children, key } => node(
+                "DefinitionList",
+                json!([[self.inlines(key), [[node("Plain", self.inlines(children))]]]]),
+            ),
+            Block::Qanda { question, answer } => node(
+                "Div",
+                json!([
+                    attr("", &["qanda"]),
+                    [
+                        node("Para", json!([node("Emph", self.inlines(question))])),
+                        node("Para", self.inlines(answer)),
+                    ]
+                ]),
+            ),
+            Block::CodeBlock {
+                children,
+                title,
+                file_type,
+            } => {
+                let classes = file_type.iter().map(|file_type| file_type.as_str());
+                let mut attributes = vec![];
+                if let Some(title) = title {
+                    attributes.push(json!(["title", title]));
+                }
+                node(
+                    "CodeBlock",
+                    json!([
+                        ["", classes.collect::<Vec<_>>(), attributes],
+                        inline_text(children).trim_end_matches('\n')
+                    ]),
+                )
+            }
+            Block::Block {
+                kind: DelimitedKind::Comment,
+                ..
+            } => return None,
+            Block::Block {
+                kind: DelimitedKind::Pass,
+                ..
+            } => node("RawBlock", json!(["html", block_text(block)])),
+            Block::Block {
+                kind,
+                children,
+                title,
+                ..
+            } => {
+                let mut blocks = vec![];
+                if let Some(title) = title {
+                    blocks.push(node("Para", json!([node("Strong", self.inlines(title))])));
+                }
+                blocks.extend(children.iter().filter_map(|block| self.block(block)));
+                match kind {
+                    DelimitedKind::Quote => node("BlockQuote", json!(blocks)),
+                    _ => node("Div", json!([attr("", &[kind.name()]), blocks])),
+                }
+            }
+            Block::Table {
+                columns,
+                rows,
+                title,
+            } => {
+                let width = columns.len().max(1);
+                let caption = match title {
+                    Some(title) => json!([null, [node("Plain", text(title))]]),
+                    None => json!([null, []]),
+                };
+                let colspecs = (0..width)
+                    .map(|_| json!([{"t": "AlignDefault"}, {"t": "ColWidthDefault"}]))
+                    .collect::<Vec<_>>();
+                let head = columns
+                    .iter()
+                    .map(|column| cell(text(column.name()), 1))
+                    .collect::<Vec<_>>();
+                let head = if head.is_empty() {
+                    json!([])
+                } else {
+                    json!([[attr("", &[]), head]])
+                };
+                // A row of a single cell spans all the columns.
+                let body = rows
+                    .iter()
+                    .map(|row| {
+                        let span = if row.cells().len() == 1 { width } else { 1 };
+                        let cells = row
+                            .cells()
+                            .iter()
+                            .map(|row_cell| cell(text(&cell_text(row_cell)), span))
+                            .collect::<Vec<_>>();
+                        json!([attr("", &[]), cells])
+                    })
+                    .collect::<Vec<_>>();
+                node(
+                    "Table",
+                    json!([
+                        attr("", &[]),
+                        caption,
+                        colspecs,
+                        [attr("", &[]), head],
+                        [[attr("", &[]), 0, [], body]],
+                        [attr("", &[]), []]
+                    ]),
+                )
+            }
+            Block::BlankBlock => return None,
+        })
+    }
+
+    // Items carry their nesting level, Pandoc nests a list in the item it belongs to.
+    fn list(&self, items: &[ListItem], ordered: bool) -> Value {
+        let mut items = items.iter().peekable();
+        self.nested_list(&mut items, 1, ordered)
+    }
+
+    fn nested_list<'i, I: Iterator<Item = &'i ListItem>>(
+        &self,
+        items: &mut std::iter::Peekable<I>,
+        level: u32,
+        ordered: bool,
+    ) -> Value {
+        let mut entries = vec![];
+        while let Some(item) = items.next_if(|item| item.level().max(1) >= level) {
+            let mut inlines = vec![];
+            if let ListItem::Check { checked, .. } = item {
+                inlines.push(node("Str", json!(if *checked { "☒" } else { "☐" })));
+                inlines.push(json!({"t": "Space"}));
+            }
+            if let Value::Array(children) = self.inlines(item.children()) {
+                inlines.extend(children);
+            }
+            let mut blocks = vec![node("Plain", Value::Array(inlines))];
+            if items.peek().is_some_and(|next| next.level() > level) {
+                blocks.push(self.nested_list(items, level + 1, ordered));
+            }
+            entries.push(Value::Array(blocks));
+        }
+        if ordered {
+            node(
+                "OrderedList",
+                json!([[1, {"t": "Decimal"}, {"t": "Period"}], entries]),
+            )
+        } else {
+            node("BulletList", Value::Array(entries))
+        }
+    }
+
+    fn inlines(&self, inlines: &[Inline]) -> Value {
+        let mut out = vec![];
+        for inline in inlines {
+            match inline {
+                Inline::Value(value) => {
+                    if let Value::Array(words) = text(value) {
+                        out.extend(words);
+                    }
+                }
+                inline => out.push(self.inline(inline)),
+            }
+        }
+        Value::Array(out)
+    }
+
+    fn inline(&self, inline: &Inline) -> Value {
+        match inline {
+            // Not reached, `inlines` splits text into words in place.
+            Inline::Value(value) => node("Span", json!([attr("", &[]), text(value)])),
+            Inline::HardBreak => json!({"t": "LineBreak"}),
+            Inline::SoftBreak => json!({"t": "SoftBreak"}),
+            Inline::Bold { children } => node("Strong", self.child(children)),
+            Inline::Italic { children } => node("Emph", self.child(children)),
+            Inline::Lead { children } => {
+                node("Span", json!([attr("", &["lead"]), self.child(children)]))
+            }
+            Inline::Marker { children } => {
+                node("Span", json!([attr("", &["mark"]), self.child(children)]))
+            }
+            Inline::Styled { roles, children } => {
+                let classes = RoleStyles::default().html_classes(roles);
+                let classes = classes.split(' ').collect::<Vec<_>>();
+                node("Span", json!([attr("", &classes), self.child(children)]))
+            }
+            Inline::Monospace { children }
+            | Inline::InlineCode { children }
+            | Inline::Literal { children } => node(
+                "Code",
+                json!([
+                    attr("", &[]),
+                    inline_text(std::slice::from_ref(children.as_ref()))
+                ]),
+            ),
+            Inline::Footnote { children, .. } => {
+                node("Note", json!([node("Para", self.child(children))]))
+            }
+            Inline::Macro {
+                attributes,
+                kind,
+                id,
+            } => {
+                let first = attributes
+                    .get("alt")
+                    .or_else(|| attributes.positional(0))
+                    .map(str::to_string);
+                if let Some(notation) = MathNotation::of_macro(kind, self.stem) {
+                    let source = first.unwrap_or_default();
+                    return match notation {
+                        MathNotation::LatexMath => {
+                            node("Math", json!([{"t": "InlineMath"}, source]))
+                        }
+                        // Pandoc has no AsciiMath, keep the source for a filter to convert.
+                        MathNotation::AsciiMath => {
+                            node("Code", json!([attr("", &["asciimath"]), source]))
+                        }
+                    };
+                }
+                match kind.as_str() {
+                    "image" => node(
+                        "Image",
+                        json!([attr("", &[]), text(&first.unwrap_or_default()), [id, ""]]),
+                    ),
+                    "link" => node(
+                        "Link",
+                        json!([attr("", &[]), text(first.as_ref().unwrap_or(id)), [id, ""]]),
+                    ),
+                    "http" | "https" | "mailto" => {
+                        let url = format!("{}:{}", kind, id);
+                        node(
+                            "Link",
+                            json!([
+                                attr("", &[]),
+                                text(first.as_ref().unwrap_or(&url)),
+                                [url, ""]
+                            ]),
+                        )
+                    }
+                    _ => node(
+                        "Span",
+                        json!([attr("", &[]), text(&format!("{}:{}", kind, id))]),
+                    ),
+                }
+            }
+        }
+    }
+
+    fn child(&self, child: &Inline) -> Value {
+        self.inlines(std::slice::from_ref(child))
+    }
+}
+
+fn node(t: &str, c: Value) -> Value {
+    json!({"t": t, "c": c})
+}
+
+// Pandoc's `Attr`: identifier, classes and key value pairs.
+fn attr(id: &str, classes: &[&str]) -> Value {
+    json!([id, classes, []])
+}
+
+fn cell(inlines: Value, colspan: usize) -> Value {
+    json!([
+        attr("", &[]),
+        {"t": "AlignDefault"},
+        1,
+        colspan,
+        [node("Plain", inlines)]
+    ])
+}
+
+// Text as Pandoc splits it, words with a `Space` between them.
+fn text(s: &str) -> Value {
+    let mut out = vec![];
+    let mut word = String::new();
+    for c in s.chars() {
+        if c.is_whitespace() {
+            if !word.is_empty() {
+                out.push(node("Str", json!(word)));
+                word.clear();
+            }
+            if !matches!(out.last(), Some(last) if last["t"] == "Space") {
+                out.push(json!({"t": "Space"}));
+            }
+        } else {
+            word.push(c);
+        }
+    }
+    if !word.is_empty() {
+        out.push(node("Str", json!(word)));
+    }
+    Value::Array(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AttrList, ParseOptions};
+    use pretty_assertions::assert_eq;
+    use smallvec::smallvec;
+
+    #[test]
+    fn test_to_pandoc_json() {
+        let document =
+            Document::parse("= Title\n\n== Intro\n\nSome *bold* text\n\n* a\n** b\n* c").unwrap();
+        assert_eq!(
+            to_pandoc_json(&document),
+            json!({
+                "pandoc-api-version": [1, 23, 1],
+                "meta": {"title": {"t": "MetaInlines", "c": [{"t": "Str", "c": "Title"}]}},
+                "blocks": [
+                    {"t": "Header", "c": [1, ["", [], []], [{"t": "Str", "c": "Intro"}]]},
+                    {"t": "Para", "c": [
+                        {"t": "Str", "c": "Some"},
+                        {"t": "Space"},
+                        {"t": "Strong", "c": [{"t": "Str", "c": "bold"}]},
+                        {"t": "Space"},
+                        {"t": "Str", "c": "text"}
+                    ]},
+                    {"t": "BulletList", "c": [
+                        [
+                            {"t": "Plain", "c": [{"t": "Str", "c": "a"}]},
+                            {"t": "BulletList", "c": [[{"t": "Plain", "c": [{"t": "Str", "c": "b"}]}]]}
+                        ],
+                        [{"t": "Plain", "c": [{"t": "Str", "c": "c"}]}]
+                    ]}
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_code_and_math() {
+        let mut document =
+            Document::parse_with_options(":stem: latexmath\n", &ParseOptions::new()).unwrap();
+        document.append(Document::new(vec![
+            Block::Paragraph {
+                children: smallvec![Inline::Macro {
+                    attributes: Box::new(AttrList::from(vec!["x^2".to_string()])),
+                    kind: "stem".into(),
+                    id: "".to_string(),
+                }]
+                .into(),
+            },
+            Block::CodeBlock {
+                children: smallvec![Inline::Value("fn main() {}\n".to_string())],
+                title: Some("main.rs".to_string()),
+                file_type: Some("rust".into()),
+            },
+        ]));
+        let blocks = &to_pandoc_json(&document)["blocks"];
+        assert_eq!(
+            blocks[0]["c"][0],
+            json!({"t": "Math", "c": [{"t": "InlineMath"}, "x^2"]})
+        );
+        assert_eq!(
+            blocks[1],
+            json!({"t": "CodeBlock", "c": [["", ["rust"], [["title", "main.rs"]]], "fn main() {}"]})
+        );
+    }
+}
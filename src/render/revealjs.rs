@@ -0,0 +1,191 @@
+use crate::document::Document;
+use crate::render::html::{self, escape, HtmlOptions};
+use crate::{Block, HeadingLevel};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RevealJsOptions {
+    pub reveal_js_dir: String,
+    pub theme: String,
+}
+
+impl Default for RevealJsOptions {
+    fn default() -> Self {
+        RevealJsOptions {
+            reveal_js_dir: "https://cdn.jsdelivr.net/npm/reveal.js@5".to_string(),
+            theme: "black".to_string(),
+        }
+    }
+}
+
+impl RevealJsOptions {
+    // `revealjsdir` and `revealjs_theme` document attributes win over the defaults.
+    pub fn from_document(document: &Document) -> Self {
+        let mut options = RevealJsOptions::default();
+        if let Some(dir) = document.attribute("revealjsdir") {
+            options.reveal_js_dir = dir.to_string();
+        }
+        if let Some(theme) = document.attribute("revealjs_theme") {
+            options.theme = theme.to_string();
+        }
+        options
+    }
+}
+
+#[derive(Default)]
+struct Slide {
+    id: Option<String>,
+    class: Option<&'static str>,
+    content: String,
+    notes: String,
+}
+
+// Level 1 sections and `<<<` start new slides, blocks with the `notes` role become speaker notes.
+pub fn render(document: &Document, options: &RevealJsOptions) -> String {
+    let html_options = HtmlOptions::default();
+    let render_block = |block| html::render_block(block, &html_options);
+    let render_inlines = |inlines| html::render_inlines(inlines, &html_options);
+
+    let mut slides: Vec<Slide> = vec![];
+    let mut current = Slide::default();
+    let mut title = None;
+
+    for (index, block) in document.blocks().iter().enumerate() {
+        if document
+            .meta(index)
+            .is_some_and(|meta| meta.has_role("notes"))
+        {
+            current.notes.push_str(&render_block(block));
+            continue;
+        }
+        match block {
+            Block::Heading {
+                level: HeadingLevel::Title,
+                children,
+                id,
+            } => {
+                slides.push(std::mem::take(&mut current));
+                title = Some(render_inlines(children));
+                current.id = id.clone();
+                current.class = Some("title");
+                current.content = format!("<h1>{}</h1>\n", render_inlines(children));
+                if let Some(author) = document.attribute("author") {
+                    current
+                        .content
+                        .push_str(&format!("<p class=\"author\">{}</p>\n", escape(author)));
+                }
+            }
+            Block::Heading {
+                level: HeadingLevel::Level1,
+                children,
+                id,
+            } => {
+                slides.push(std::mem::take(&mut current));
+                current.id = id.clone();
+                current.content = format!("<h2>{}</h2>\n", render_inlines(children));
+            }
+            Block::HorizontalRuledLine => slides.push(std::mem::take(&mut current)),
+            Block::BlankBlock => {}
+            block => current.content.push_str(&render_block(block)),
+        }
+    }
+    slides.push(current);
+
+    let mut sections = String::new();
+    for slide in slides {
+        if slide.content.is_empty() && slide.notes.is_empty() {
+            continue;
+        }
+        sections.push_str("<section");
+        if let Some(id) = slide.id {
+            sections.push_str(&format!(" id=\"{}\"", escape(&id)));
+        }
+        if let Some(class) = slide.class {
+            sections.push_str(&format!(" class=\"{}\"", class));
+        }
+        sections.push_str(">\n");
+        sections.push_str(&slide.content);
+        if !slide.notes.is_empty() {
+            sections.push_str(&format!(
+                "<aside class=\"notes\">\n{}</aside>\n",
+                slide.notes
+            ));
+        }
+        sections.push_str("</section>\n");
+    }
+
+    let dir = escape(&options.reveal_js_dir);
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<link rel="stylesheet" href="{dir}/dist/reveal.css">
+<link rel="stylesheet" href="{dir}/dist/theme/{theme}.css">
+</head>
+<body>
+<div class="reveal">
+<div class="slides">
+{sections}</div>
+</div>
+<script src="{dir}/dist/reveal.js"></script>
+<script src="{dir}/plugin/notes/notes.js"></script>
+<script>Reveal.initialize({{ hash: true, plugins: [RevealNotes] }});</script>
+</body>
+</html>
+"#,
+        title = title.unwrap_or_default(),
+        dir = dir,
+        theme = escape(&options.theme),
+        sections = sections
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ParseOptions;
+    use pretty_assertions::assert_eq;
+
+    fn slides(html: &str) -> &str {
+        let start = html.find("<div class=\"slides\">\n").unwrap() + 21;
+        let end = html.find("</div>\n</div>\n<script").unwrap();
+        &html[start..end]
+    }
+
+    #[test]
+    fn test_render() {
+        let document = Document::parse_with_options(
+            ":author: himanoa\n= Talk\n\n== First\n\nHello\n\n[.notes]\nSay hi\n\n<<<\n\nContinued\n\n== Second\n\n* a",
+            &ParseOptions::new(),
+        )
+        .unwrap();
+        let html = render(&document, &RevealJsOptions::from_document(&document));
+
+        assert_eq!(
+            slides(&html),
+            r#"<section class="title">
+<h1>Talk</h1>
+<p class="author">himanoa</p>
+</section>
+<section>
+<h2>First</h2>
+<p>Hello</p>
+<aside class="notes">
+<p>Say hi</p>
+</aside>
+</section>
+<section>
+<p>Continued</p>
+</section>
+<section>
+<h2>Second</h2>
+<ul>
+<li>a</li>
+</ul>
+</section>
+"#
+        );
+        assert_eq!(html.contains("<title>Talk</title>"), true);
+    }
+}
@@ -0,0 +1,139 @@
+use crate::symbol::Symbol;
+use std::collections::BTreeMap;
+
+// How renderers show text with a role, `[.underline]#text#`.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct RoleStyle {
+    // Extra HTML class, for stylesheets that don't know the role.
+    pub class: Option<String>,
+    // LaTeX command taking the text as argument, `underline` for `\underline{text}`.
+    pub latex: Option<String>,
+    // Roff font for man pages, `I` for `\fItext\fP`.
+    pub roff: Option<String>,
+}
+
+impl RoleStyle {
+    pub fn new() -> Self {
+        RoleStyle::default()
+    }
+
+    pub fn class<S: Into<String>>(mut self, class: S) -> Self {
+        self.class = Some(class.into());
+        self
+    }
+
+    pub fn latex<S: Into<String>>(mut self, command: S) -> Self {
+        self.latex = Some(command.into());
+        self
+    }
+
+    pub fn roff<S: Into<String>>(mut self, font: S) -> Self {
+        self.roff = Some(font.into());
+        self
+    }
+}
+
+// Styles of the roles documents put on text, shared by the backends so a role looks the same in
+// all of them. The default has the built-in roles of Asciidoctor that the formats can show.
+// Roles are written as HTML and Pandoc classes whether they have a style or not.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RoleStyles {
+    styles: BTreeMap<String, RoleStyle>,
+}
+
+impl Default for RoleStyles {
+    fn default() -> Self {
+        RoleStyles::empty()
+            // Terminals show the italic font of man pages underlined.
+            .role("underline", RoleStyle::new().latex("underline").roff("I"))
+            // `\st` of the soul package, loaded for highlights anyway.
+            .role("line-through", RoleStyle::new().latex("st"))
+    }
+}
+
+impl RoleStyles {
+    pub fn new() -> Self {
+        RoleStyles::default()
+    }
+
+    // No styles, roles are only written as classes.
+    pub fn empty() -> Self {
+        RoleStyles {
+            styles: BTreeMap::new(),
+        }
+    }
+
+    // Replaces the style of `role`.
+    pub fn role<S: Into<String>>(mut self, role: S, style: RoleStyle) -> Self {
+        self.styles.insert(role.into(), style);
+        self
+    }
+
+    pub fn get(&self, role: &str) -> Option<&RoleStyle> {
+        self.styles.get(role)
+    }
+
+    fn styles<'a>(&'a self, roles: &'a [Symbol]) -> impl Iterator<Item = &'a RoleStyle> + 'a {
+        roles.iter().filter_map(move |role| self.get(role))
+    }
+
+    // Value of the `class` attribute, the roles followed by the classes they map to.
+    pub fn html_classes(&self, roles: &[Symbol]) -> String {
+        let classes = roles.iter().map(Symbol::as_str).chain(
+            self.styles(roles)
+                .filter_map(|style| style.class.as_deref()),
+        );
+        classes.collect::<Vec<_>>().join(" ")
+    }
+
+    // `text` inside the commands of the roles, the first role outermost.
+    pub fn latex(&self, roles: &[Symbol], text: &str) -> String {
+        let commands = self
+            .styles(roles)
+            .filter_map(|style| style.latex.as_deref())
+            .collect::<Vec<_>>();
+        let mut out = String::new();
+        for command in &commands {
+            out.push_str(&format!("\\{}{{", command));
+        }
+        out.push_str(text);
+        out.push_str(&"}".repeat(commands.len()));
+        out
+    }
+
+    // `text` in the font of the first role that has one. Roff only goes back to the previous
+    // font, so fonts don't nest.
+    pub fn roff(&self, roles: &[Symbol], text: &str) -> String {
+        let font = self.styles(roles).find_map(|style| style.roff.as_deref());
+        match font {
+            Some(font) if font.len() == 1 => format!("\\f{}{}\\fP", font, text),
+            Some(font) if font.len() == 2 => format!("\\f({}{}\\fP", font, text),
+            Some(font) => format!("\\f[{}]{}\\fP", font, text),
+            None => text.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_role_styles() {
+        let styles = RoleStyles::new().role(
+            "keyword",
+            RoleStyle::new()
+                .class("hl-keyword")
+                .latex("textbf")
+                .roff("B"),
+        );
+        let roles = vec![Symbol::from("keyword"), Symbol::from("underline")];
+        assert_eq!(styles.html_classes(&roles), "keyword underline hl-keyword");
+        assert_eq!(styles.latex(&roles, "fn"), "\\textbf{\\underline{fn}}");
+        assert_eq!(styles.roff(&roles, "fn"), "\\fBfn\\fP");
+        assert_eq!(styles.roff(&roles[1..], "fn"), "\\fIfn\\fP");
+        assert_eq!(styles.roff(&[Symbol::from("lead")], "fn"), "fn");
+        assert_eq!(RoleStyles::empty().latex(&roles, "fn"), "fn");
+    }
+}
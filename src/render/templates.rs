@@ -0,0 +1,103 @@
+use crate::json::{block_to_json, block_type_name};
+use crate::Block;
+use anyhow::Result;
+use minijinja::{context, AutoEscape, Environment, Value};
+
+// Jinja templates keyed by the node `type` of the JSON AST (`paragraph`, `code_block`, ...).
+//
+// A template sees the node as `node`, its rendered inline content as `content` and the markup
+// the built-in renderer would have produced as `default`. Text interpolated from `node` is
+// HTML-escaped.
+pub struct TemplateRegistry {
+    env: Environment<'static>,
+}
+
+impl Default for TemplateRegistry {
+    fn default() -> Self {
+        TemplateRegistry::new()
+    }
+}
+
+impl TemplateRegistry {
+    pub fn new() -> Self {
+        let mut env = Environment::new();
+        env.set_auto_escape_callback(|_| AutoEscape::Html);
+        env.set_keep_trailing_newline(true);
+        TemplateRegistry { env }
+    }
+
+    pub fn register<N: Into<String>, S: Into<String>>(&mut self, node: N, source: S) -> Result<()> {
+        self.env.add_template_owned(node.into(), source.into())?;
+        Ok(())
+    }
+
+    pub fn has_template(&self, node: &str) -> bool {
+        self.env.get_template(node).is_ok()
+    }
+
+    pub(crate) fn has_template_for(&self, block: &Block) -> bool {
+        self.has_template(block_type_name(block))
+    }
+
+    // Falls back to `default` when the template fails, with the error left in a comment.
+    pub(crate) fn render_block(&self, block: &Block, content: String, default: String) -> String {
+        let node = block_to_json(block);
+        let result = self
+            .env
+            .get_template(block_type_name(block))
+            .and_then(|template| {
+                template.render(context! {
+                    node => Value::from_serialize(&node),
+                    content => Value::from_safe_string(content),
+                    default => Value::from_safe_string(default.clone()),
+                })
+            });
+        match result {
+            Ok(html) => html,
+            Err(error) => format!(
+                "<!-- template error: {} -->\n{}",
+                error.to_string().replace("--", "- -"),
+                default
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Document;
+    use crate::render::html::{render_with_options, HtmlOptions};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_templates() {
+        let mut templates = TemplateRegistry::new();
+        templates
+            .register(
+                "heading",
+                "<h{{ node.level + 1 }} class=\"section\">{{ node.children[0].value }}</h{{ node.level + 1 }}>\n",
+            )
+            .unwrap();
+        templates
+            .register(
+                "paragraph",
+                "<div class=\"para\">{{ content }}</div>\n{{ default }}",
+            )
+            .unwrap();
+        let options = HtmlOptions::new().templates(templates);
+
+        let document = Document::parse("== A&B\n\nx *b* <c>").unwrap();
+        assert_eq!(
+            render_with_options(&document, &options),
+            "<h2 class=\"section\">A&amp;B</h2>\n<div class=\"para\">x <strong>b</strong> &lt;c&gt;</div>\n<p>x <strong>b</strong> &lt;c&gt;</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_register_rejects_invalid_template() {
+        let mut templates = TemplateRegistry::new();
+        assert_eq!(templates.register("paragraph", "{% if %}").is_err(), true);
+        assert_eq!(templates.has_template("paragraph"), false);
+    }
+}
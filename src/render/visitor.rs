@@ -0,0 +1,260 @@
+use crate::document::Document;
+use crate::symbol::Symbol;
+use crate::{
+    AttrList, Block, DelimitedKind, FootnoteType, HeadingLevel, Inline, ListItem, TableColumn,
+    TableRow,
+};
+
+// Depth-first driver over the AST. Every method defaults to visiting the node's children, so an
+// implementation only overrides the nodes it wants to emit something for.
+pub trait Renderer {
+    fn document(&mut self, document: &Document) {
+        for block in document.blocks() {
+            self.block(block);
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        walk_block(self, block)
+    }
+
+    fn paragraph(&mut self, children: &[Inline]) {
+        self.inlines(children)
+    }
+
+    fn heading(&mut self, _level: &HeadingLevel, children: &[Inline], _id: Option<&str>) {
+        self.inlines(children)
+    }
+
+    fn horizontal_rule(&mut self) {}
+
+    fn page_break(&mut self) {}
+
+    fn unordered_list(&mut self, items: &[ListItem]) {
+        for item in items {
+            self.list_item(item);
+        }
+    }
+
+    fn ordered_list(&mut self, items: &[ListItem]) {
+        for item in items {
+            self.list_item(item);
+        }
+    }
+
+    fn list_item(&mut self, item: &ListItem) {
+        self.inlines(item.children())
+    }
+
+    fn label(&mut self, key: &[Inline], children: &[Inline]) {
+        self.inlines(key);
+        self.inlines(children);
+    }
+
+    fn qanda(&mut self, question: &[Inline], answer: &[Inline]) {
+        self.inlines(question);
+        self.inlines(answer);
+    }
+
+    fn code_block(&mut self, children: &[Inline], _title: Option<&str>, _file_type: Option<&str>) {
+        self.inlines(children)
+    }
+
+    fn open_block(&mut self, children: &[Block], title: Option<&[Inline]>) {
+        if let Some(title) = title {
+            self.inlines(title);
+        }
+        for block in children {
+            self.block(block);
+        }
+    }
+
+    fn table(&mut self, _columns: &[TableColumn], rows: &[TableRow], _title: Option<&str>) {
+        for cell in rows.iter().flat_map(TableRow::cells) {
+            for block in &cell.blocks {
+                self.block(block);
+            }
+        }
+    }
+
+    fn blank(&mut self) {}
+
+    fn inlines(&mut self, inlines: &[Inline]) {
+        for inline in inlines {
+            self.inline(inline);
+        }
+    }
+
+    fn inline(&mut self, inline: &Inline) {
+        walk_inline(self, inline)
+    }
+
+    fn text(&mut self, _text: &str) {}
+
+    fn hard_break(&mut self) {}
+
+    fn soft_break(&mut self) {}
+
+    fn literal(&mut self, child: &Inline) {
+        self.inline(child)
+    }
+
+    fn footnote(&mut self, _kind: &FootnoteType, child: &Inline) {
+        self.inline(child)
+    }
+
+    fn lead(&mut self, child: &Inline) {
+        self.inline(child)
+    }
+
+    fn bold(&mut self, child: &Inline) {
+        self.inline(child)
+    }
+
+    fn italic(&mut self, child: &Inline) {
+        self.inline(child)
+    }
+
+    fn monospace(&mut self, child: &Inline) {
+        self.inline(child)
+    }
+
+    fn marker(&mut self, child: &Inline) {
+        self.inline(child)
+    }
+
+    fn styled(&mut self, _roles: &[Symbol], child: &Inline) {
+        self.inline(child)
+    }
+
+    fn inline_code(&mut self, child: &Inline) {
+        self.inline(child)
+    }
+
+    fn inline_macro(&mut self, _kind: &str, _id: &str, _attributes: &AttrList) {}
+}
+
+// Dispatch `block` to the matching `Renderer` method. Useful from an overridden `block()` that
+// wants to wrap the default behaviour.
+pub fn walk_block<R: Renderer + ?Sized>(renderer: &mut R, block: &Block) {
+    match block {
+        Block::Paragraph { children } => renderer.paragraph(children),
+        Block::Macro { children } => renderer.paragraph(std::slice::from_ref(children)),
+        Block::Heading {
+            level,
+            children,
+            id,
+        } => renderer.heading(level, children, id.as_deref()),
+        Block::HorizontalRuledLine => renderer.horizontal_rule(),
+        Block::NextPage => renderer.page_break(),
+        Block::UnorderdList { children } => renderer.unordered_list(children),
+        Block::OrderdList { children } => renderer.ordered_list(children),
+        Block::Label { children, key } => renderer.label(key, children),
+        Block::Qanda { question, answer } => renderer.qanda(question, answer),
+        Block::CodeBlock {
+            children,
+            title,
+            file_type,
+        } => renderer.code_block(children, title.as_deref(), file_type.as_deref()),
+        // Comments are not part of the content.
+        Block::Block {
+            kind: DelimitedKind::Comment,
+            ..
+        } => {}
+        Block::Block {
+            children, title, ..
+        } => renderer.open_block(children, title.as_ref().map(|title| title.as_slice())),
+        Block::Table {
+            columns,
+            rows,
+            title,
+        } => renderer.table(columns, rows, title.as_deref()),
+        Block::BlankBlock => renderer.blank(),
+    }
+}
+
+pub fn walk_inline<R: Renderer + ?Sized>(renderer: &mut R, inline: &Inline) {
+    match inline {
+        Inline::Value(value) => renderer.text(value),
+        Inline::HardBreak => renderer.hard_break(),
+        Inline::SoftBreak => renderer.soft_break(),
+        Inline::Literal { children } => renderer.literal(children),
+        Inline::Footnote { kind, children } => renderer.footnote(kind, children),
+        Inline::Lead { children } => renderer.lead(children),
+        Inline::Bold { children } => renderer.bold(children),
+        Inline::Italic { children } => renderer.italic(children),
+        Inline::Monospace { children } => renderer.monospace(children),
+        Inline::Marker { children } => renderer.marker(children),
+        Inline::Styled { roles, children } => renderer.styled(roles, children),
+        Inline::InlineCode { children } => renderer.inline_code(children),
+        Inline::Macro {
+            attributes,
+            kind,
+            id,
+        } => renderer.inline_macro(kind, id, attributes),
+    }
+}
+
+pub fn render_with<R: Renderer>(document: &Document, mut renderer: R) -> R {
+    renderer.document(document);
+    renderer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[derive(Default)]
+    struct BbCode {
+        out: String,
+    }
+
+    impl Renderer for BbCode {
+        fn heading(&mut self, _level: &HeadingLevel, children: &[Inline], _id: Option<&str>) {
+            self.out.push_str("[size=5]");
+            self.inlines(children);
+            self.out.push_str("[/size]\n");
+        }
+
+        fn paragraph(&mut self, children: &[Inline]) {
+            self.inlines(children);
+            self.out.push('\n');
+        }
+
+        fn text(&mut self, text: &str) {
+            self.out.push_str(text);
+        }
+
+        fn bold(&mut self, child: &Inline) {
+            self.out.push_str("[b]");
+            self.inline(child);
+            self.out.push_str("[/b]");
+        }
+    }
+
+    #[derive(Default)]
+    struct HeadingCounter {
+        headings: usize,
+    }
+
+    impl Renderer for HeadingCounter {
+        fn heading(&mut self, _level: &HeadingLevel, _children: &[Inline], _id: Option<&str>) {
+            self.headings += 1;
+        }
+    }
+
+    #[test]
+    fn test_render_with() {
+        let document = Document::parse("== A\n\nsome *bold* _text_\n\n== B").unwrap();
+
+        let bbcode = render_with(&document, BbCode::default());
+        assert_eq!(
+            bbcode.out,
+            "[size=5]A[/size]\nsome [b]bold[/b] text\n[size=5]B[/size]\n"
+        );
+
+        let counter = render_with(&document, HeadingCounter::default());
+        assert_eq!(counter.headings, 2);
+    }
+}
@@ -0,0 +1,253 @@
+use anyhow::{bail, Result};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Template {
+    Article,
+    Book,
+    Manpage,
+    Readme,
+}
+
+impl Template {
+    pub const ALL: [Template; 4] = [
+        Template::Article,
+        Template::Book,
+        Template::Manpage,
+        Template::Readme,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Template::Article => "article",
+            Template::Book => "book",
+            Template::Manpage => "manpage",
+            Template::Readme => "readme",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Template> {
+        Template::ALL
+            .iter()
+            .copied()
+            .find(|template| template.name() == name)
+    }
+
+    // Skeleton with `{{title}}`, `{{author}}`, `{{email}}` and `{{name}}` placeholders.
+    pub fn source(self) -> &'static str {
+        match self {
+            Template::Article => ARTICLE,
+            Template::Book => BOOK,
+            Template::Manpage => MANPAGE,
+            Template::Readme => README,
+        }
+    }
+}
+
+const ARTICLE: &str = ":author: {{author}}
+:email: {{email}}
+:doctype: article
+:toc:
+:sectnums:
+
+= {{title}}
+
+Summary of the article.
+
+== Introduction
+
+What the reader will learn.
+
+== Conclusion
+
+What to read next.
+";
+
+const BOOK: &str = ":author: {{author}}
+:email: {{email}}
+:doctype: book
+:toc:
+:sectnums:
+
+= {{title}}
+
+== Preface
+
+Who the book is for.
+
+== Getting started
+
+First steps.
+
+=== Installation
+
+How to install.
+
+== Reference
+
+Details.
+";
+
+const MANPAGE: &str = ":author: {{author}}
+:email: {{email}}
+:doctype: manpage
+:manmanual: {{title}} Manual
+:mansource: {{title}}
+
+= {{name}}(1)
+
+== NAME
+
+{{name}} - one line description
+
+== SYNOPSIS
+
+{{name}} [OPTIONS] FILE
+
+== DESCRIPTION
+
+What the command does.
+
+== OPTIONS
+
+Options it accepts.
+
+== AUTHOR
+
+{{author}}
+";
+
+const README: &str = ":author: {{author}}
+:email: {{email}}
+
+= {{title}}
+
+One paragraph about the project.
+
+== Installation
+
+How to install it.
+
+== Usage
+
+How to use it.
+
+== License
+
+Under which terms it is distributed.
+";
+
+#[derive(Debug, Clone)]
+pub struct ScaffoldOptions {
+    pub title: String,
+    pub author: Option<String>,
+    pub email: Option<String>,
+}
+
+impl Default for ScaffoldOptions {
+    fn default() -> Self {
+        ScaffoldOptions {
+            title: "Untitled".to_string(),
+            author: None,
+            email: None,
+        }
+    }
+}
+
+impl ScaffoldOptions {
+    pub fn new() -> Self {
+        ScaffoldOptions::default()
+    }
+
+    pub fn title<S: Into<String>>(mut self, title: S) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    pub fn author<S: Into<String>>(mut self, author: S) -> Self {
+        self.author = Some(author.into());
+        self
+    }
+
+    pub fn email<S: Into<String>>(mut self, email: S) -> Self {
+        self.email = Some(email.into());
+        self
+    }
+}
+
+// Fill the placeholders of `template`, a built-in skeleton or a user file. Attribute entries
+// whose placeholder has no value are dropped rather than left empty, and a placeholder the
+// options don't know is an error so typos don't end up in the document.
+pub fn scaffold(template: &str, options: &ScaffoldOptions) -> Result<String> {
+    // The command name of manpages, e.g. `My Tool` becomes `my-tool`.
+    let name = options
+        .title
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase();
+    let value = |key: &str| -> Result<Option<&str>> {
+        Ok(match key {
+            "title" => Some(&options.title),
+            "name" => Some(&name),
+            "author" => options.author.as_deref(),
+            "email" => options.email.as_deref(),
+            _ => bail!("unknown placeholder `{{{{{}}}}}` in template", key),
+        })
+    };
+
+    let mut out = String::with_capacity(template.len());
+    'lines: for line in template.split_inclusive('\n') {
+        let mut filled = String::with_capacity(line.len());
+        let mut rest = line;
+        while let Some(start) = rest.find("{{") {
+            let end = match rest[start..].find("}}") {
+                Some(end) => start + end,
+                None => break,
+            };
+            filled.push_str(&rest[..start]);
+            match value(rest[start + 2..end].trim())? {
+                Some(value) => filled.push_str(value),
+                None if line.starts_with(':') => continue 'lines,
+                None => {}
+            }
+            rest = &rest[end + 2..];
+        }
+        filled.push_str(rest);
+        out.push_str(&filled);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Document;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_scaffold() {
+        let options = ScaffoldOptions::new().title("My Tool").author("himanoa");
+        let readme = scaffold(Template::Readme.source(), &options).unwrap();
+        assert_eq!(readme.starts_with(":author: himanoa\n\n= My Tool\n"), true);
+
+        let manpage = scaffold(Template::Manpage.source(), &options).unwrap();
+        assert_eq!(manpage.contains("= my-tool(1)\n"), true);
+        assert_eq!(manpage.contains("\nmy-tool - one line description\n"), true);
+
+        assert_eq!(scaffold("{{titel}}", &options).is_err(), true);
+        assert_eq!(Template::from_name("book"), Some(Template::Book));
+    }
+
+    #[test]
+    fn test_templates_parse() {
+        let options = ScaffoldOptions::new()
+            .author("himanoa")
+            .email("h@example.com");
+        for template in Template::ALL.iter() {
+            let source = scaffold(template.source(), &options).unwrap();
+            let document = Document::parse_with_options(&source, &Default::default()).unwrap();
+            assert_eq!(document.attribute("author"), Some("himanoa"));
+            assert_eq!(document.outline(None).is_empty(), false);
+        }
+    }
+}
@@ -0,0 +1,503 @@
+// Without the `serve` feature only the routing and rendering remain, for the tests.
+#![cfg_attr(not(feature = "serve"), allow(dead_code))]
+
+use crate::build::{layout, resolve_xrefs};
+use crate::config::Config;
+use crate::document::Document;
+use crate::options::ParseOptions;
+use crate::render::html::{self, escape, HtmlOptions};
+use crate::text::inline_text;
+use crate::SlugStrategy;
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+// Path of the websocket pages connect to, a message on it means a file has changed.
+pub const RELOAD_PATH: &str = "/__livereload";
+
+const RELOAD_SCRIPT: &str = "<script>
+new WebSocket((location.protocol === 'https:' ? 'wss://' : 'ws://') + location.host + '/__livereload')
+  .onmessage = () => location.reload();
+</script>
+";
+
+#[derive(Clone)]
+pub struct ServeOptions {
+    pub html: HtmlOptions,
+    // Base options of every document. Includes resolve relative to each document.
+    pub parse: ParseOptions,
+    pub config: Config,
+    pub host: String,
+    pub port: u16,
+    // How often the directory is checked for changed files.
+    pub poll_interval: Duration,
+}
+
+impl Default for ServeOptions {
+    fn default() -> Self {
+        ServeOptions {
+            html: HtmlOptions::new().heading_ids(SlugStrategy::default()),
+            parse: ParseOptions::new(),
+            config: Config::default(),
+            host: "127.0.0.1".to_string(),
+            port: 8000,
+            poll_interval: Duration::from_millis(500),
+        }
+    }
+}
+
+impl ServeOptions {
+    pub fn new() -> Self {
+        ServeOptions::default()
+    }
+
+    pub fn html(mut self, html: HtmlOptions) -> Self {
+        self.html = html;
+        self
+    }
+
+    pub fn parse(mut self, parse: ParseOptions) -> Self {
+        self.parse = parse;
+        self
+    }
+
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn host<S: Into<String>>(mut self, host: S) -> Self {
+        self.host = host.into();
+        self
+    }
+
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    pub fn address(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+}
+
+// What a request path refers to below the served directory.
+#[derive(Debug, PartialEq, Eq)]
+enum Route {
+    // An .adoc file, asked for directly or by the .html name `build` would give its page.
+    Document(PathBuf),
+    Directory(PathBuf),
+    File(PathBuf),
+    NotFound,
+}
+
+struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn html(status: u16, html: String) -> Self {
+        Response {
+            status,
+            content_type: "text/html; charset=utf-8",
+            body: html.into_bytes(),
+        }
+    }
+}
+
+// Serve the documents under `root` as HTML pages rendered on every request, until the process
+// is stopped. Pages reload themselves when a file under `root` changes.
+#[cfg(feature = "serve")]
+pub fn serve(root: &Path, options: &ServeOptions) -> Result<()> {
+    use std::sync::{Arc, Mutex};
+    use tiny_http::{Header, Server, StatusCode};
+    use tungstenite::handshake::derive_accept_key;
+    use tungstenite::protocol::{Role, WebSocket};
+    use tungstenite::Message;
+
+    let server = Server::http(options.address())
+        .map_err(|error| anyhow::anyhow!("failed to listen on {}: {}", options.address(), error))?;
+    let clients = Arc::new(Mutex::new(Vec::new()));
+
+    let watched = Arc::clone(&clients);
+    let watch_root = root.to_path_buf();
+    let interval = options.poll_interval;
+    std::thread::spawn(move || {
+        let mut last = modification_times(&watch_root);
+        loop {
+            std::thread::sleep(interval);
+            let current = modification_times(&watch_root);
+            if current != last {
+                last = current;
+                // Pages that went away fail the send and are dropped.
+                watched
+                    .lock()
+                    .unwrap()
+                    .retain_mut(|client: &mut WebSocket<_>| {
+                        client.send(Message::text("reload")).is_ok()
+                    });
+            }
+        }
+    });
+
+    // Requests are handled one at a time, a preview has a single reader.
+    for request in server.incoming_requests() {
+        let path = request
+            .url()
+            .split(['?', '#'])
+            .next()
+            .unwrap_or("/")
+            .to_string();
+        if path == RELOAD_PATH {
+            let key = request
+                .headers()
+                .iter()
+                .find(|header| header.field.equiv("Sec-WebSocket-Key"))
+                .map(|header| derive_accept_key(header.value.as_bytes()));
+            let accept = match key {
+                Some(accept) => accept,
+                None => {
+                    let _ = request.respond(tiny_http::Response::empty(StatusCode(400)));
+                    continue;
+                }
+            };
+            let response = tiny_http::Response::empty(StatusCode(101))
+                .with_header(Header::from_bytes("Sec-WebSocket-Accept", accept).unwrap());
+            let stream = request.upgrade("websocket", response);
+            clients
+                .lock()
+                .unwrap()
+                .push(WebSocket::from_raw_socket(stream, Role::Server, None));
+            continue;
+        }
+        let response = respond(root, &path, options);
+        let content_type = Header::from_bytes("Content-Type", response.content_type).unwrap();
+        let _ = request.respond(
+            tiny_http::Response::from_data(response.body)
+                .with_status_code(response.status)
+                .with_header(content_type),
+        );
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "serve"))]
+pub fn serve(_root: &Path, _options: &ServeOptions) -> Result<()> {
+    anyhow::bail!("serving documents needs the `serve` feature")
+}
+
+fn respond(root: &Path, path: &str, options: &ServeOptions) -> Response {
+    let result = match route(root, path) {
+        Route::Document(source) => {
+            render_page(root, &source, options).map(|html| Response::html(200, html))
+        }
+        Route::Directory(dir) => listing(root, &dir).map(|html| Response::html(200, html)),
+        Route::File(file) => fs::read(root.join(&file))
+            .with_context(|| format!("failed to read {}", file.display()))
+            .map(|body| Response {
+                status: 200,
+                content_type: content_type(&file),
+                body,
+            }),
+        Route::NotFound => Ok(Response::html(
+            404,
+            page(
+                "Not found",
+                &format!("<p>{} was not found.</p>\n", escape(path)),
+            ),
+        )),
+    };
+    // Errors are shown as a page too, it reloads once the file is fixed.
+    result.unwrap_or_else(|error| {
+        Response::html(
+            500,
+            page(
+                "Error",
+                &format!(
+                    "<pre class=\"error\">{}</pre>\n",
+                    escape(&format!("{:#}", error))
+                ),
+            ),
+        )
+    })
+}
+
+fn route(root: &Path, path: &str) -> Route {
+    let relative = match relative_path(path) {
+        Some(relative) => relative,
+        None => return Route::NotFound,
+    };
+    let full = root.join(&relative);
+    if full.is_dir() {
+        return Route::Directory(relative);
+    }
+    if full.is_file() {
+        return match relative.extension() {
+            Some(extension) if extension == "adoc" => Route::Document(relative),
+            _ => Route::File(relative),
+        };
+    }
+    if relative
+        .extension()
+        .is_some_and(|extension| extension == "html")
+    {
+        let source = relative.with_extension("adoc");
+        if root.join(&source).is_file() {
+            return Route::Document(source);
+        }
+    }
+    Route::NotFound
+}
+
+// The decoded request path relative to the served directory, `None` when it would leave it or
+// names a hidden file or directory such as `.git` or `.env`, which listings leave out too.
+fn relative_path(path: &str) -> Option<PathBuf> {
+    let decoded = percent_decode(path)?;
+    let mut relative = PathBuf::new();
+    for component in Path::new(decoded.trim_start_matches('/')).components() {
+        match component {
+            Component::Normal(name) if name.to_string_lossy().starts_with('.') => return None,
+            Component::Normal(name) => relative.push(name),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    Some(relative)
+}
+
+fn percent_decode(path: &str) -> Option<String> {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            let hex = path.get(index + 1..index + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            index += 3;
+        } else {
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+fn render_page(root: &Path, source: &Path, options: &ServeOptions) -> Result<String> {
+    let path = root.join(source);
+    let text =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    let parse = options.config.apply(options.parse.clone(), base_dir);
+    let document = Document::parse_with_options(&text, &parse)
+        .with_context(|| format!("failed to parse {}", source.display()))?;
+    let title = document
+        .title()
+        .map(inline_text)
+        .unwrap_or_else(|| source.display().to_string());
+    // Links to other documents point at their .html names, which route back to the documents.
+    let (body, _) = resolve_xrefs(
+        &html::render_with_options(&document, &options.html),
+        source,
+        &BTreeMap::new(),
+    );
+    Ok(page(&title, &body))
+}
+
+// Documents and subdirectories of `dir`, the other files are left out.
+fn listing(root: &Path, dir: &Path) -> Result<String> {
+    let full = root.join(dir);
+    let entries = fs::read_dir(&full)
+        .with_context(|| format!("failed to read directory {}", full.display()))?;
+    let mut names = BTreeSet::new();
+    for entry in entries {
+        let path = entry?.path();
+        let name = match path.file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => continue,
+        };
+        if name.starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            names.insert(format!("{}/", name));
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "adoc")
+        {
+            names.insert(name);
+        }
+    }
+    let base = dir
+        .components()
+        .map(|component| format!("/{}", component.as_os_str().to_string_lossy()))
+        .collect::<String>();
+    let mut body = String::from("<ul>\n");
+    for name in &names {
+        body.push_str(&format!(
+            "<li><a href=\"{}/{}\">{}</a></li>\n",
+            escape(&base),
+            escape(name),
+            escape(name)
+        ));
+    }
+    body.push_str("</ul>\n");
+    let title = format!("/{}", dir.display());
+    Ok(page(&title, &body))
+}
+
+// A full page with the reload script before the end of the body.
+fn page(title: &str, body: &str) -> String {
+    let html = layout(title, body, None);
+    match html.rfind("</body>") {
+        Some(end) => format!("{}{}{}", &html[..end], RELOAD_SCRIPT, &html[end..]),
+        None => html + RELOAD_SCRIPT,
+    }
+}
+
+fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("html") => "text/html; charset=utf-8",
+        Some("css") => "text/css",
+        Some("js") => "text/javascript",
+        Some("json") => "application/json",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("txt") | Some("adoc") => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+// Modification time of every file under `dir`, hidden files and directories left out. A
+// directory that can't be read counts as empty.
+fn modification_times(dir: &Path) -> BTreeMap<PathBuf, SystemTime> {
+    let mut times = BTreeMap::new();
+    collect_modification_times(dir, &mut times);
+    times
+}
+
+fn collect_modification_times(dir: &Path, times: &mut BTreeMap<PathBuf, SystemTime>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        if path.is_dir() {
+            collect_modification_times(&path, times);
+        } else if let Ok(modified) = entry.metadata().and_then(|metadata| metadata.modified()) {
+            times.insert(path, modified);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn site(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("combine-sandbox-serve-{}", name));
+        let _ = fs::remove_dir_all(&root);
+        for (path, source) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, source).unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn test_route() {
+        let root = site(
+            "route",
+            &[
+                ("index.adoc", "= Home\n\nsee xref:guide/install.adoc[]"),
+                ("guide/install.adoc", "= Install"),
+                ("images/logo.png", "png"),
+            ],
+        );
+        assert_eq!(
+            route(&root, "/index.adoc"),
+            Route::Document(PathBuf::from("index.adoc"))
+        );
+        assert_eq!(
+            route(&root, "/guide/install.html"),
+            Route::Document(PathBuf::from("guide/install.adoc"))
+        );
+        assert_eq!(
+            route(&root, "/guide/"),
+            Route::Directory(PathBuf::from("guide"))
+        );
+        assert_eq!(
+            route(&root, "/images/logo.png"),
+            Route::File(PathBuf::from("images/logo.png"))
+        );
+        assert_eq!(route(&root, "/missing.html"), Route::NotFound);
+        assert_eq!(route(&root, "/../secret"), Route::NotFound);
+        assert_eq!(route(&root, "/%2e%2e/secret"), Route::NotFound);
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::write(root.join(".git/config"), "secret").unwrap();
+        fs::write(root.join(".env"), "secret").unwrap();
+        assert_eq!(route(&root, "/.git/config"), Route::NotFound);
+        assert_eq!(route(&root, "/.git/"), Route::NotFound);
+        assert_eq!(route(&root, "/%2eenv"), Route::NotFound);
+        assert_eq!(
+            route(&root, "/./index.adoc"),
+            Route::Document(PathBuf::from("index.adoc"))
+        );
+
+        let response = respond(&root, "/index.adoc", &ServeOptions::new());
+        let html = String::from_utf8(response.body).unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(html.contains("<title>Home</title>"), true);
+        assert_eq!(html.contains("href=\"guide/install.html\""), true);
+        assert_eq!(html.contains(RELOAD_PATH), true);
+    }
+
+    #[test]
+    fn test_listing() {
+        let root = site(
+            "listing",
+            &[
+                ("b.adoc", "= B"),
+                ("a.adoc", "= A"),
+                ("notes.txt", "skipped"),
+                ("guide/install.adoc", "= Install"),
+            ],
+        );
+        let html = listing(&root, Path::new("")).unwrap();
+        assert_eq!(
+            html.contains(
+                "<ul>\n<li><a href=\"/a.adoc\">a.adoc</a></li>\n<li><a href=\"/b.adoc\">b.adoc</a></li>\n<li><a href=\"/guide/\">guide/</a></li>\n</ul>\n"
+            ),
+            true
+        );
+    }
+
+    #[test]
+    fn test_modification_times() {
+        let root = site("watch", &[("a.adoc", "= A"), (".git/HEAD", "ref")]);
+        let before = modification_times(&root);
+        assert_eq!(before.len(), 1);
+        fs::write(root.join("b.adoc"), "= B").unwrap();
+        assert_eq!(modification_times(&root) == before, false);
+    }
+}
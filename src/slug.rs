@@ -0,0 +1,176 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+
+pub type SlugHook = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+// How ids are derived from heading text when the source doesn't give one.
+#[derive(Clone, Default)]
+pub enum SlugStrategy {
+    // Asciidoctor's default: `_` prefix, `_` separator, letters of any script kept.
+    #[default]
+    Unicode,
+    // Like `Unicode` but characters outside ASCII are dropped.
+    Ascii,
+    // GitHub's Markdown anchors: no prefix, `-` separator, punctuation removed.
+    GitHub,
+    // Asciidoctor with the `idprefix` and `idseparator` attributes set.
+    Separated {
+        prefix: String,
+        separator: char,
+    },
+    Custom(SlugHook),
+}
+
+impl fmt::Debug for SlugStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SlugStrategy::Unicode => write!(f, "Unicode"),
+            SlugStrategy::Ascii => write!(f, "Ascii"),
+            SlugStrategy::GitHub => write!(f, "GitHub"),
+            SlugStrategy::Separated { prefix, separator } => f
+                .debug_struct("Separated")
+                .field("prefix", prefix)
+                .field("separator", separator)
+                .finish(),
+            SlugStrategy::Custom(_) => write!(f, "Custom(..)"),
+        }
+    }
+}
+
+impl SlugStrategy {
+    pub fn custom<F: Fn(&str) -> String + Send + Sync + 'static>(hook: F) -> Self {
+        SlugStrategy::Custom(Arc::new(hook))
+    }
+
+    pub fn slugify(&self, text: &str) -> String {
+        match self {
+            SlugStrategy::Unicode => separated(text, "_", '_', |c| c.is_alphanumeric()),
+            SlugStrategy::Ascii => separated(text, "_", '_', |c| c.is_ascii_alphanumeric()),
+            SlugStrategy::GitHub => github(text),
+            SlugStrategy::Separated { prefix, separator } => {
+                separated(text, prefix, *separator, |c| c.is_alphanumeric())
+            }
+            SlugStrategy::Custom(hook) => hook(text),
+        }
+    }
+
+    fn duplicate_suffix(&self, count: usize) -> String {
+        match self {
+            SlugStrategy::GitHub => format!("-{}", count - 1),
+            SlugStrategy::Separated { separator, .. } => format!("{}{}", separator, count),
+            _ => format!("_{}", count),
+        }
+    }
+}
+
+// Hands out slugs that are unique within one document.
+#[derive(Debug, Default)]
+pub struct Slugger {
+    strategy: SlugStrategy,
+    seen: HashSet<String>,
+}
+
+impl Slugger {
+    pub fn new(strategy: SlugStrategy) -> Self {
+        Slugger {
+            strategy,
+            seen: HashSet::new(),
+        }
+    }
+
+    pub fn slug(&mut self, text: &str) -> String {
+        let slug = self.strategy.slugify(text);
+        let mut candidate = slug.clone();
+        let mut count = 1;
+        while self.seen.contains(&candidate) {
+            count += 1;
+            candidate = format!("{}{}", slug, self.strategy.duplicate_suffix(count));
+        }
+        self.seen.insert(candidate.clone());
+        candidate
+    }
+
+    // Reserve an id written in the source so generated ones don't collide with it.
+    pub fn reserve(&mut self, id: &str) {
+        self.seen.insert(id.to_string());
+    }
+}
+
+fn separated<F: Fn(char) -> bool>(text: &str, prefix: &str, separator: char, keep: F) -> String {
+    let mut slug = prefix.to_string();
+    let mut pending_separator = false;
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if keep(c) {
+            if pending_separator && slug.len() > prefix.len() {
+                slug.push(separator);
+            }
+            pending_separator = false;
+            slug.push(c);
+        } else if c.is_whitespace() || c == '-' || c == '.' || c == '_' {
+            pending_separator = true;
+        }
+    }
+    slug
+}
+
+fn github(text: &str) -> String {
+    text.trim()
+        .chars()
+        .flat_map(char::to_lowercase)
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_slugify() {
+        let text = "Getting Started — Über die API (v1.0)";
+        assert_eq!(
+            SlugStrategy::Unicode.slugify(text),
+            "_getting_started_über_die_api_v1_0"
+        );
+        assert_eq!(
+            SlugStrategy::Ascii.slugify(text),
+            "_getting_started_ber_die_api_v1_0"
+        );
+        assert_eq!(
+            SlugStrategy::GitHub.slugify(text),
+            "getting-started--über-die-api-v10"
+        );
+        assert_eq!(
+            SlugStrategy::Separated {
+                prefix: "user-content-".to_string(),
+                separator: '-',
+            }
+            .slugify(text),
+            "user-content-getting-started-über-die-api-v1-0"
+        );
+        assert_eq!(
+            SlugStrategy::custom(|text| text.to_uppercase()).slugify("a b"),
+            "A B"
+        );
+    }
+
+    #[test]
+    fn test_slugger_deduplicates() {
+        let mut slugger = Slugger::new(SlugStrategy::Unicode);
+        slugger.reserve("_intro_2");
+        assert_eq!(slugger.slug("Intro"), "_intro");
+        assert_eq!(slugger.slug("Intro"), "_intro_3");
+        assert_eq!(slugger.slug("Intro 2"), "_intro_2_2");
+
+        let mut slugger = Slugger::new(SlugStrategy::GitHub);
+        assert_eq!(slugger.slug("Intro"), "intro");
+        assert_eq!(slugger.slug("Intro"), "intro-1");
+        assert_eq!(slugger.slug("Intro"), "intro-2");
+    }
+}
@@ -0,0 +1,113 @@
+use crate::document::Document;
+use crate::{BlockKind, Span};
+use std::fmt::Write;
+
+// Node ids are indexes of top level blocks in `Document::blocks()`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SourceMapEntry {
+    pub node: usize,
+    pub kind: BlockKind,
+    pub source: Span,
+    pub output: Option<Span>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct SourceMap {
+    entries: Vec<SourceMapEntry>,
+}
+
+impl SourceMap {
+    // Blocks that were not parsed from source (e.g. appended programmatically) are skipped.
+    pub fn from_document(document: &Document) -> Self {
+        let entries = document
+            .blocks()
+            .iter()
+            .enumerate()
+            .filter_map(|(node, block)| {
+                document.span(node).map(|source| SourceMapEntry {
+                    node,
+                    kind: block.kind(),
+                    source,
+                    output: None,
+                })
+            })
+            .collect();
+        SourceMap { entries }
+    }
+
+    pub fn entries(&self) -> &[SourceMapEntry] {
+        &self.entries
+    }
+
+    pub fn set_output(&mut self, node: usize, output: Span) {
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.node == node) {
+            entry.output = Some(output);
+        }
+    }
+
+    // Node whose source contains the byte `offset`, for scroll syncing.
+    pub fn node_at_source(&self, offset: usize) -> Option<&SourceMapEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.source.start <= offset && offset < entry.source.end)
+    }
+
+    // Node that produced the rendered byte `offset`, for click-to-source.
+    pub fn node_at_output(&self, offset: usize) -> Option<&SourceMapEntry> {
+        self.entries.iter().find(|entry| {
+            entry
+                .output
+                .is_some_and(|output| output.start <= offset && offset < output.end)
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{\"version\":1,\"mappings\":[");
+        for (index, entry) in self.entries.iter().enumerate() {
+            if index > 0 {
+                json.push(',');
+            }
+            write!(
+                json,
+                "{{\"node\":{},\"kind\":\"{:?}\",\"source\":[{},{}]",
+                entry.node, entry.kind, entry.source.start, entry.source.end
+            )
+            .unwrap();
+            if let Some(output) = entry.output {
+                write!(json, ",\"output\":[{},{}]", output.start, output.end).unwrap();
+            }
+            json.push('}');
+        }
+        json.push_str("]}");
+        json
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::html;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_source_map() {
+        let document = Document::parse("== A\n\nfoo").unwrap();
+        let (output, source_map) =
+            html::render_with_source_map(&document, &html::HtmlOptions::default());
+
+        assert_eq!(output, "<h2>A</h2>\n<p>foo</p>\n");
+        assert_eq!(
+            source_map.to_json(),
+            r#"{"version":1,"mappings":[{"node":0,"kind":"Heading","source":[0,4],"output":[0,11]},{"node":1,"kind":"BlankBlock","source":[4,6]},{"node":2,"kind":"Paragraph","source":[6,9],"output":[11,22]}]}"#
+        );
+        assert_eq!(
+            source_map.node_at_source(7).map(|entry| entry.node),
+            Some(2)
+        );
+        assert_eq!(
+            source_map.node_at_output(3).map(|entry| entry.source),
+            Some(Span { start: 0, end: 4 })
+        );
+        assert_eq!(source_map.node_at_output(100), None);
+    }
+}
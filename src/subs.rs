@@ -0,0 +1,121 @@
+use crate::BlockMeta;
+use std::borrow::Cow;
+
+// Substitutions applied to the text of a block, controlled by its `subs` attribute
+// (`[subs=none]`, `[subs=-specialchars]`) or the `pass` style.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Substitutions {
+    // Replace `<`, `>` and `&` with entity references.
+    pub special_chars: bool,
+}
+
+impl Default for Substitutions {
+    fn default() -> Self {
+        Substitutions {
+            special_chars: true,
+        }
+    }
+}
+
+impl Substitutions {
+    pub fn none() -> Self {
+        Substitutions {
+            special_chars: false,
+        }
+    }
+
+    pub fn from_meta(meta: Option<&BlockMeta>) -> Self {
+        let meta = match meta {
+            Some(meta) => meta,
+            None => return Substitutions::default(),
+        };
+        let mut subs = if meta.style.as_deref() == Some("pass") {
+            Substitutions::none()
+        } else {
+            Substitutions::default()
+        };
+        let value = meta.attributes.get("subs");
+        for step in value.into_iter().flat_map(|value| value.split(',')) {
+            match step.trim() {
+                "none" => subs = Substitutions::none(),
+                "normal" | "verbatim" => subs = Substitutions::default(),
+                "specialchars" | "+specialchars" | "specialchars+" => subs.special_chars = true,
+                "-specialchars" => subs.special_chars = false,
+                _ => {}
+            }
+        }
+        subs
+    }
+}
+
+// The special characters substitution. Borrows when there is nothing to replace.
+pub fn escape_special_chars(s: &str) -> Cow<'_, str> {
+    escape_chars(s, false)
+}
+
+// For attribute values, which also need `"` replaced.
+pub fn escape_attribute(s: &str) -> Cow<'_, str> {
+    escape_chars(s, true)
+}
+
+fn escape_chars(s: &str, quote: bool) -> Cow<'_, str> {
+    let needs_escape = |c: char| matches!(c, '&' | '<' | '>') || (quote && c == '"');
+    let first = match s.find(needs_escape) {
+        Some(first) => first,
+        None => return Cow::Borrowed(s),
+    };
+    let mut escaped = String::with_capacity(s.len() + 8);
+    escaped.push_str(&s[..first]);
+    for c in s[first..].chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' if quote => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AttrList;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_escape_special_chars() {
+        assert_eq!(
+            matches!(escape_special_chars("plain"), Cow::Borrowed(_)),
+            true
+        );
+        assert_eq!(
+            escape_special_chars("a < b & \"c\""),
+            "a &lt; b &amp; \"c\""
+        );
+        assert_eq!(escape_attribute("a \"b\""), "a &quot;b&quot;");
+        assert_eq!(escape_special_chars("&amp;"), "&amp;amp;");
+    }
+
+    #[test]
+    fn test_substitutions_from_meta() {
+        let named = |subs: &str| {
+            let mut values = AttrList::new();
+            values.push_named("subs", subs);
+            BlockMeta::from_attributes(values)
+        };
+        let pass = BlockMeta::from_attributes(AttrList::from(vec!["pass".to_string()]));
+
+        assert_eq!(Substitutions::from_meta(None), Substitutions::default());
+        assert_eq!(Substitutions::from_meta(Some(&pass)), Substitutions::none());
+        assert_eq!(
+            Substitutions::from_meta(Some(&named("-specialchars"))),
+            Substitutions::none()
+        );
+        assert_eq!(
+            Substitutions::from_meta(Some(&named("none,specialchars"))),
+            Substitutions::default()
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use std::borrow::Borrow;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+// Distinct symbols kept per thread. Past this the table starts over, symbols handed out
+// before stay valid but are no longer shared with new ones.
+const MAX_INTERNED: usize = 4096;
+
+thread_local! {
+    static INTERNED: RefCell<HashSet<Arc<str>>> = RefCell::new(HashSet::new());
+}
+
+// A name that repeats across documents: block styles, roles, options, attribute names, macro
+// kinds and source languages. Symbols with the same text made on one thread share their
+// allocation, so the code blocks below a thousand `[source,rust]` lines store their language
+// once. Attribute values aren't symbols, each attribute list keeps its own `rust`.
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(Arc<str>);
+
+impl Symbol {
+    pub fn new(text: &str) -> Self {
+        INTERNED.with(|interned| {
+            let mut interned = interned.borrow_mut();
+            if let Some(symbol) = interned.get(text) {
+                return Symbol(symbol.clone());
+            }
+            if interned.len() >= MAX_INTERNED {
+                interned.clear();
+            }
+            let symbol: Arc<str> = Arc::from(text);
+            interned.insert(symbol.clone());
+            Symbol(symbol)
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Symbol {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(text: &str) -> Self {
+        Symbol::new(text)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(text: String) -> Self {
+        Symbol::new(&text)
+    }
+}
+
+impl From<&String> for Symbol {
+    fn from(text: &String) -> Self {
+        Symbol::new(text)
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_symbol() {
+        let a = Symbol::new("rust");
+        let b = Symbol::from("rust".to_string());
+        assert_eq!(a, b);
+        assert_eq!(Arc::ptr_eq(&a.0, &b.0), true);
+        assert_eq!(a == "rust", true);
+        assert_eq!(format!("{} {:?}", a, a), "rust \"rust\"");
+        assert_eq!(Symbol::new("ruby") == a, false);
+    }
+}
@@ -0,0 +1,215 @@
+use crate::document::Document;
+use crate::json::{block_type_name, inline_type_name};
+use crate::lines::LineIndex;
+use crate::recovery::{parse_lenient, RecoveryPolicy};
+use crate::{Block, BlockOptions, Inline, ListItem, Span};
+use std::fmt::Write;
+
+// Zero-based row and byte column, like tree-sitter's `Point`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Point {
+    pub row: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct NodeRange {
+    pub start_byte: usize,
+    pub end_byte: usize,
+    pub start_point: Point,
+    pub end_point: Point,
+}
+
+// Node of a concrete syntax tree in the shape tree-sitter gives editors, for highlighting and
+// folding until there is a tree-sitter grammar. Kinds are the `type` names of the JSON AST.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SyntaxNode {
+    pub kind: &'static str,
+    // Only the document and its top level blocks have one, the parser doesn't record where
+    // the nodes inside a block are.
+    pub range: Option<NodeRange>,
+    pub children: Vec<SyntaxNode>,
+}
+
+impl SyntaxNode {
+    fn new(kind: &'static str, children: Vec<SyntaxNode>) -> Self {
+        SyntaxNode {
+            kind,
+            range: None,
+            children,
+        }
+    }
+
+    // The tree as `tree-sitter parse` prints it, one node per line with its range.
+    pub fn to_sexp(&self) -> String {
+        let mut out = String::new();
+        self.write_sexp(&mut out, 0);
+        out.push('\n');
+        out
+    }
+
+    fn write_sexp(&self, out: &mut String, depth: usize) {
+        if depth > 0 {
+            out.push('\n');
+        }
+        write!(out, "{}({}", "  ".repeat(depth), self.kind).unwrap();
+        if let Some(range) = &self.range {
+            write!(
+                out,
+                " [{}, {}] - [{}, {}]",
+                range.start_point.row,
+                range.start_point.column,
+                range.end_point.row,
+                range.end_point.column
+            )
+            .unwrap();
+        }
+        for child in &self.children {
+            child.write_sexp(out, depth + 1);
+        }
+        out.push(')');
+    }
+}
+
+// Syntax tree of `source`, parsed leniently without preprocessing so the ranges are the ones
+// of the file. Blank lines between blocks are not nodes.
+pub fn syntax_tree(source: &str) -> SyntaxNode {
+    let (blocks, _) = parse_lenient(source, &RecoveryPolicy::default(), BlockOptions::default());
+    let document = Document::from_spanned(blocks);
+    let lines = LineIndex::new(source);
+    let children = document
+        .blocks()
+        .iter()
+        .enumerate()
+        .filter(|(_, block)| !matches!(block, Block::BlankBlock))
+        .map(|(index, block)| {
+            let mut node = block_node(block);
+            node.range = document.span(index).map(|span| range(&lines, span));
+            node
+        })
+        .collect();
+    SyntaxNode {
+        kind: "document",
+        range: Some(range(
+            &lines,
+            Span {
+                start: 0,
+                end: source.len(),
+            },
+        )),
+        children,
+    }
+}
+
+fn range(lines: &LineIndex, span: Span) -> NodeRange {
+    let point = |offset: usize| {
+        let row = lines.line_of(offset);
+        Point {
+            row,
+            column: offset - lines.start(row),
+        }
+    };
+    NodeRange {
+        start_byte: span.start,
+        end_byte: span.end,
+        start_point: point(span.start),
+        end_point: point(span.end),
+    }
+}
+
+fn block_node(block: &Block) -> SyntaxNode {
+    let children = match block {
+        Block::Paragraph { children } => inline_nodes(children),
+        Block::Macro { children } => inline_nodes(std::slice::from_ref(children)),
+        Block::Heading { children, .. } | Block::CodeBlock { children, .. } => {
+            inline_nodes(children)
+        }
+        Block::UnorderdList { children } | Block::OrderdList { children } => children
+            .iter()
+            .map(|item| {
+                let kind = match item {
+                    ListItem::Normal { .. } => "list_item",
+                    ListItem::Check { .. } => "check_list_item",
+                };
+                SyntaxNode::new(kind, inline_nodes(item.children()))
+            })
+            .collect(),
+        Block::Label { children, key } => {
+            let mut nodes = vec![SyntaxNode::new("label_key", inline_nodes(key))];
+            nodes.extend(inline_nodes(children));
+            nodes
+        }
+        Block::Qanda { question, answer } => vec![
+            SyntaxNode::new("question", inline_nodes(question)),
+            SyntaxNode::new("answer", inline_nodes(answer)),
+        ],
+        Block::Block {
+            children, title, ..
+        } => {
+            let mut nodes = vec![];
+            if let Some(title) = title {
+                nodes.push(SyntaxNode::new("block_title", inline_nodes(title)));
+            }
+            nodes.extend(children.iter().map(block_node));
+            nodes
+        }
+        Block::Table { columns, rows, .. } => {
+            let mut nodes = columns
+                .iter()
+                .map(|_| SyntaxNode::new("table_column", vec![]))
+                .collect::<Vec<_>>();
+            nodes.extend(rows.iter().map(|row| {
+                let cells = row
+                    .cells()
+                    .iter()
+                    .map(|cell| {
+                        SyntaxNode::new("table_cell", cell.blocks.iter().map(block_node).collect())
+                    })
+                    .collect();
+                SyntaxNode::new("table_row", cells)
+            }));
+            nodes
+        }
+        Block::HorizontalRuledLine | Block::NextPage | Block::BlankBlock => vec![],
+    };
+    SyntaxNode::new(block_type_name(block), children)
+}
+
+fn inline_nodes(inlines: &[Inline]) -> Vec<SyntaxNode> {
+    inlines
+        .iter()
+        .map(|inline| {
+            let children = inline
+                .child()
+                .map_or_else(Vec::new, |child| inline_nodes(std::slice::from_ref(child)));
+            SyntaxNode::new(inline_type_name(inline), children)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_syntax_tree() {
+        let tree = syntax_tree("== Intro\n\nSome *bold*\n\n* a\n* [x] b\n");
+        assert_eq!(
+            tree.to_sexp(),
+            "(document [0, 0] - [6, 0]
+  (heading [0, 0] - [0, 8]
+    (text))
+  (paragraph [2, 0] - [2, 11]
+    (text)
+    (bold
+      (text)))
+  (unordered_list [4, 0] - [6, 0]
+    (list_item
+      (text))
+    (check_list_item
+      (text))))
+"
+        );
+    }
+}
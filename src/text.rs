@@ -0,0 +1,87 @@
+use crate::{Block, DelimitedKind, Inline, ListItem, TableCell};
+
+// Text content of inlines with all formatting stripped.
+pub fn inline_text(inlines: &[Inline]) -> String {
+    let mut text = String::new();
+    for inline in inlines {
+        push_inline_text(inline, &mut text);
+    }
+    text
+}
+
+// Text of a table cell, each of its blocks on a line.
+pub fn cell_text(cell: &TableCell) -> String {
+    cell.blocks
+        .iter()
+        .map(block_text)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn block_text(block: &Block) -> String {
+    match block {
+        Block::Paragraph { children } => inline_text(children),
+        Block::Macro { children } => inline_text(std::slice::from_ref(children)),
+        Block::Heading { children, .. } | Block::CodeBlock { children, .. } => {
+            inline_text(children)
+        }
+        Block::Block {
+            kind: DelimitedKind::Comment,
+            ..
+        } => String::new(),
+        Block::Block { children, .. } => children
+            .iter()
+            .map(block_text)
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Block::UnorderdList { children } | Block::OrderdList { children } => children
+            .iter()
+            .map(|item| match item {
+                ListItem::Normal { children, .. } | ListItem::Check { children, .. } => {
+                    inline_text(children)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Block::Label { children, key } => format!("{} {}", inline_text(key), inline_text(children)),
+        Block::Qanda { question, answer } => {
+            format!("{} {}", inline_text(question), inline_text(answer))
+        }
+        Block::HorizontalRuledLine | Block::NextPage | Block::Table { .. } | Block::BlankBlock => {
+            String::new()
+        }
+    }
+}
+
+fn push_inline_text(inline: &Inline, text: &mut String) {
+    match inline {
+        Inline::Value(value) => text.push_str(value),
+        Inline::HardBreak | Inline::SoftBreak => text.push(' '),
+        Inline::Literal { children }
+        | Inline::Footnote { children, .. }
+        | Inline::Lead { children }
+        | Inline::Bold { children }
+        | Inline::Italic { children }
+        | Inline::Monospace { children }
+        | Inline::Marker { children }
+        | Inline::Styled { children, .. }
+        | Inline::InlineCode { children } => push_inline_text(children, text),
+        Inline::Macro { .. } => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_inline_text() {
+        let blocks = parse("This is a *bold* and _italic_\ntext").unwrap();
+        assert_eq!(
+            block_text(&blocks[0]),
+            "This is a bold and italic text".to_string()
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use crate::outline::DEFAULT_TOC_LEVELS;
+use crate::render::asciidoc::to_asciidoc;
+use crate::render::html::heading_ids;
+use crate::{AttrList, Block, Document, HeadingLevel, Inline, ListItem, ListLevel, OutlineEntry};
+use crate::{SlugStrategy, Slugger};
+use anyhow::{bail, Result};
+use smallvec::smallvec;
+
+// Comment lines around the generated list, after markdown-toc's `<!-- toc -->`.
+pub const TOC_START: &str = "// toc";
+pub const TOC_END: &str = "// tocstop";
+
+// The outline of `document` as an AsciiDoc list of xrefs, one item per section. Ids are the
+// ones the HTML renderer gives the sections.
+pub fn toc_list(document: &Document, max_depth: Option<u8>) -> String {
+    let depth = max_depth
+        .or_else(|| document.attribute("toclevels").and_then(|v| v.parse().ok()))
+        .unwrap_or(DEFAULT_TOC_LEVELS);
+    let generated = heading_ids(document.blocks(), &SlugStrategy::default());
+    // Same headings in the same order as the outline walks them.
+    let mut ids = document.blocks().iter().filter_map(|block| match block {
+        Block::Heading { level, id, .. }
+            if *level != HeadingLevel::Title && level.as_u8() <= depth =>
+        {
+            id.clone()
+                .or_else(|| generated.get(&(block as *const Block)).cloned())
+        }
+        _ => None,
+    });
+
+    let mut items = vec![];
+    push_items(&document.outline(Some(depth)), 1, &mut ids, &mut items);
+    if items.is_empty() {
+        return String::new();
+    }
+    to_asciidoc(&Document::new(vec![Block::UnorderdList {
+        children: items,
+    }]))
+}
+
+fn push_items<I: Iterator<Item = String>>(
+    entries: &[OutlineEntry],
+    level: u32,
+    ids: &mut I,
+    items: &mut Vec<ListItem>,
+) {
+    for entry in entries {
+        let id = ids
+            .next()
+            .unwrap_or_else(|| Slugger::new(SlugStrategy::default()).slug(&entry.title_text));
+        items.push(ListItem::Normal {
+            children: smallvec![Inline::Macro {
+                attributes: Box::new(AttrList::from(vec![entry.title_text.replace(']', "\\]")])),
+                kind: "xref".into(),
+                id,
+            }],
+            level: ListLevel::from_depth(level),
+        });
+        push_items(&entry.children, level + 1, ids, items);
+    }
+}
+
+// `source` with the lines between the `// toc` and `// tocstop` markers replaced by the list
+// of `document`, normally parsed from `source`. Running it again without changing the sections
+// gives the same text.
+pub fn update_toc(source: &str, document: &Document, max_depth: Option<u8>) -> Result<String> {
+    let lines = source.split_inclusive('\n').collect::<Vec<_>>();
+    let start = match lines.iter().position(|line| line.trim_end() == TOC_START) {
+        Some(start) => start,
+        None => bail!("no `{}` marker line in the document", TOC_START),
+    };
+    let end = match lines[start + 1..]
+        .iter()
+        .position(|line| line.trim_end() == TOC_END)
+    {
+        Some(end) => start + 1 + end,
+        None => bail!("no `{}` line after `{}`", TOC_END, TOC_START),
+    };
+
+    let mut out = lines[..=start].concat();
+    if !out.ends_with('\n') {
+        out.push('\n');
+    }
+    let list = toc_list(document, max_depth);
+    if !list.is_empty() {
+        // Blank lines keep the list apart from the comments.
+        out.push('\n');
+        out.push_str(&list);
+        out.push('\n');
+    }
+    out.push_str(&lines[end..].concat());
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_update_toc() {
+        let source = "= Guide\n\n// toc\nstale\n// tocstop\n\n== Install\n\n=== On Linux\n\n== Use [it]\n\nText\n";
+        let document = Document::parse(source).unwrap();
+        let updated = update_toc(source, &document, None).unwrap();
+        assert_eq!(
+            updated,
+            "= Guide\n\n// toc\n\n* xref:_install[Install]\n** xref:_on_linux[On Linux]\n* xref:_use_it[Use [it\\]]\n\n// tocstop\n\n== Install\n\n=== On Linux\n\n== Use [it]\n\nText\n"
+        );
+        let document = Document::parse(&updated).unwrap();
+        assert_eq!(update_toc(&updated, &document, None).unwrap(), updated);
+        assert_eq!(
+            update_toc(&updated, &document, Some(1))
+                .unwrap()
+                .contains("xref:_on_linux"),
+            false
+        );
+
+        assert_eq!(update_toc("== A\n", &document, None).is_err(), true);
+        assert_eq!(update_toc("// toc\n== A\n", &document, None).is_err(), true);
+    }
+}
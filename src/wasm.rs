@@ -0,0 +1,96 @@
+// Bindings for JavaScript, built with `wasm-pack build --features wasm`. Sources are parsed
+// leniently: a preview that is being typed into shows the blocks that parse and reports the
+// rest as diagnostics instead of failing.
+use crate::document::Document;
+use crate::json::to_json_string;
+use crate::options::ParseOptions;
+use crate::recovery::RecoveryPolicy;
+use crate::render::html::{render_with_options, HtmlOptions, HtmlProfile};
+use crate::slug::SlugStrategy;
+use wasm_bindgen::prelude::*;
+
+// Options of `renderHtml`. A class on the JS side, `new RenderOptions()` starts from the
+// defaults and the fields are plain properties.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RenderOptions {
+    // asciidoctor's markup and class names, for its stylesheets.
+    pub asciidoctor: bool,
+    // Drop link and image targets that can run script, for untrusted input.
+    pub sanitize: bool,
+    pub hide_title: bool,
+    // Give headings without an explicit id one derived from their text.
+    pub heading_ids: bool,
+    // Render like github.com does, the other fields are ignored.
+    pub github: bool,
+}
+
+#[wasm_bindgen]
+impl RenderOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        RenderOptions::default()
+    }
+}
+
+impl From<&RenderOptions> for HtmlOptions {
+    fn from(options: &RenderOptions) -> Self {
+        if options.github {
+            return HtmlOptions::github();
+        }
+        let mut html = HtmlOptions::new()
+            .sanitize(options.sanitize)
+            .hide_title(options.hide_title);
+        if options.asciidoctor {
+            html = html.profile(HtmlProfile::Asciidoctor);
+        }
+        if options.heading_ids {
+            html = html.heading_ids(SlugStrategy::default());
+        }
+        html
+    }
+}
+
+fn parse(input: &str) -> Result<Document, JsError> {
+    let options = ParseOptions::new().recovery(RecoveryPolicy::default());
+    Document::parse_with_options(input, &options).map_err(|error| JsError::new(&error.to_string()))
+}
+
+// The JSON AST described by `schema/ast-v2.schema.json`, as a string for `JSON.parse`.
+#[wasm_bindgen(js_name = parseToJson)]
+pub fn parse_to_json(input: &str) -> Result<String, JsError> {
+    Ok(to_json_string(&parse(input)?))
+}
+
+#[wasm_bindgen(js_name = renderHtml)]
+pub fn render_html(input: &str, options: Option<RenderOptions>) -> Result<String, JsError> {
+    let options = HtmlOptions::from(&options.unwrap_or_default());
+    Ok(render_with_options(&parse(input)?, &options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_render_html() {
+        let html = render_html("== Intro\n\nsome *bold*", None).unwrap();
+        assert_eq!(html.contains("<strong>bold</strong>"), true);
+
+        let options = RenderOptions {
+            heading_ids: true,
+            ..RenderOptions::new()
+        };
+        let html = render_html("== Intro", Some(options)).unwrap();
+        assert_eq!(html.contains("id=\"_intro\""), true);
+    }
+
+    #[test]
+    fn test_parse_to_json() {
+        let json = parse_to_json("=broken\n\nok").unwrap();
+        let value = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+        assert_eq!(value["blocks"].as_array().map(Vec::len), Some(3));
+        assert_eq!(value["diagnostics"].as_array().map(Vec::len), Some(1));
+    }
+}
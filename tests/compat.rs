@@ -0,0 +1,114 @@
+// Compatibility with Asciidoctor. Each `tests/compat/<construct>.adoc` comes with the
+// `<construct>.html` Asciidoctor's html5 backend produces for it in embedded mode. The harness
+// renders every input with the asciidoctor profile and records in `tests/compat/MATRIX.md`
+// which constructs parse and which render the same markup. The test fails when the matrix
+// changes, so improvements and regressions both show up in review; rerun with
+// `UPDATE_SNAPSHOTS=1` to accept the new matrix.
+use combine_sandbox::render::html::{render_with_options, HtmlOptions, HtmlProfile};
+use combine_sandbox::{Document, ParseOptions, SlugStrategy};
+use pretty_assertions::Comparison;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn compat_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/compat")
+}
+
+// Indentation and blank lines differ between the backends without changing the markup.
+fn normalize(html: &str) -> Vec<&str> {
+    html.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+struct Row {
+    construct: String,
+    parses: bool,
+    renders: bool,
+}
+
+fn check(input: &Path) -> Row {
+    let construct = input.file_stem().unwrap().to_string_lossy().into_owned();
+    let source = fs::read_to_string(input).unwrap();
+    let expected = fs::read_to_string(input.with_extension("html")).unwrap();
+
+    let document = match Document::parse_with_options(&source, &ParseOptions::new()) {
+        Ok(document) => document,
+        Err(_) => {
+            return Row {
+                construct,
+                parses: false,
+                renders: false,
+            }
+        }
+    };
+    let options = HtmlOptions::new()
+        .profile(HtmlProfile::Asciidoctor)
+        .heading_ids(SlugStrategy::Unicode)
+        .hide_title(true);
+    let actual = render_with_options(&document, &options);
+    Row {
+        construct,
+        parses: true,
+        renders: normalize(&actual) == normalize(&expected),
+    }
+}
+
+fn matrix(rows: &[Row]) -> String {
+    let mark = |ok: bool| if ok { "yes" } else { "no" };
+    let mut out = String::from(
+        "# Asciidoctor compatibility\n\n\
+         Generated by `tests/compat.rs` from the fixtures in this directory.\n\n\
+         | Construct | Parses | Renders like Asciidoctor |\n\
+         |-----------|--------|--------------------------|\n",
+    );
+    for row in rows {
+        out.push_str(&format!(
+            "| {} | {} | {} |\n",
+            row.construct,
+            mark(row.parses),
+            mark(row.renders)
+        ));
+    }
+    let compatible = rows.iter().filter(|row| row.renders).count();
+    out.push_str(&format!(
+        "\n{} of {} constructs render like Asciidoctor.\n",
+        compatible,
+        rows.len()
+    ));
+    out
+}
+
+#[test]
+fn test_asciidoctor_compatibility() {
+    let mut inputs = fs::read_dir(compat_dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "adoc")
+        })
+        .collect::<Vec<_>>();
+    inputs.sort();
+    assert!(!inputs.is_empty(), "tests/compat has no .adoc files");
+
+    let rows = inputs.iter().map(|input| check(input)).collect::<Vec<_>>();
+    let actual = matrix(&rows);
+    let path = compat_dir().join("MATRIX.md");
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_default();
+    let (expected, actual) = (
+        expected.lines().collect::<Vec<_>>(),
+        actual.lines().collect::<Vec<_>>(),
+    );
+    assert!(
+        expected == actual,
+        "the compatibility matrix changed:\n{}\nrerun with UPDATE_SNAPSHOTS=1 to accept it",
+        Comparison::new(&expected, &actual)
+    );
+}
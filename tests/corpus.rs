@@ -0,0 +1,66 @@
+// Parses every `tests/corpus/*.adoc` and compares the syntax tree with the committed
+// `*.snap` file next to it. Run with `UPDATE_SNAPSHOTS=1` to accept the current output; new
+// files without a snapshot fail until one is written.
+use combine_sandbox::{Document, ParseOptions, RecoveryPolicy};
+use pretty_assertions::Comparison;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn corpus_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus")
+}
+
+fn snapshot(source: &str) -> String {
+    let options = ParseOptions::new().recovery(RecoveryPolicy::default());
+    match Document::parse_with_options(source, &options) {
+        Ok(document) => {
+            let mut snapshot = document.dump_tree();
+            for diagnostic in document.diagnostics() {
+                snapshot.push_str(&format!("{}\n", diagnostic));
+            }
+            snapshot
+        }
+        Err(error) => format!("error: {:#}\n", error),
+    }
+}
+
+#[test]
+fn test_corpus() {
+    let update = env::var_os("UPDATE_SNAPSHOTS").is_some();
+    let mut inputs = fs::read_dir(corpus_dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "adoc")
+        })
+        .collect::<Vec<_>>();
+    inputs.sort();
+    assert!(!inputs.is_empty(), "tests/corpus has no .adoc files");
+
+    let mut failures = vec![];
+    for input in inputs {
+        let actual = snapshot(&fs::read_to_string(&input).unwrap());
+        let snap = input.with_extension("snap");
+        match fs::read_to_string(&snap) {
+            Ok(expected) if expected == actual => {}
+            _ if update => fs::write(&snap, &actual).unwrap(),
+            Ok(expected) => {
+                let expected = expected.lines().collect::<Vec<_>>();
+                let actual = actual.lines().collect::<Vec<_>>();
+                failures.push(format!(
+                    "{} changed:\n{}",
+                    snap.display(),
+                    Comparison::new(&expected, &actual)
+                ));
+            }
+            Err(_) => failures.push(format!("{} is missing", snap.display())),
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{}\nrerun with UPDATE_SNAPSHOTS=1 to accept the changes",
+        failures.join("\n")
+    );
+}
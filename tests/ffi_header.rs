@@ -0,0 +1,46 @@
+// Generates the C header of `src/ffi.rs` and compares it with the committed
+// `include/combine_sandbox.h`. Run with `UPDATE_SNAPSHOTS=1` to rewrite the header.
+use pretty_assertions::Comparison;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn test_ffi_header() {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let config = cbindgen::Config {
+        language: cbindgen::Language::C,
+        include_guard: Some("COMBINE_SANDBOX_H".to_string()),
+        cpp_compat: true,
+        no_includes: true,
+        sys_includes: vec!["stdint.h".to_string()],
+        autogen_warning: Some(
+            "/* Generated from src/ffi.rs by tests/ffi_header.rs, do not edit. */".to_string(),
+        ),
+        ..cbindgen::Config::default()
+    };
+    let mut header = vec![];
+    cbindgen::Builder::new()
+        .with_src(crate_dir.join("src/ffi.rs"))
+        .with_config(config)
+        .generate()
+        .unwrap()
+        .write(&mut header);
+    let actual = String::from_utf8(header).unwrap();
+
+    let path = crate_dir.join("include/combine_sandbox.h");
+    match fs::read_to_string(&path) {
+        Ok(expected) if expected == actual => {}
+        _ if env::var_os("UPDATE_SNAPSHOTS").is_some() => fs::write(&path, &actual).unwrap(),
+        Ok(expected) => {
+            let expected = expected.lines().collect::<Vec<_>>();
+            let actual = actual.lines().collect::<Vec<_>>();
+            panic!(
+                "{} is out of date, run with UPDATE_SNAPSHOTS=1 to regenerate it:\n{}",
+                path.display(),
+                Comparison::new(&expected, &actual)
+            );
+        }
+        Err(_) => panic!("{} is missing", path.display()),
+    }
+}
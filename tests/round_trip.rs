@@ -0,0 +1,335 @@
+// Random documents written with the AsciiDoc emitter must parse back to the same tree. The
+// generators stay inside what the grammar can express: every line starts with a letter or digit
+// (`*` or `.` there would start a list), formatted words are separated by spaces, words hold no
+// markup characters and blocks have no titles, which the grammar doesn't read.
+use combine_sandbox::symbol::Symbol;
+use combine_sandbox::{
+    to_asciidoc, AttrList, Block, BlockMeta, DelimitedKind, Document, HeadingLevel, Inline,
+    InlineVec, ListItem, ListLevel,
+};
+use pretty_assertions::assert_eq;
+use proptest::prelude::*;
+
+#[derive(Debug, Clone)]
+enum Word {
+    Plain(String),
+    Bold(String),
+    Italic(String),
+    Monospace(String),
+    Marker(String),
+}
+
+#[derive(Debug, Clone)]
+enum Model {
+    Paragraph(Vec<(String, Vec<Word>)>),
+    Heading(u8, Vec<String>),
+    Rule,
+    List {
+        ordered: bool,
+        items: Vec<(u8, Option<bool>, Vec<String>)>,
+    },
+    // Listing block lines, indented by the number of spaces, and its language.
+    Code(Vec<(usize, Vec<String>)>, Option<String>),
+    Delimited(DelimitedKind, Vec<(Option<Meta>, Model)>),
+    Verbatim(DelimitedKind, Vec<Vec<String>>),
+}
+
+// The `#id.role` shorthand and a named entry of an attribute line.
+#[derive(Debug, Clone)]
+struct Meta {
+    id: Option<String>,
+    role: Option<String>,
+    named: Option<(String, String)>,
+}
+
+fn word() -> impl Strategy<Value = String> {
+    "[a-zA-Z0-9äöéß漢字][a-zA-Z0-9äöéß漢字.,;!?'()-]{0,7}"
+}
+
+fn words() -> impl Strategy<Value = Vec<String>> {
+    prop::collection::vec(word(), 1..5)
+}
+
+fn name() -> impl Strategy<Value = String> {
+    "[a-z][a-z0-9-]{0,5}"
+}
+
+fn formatted_word() -> impl Strategy<Value = Word> {
+    prop_oneof![
+        4 => word().prop_map(Word::Plain),
+        1 => word().prop_map(Word::Bold),
+        1 => word().prop_map(Word::Italic),
+        1 => word().prop_map(Word::Monospace),
+        1 => word().prop_map(Word::Marker),
+    ]
+}
+
+fn meta() -> impl Strategy<Value = Meta> {
+    (
+        prop::option::of(name()),
+        prop::option::of(name()),
+        prop::option::of((name(), "[a-z0-9]{1,5}")),
+    )
+        .prop_filter("an attribute line has an entry", |(id, role, named)| {
+            id.is_some() || role.is_some() || named.is_some()
+        })
+        .prop_map(|(id, role, named)| Meta { id, role, named })
+}
+
+// Blocks that can go anywhere, in a delimited block too.
+fn leaf() -> impl Strategy<Value = Model> {
+    let line = (word(), prop::collection::vec(formatted_word(), 0..6));
+    let item = (1u8..3, prop::option::of(any::<bool>()), words());
+    let code_line = (0usize..3, words());
+    prop_oneof![
+        3 => prop::collection::vec(line, 1..4).prop_map(Model::Paragraph),
+        2 => (any::<bool>(), prop::collection::vec(item, 1..5)).prop_map(|(ordered, mut items)| {
+            // A list opens at the first level.
+            items[0].0 = 1;
+            Model::List { ordered, items }
+        }),
+        1 => (prop::collection::vec(code_line, 1..4), prop::option::of(name()))
+            .prop_map(|(lines, language)| Model::Code(lines, language)),
+    ]
+}
+
+fn model() -> impl Strategy<Value = Model> {
+    let kind = prop_oneof![
+        Just(DelimitedKind::Open),
+        Just(DelimitedKind::Example),
+        Just(DelimitedKind::Sidebar),
+        Just(DelimitedKind::Quote),
+    ];
+    let verbatim = prop_oneof![Just(DelimitedKind::Pass), Just(DelimitedKind::Comment)];
+    let child = (prop::option::of(meta()), leaf());
+    prop_oneof![
+        6 => leaf(),
+        1 => (0u8..5, words()).prop_map(|(level, words)| Model::Heading(level, words)),
+        1 => Just(Model::Rule),
+        1 => (kind, prop::collection::vec(child, 1..4))
+            .prop_map(|(kind, children)| Model::Delimited(kind, children)),
+        1 => (verbatim, prop::collection::vec(words(), 1..3))
+            .prop_map(|(kind, lines)| Model::Verbatim(kind, lines)),
+    ]
+}
+
+fn value(text: &str) -> Inline {
+    Inline::Value(text.to_string())
+}
+
+// A code block with an attribute line names its language in it, after the `source` style.
+fn to_meta(meta: Meta, language: Option<&str>) -> BlockMeta {
+    let mut attributes = AttrList::new();
+    let mut shorthand = String::new();
+    if language.is_some() {
+        shorthand.push_str("source");
+    }
+    if let Some(id) = meta.id {
+        shorthand.push_str(&format!("#{}", id));
+    }
+    if let Some(role) = meta.role {
+        shorthand.push_str(&format!(".{}", role));
+    }
+    if !shorthand.is_empty() {
+        attributes.push_positional(shorthand);
+    }
+    if let Some(language) = language {
+        attributes.push_positional(language);
+    }
+    if let Some((key, value)) = meta.named {
+        attributes.push_named(key.as_str(), value);
+    }
+    BlockMeta::from_attributes(attributes)
+}
+
+fn to_block(model: Model) -> Block {
+    match model {
+        Model::Paragraph(lines) => {
+            let mut children = vec![];
+            for (index, (first, words)) in lines.into_iter().enumerate() {
+                if index > 0 {
+                    children.push(Inline::SoftBreak);
+                }
+                children.push(value(&first));
+                for word in words {
+                    children.push(value(" "));
+                    children.push(match word {
+                        Word::Plain(text) => value(&text),
+                        Word::Bold(text) => Inline::Bold {
+                            children: Box::new(value(&text)),
+                        },
+                        Word::Italic(text) => Inline::Italic {
+                            children: Box::new(value(&text)),
+                        },
+                        Word::Monospace(text) => Inline::Monospace {
+                            children: Box::new(value(&text)),
+                        },
+                        Word::Marker(text) => Inline::Marker {
+                            children: Box::new(value(&text)),
+                        },
+                    });
+                }
+            }
+            Block::Paragraph {
+                children: children.into(),
+            }
+        }
+        Model::Heading(level, words) => Block::Heading {
+            level: HeadingLevel::from_u8(level).unwrap(),
+            children: vec![value(&words.join(" "))].into(),
+            id: None,
+        },
+        Model::Rule => Block::HorizontalRuledLine,
+        Model::List { ordered, items } => {
+            let children = items
+                .into_iter()
+                .map(|(level, checked, words)| {
+                    let children = vec![value(&words.join(" "))].into();
+                    let level = ListLevel::from_u8(level).unwrap();
+                    match checked {
+                        Some(checked) => ListItem::Check {
+                            children,
+                            level,
+                            checked,
+                        },
+                        None => ListItem::Normal { children, level },
+                    }
+                })
+                .collect();
+            if ordered {
+                Block::OrderdList { children }
+            } else {
+                Block::UnorderdList { children }
+            }
+        }
+        Model::Code(lines, language) => {
+            let code = lines
+                .into_iter()
+                .map(|(indent, words)| format!("{}{}", " ".repeat(indent), words.join(" ")))
+                .collect::<Vec<_>>()
+                .join("\n");
+            Block::CodeBlock {
+                children: vec![value(&code)].into(),
+                title: None,
+                file_type: language.as_deref().map(Symbol::from),
+            }
+        }
+        Model::Delimited(kind, children) => {
+            let (children, metas) = children
+                .into_iter()
+                .map(|(meta, model)| {
+                    let language = match &model {
+                        Model::Code(_, language) => language.clone(),
+                        _ => None,
+                    };
+                    let meta = meta.map(|meta| to_meta(meta, language.as_deref()));
+                    (to_block(model), meta)
+                })
+                .unzip();
+            Block::Block {
+                kind,
+                children,
+                metas,
+                title: None,
+            }
+        }
+        Model::Verbatim(kind, lines) => {
+            let text = lines
+                .iter()
+                .map(|words| words.join(" "))
+                .collect::<Vec<_>>()
+                .join("\n");
+            Block::Block {
+                kind,
+                children: vec![Block::Paragraph {
+                    children: vec![value(&text)].into(),
+                }],
+                metas: vec![None],
+                title: None,
+            }
+        }
+    }
+}
+
+// The attribute line the emitter writes to name the language of a code block, `[source,rust]`.
+fn names_language(meta: &BlockMeta, block: &Block) -> bool {
+    match block {
+        Block::CodeBlock {
+            file_type: Some(language),
+            ..
+        } => {
+            let mut attributes = AttrList::new();
+            attributes.push_positional("source");
+            attributes.push_positional(language.as_str());
+            meta.attributes == attributes
+        }
+        _ => false,
+    }
+}
+
+// Adjacent text nodes are merged, the separators the grammar keeps between blocks and the
+// attribute lines naming languages dropped.
+fn normalize(blocks: Vec<Block>, metas: Vec<Option<BlockMeta>>) -> Vec<(Block, Option<BlockMeta>)> {
+    blocks
+        .into_iter()
+        .zip(metas)
+        .filter(|(block, _)| *block != Block::BlankBlock)
+        .map(|(block, meta)| {
+            let meta = meta.filter(|meta| !names_language(meta, &block));
+            let block = match block {
+                Block::Paragraph { children } => {
+                    let mut merged = InlineVec::new();
+                    for inline in children {
+                        match (merged.last_mut(), inline) {
+                            (Some(Inline::Value(last)), Inline::Value(text)) => {
+                                last.push_str(&text)
+                            }
+                            (_, inline) => merged.push(inline),
+                        }
+                    }
+                    Block::Paragraph {
+                        children: merged.into(),
+                    }
+                }
+                Block::Block {
+                    kind,
+                    children,
+                    metas,
+                    title,
+                } => {
+                    let (children, metas) = normalize(children, metas).into_iter().unzip();
+                    Block::Block {
+                        kind,
+                        children,
+                        metas,
+                        title,
+                    }
+                }
+                block => block,
+            };
+            (block, meta)
+        })
+        .collect()
+}
+
+proptest! {
+    #[test]
+    fn test_emitted_asciidoc_parses_back(models in prop::collection::vec(model(), 1..6)) {
+        let blocks = models.into_iter().map(to_block).collect::<Vec<_>>();
+        let metas = blocks.iter().map(|_| None).collect::<Vec<_>>();
+        let document = Document::new(blocks);
+        let emitted = to_asciidoc(&document);
+
+        let parsed = Document::parse(&emitted).unwrap();
+        let parsed_metas = (0..parsed.blocks().len())
+            .map(|index| parsed.meta(index).cloned())
+            .collect();
+        let expected = normalize(document.into_blocks(), metas);
+        assert_eq!(
+            normalize(parsed.into_blocks(), parsed_metas),
+            expected,
+            "emitted:\n{}",
+            emitted
+        );
+    }
+}